@@ -13,15 +13,37 @@
 //! ## Revert
 //! If you bumped by mistake, use `revert` to restore pubspec.yaml from the last git commit.
 //!
+//! ## Release notes
+//! `release-notes` extracts the current version's section from CHANGELOG.md and writes
+//! fastlane metadata: `android/<locale>/changelogs/<build>.txt` (max 500 chars) and
+//! `<locale>/release_notes.txt` (max 4000 chars). A `CHANGELOG.<locale>.md` file, when
+//! present, is used for that locale instead of CHANGELOG.md.
+//!
+//! ## Version badge
+//! `--badge [PATH]` on a bump (or the `badge` subcommand) writes a shields.io endpoint
+//! JSON (default `badges/version.json`) for the new version, so READMEs can use
+//! `https://img.shields.io/endpoint?url=<raw url of the file>`. `--commit-badge` also
+//! commits that file on its own.
+//!
+//! ## Version control
+//! `--vcs auto` (default) uses git when a repository is found, otherwise a Jujutsu (`jj`)
+//! repository where tags map to bookmarks on `@-`; with neither (or `--vcs none`) the tag
+//! step is skipped and the bump still happens.
+//!
 //! Notes:
-//! - Tag creation is **local only** (no fetch/push).
-//! - If not in a git repo, if `HEAD` is unborn (no commits), or if the version can't be read,
+//! - Tag creation is **local only** (no push). In shallow CI clones the tag step is skipped
+//!   unless `--fetch-tags` fetches the remote tags first or `--check-remote` asks `origin`
+//!   (`git ls-remote`) instead, so existing tags aren't duplicated.
+//! - If not in a git/jj repo, if `HEAD` is unborn (no commits), or if the version can't be read,
 //!   the tag step is skipped.
 //! - Writing the new `version:` uses a regex replace to preserve formatting/comments.
 //!
 //! Usage:
 //!   rust-script bump_version.rs <major|minor|patch|build> [--pubspec PATH] [--tag-prefix v|none]
+//!   rust-script bump_version.rs <major|minor|patch|build> [--badge [PATH]] [--commit-badge]
 //!   rust-script bump_version.rs revert [--pubspec PATH]
+//!   rust-script bump_version.rs badge [--pubspec PATH] [--output PATH]
+//!   rust-script bump_version.rs release-notes [--pubspec PATH] [--changelog PATH] [--locales en-US,de-DE]
 //!
 //! Examples:
 //! - Patch bump, default tag prefix `v`:
@@ -32,6 +54,10 @@
 //!   `rust-script bump_version.rs minor --tag-prefix none`
 //! - Revert the last bump:
 //!   `rust-script bump_version.rs revert`
+//! - Patch bump and commit the updated version badge:
+//!   `rust-script bump_version.rs patch --badge --commit-badge`
+//! - Write store release notes for the current version:
+//!   `rust-script bump_version.rs release-notes --locales en-US,zh-CN`
 //!
 //! ```cargo
 //! [dependencies]
@@ -42,11 +68,12 @@
 //! gix = "0.78"
 //! serde = { version = "1.0", features = ["derive"] }
 //! serde_yaml = "0.9"
+//! serde_json = "1.0"
 //! ```
 
 use clap::{Parser, Subcommand, ValueEnum};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use regex::Regex;
 use semver::{Version, Prerelease, BuildMetadata};
@@ -68,6 +95,21 @@ enum Command {
         pubspec: String,
         #[arg(long, value_enum, default_value = "v")]
         tag_prefix: TagPrefix,
+        /// Version control backend used for tags and badge commits
+        #[arg(long, value_enum, default_value = "auto")]
+        vcs: VcsKind,
+        /// Fetch tags from the remote before checking for the current version's tag
+        #[arg(long)]
+        fetch_tags: bool,
+        /// Also check `origin` for the current version's tag before creating it
+        #[arg(long)]
+        check_remote: bool,
+        /// Write a shields.io endpoint badge for the new version
+        #[arg(long, num_args = 0..=1, default_missing_value = DEFAULT_BADGE_PATH)]
+        badge: Option<String>,
+        /// Commit the badge file after writing it
+        #[arg(long, requires = "badge")]
+        commit_badge: bool,
     },
     /// Bump minor version (x.Y.0+1)
     Minor {
@@ -75,6 +117,21 @@ enum Command {
         pubspec: String,
         #[arg(long, value_enum, default_value = "v")]
         tag_prefix: TagPrefix,
+        /// Version control backend used for tags and badge commits
+        #[arg(long, value_enum, default_value = "auto")]
+        vcs: VcsKind,
+        /// Fetch tags from the remote before checking for the current version's tag
+        #[arg(long)]
+        fetch_tags: bool,
+        /// Also check `origin` for the current version's tag before creating it
+        #[arg(long)]
+        check_remote: bool,
+        /// Write a shields.io endpoint badge for the new version
+        #[arg(long, num_args = 0..=1, default_missing_value = DEFAULT_BADGE_PATH)]
+        badge: Option<String>,
+        /// Commit the badge file after writing it
+        #[arg(long, requires = "badge")]
+        commit_badge: bool,
     },
     /// Bump patch version (x.y.Z+1)
     Patch {
@@ -82,6 +139,21 @@ enum Command {
         pubspec: String,
         #[arg(long, value_enum, default_value = "v")]
         tag_prefix: TagPrefix,
+        /// Version control backend used for tags and badge commits
+        #[arg(long, value_enum, default_value = "auto")]
+        vcs: VcsKind,
+        /// Fetch tags from the remote before checking for the current version's tag
+        #[arg(long)]
+        fetch_tags: bool,
+        /// Also check `origin` for the current version's tag before creating it
+        #[arg(long)]
+        check_remote: bool,
+        /// Write a shields.io endpoint badge for the new version
+        #[arg(long, num_args = 0..=1, default_missing_value = DEFAULT_BADGE_PATH)]
+        badge: Option<String>,
+        /// Commit the badge file after writing it
+        #[arg(long, requires = "badge")]
+        commit_badge: bool,
     },
     /// Bump build number only (x.y.z+N)
     Build {
@@ -89,14 +161,56 @@ enum Command {
         pubspec: String,
         #[arg(long, value_enum, default_value = "v")]
         tag_prefix: TagPrefix,
+        /// Version control backend used for tags and badge commits
+        #[arg(long, value_enum, default_value = "auto")]
+        vcs: VcsKind,
+        /// Fetch tags from the remote before checking for the current version's tag
+        #[arg(long)]
+        fetch_tags: bool,
+        /// Also check `origin` for the current version's tag before creating it
+        #[arg(long)]
+        check_remote: bool,
+        /// Write a shields.io endpoint badge for the new version
+        #[arg(long, num_args = 0..=1, default_missing_value = DEFAULT_BADGE_PATH)]
+        badge: Option<String>,
+        /// Commit the badge file after writing it
+        #[arg(long, requires = "badge")]
+        commit_badge: bool,
     },
     /// Revert the last bump by restoring pubspec.yaml from git HEAD
     Revert {
         #[arg(long, default_value = "pubspec.yaml")]
         pubspec: String,
     },
+    /// Write the shields.io endpoint badge for the current version
+    Badge {
+        #[arg(long, default_value = "pubspec.yaml")]
+        pubspec: String,
+        #[arg(long, default_value = DEFAULT_BADGE_PATH)]
+        output: String,
+    },
+    /// Write fastlane release notes for the current version from CHANGELOG.md
+    ReleaseNotes {
+        #[arg(long, default_value = "pubspec.yaml")]
+        pubspec: String,
+        #[arg(long, default_value = "CHANGELOG.md")]
+        changelog: String,
+        /// Comma-separated store locales
+        #[arg(long, value_delimiter = ',', default_value = "en-US")]
+        locales: Vec<String>,
+        /// fastlane metadata root
+        #[arg(long, default_value = "fastlane/metadata")]
+        metadata_dir: String,
+    },
 }
 
+const DEFAULT_BADGE_PATH: &str = "badges/version.json";
+
+/// Play Console "What's new" limit per locale.
+const PLAY_RELEASE_NOTES_LIMIT: usize = 500;
+/// App Store Connect "What's New in This Version" limit.
+const APP_STORE_RELEASE_NOTES_LIMIT: usize = 4000;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
 enum TagPrefix {
     V,
@@ -111,9 +225,262 @@ enum VersionPart {
     Build,
 }
 
-fn tag_exists(repo: &gix::Repository, tag: &str) -> Result<bool> {
-    let full = format!("refs/tags/{tag}");
-    Ok(repo.try_find_reference(full.as_str())?.is_some())
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum VcsKind {
+    /// git if a repository is found, else jj, else tags are skipped
+    Auto,
+    Git,
+    /// Jujutsu: tags are mapped to bookmarks
+    Jj,
+    /// Disable tagging
+    None,
+}
+
+/// Tag operations used by this tool, so jj checkouts (or plain source archives)
+/// don't depend on a git repository being discoverable.
+trait Vcs {
+    fn name(&self) -> &'static str;
+    /// True when local history (and therefore the tag list) may be incomplete.
+    fn is_shallow(&self) -> bool;
+    /// True when the revision being tagged is not on a branch (typical CI checkout).
+    fn is_detached(&self) -> Result<bool>;
+    /// Fetches tags from the default remote so existence checks see them.
+    fn fetch_tags(&self) -> Result<()>;
+    /// Asks the default remote whether `tag` exists there, without fetching it.
+    fn remote_tag_exists(&self, tag: &str) -> Result<bool>;
+    fn tag_exists(&self, tag: &str) -> Result<bool>;
+    /// Points `tag` at the current revision. Returns `false` when there is no
+    /// revision to tag yet (e.g. an unborn HEAD).
+    fn create_tag(&self, tag: &str) -> Result<bool>;
+    /// Commits only `path`, leaving other changes untouched. Returns `false` when
+    /// `path` has no changes to commit.
+    fn commit_path(&self, path: &Path, message: &str) -> Result<bool>;
+}
+
+struct GitVcs {
+    repo: gix::Repository,
+    dir: PathBuf,
+}
+
+impl Vcs for GitVcs {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn is_shallow(&self) -> bool {
+        self.repo.is_shallow()
+    }
+
+    fn is_detached(&self) -> Result<bool> {
+        Ok(self.repo.head_name()?.is_none())
+    }
+
+    fn fetch_tags(&self) -> Result<()> {
+        let status = std::process::Command::new("git")
+            .args(["fetch", "--tags", "--force", "--quiet", "origin"])
+            .current_dir(&self.dir)
+            .status()
+            .context("Failed to run 'git fetch'")?;
+        if !status.success() {
+            anyhow::bail!("git fetch --tags origin failed");
+        }
+        Ok(())
+    }
+
+    fn remote_tag_exists(&self, tag: &str) -> Result<bool> {
+        let output = std::process::Command::new("git")
+            .args(["ls-remote", "--tags", "origin", &format!("refs/tags/{tag}")])
+            .current_dir(&self.dir)
+            .output()
+            .context("Failed to run 'git ls-remote'")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git ls-remote origin failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+    }
+
+    fn tag_exists(&self, tag: &str) -> Result<bool> {
+        let full = format!("refs/tags/{tag}");
+        Ok(self.repo.try_find_reference(full.as_str())?.is_some())
+    }
+
+    fn create_tag(&self, tag: &str) -> Result<bool> {
+        let head_id = match self.repo.head_id() {
+            Ok(id) => id.detach(),
+            Err(_) => return Ok(false),
+        };
+        self.repo
+            .tag_reference(tag, head_id, PreviousValue::MustNotExist)
+            .with_context(|| format!("Failed to create lightweight tag '{tag}'"))?;
+        Ok(true)
+    }
+
+    fn commit_path(&self, path: &Path, message: &str) -> Result<bool> {
+        let path_str = path.to_str().context("Path is not valid UTF-8")?;
+        let status = std::process::Command::new("git")
+            .args(["add", "--", path_str])
+            .status()
+            .context("Failed to run 'git add'")?;
+        if !status.success() {
+            anyhow::bail!("git add -- {} failed", path.display());
+        }
+
+        let diff_status = std::process::Command::new("git")
+            .args(["diff", "--cached", "--quiet", "--", path_str])
+            .status()
+            .context("Failed to run 'git diff'")?;
+        if diff_status.success() {
+            return Ok(false);
+        }
+
+        let status = std::process::Command::new("git")
+            .args(["commit", "-m", message, "--", path_str])
+            .status()
+            .context("Failed to run 'git commit'")?;
+        if !status.success() {
+            anyhow::bail!("git commit -- {} failed", path.display());
+        }
+        Ok(true)
+    }
+}
+
+/// Jujutsu repository; tags are bookmarks on the parent of the working-copy commit (`@-`).
+struct JjVcs {
+    root: PathBuf,
+}
+
+impl JjVcs {
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let output = std::process::Command::new("jj")
+            .args(args)
+            .current_dir(&self.root)
+            .output()
+            .context("Failed to run 'jj'")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "jj {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+impl Vcs for JjVcs {
+    fn name(&self) -> &'static str {
+        "jj"
+    }
+
+    fn is_shallow(&self) -> bool {
+        false
+    }
+
+    fn is_detached(&self) -> Result<bool> {
+        // jj has no current branch; bookmarks are always placed explicitly.
+        Ok(false)
+    }
+
+    fn fetch_tags(&self) -> Result<()> {
+        self.run(&["git", "fetch"])?;
+        Ok(())
+    }
+
+    fn remote_tag_exists(&self, _tag: &str) -> Result<bool> {
+        anyhow::bail!("Remote tag checks are not supported for jj repositories; use --fetch-tags instead")
+    }
+
+    fn tag_exists(&self, tag: &str) -> Result<bool> {
+        let pattern = format!("exact:{tag}");
+        let template = r#"name ++ "\n""#;
+        let bookmarks = self.run(&["bookmark", "list", "-T", template, &pattern])?;
+        let tags = self.run(&["tag", "list", "-T", template, &pattern])?;
+        Ok(bookmarks.lines().chain(tags.lines()).any(|name| name.trim() == tag))
+    }
+
+    fn create_tag(&self, tag: &str) -> Result<bool> {
+        self.run(&["bookmark", "create", tag, "-r", "@-"])?;
+        Ok(true)
+    }
+
+    fn commit_path(&self, path: &Path, message: &str) -> Result<bool> {
+        // Commands run from the workspace root, so pass an absolute path.
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", path.display()))?;
+        let path_str = path.to_str().context("Path is not valid UTF-8")?;
+        if self.run(&["diff", "--summary", path_str])?.trim().is_empty() {
+            return Ok(false);
+        }
+        self.run(&["commit", "-m", message, path_str])?;
+        Ok(true)
+    }
+}
+
+fn find_jj_root(start_dir: &Path) -> Option<PathBuf> {
+    let start = start_dir.canonicalize().ok()?;
+    start.ancestors().find(|dir| dir.join(".jj").is_dir()).map(Path::to_path_buf)
+}
+
+/// Returns `None` when tagging is disabled or no supported repository is found.
+fn open_vcs(kind: VcsKind, start_dir: &Path) -> Result<Option<Box<dyn Vcs>>> {
+    match kind {
+        VcsKind::None => Ok(None),
+        VcsKind::Git => {
+            let repo = gix::discover(start_dir)
+                .with_context(|| format!("No git repository found at {}", start_dir.display()))?;
+            Ok(Some(Box::new(GitVcs { repo, dir: start_dir.to_path_buf() })))
+        }
+        VcsKind::Jj => {
+            let root = find_jj_root(start_dir)
+                .with_context(|| format!("No jj repository found at {}", start_dir.display()))?;
+            Ok(Some(Box::new(JjVcs { root })))
+        }
+        VcsKind::Auto => {
+            // Colocated jj repos are handled as git; jj imports the refs on its next command.
+            if let Ok(repo) = gix::discover(start_dir) {
+                return Ok(Some(Box::new(GitVcs { repo, dir: start_dir.to_path_buf() })));
+            }
+            if which_jj() {
+                if let Some(root) = find_jj_root(start_dir) {
+                    return Ok(Some(Box::new(JjVcs { root })));
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Makes sure the tag list is trustworthy before checking for existing tags.
+/// Returns `false` when tags can't be trusted (shallow clone without `--fetch-tags` or
+/// `--check-remote`, which asks origin instead of the local tag list).
+fn prepare_tags(vcs: &dyn Vcs, fetch_tags: bool, check_remote: bool) -> Result<bool> {
+    if fetch_tags {
+        vcs.fetch_tags()?;
+        println!("[bump-version] Fetched tags from the remote");
+    } else if vcs.is_shallow() {
+        if check_remote {
+            println!("[bump-version] Shallow clone detected: checking origin for existing tags (--check-remote)");
+        } else {
+            println!("[bump-version] Shallow clone detected: existing tags may be missing locally (use --fetch-tags or --check-remote)");
+            return Ok(false);
+        }
+    }
+    if vcs.is_detached()? {
+        println!("[bump-version] Detached HEAD: tagging the checked-out commit");
+    }
+    Ok(true)
+}
+
+fn which_jj() -> bool {
+    std::process::Command::new("jj")
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
 }
 
 #[derive(Debug, Deserialize)]
@@ -140,18 +507,21 @@ fn read_pubspec_version(content: &str) -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
-fn ensure_current_version_tag(pubspec_path: &Path, tag_prefix: TagPrefix) -> Result<()> {
-    let start_dir = pubspec_path
-        .parent()
-        .filter(|p| !p.as_os_str().is_empty())
-        .unwrap_or_else(|| Path::new("."));
-    let repo = match gix::discover(start_dir) {
-        Ok(r) => r,
-        Err(_) => {
-            println!("[bump-version] Skipping tag check (not in a git repository)");
-            return Ok(());
-        }
+fn ensure_current_version_tag(
+    vcs: Option<&dyn Vcs>,
+    pubspec_path: &Path,
+    tag_prefix: TagPrefix,
+    fetch_tags: bool,
+    check_remote: bool,
+) -> Result<()> {
+    let Some(vcs) = vcs else {
+        println!("[bump-version] Skipping tag check (no git/jj repository, or --vcs none)");
+        return Ok(());
     };
+    if !prepare_tags(vcs, fetch_tags, check_remote)? {
+        println!("[bump-version] Skipping tag check");
+        return Ok(());
+    }
 
     let content = fs::read_to_string(pubspec_path)
         .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
@@ -188,7 +558,7 @@ fn ensure_current_version_tag(pubspec_path: &Path, tag_prefix: TagPrefix) -> Res
     };
 
     // If either convention exists, do nothing.
-    if tag_exists(&repo, &tag_plain)? || tag_exists(&repo, &tag_v)? {
+    if vcs.tag_exists(&tag_plain)? || vcs.tag_exists(&tag_v)? {
         println!(
             "[bump-version] Tag already exists for current version: {} (checked '{}' and '{}')",
             version_str, tag_plain, tag_v
@@ -196,19 +566,21 @@ fn ensure_current_version_tag(pubspec_path: &Path, tag_prefix: TagPrefix) -> Res
         return Ok(());
     }
 
-    let head_id = match repo.head_id() {
-        Ok(id) => id.detach(),
-        Err(_) => {
-            println!("[bump-version] Skipping tag creation (repository has no commits yet)");
-            return Ok(());
-        }
-    };
+    if check_remote && (vcs.remote_tag_exists(&tag_plain)? || vcs.remote_tag_exists(&tag_v)?) {
+        println!(
+            "[bump-version] Tag for current version {} already exists on origin (not fetched locally)",
+            version_str
+        );
+        return Ok(());
+    }
 
-    repo.tag_reference(&preferred_tag, head_id, PreviousValue::MustNotExist)
-        .with_context(|| format!("Failed to create lightweight tag '{preferred_tag}'"))?;
+    if !vcs.create_tag(&preferred_tag)? {
+        println!("[bump-version] Skipping tag creation (repository has no commits yet)");
+        return Ok(());
+    }
     println!(
-        "[bump-version] Created lightweight tag '{}' for current version {}",
-        preferred_tag, version_str
+        "[bump-version] Created {} tag '{}' for current version {}",
+        vcs.name(), preferred_tag, version_str
     );
     Ok(())
 }
@@ -250,9 +622,166 @@ fn revert_bump(pubspec_path: &Path) -> Result<()> {
     Ok(())
 }
 
-fn do_bump(pubspec_path: &Path, part: VersionPart, tag_prefix: TagPrefix) -> Result<()> {
+/// Returns the body of the `## <version>` section (also matches `## [1.2.3]`,
+/// `## v1.2.3 - 2024-01-01`), without the heading line.
+fn extract_changelog_section(changelog: &str, version: &Version) -> Option<String> {
+    let mut core = Version::new(version.major, version.minor, version.patch);
+    core.pre = version.pre.clone();
+    let wanted = [version.to_string(), core.to_string()];
+
+    let mut section = Vec::new();
+    let mut in_section = false;
+    let mut found = false;
+    for line in changelog.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if found {
+                break;
+            }
+            let name = heading
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim_start_matches('v');
+            in_section = wanted.iter().any(|w| w == name);
+            found = in_section;
+            continue;
+        }
+        if in_section {
+            section.push(line);
+        }
+    }
+    found.then(|| section.join("\n").trim().to_string())
+}
+
+/// Converts markdown to the plain text accepted by the stores.
+fn to_store_text(markdown: &str) -> String {
+    let link_regex = Regex::new(r"\[([^\]]+)\]\([^)]+\)").unwrap();
+    markdown
+        .lines()
+        .map(|line| {
+            let line = link_regex.replace_all(line, "$1").replace("**", "").replace('`', "");
+            let trimmed = line.trim_start();
+            if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+                format!("• {}", item)
+            } else {
+                trimmed.trim_start_matches('#').trim().to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+fn write_release_notes(
+    pubspec_path: &Path,
+    changelog_path: &Path,
+    locales: &[String],
+    metadata_dir: &Path,
+) -> Result<()> {
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let version_str = read_pubspec_version(&content)
+        .with_context(|| format!("No version found in {}", pubspec_path.display()))?;
+    let version = Version::parse(&version_str)
+        .with_context(|| format!("Invalid semver in pubspec '{}'", version_str))?;
+    let build_number: u64 = version.build.as_str().parse().unwrap_or(1);
+
+    let mut problems = Vec::new();
+    for locale in locales {
+        let locale_changelog = changelog_path.with_extension(format!("{}.md", locale));
+        let source = if locale_changelog.exists() { locale_changelog } else { changelog_path.to_path_buf() };
+        let changelog = fs::read_to_string(&source)
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+        let section = extract_changelog_section(&changelog, &version).with_context(|| {
+            format!("No section for version {} in {}", version_str, source.display())
+        })?;
+        let notes = to_store_text(&section);
+
+        let targets = [
+            (
+                metadata_dir.join("android").join(locale).join("changelogs").join(format!("{}.txt", build_number)),
+                PLAY_RELEASE_NOTES_LIMIT,
+                "Play",
+            ),
+            (
+                metadata_dir.join(locale).join("release_notes.txt"),
+                APP_STORE_RELEASE_NOTES_LIMIT,
+                "App Store",
+            ),
+        ];
+        for (path, limit, store) in targets {
+            let len = notes.chars().count();
+            if len > limit {
+                problems.push(format!("{} {}: {} chars exceeds limit of {}", store, locale, len, limit));
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create {}", parent.display()))?;
+            }
+            fs::write(&path, format!("{}\n", notes))
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("[bump-version] Wrote {} release notes: {}", store, path.display());
+        }
+    }
+
+    if !problems.is_empty() {
+        anyhow::bail!("Release notes too long:\n  {}", problems.join("\n  "));
+    }
+    Ok(())
+}
+
+/// Writes a shields.io endpoint JSON (https://shields.io/badges/endpoint-badge) for `version`.
+fn write_version_badge(path: &Path, version: &str) -> Result<()> {
+    let color = match Version::parse(version) {
+        Ok(v) if !v.pre.is_empty() => "orange",
+        _ => "blue",
+    };
+    let badge = serde_json::json!({
+        "schemaVersion": 1,
+        "label": "version",
+        "message": version,
+        "color": color,
+    });
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&badge)? + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("[bump-version] Wrote version badge {} ({})", path.display(), version);
+    Ok(())
+}
+
+fn write_current_badge(pubspec_path: &Path, output: &Path) -> Result<()> {
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let version = read_pubspec_version(&content)
+        .with_context(|| format!("No version found in {}", pubspec_path.display()))?;
+    write_version_badge(output, &version)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn do_bump(
+    pubspec_path: &Path,
+    part: VersionPart,
+    tag_prefix: TagPrefix,
+    vcs_kind: VcsKind,
+    fetch_tags: bool,
+    check_remote: bool,
+    badge: Option<&Path>,
+    commit_badge: bool,
+) -> Result<()> {
+    let start_dir = pubspec_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let vcs = open_vcs(vcs_kind, start_dir)?;
+
     // Ensure the current version is tagged before bumping.
-    ensure_current_version_tag(pubspec_path, tag_prefix)?;
+    ensure_current_version_tag(vcs.as_deref(), pubspec_path, tag_prefix, fetch_tags, check_remote)?;
 
     let content = fs::read_to_string(pubspec_path)
         .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
@@ -313,6 +842,24 @@ fn do_bump(pubspec_path: &Path, part: VersionPart, tag_prefix: TagPrefix) -> Res
     fs::write(pubspec_path, new_content.to_string())?;
     println!("Bumped version to: {}", new_version_string);
 
+    if let Some(badge_path) = badge {
+        write_version_badge(badge_path, &new_version_string)?;
+        if commit_badge {
+            let Some(vcs) = vcs.as_deref() else {
+                anyhow::bail!("--commit-badge needs a git or jj repository");
+            };
+            if vcs.is_detached()? {
+                println!("[bump-version] Warning: detached HEAD, the badge commit won't be on a branch");
+            }
+            let message = format!("Update version badge to {}", new_version_string);
+            if vcs.commit_path(badge_path, &message)? {
+                println!("[bump-version] Committed {}", badge_path.display());
+            } else {
+                println!("[bump-version] Badge unchanged, nothing to commit");
+            }
+        }
+    }
+
     Ok(())
 }
 
@@ -320,20 +867,67 @@ fn main() -> Result<()> {
     let args = Args::parse();
 
     match args.command {
-        Command::Major { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Major, tag_prefix)
+        Command::Major { pubspec, tag_prefix, vcs, fetch_tags, check_remote, badge, commit_badge } => {
+            do_bump(
+                Path::new(&pubspec),
+                VersionPart::Major,
+                tag_prefix,
+                vcs,
+                fetch_tags,
+                check_remote,
+                badge.as_deref().map(Path::new),
+                commit_badge,
+            )
         }
-        Command::Minor { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Minor, tag_prefix)
+        Command::Minor { pubspec, tag_prefix, vcs, fetch_tags, check_remote, badge, commit_badge } => {
+            do_bump(
+                Path::new(&pubspec),
+                VersionPart::Minor,
+                tag_prefix,
+                vcs,
+                fetch_tags,
+                check_remote,
+                badge.as_deref().map(Path::new),
+                commit_badge,
+            )
         }
-        Command::Patch { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Patch, tag_prefix)
+        Command::Patch { pubspec, tag_prefix, vcs, fetch_tags, check_remote, badge, commit_badge } => {
+            do_bump(
+                Path::new(&pubspec),
+                VersionPart::Patch,
+                tag_prefix,
+                vcs,
+                fetch_tags,
+                check_remote,
+                badge.as_deref().map(Path::new),
+                commit_badge,
+            )
         }
-        Command::Build { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Build, tag_prefix)
+        Command::Build { pubspec, tag_prefix, vcs, fetch_tags, check_remote, badge, commit_badge } => {
+            do_bump(
+                Path::new(&pubspec),
+                VersionPart::Build,
+                tag_prefix,
+                vcs,
+                fetch_tags,
+                check_remote,
+                badge.as_deref().map(Path::new),
+                commit_badge,
+            )
         }
         Command::Revert { pubspec } => {
             revert_bump(Path::new(&pubspec))
         }
+        Command::Badge { pubspec, output } => {
+            write_current_badge(Path::new(&pubspec), Path::new(&output))
+        }
+        Command::ReleaseNotes { pubspec, changelog, locales, metadata_dir } => {
+            write_release_notes(
+                Path::new(&pubspec),
+                Path::new(&changelog),
+                &locales,
+                Path::new(&metadata_dir),
+            )
+        }
     }
 }
@@ -7,14 +7,73 @@
 //!
 //! Usage:
 //!   rust-script cmd-run.rs [OPTIONS] <command> [args...]
+//!   rust-script cmd-run.rs daemon [--control=<file>] [OPTIONS] <command> [args...]
+//!   rust-script cmd-run.rs send [--control=<file>] <reload|restart|screenshot|quit>
 //!
 //! Options:
 //!   --log=<file>    Log output to specified file
-//!   --cwd=<dir>     Change working directory before executing command
+//!   --cwd=<dir>     Working directory of the command (cmd-run's own directory is not
+//!                   changed, so a relative --log stays relative to where you ran it)
+//!   --encoding=<e>  Encoding of the child's output: `auto` (default), `utf-8`, or any
+//!                   WHATWG label such as `gbk`, `big5`, `shift_jis`
+//!   --log-format=<f> `text` (default) or `jsonl` (one JSON object per line with
+//!                   time, stream, level and message)
+//!   --no-color      Don't colorize console lines (also honored: `NO_COLOR` env var)
+//!   --flutter-test-junit=<file>
+//!                   Parse `flutter test --machine` JSON events from stdout while
+//!                   streaming and write a JUnit XML report for CI
+//!   --flutter-run-machine
+//!                   Drive `flutter run --machine`: daemon-protocol events are shown
+//!                   and logged as readable lines, and r/R/q (keys, Ctrl-C or `send`)
+//!                   become app.restart/app.stop requests
+//!   --supervise     Restart a command that exits with a non-zero code (not after
+//!                   Ctrl-C or `send quit`), e.g. to keep a device-attached
+//!                   `flutter run` alive across device reconnects
+//!   --max-restarts=<n>     Restarts per command before giving up (default 5)
+//!   --restart-backoff=<s>  Seconds before the first restart, doubled each time up to
+//!                   60s (default 2)
+//!
+//! Lines are classified as error/warning/success using Gradle, Kotlin, flutter and
+//! dart analyzer patterns; the console colors them (red/yellow/green) and JSONL logs
+//! record the level, so long build logs can be filtered. When the command finishes, a
+//! de-duplicated summary of errors and warnings is printed with their counts and the
+//! log line of the first occurrence.
+//!
+//! Several commands can be chained with `--then`; they run in order and the chain
+//! stops at the first failure or Ctrl-C. Each command may set its own `--cwd`, and
+//! the first command's `--cwd` is the default for the others. All output goes to the
+//! same log and summary.
+//!
+//! Children get `CMD_RUN_SESSION_ID` (`<start time>-<pid>`), `CMD_RUN_START_TS`
+//! (RFC 3339) and, when logging, `CMD_RUN_LOG` (absolute log path) in their
+//! environment, so wrapped scripts can reference their log and tag artifacts.
+//!
+//! Gradle report links (problems report, "See the report at", build scans) seen in the
+//! output, plus a `build/reports/problems` report written during the run, are listed
+//! at the end and attached to the log footer (`reports` in the JSONL exit event).
+//!
+//! `daemon` additionally listens on a loopback TCP port so editors and scripts can drive
+//! a `flutter run` session without owning its terminal: `send reload|restart|screenshot|quit`
+//! forwards `r`/`R`/`s`/`q` to the child. The port and a per-session token are written to
+//! the control file (default `.cmd-run-daemon.json`), which is removed on exit. The
+//! protocol is one `<token> <command>` line per connection, answered with `ok` or `error: ...`.
+//! With `--flutter-run-machine` the keys are sent as JSON-RPC requests instead (`screenshot`
+//! has no machine-mode equivalent and is rejected).
+//!
+//! Carriage-return progress updates (download/build bars) are redrawn in place on the
+//! console; the log only gets the final state of each such line.
+//!
+//! Output is always printed and logged as UTF-8. With `auto`, lines that are valid UTF-8
+//! are kept as-is and anything else is decoded with the Windows ANSI code page (e.g. GBK
+//! on Chinese-locale systems, where Gradle/Java emit localized messages).
 //!
 //! Examples:
 //!   rust-script cmd-run.rs --log=build.log flutter build apk
 //!   rust-script cmd-run.rs --log=logs/test.log --cwd=project cargo test
+//!   rust-script cmd-run.rs --log=build.log --cwd=flutter flutter pub get --then --cwd=flutter/android ./gradlew assembleRelease
+//!   rust-script cmd-run.rs --log=test.log --flutter-test-junit=junit.xml flutter test --machine
+//!   rust-script cmd-run.rs daemon --log=run.log --flutter-run-machine flutter run --machine -d chrome
+//!   rust-script cmd-run.rs --encoding=gbk --log=build.log gradlew.bat assembleRelease
 //!
 //! ```cargo
 //! [dependencies]
@@ -22,18 +81,28 @@
 //! anyhow = "1.0"
 //! which = "6.0"
 //! ctrlc = "3.4"
+//! encoding_rs = "0.8"
+//! regex = "1.10"
+//! serde_json = "1.0"
+//! getrandom = "0.3"
 //!
 //! [target.'cfg(windows)'.dependencies]
-//! windows-sys = { version = "0.59", features = ["Win32_System_Console", "Win32_Foundation"] }
+//! windows-sys = { version = "0.59", features = ["Win32_System_Console", "Win32_Foundation", "Win32_Globalization"] }
 //! ```
 
 use anyhow::{Context, Result};
 use chrono::Local;
+use encoding_rs::Encoding;
+use regex::Regex;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, Read, Write};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use std::sync::{Arc, Mutex};
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{ChildStdin, Command, Stdio};
+use std::collections::HashMap;
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+use std::sync::{Arc, Mutex, OnceLock};
 use which::which;
 
 /// Enable raw mode on Windows stdin so each keypress is available immediately.
@@ -71,15 +140,1158 @@ fn restore_console_mode(mode: u32) {
     }
 }
 
+/// Enable ANSI escape processing so colored lines render in the Windows console.
+#[cfg(windows)]
+fn enable_vt_mode() {
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::System::Console::*;
+    unsafe {
+        for std_handle in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
+            let handle = GetStdHandle(std_handle);
+            if handle.is_null() || handle == INVALID_HANDLE_VALUE {
+                continue;
+            }
+            let mut mode: u32 = 0;
+            if GetConsoleMode(handle, &mut mode) != 0 {
+                SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LogFormat {
+    Text,
+    Jsonl,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Error,
+    Warning,
+    Success,
+    Info,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Success => "success",
+            Level::Info => "info",
+        }
+    }
+
+    fn color(self) -> Option<&'static str> {
+        match self {
+            Level::Error => Some("\x1b[31m"),
+            Level::Warning => Some("\x1b[33m"),
+            Level::Success => Some("\x1b[32m"),
+            Level::Info => None,
+        }
+    }
+}
+
+/// Classifies a build output line (Gradle, Kotlin/Java compiler, flutter, dart analyzer).
+fn classify(line: &str) -> Level {
+    static PATTERNS: OnceLock<[(Level, Regex); 3]> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            (
+                Level::Error,
+                Regex::new(concat!(
+                    r"^\s*(e: |error( •|:)|ERROR:|\[ERROR|FAILURE:|\* What went wrong|BUILD FAILED|Error: |Exception: |Unhandled exception)",
+                    r"|^\S+\.(dart|kt|java):\d+:\d+: Error\b",
+                    r"|^.*\bFAILED\s*$",
+                ))
+                .unwrap(),
+            ),
+            (
+                Level::Warning,
+                Regex::new(r"^\s*(w: |warning( •|:)|WARNING:|Warning: |\[WARNING|Note: .*deprecated)").unwrap(),
+            ),
+            (
+                Level::Success,
+                Regex::new(r"^\s*(BUILD SUCCESSFUL|✓|√|No issues found!)").unwrap(),
+            ),
+        ]
+    });
+    patterns
+        .iter()
+        .find(|(_, regex)| regex.is_match(line))
+        .map(|(level, _)| *level)
+        .unwrap_or(Level::Info)
+}
+
+/// Extracts a rich report link from a Gradle output line: the problems report, test/lint
+/// "See the report at" links, or a published build scan.
+fn report_link(line: &str) -> Option<String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(concat!(
+            r"(?:[Rr]eport is available at|[Ss]ee the (?:complete )?report at):?\s*(file:\S+)",
+            r"|(https://(?:scans\.)?gradle\.com/s/\S+)",
+        ))
+        .unwrap()
+    });
+    let caps = pattern.captures(line)?;
+    let link = caps.get(1).or_else(|| caps.get(2))?.as_str();
+    Some(file_url_to_path(link).unwrap_or_else(|| link.to_string()))
+}
+
+/// `file:///C:/x/report.html` -> `C:/x/report.html`, `file:/home/x` -> `/home/x`.
+fn file_url_to_path(link: &str) -> Option<String> {
+    let path = link.strip_prefix("file://").or_else(|| link.strip_prefix("file:"))?;
+    let path = path.replace("%20", " ");
+    let bytes = path.as_bytes();
+    if bytes.len() > 2 && bytes[0] == b'/' && bytes[2] == b':' {
+        Some(path[1..].to_string())
+    } else {
+        Some(path)
+    }
+}
+
+/// Where Gradle (8.6+) writes its problems report, relative to the build's root project.
+const PROBLEMS_REPORT: &str = "build/reports/problems/problems-report.html";
+
+/// One test from `flutter test --machine` events.
+#[derive(Default)]
+struct JunitCase {
+    name: String,
+    suite: String,
+    start_ms: u64,
+    time_ms: u64,
+    done: bool,
+    hidden: bool,
+    skipped: Option<String>,
+    /// (is_failure, message, stack trace)
+    errors: Vec<(bool, String, String)>,
+    output: String,
+}
+
+/// Collects `flutter test --machine` JSON events into JUnit test cases. Test ids are only
+/// unique per run, so cases are keyed by (run, id) when several runs are chained.
+#[derive(Default)]
+struct JunitReport {
+    run: usize,
+    suites: HashMap<(usize, u64), String>,
+    cases: Vec<JunitCase>,
+    index: HashMap<(usize, u64), usize>,
+}
+
+impl JunitReport {
+    fn handle_line(&mut self, line: &str) {
+        let line = line.trim();
+        if !line.starts_with('{') {
+            return;
+        }
+        let Ok(event) = serde_json::from_str::<serde_json::Value>(line) else {
+            return;
+        };
+        let time = event["time"].as_u64().unwrap_or(0);
+        match event["type"].as_str() {
+            Some("start") => self.run += 1,
+            Some("suite") => {
+                if let (Some(id), Some(path)) = (event["suite"]["id"].as_u64(), event["suite"]["path"].as_str()) {
+                    self.suites.insert((self.run, id), path.to_string());
+                }
+            }
+            Some("testStart") => {
+                let test = &event["test"];
+                let Some(id) = test["id"].as_u64() else {
+                    return;
+                };
+                let suite = test["suiteID"]
+                    .as_u64()
+                    .and_then(|suite_id| self.suites.get(&(self.run, suite_id)).cloned())
+                    .unwrap_or_default();
+                self.index.insert((self.run, id), self.cases.len());
+                self.cases.push(JunitCase {
+                    name: test["name"].as_str().unwrap_or_default().to_string(),
+                    suite,
+                    start_ms: time,
+                    ..Default::default()
+                });
+            }
+            Some("print") => {
+                if let Some(case) = self.case_mut(&event) {
+                    case.output.push_str(event["message"].as_str().unwrap_or_default());
+                    case.output.push('\n');
+                }
+            }
+            Some("error") => {
+                if let Some(case) = self.case_mut(&event) {
+                    case.errors.push((
+                        event["isFailure"].as_bool().unwrap_or(false),
+                        event["error"].as_str().unwrap_or_default().to_string(),
+                        event["stackTrace"].as_str().unwrap_or_default().to_string(),
+                    ));
+                }
+            }
+            Some("testDone") => {
+                if let Some(case) = self.case_mut(&event) {
+                    case.done = true;
+                    case.time_ms = time.saturating_sub(case.start_ms);
+                    case.hidden = event["hidden"].as_bool().unwrap_or(false);
+                    if event["skipped"].as_bool().unwrap_or(false) {
+                        case.skipped = Some(String::new());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn case_mut(&mut self, event: &serde_json::Value) -> Option<&mut JunitCase> {
+        let id = event["testID"].as_u64()?;
+        let index = *self.index.get(&(self.run, id))?;
+        self.cases.get_mut(index)
+    }
+
+    fn write(&self, path: &Path) -> Result<()> {
+        // Hidden tests are flutter's "loading <file>" entries; keep them only when they
+        // fail (compile errors) so the failure is visible in CI.
+        let cases: Vec<&JunitCase> = self
+            .cases
+            .iter()
+            .filter(|case| !case.hidden || !case.errors.is_empty())
+            .collect();
+        let mut suites: Vec<&str> = Vec::new();
+        for case in &cases {
+            if !suites.contains(&case.suite.as_str()) {
+                suites.push(&case.suite);
+            }
+        }
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites>\n");
+        for suite in suites {
+            let suite_cases: Vec<&&JunitCase> = cases.iter().filter(|case| case.suite == suite).collect();
+            let count = |pred: fn(&JunitCase) -> bool| suite_cases.iter().filter(|case| pred(case)).count();
+            let failures = count(|case| case.errors.iter().any(|(is_failure, ..)| *is_failure));
+            let errors = count(|case| {
+                (!case.errors.is_empty() && case.errors.iter().all(|(is_failure, ..)| !is_failure)) || !case.done
+            });
+            let skipped = count(|case| case.skipped.is_some());
+            let time_ms: u64 = suite_cases.iter().map(|case| case.time_ms).sum();
+            xml.push_str(&format!(
+                "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+                xml_escape(suite),
+                suite_cases.len(),
+                failures,
+                errors,
+                skipped,
+                time_ms as f64 / 1000.0
+            ));
+            for case in suite_cases {
+                xml.push_str(&format!(
+                    "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">\n",
+                    xml_escape(suite),
+                    xml_escape(&case.name),
+                    case.time_ms as f64 / 1000.0
+                ));
+                for (is_failure, message, stack) in &case.errors {
+                    let tag = if *is_failure { "failure" } else { "error" };
+                    let first_line = message.lines().next().unwrap_or_default();
+                    xml.push_str(&format!(
+                        "      <{} message=\"{}\">{}\n{}</{}>\n",
+                        tag,
+                        xml_escape(first_line),
+                        xml_escape(message),
+                        xml_escape(stack),
+                        tag
+                    ));
+                }
+                if !case.done {
+                    xml.push_str("      <error message=\"Test did not complete\" />\n");
+                }
+                if case.skipped.is_some() {
+                    xml.push_str("      <skipped />\n");
+                }
+                if !case.output.is_empty() {
+                    xml.push_str(&format!("      <system-out>{}</system-out>\n", xml_escape(&case.output)));
+                }
+                xml.push_str("    </testcase>\n");
+            }
+            xml.push_str("  </testsuite>\n");
+        }
+        xml.push_str("</testsuites>\n");
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        fs::write(path, xml).with_context(|| format!("Failed to write JUnit report: {}", path.display()))
+    }
+}
+
+/// Escapes text for XML attributes and content, dropping control characters XML 1.0 forbids.
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' | '\r' | '\t' => out.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Max distinct entries listed per level in the end-of-run summary.
+const SUMMARY_LIMIT: usize = 20;
+
+/// `flutter run --machine` session state for --flutter-run-machine: turns daemon-protocol
+/// events into readable lines and keys into JSON-RPC requests for the running app.
+#[derive(Default)]
+struct FlutterRunMachine {
+    app_id: Option<String>,
+    next_id: u64,
+    /// Labels of requests awaiting a response, by request id.
+    pending: HashMap<u64, &'static str>,
+}
+
+impl FlutterRunMachine {
+    /// Readable lines for one stdout line; non-protocol output passes through unchanged.
+    fn render(&mut self, line: &str) -> Vec<String> {
+        let trimmed = line.trim();
+        if !(trimmed.starts_with("[{") && trimmed.ends_with("}]")) {
+            return vec![line.to_string()];
+        }
+        let Ok(messages) = serde_json::from_str::<Vec<serde_json::Value>>(trimmed) else {
+            return vec![line.to_string()];
+        };
+        messages
+            .iter()
+            .filter_map(|message| self.render_message(message))
+            .flat_map(|text| text.lines().map(str::to_string).collect::<Vec<_>>())
+            .collect()
+    }
+
+    fn render_message(&mut self, message: &serde_json::Value) -> Option<String> {
+        let params = &message["params"];
+        let Some(event) = message["event"].as_str() else {
+            // Response to one of our requests
+            let label = self.pending.remove(&message["id"].as_u64()?).unwrap_or("Request");
+            let result = &message["result"];
+            let failure = if !message["error"].is_null() {
+                Some(message["error"].to_string())
+            } else if result["code"].as_i64().unwrap_or(0) != 0 {
+                Some(result["message"].as_str().unwrap_or_default().to_string())
+            } else {
+                None
+            };
+            return Some(match failure {
+                Some(error) => format!("Error: {} failed: {}", label, error),
+                None => match result["message"].as_str().filter(|m| !m.is_empty()) {
+                    Some(detail) => format!("✓ {} done: {}", label, detail),
+                    None => format!("✓ {} done", label),
+                },
+            });
+        };
+        match event {
+            "app.start" => {
+                self.app_id = params["appId"].as_str().map(str::to_string);
+                Some(format!(
+                    "Launching on {} in {} mode...",
+                    params["deviceId"].as_str().unwrap_or("device"),
+                    params["mode"].as_str().unwrap_or("debug")
+                ))
+            }
+            "app.debugPort" => params["wsUri"].as_str().map(|uri| format!("Dart VM service: {}", uri)),
+            "app.webLaunchUrl" => params["url"].as_str().map(|url| format!("Web app: {}", url)),
+            "app.started" => Some("✓ App started (r: hot reload, R: hot restart, q: quit)".to_string()),
+            "app.progress" => params["message"].as_str().filter(|m| !m.is_empty()).map(str::to_string),
+            "app.log" => params["log"].as_str().map(str::to_string),
+            "daemon.logMessage" => params["message"].as_str().map(str::to_string),
+            "app.stop" => {
+                self.app_id = None;
+                Some(match params["error"].as_str() {
+                    Some(error) => format!("Error: App stopped: {}", error),
+                    None => "App stopped".to_string(),
+                })
+            }
+            // daemon.connected, device.* and other protocol bookkeeping
+            _ => None,
+        }
+    }
+
+    /// JSON-RPC request for a `flutter run` key (r, R or q).
+    fn request(&mut self, key: u8) -> std::result::Result<String, &'static str> {
+        let (method, label) = match key {
+            b'r' => ("app.restart", "Hot reload"),
+            b'R' => ("app.restart", "Hot restart"),
+            b'q' => ("app.stop", "Stop"),
+            _ => return Err("not supported with --flutter-run-machine"),
+        };
+        let app_id = self.app_id.clone().ok_or("the app has not started yet")?;
+        self.next_id += 1;
+        let mut params = serde_json::json!({ "appId": app_id });
+        if method == "app.restart" {
+            params["fullRestart"] = serde_json::json!(key == b'R');
+            params["pause"] = serde_json::json!(false);
+            params["reason"] = serde_json::json!("manual");
+        }
+        self.pending.insert(self.next_id, label);
+        Ok(format!("{}\n", serde_json::json!([{ "id": self.next_id, "method": method, "params": params }])))
+    }
+}
+
+/// What to write to the child for `input`: the bytes themselves, or with
+/// --flutter-run-machine the requests for its keys (whitespace is dropped).
+fn child_input(log_state: &Mutex<LogState>, input: &[u8]) -> std::result::Result<Vec<u8>, &'static str> {
+    let mut log = log_state.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(machine) = log.flutter_run.as_mut() else {
+        return Ok(input.to_vec());
+    };
+    let mut requests = String::new();
+    for &key in input.iter().filter(|key| !key.is_ascii_whitespace()) {
+        requests.push_str(&machine.request(key)?);
+    }
+    Ok(requests.into_bytes())
+}
+
+/// A distinct error/warning line and where it first appeared.
+struct Issue {
+    level: Level,
+    message: String,
+    count: usize,
+    first_line: usize,
+}
+
+/// Log file and issue summary shared by the stdout/stderr threads, so log line
+/// numbers stay correct while both streams write.
+struct LogState {
+    file: Option<File>,
+    log_format: LogFormat,
+    /// Lines written so far (log file lines when logging, output lines otherwise).
+    line_no: usize,
+    issues: Vec<Issue>,
+    index: HashMap<String, usize>,
+    /// Report paths/URLs found in the output or on disk, in discovery order.
+    reports: Vec<String>,
+    /// Start of the running command's entries in `reports`; each chained command lists
+    /// the reports it produced in its own footer, even ones an earlier command also wrote.
+    reports_from: usize,
+    /// Set with --flutter-test-junit; fed from stdout lines.
+    junit: Option<JunitReport>,
+    /// Set with --flutter-run-machine; renders stdout lines and translates input.
+    flutter_run: Option<FlutterRunMachine>,
+}
+
+impl LogState {
+    /// Writes header/footer text that isn't command output. It only reaches the log, so
+    /// it only moves `line_no` when logging.
+    fn write_raw(&mut self, text: &str) {
+        if let Some(ref mut file) = self.file {
+            self.line_no += text.split('\n').count();
+            let _ = writeln!(file, "{}", text);
+        }
+    }
+
+    /// Counts `lines` printed to the console only, which are output lines when not logging.
+    fn count_console_lines(&mut self, lines: usize) {
+        if self.file.is_none() {
+            self.line_no += lines;
+        }
+    }
+
+    fn write_line(&mut self, level: Level, text: &str, is_stderr: bool) {
+        self.line_no += 1;
+        if let Some(ref mut file) = self.file {
+            let _ = match self.log_format {
+                LogFormat::Text => writeln!(file, "{}", text),
+                LogFormat::Jsonl => writeln!(
+                    file,
+                    "{}",
+                    serde_json::json!({
+                        "time": Local::now().to_rfc3339(),
+                        "stream": if is_stderr { "stderr" } else { "stdout" },
+                        "level": level.as_str(),
+                        "message": text,
+                    })
+                ),
+            };
+        }
+        if let (false, Some(junit)) = (is_stderr, self.junit.as_mut()) {
+            junit.handle_line(text);
+        }
+        if let Some(report) = report_link(text) {
+            self.add_report(report);
+        }
+        if matches!(level, Level::Error | Level::Warning) {
+            let key = text.trim().to_string();
+            match self.index.get(&key) {
+                Some(&i) => self.issues[i].count += 1,
+                None => {
+                    self.index.insert(key.clone(), self.issues.len());
+                    self.issues.push(Issue {
+                        level,
+                        message: key,
+                        count: 1,
+                        first_line: self.line_no,
+                    });
+                }
+            }
+        }
+    }
+
+    fn add_report(&mut self, report: String) {
+        if !self.reports[self.reports_from..].iter().any(|r| Path::new(r) == Path::new(&report)) {
+            self.reports.push(report);
+        }
+    }
+
+    fn print_reports(&self) {
+        if self.reports.is_empty() {
+            return;
+        }
+        println!("\nReports:");
+        for (i, report) in self.reports.iter().enumerate() {
+            if !self.reports[..i].iter().any(|r| Path::new(r) == Path::new(report)) {
+                println!("  {}", report);
+            }
+        }
+    }
+
+    fn print_summary(&self, log_path: Option<&PathBuf>, color: bool) {
+        if self.issues.is_empty() {
+            return;
+        }
+        let count = |level: Level| self.issues.iter().filter(|i| i.level == level).count();
+        println!(
+            "\n=== Summary: {} distinct error(s), {} distinct warning(s) ===",
+            count(Level::Error),
+            count(Level::Warning)
+        );
+        for level in [Level::Error, Level::Warning] {
+            let issues: Vec<&Issue> = self.issues.iter().filter(|i| i.level == level).collect();
+            for issue in issues.iter().take(SUMMARY_LIMIT) {
+                let location = match log_path {
+                    Some(path) => format!("{}:{}", path.display(), issue.first_line),
+                    None => format!("line {}", issue.first_line),
+                };
+                let label = match level.color() {
+                    Some(code) if color => format!("{}{:<7}\x1b[0m", code, level.as_str()),
+                    _ => format!("{:<7}", level.as_str()),
+                };
+                println!("  {} x{:<4} {}  {}", label, issue.count, location, issue.message);
+            }
+            if issues.len() > SUMMARY_LIMIT {
+                println!("  ... and {} more {}(s)", issues.len() - SUMMARY_LIMIT, level.as_str());
+            }
+        }
+    }
+}
+
+/// Per-stream output settings shared by the stdout/stderr threads.
+#[derive(Clone, Copy)]
+struct OutputOptions {
+    encoding: OutputEncoding,
+    /// Colors wanted (no --no-color/NO_COLOR); each stream still needs to be a terminal.
+    color: bool,
+}
+
+/// How child output bytes are decoded before printing/logging.
+#[derive(Clone, Copy)]
+enum OutputEncoding {
+    /// Keep valid UTF-8, decode other lines with the given fallback.
+    Auto(&'static Encoding),
+    /// Always decode with the given encoding.
+    Fixed(&'static Encoding),
+}
+
+impl OutputEncoding {
+    fn from_label(label: &str) -> Result<Self> {
+        if label.eq_ignore_ascii_case("auto") {
+            return Ok(OutputEncoding::Auto(system_encoding()));
+        }
+        Encoding::for_label(label.as_bytes())
+            .map(OutputEncoding::Fixed)
+            .ok_or_else(|| anyhow::anyhow!("Unknown encoding: {}", label))
+    }
+
+    fn decode(self, bytes: &[u8]) -> String {
+        let encoding = match self {
+            OutputEncoding::Auto(fallback) => match std::str::from_utf8(bytes) {
+                Ok(text) => return text.to_string(),
+                Err(_) => fallback,
+            },
+            OutputEncoding::Fixed(encoding) => encoding,
+        };
+        encoding.decode_without_bom_handling(bytes).0.into_owned()
+    }
+}
+
+/// Maps a Windows code page to its encoding_rs equivalent.
+#[cfg(windows)]
+fn codepage_encoding(codepage: u32) -> Option<&'static Encoding> {
+    let label = match codepage {
+        65001 => "utf-8",
+        936 => "gbk",
+        54936 => "gb18030",
+        950 => "big5",
+        932 => "shift_jis",
+        949 => "euc-kr",
+        866 => "ibm866",
+        874 => "windows-874",
+        1250..=1258 => return Encoding::for_label(format!("windows-{}", codepage).as_bytes()),
+        _ => return None,
+    };
+    Encoding::for_label(label.as_bytes())
+}
+
+/// Encoding used by non-UTF-8 child output: the ANSI code page on Windows (what
+/// Java/Gradle write to pipes), UTF-8 elsewhere.
+fn system_encoding() -> &'static Encoding {
+    #[cfg(windows)]
+    {
+        let codepage = unsafe { windows_sys::Win32::Globalization::GetACP() };
+        if let Some(encoding) = codepage_encoding(codepage) {
+            return encoding;
+        }
+    }
+    encoding_rs::UTF_8
+}
+
+/// Console/log sink for one child stream.
+///
+/// Progress bars redraw a line with `\r`; those intermediate states are redrawn in
+/// place on the console and only the final state of the line is logged, so a
+/// download bar becomes one log line instead of thousands (or none).
+struct OutputSink {
+    options: OutputOptions,
+    log: Arc<Mutex<LogState>>,
+    is_stderr: bool,
+    /// Colorize lines: colors wanted and this sink's stream is a terminal.
+    color: bool,
+    /// Width of the progress text currently drawn on the console, if any.
+    progress_width: Option<usize>,
+}
+
+impl OutputSink {
+    fn write_console(&self, text: &str) {
+        if self.is_stderr {
+            let mut err = std::io::stderr().lock();
+            let _ = err.write_all(text.as_bytes());
+            let _ = err.flush();
+        } else {
+            let mut out = std::io::stdout().lock();
+            let _ = out.write_all(text.as_bytes());
+            let _ = out.flush();
+        }
+    }
+
+    /// Redraws `text` over the current progress line, padding out leftovers.
+    fn redraw(&self, text: &str) -> String {
+        let width = text.chars().count();
+        let pad = self.progress_width.unwrap_or(0).saturating_sub(width);
+        format!("\r{}{}", text, " ".repeat(pad))
+    }
+
+    fn progress(&mut self, bytes: &[u8]) {
+        let text = self.options.encoding.decode(bytes);
+        let drawn = self.redraw(&text);
+        self.write_console(&drawn);
+        self.progress_width = Some(text.chars().count());
+    }
+
+    fn line(&mut self, bytes: &[u8]) {
+        let text = self.options.encoding.decode(bytes);
+        if !self.is_stderr {
+            let rendered = {
+                let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+                log.flutter_run.as_mut().map(|machine| machine.render(&text))
+            };
+            if let Some(lines) = rendered {
+                for line in lines {
+                    self.emit(line);
+                }
+                return;
+            }
+        }
+        self.emit(text);
+    }
+
+    fn emit(&mut self, text: String) {
+        let level = classify(&text);
+        let drawn = if self.progress_width.is_some() {
+            self.redraw(&text)
+        } else {
+            text.clone()
+        };
+        let drawn = match level.color() {
+            Some(color) if self.color => format!("{}{}\x1b[0m", color, drawn),
+            _ => drawn,
+        };
+        self.write_console(&format!("{}\n", drawn));
+        self.progress_width = None;
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        log.write_line(level, &text, self.is_stderr);
+    }
+}
+
+/// Forwards child output, decoded to UTF-8, to the console and the log, merging
+/// `\r` progress updates (a `\r\n` pair is still a plain line ending).
+fn pump_output(mut source: impl Read, options: OutputOptions, log: Arc<Mutex<LogState>>, is_stderr: bool) {
+    let is_terminal = if is_stderr { std::io::stderr().is_terminal() } else { std::io::stdout().is_terminal() };
+    let mut sink = OutputSink {
+        options,
+        log,
+        is_stderr,
+        color: options.color && is_terminal,
+        progress_width: None,
+    };
+    let mut chunk = [0u8; 4096];
+    let mut current = Vec::new();
+    let mut pending_cr = false;
+    loop {
+        let n = match source.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        for &byte in &chunk[..n] {
+            match byte {
+                b'\n' => {
+                    sink.line(&current);
+                    current.clear();
+                    pending_cr = false;
+                }
+                b'\r' => pending_cr = true,
+                _ => {
+                    if pending_cr {
+                        sink.progress(&current);
+                        current.clear();
+                        pending_cr = false;
+                    }
+                    current.push(byte);
+                }
+            }
+        }
+    }
+    if !current.is_empty() || sink.progress_width.is_some() {
+        sink.line(&current);
+    }
+}
+
+/// Separates commands in multi-command mode.
+const THEN_SEPARATOR: &str = "--then";
+
+/// Identifies one cmd-run invocation; exported to children so wrapped scripts can
+/// find their log and tag artifacts with the session.
+struct Session {
+    id: String,
+    start_ts: String,
+    log_path: Option<PathBuf>,
+}
+
+impl Session {
+    fn new(log_path: Option<PathBuf>) -> Self {
+        let now = Local::now();
+        Session {
+            id: format!("{}-{}", now.format("%Y%m%d-%H%M%S"), std::process::id()),
+            start_ts: now.to_rfc3339(),
+            log_path,
+        }
+    }
+
+    fn apply_env(&self, command: &mut Command) {
+        command
+            .env("CMD_RUN_SESSION_ID", &self.id)
+            .env("CMD_RUN_START_TS", &self.start_ts);
+        // Don't let a nested cmd-run's children see the outer session's log
+        match &self.log_path {
+            Some(path) => command.env("CMD_RUN_LOG", path),
+            None => command.env_remove("CMD_RUN_LOG"),
+        };
+    }
+}
+
+/// One command of the chain, with its own working directory.
+struct CommandSpec {
+    name: String,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+}
+
+impl CommandSpec {
+    fn display(&self) -> String {
+        format!("{} {}", self.name, self.args.join(" ")).trim_end().to_string()
+    }
+
+    /// Working directory for the child, resolved against the invocation directory.
+    fn work_dir(&self, invocation_dir: &Path) -> PathBuf {
+        match &self.cwd {
+            Some(cwd) => invocation_dir.join(cwd),
+            None => invocation_dir.to_path_buf(),
+        }
+    }
+
+    /// Resolves the program: bare names via PATH, relative paths (e.g. `./gradlew`)
+    /// against the command's own working directory.
+    fn resolve_program(&self, invocation_dir: &Path) -> Result<PathBuf> {
+        if self.name.contains(['/', '\\']) {
+            let path = PathBuf::from(&self.name);
+            Ok(if path.is_absolute() { path } else { self.work_dir(invocation_dir).join(path) })
+        } else {
+            which(&self.name).with_context(|| format!("Command not found in PATH: {}", self.name))
+        }
+    }
+}
+
+/// Runs one command to completion, streaming its output to the console and the log.
+/// Returns the exit code.
+fn run_command(
+    spec: &CommandSpec,
+    session: &Session,
+    invocation_dir: &Path,
+    output_options: OutputOptions,
+    log_state: &Arc<Mutex<LogState>>,
+    child_stdin: &Arc<Mutex<Option<ChildStdin>>>,
+) -> Result<i32> {
+    let program = spec.resolve_program(invocation_dir)?;
+    let work_dir = spec.work_dir(invocation_dir);
+    if !work_dir.is_dir() {
+        anyhow::bail!("Working directory not found: {}", work_dir.display());
+    }
+
+    let started = SystemTime::now();
+    let reports_from = {
+        let mut log = log_state.lock().unwrap_or_else(|e| e.into_inner());
+        let timestamp = Local::now().to_rfc3339();
+        match log.log_format {
+            LogFormat::Text => log.write_raw(&format!(
+                "=== Command Log ===\nSession: {}\nTimestamp: {}\nCommand: {}\nWorking Directory: {}\n===================\n",
+                session.id,
+                timestamp,
+                spec.display(),
+                work_dir.display()
+            )),
+            LogFormat::Jsonl => log.write_raw(
+                &serde_json::json!({
+                    "time": timestamp,
+                    "event": "start",
+                    "session": session.id,
+                    "command": spec.display(),
+                    "cwd": work_dir.display().to_string(),
+                })
+                .to_string(),
+            ),
+        }
+        log.reports_from = log.reports.len();
+        log.reports_from
+    };
+
+    // Spawn command process with piped stdin for graceful Ctrl-C handling
+    let mut command = Command::new(&program);
+    session.apply_env(&mut command);
+    let mut child = command
+        .args(&spec.args)
+        .current_dir(&work_dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start command: {}", spec.name))?;
+
+    // Hand the child's stdin to the Ctrl-C handler and the stdin forwarding thread
+    *child_stdin.lock().unwrap_or_else(|e| e.into_inner()) = child.stdin.take();
+
+    // Get stdout and stderr handles
+    let stdout = child.stdout.take().expect("Failed to capture stdout");
+    let stderr = child.stderr.take().expect("Failed to capture stderr");
+
+    // Create threads to handle output
+    let log_for_stdout = Arc::clone(log_state);
+    let stdout_thread = std::thread::spawn(move || {
+        pump_output(stdout, output_options, log_for_stdout, false);
+    });
+
+    let log_for_stderr = Arc::clone(log_state);
+    let stderr_thread = std::thread::spawn(move || {
+        pump_output(stderr, output_options, log_for_stderr, true);
+    });
+
+    // Wait for process to complete first — this closes stdout/stderr pipes
+    let status = child.wait().with_context(|| format!("Failed to wait for command: {}", spec.name))?;
+    let exit_code = status.code().unwrap_or(1);
+    *child_stdin.lock().unwrap_or_else(|e| e.into_inner()) = None;
+
+    // Now output threads can finish (pipes are closed)
+    stdout_thread.join().expect("stdout thread panicked");
+    stderr_thread.join().expect("stderr thread panicked");
+
+    // Write log footer
+    let mut log = log_state.lock().unwrap_or_else(|e| e.into_inner());
+    // Gradle doesn't always print the problems report link; pick up one written by this
+    // command in the working dir or, for `flutter build`, its android/ project.
+    for root in [work_dir.clone(), work_dir.join("android")] {
+        let report = root.join(PROBLEMS_REPORT);
+        let fresh = fs::metadata(&report)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified >= started);
+        if fresh {
+            log.add_report(report.display().to_string());
+        }
+    }
+    let reports = log.reports[reports_from..].to_vec();
+    match log.log_format {
+        LogFormat::Text => {
+            let mut footer = String::from("\n===================");
+            if !reports.is_empty() {
+                footer.push_str("\nReports:");
+                for report in &reports {
+                    footer.push_str(&format!("\n  {}", report));
+                }
+            }
+            footer.push_str(&format!(
+                "\nExit code: {}\nFinished at: {}\n",
+                exit_code,
+                Local::now().to_rfc3339()
+            ));
+            log.write_raw(&footer);
+        }
+        LogFormat::Jsonl => log.write_raw(
+            &serde_json::json!({
+                "time": Local::now().to_rfc3339(),
+                "event": "exit",
+                "exit_code": exit_code,
+                "reports": reports,
+            })
+            .to_string(),
+        ),
+    }
+    Ok(exit_code)
+}
+
+/// Upper bound for the doubling --supervise backoff.
+const MAX_RESTART_BACKOFF_SECS: f64 = 60.0;
+
+/// Reports a --supervise restart on the console and in the log.
+fn log_restart(
+    log_state: &Arc<Mutex<LogState>>,
+    spec: &CommandSpec,
+    exit_code: i32,
+    attempt: u32,
+    max_restarts: u32,
+    delay: f64,
+) {
+    let message = format!(
+        "Command `{}` exited with code {}; restart {}/{} in {:.1}s",
+        spec.display(),
+        exit_code,
+        attempt,
+        max_restarts,
+        delay
+    );
+    eprintln!("\n>>> {}", message);
+    let mut log = log_state.lock().unwrap_or_else(|e| e.into_inner());
+    log.count_console_lines(2);
+    match log.log_format {
+        LogFormat::Text => log.write_raw(&format!(">>> {}\n", message)),
+        LogFormat::Jsonl => log.write_raw(
+            &serde_json::json!({
+                "time": Local::now().to_rfc3339(),
+                "event": "restart",
+                "command": spec.display(),
+                "exit_code": exit_code,
+                "attempt": attempt,
+                "max_restarts": max_restarts,
+                "delay_secs": delay,
+            })
+            .to_string(),
+        ),
+    }
+}
+
+/// Default control file written by `daemon` and read by `send`.
+const DEFAULT_CONTROL_FILE: &str = ".cmd-run-daemon.json";
+
+/// Maps a daemon command to the `flutter run` key it sends.
+fn daemon_key(command: &str) -> Option<&'static [u8]> {
+    match command {
+        "reload" => Some(b"r"),
+        "restart" => Some(b"R"),
+        "screenshot" => Some(b"s"),
+        "quit" => Some(b"q"),
+        _ => None,
+    }
+}
+
+/// Token from the OS random source, so other local users can't drive the session via the open port.
+fn daemon_token() -> Result<String> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).map_err(|e| anyhow::anyhow!("Failed to generate daemon token: {}", e))?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// How long a control client may take to send its command line.
+const DAEMON_CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The daemon's control file, removed when dropped so no error path leaves a stale token behind.
+struct ControlFile(PathBuf);
+
+impl Drop for ControlFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Creates the control file readable by the owner only; it is replaced rather than reused,
+/// so an existing file's looser permissions never apply to the new token.
+fn create_control_file(path: &Path) -> std::io::Result<File> {
+    let _ = fs::remove_file(path);
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+/// Binds the control port, writes the control file and serves commands on a background thread.
+fn start_daemon_listener(
+    control_path: &Path,
+    session: &Session,
+    child_stdin: Arc<Mutex<Option<ChildStdin>>>,
+    log_state: Arc<Mutex<LogState>>,
+) -> Result<ControlFile> {
+    let listener = TcpListener::bind("127.0.0.1:0").context("Failed to bind daemon control port")?;
+    let port = listener.local_addr()?.port();
+    let token = daemon_token()?;
+    let control = serde_json::json!({
+        "port": port,
+        "token": token,
+        "pid": std::process::id(),
+        "session": session.id,
+    });
+    if let Some(parent) = control_path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let mut file = create_control_file(control_path)
+        .with_context(|| format!("Failed to write control file: {}", control_path.display()))?;
+    let control_file = ControlFile(control_path.to_path_buf());
+    file.write_all(serde_json::to_string_pretty(&control)?.as_bytes())
+        .with_context(|| format!("Failed to write control file: {}", control_path.display()))?;
+    println!("Daemon listening on 127.0.0.1:{} (control file: {})\n", port, control_path.display());
+
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let _ = handle_daemon_client(stream, &token, &child_stdin, &log_state);
+        }
+    });
+    Ok(control_file)
+}
+
+fn handle_daemon_client(
+    stream: TcpStream,
+    token: &str,
+    child_stdin: &Arc<Mutex<Option<ChildStdin>>>,
+    log_state: &Mutex<LogState>,
+) -> std::io::Result<()> {
+    // A client that connects and stays silent would otherwise block every later one
+    stream.set_read_timeout(Some(DAEMON_CLIENT_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(&stream).take(4096).read_line(&mut line)?;
+    let mut writer = &stream;
+    let (client_token, command) = line.trim().split_once(' ').unwrap_or(("", ""));
+    if client_token != token {
+        return writeln!(writer, "error: invalid token");
+    }
+    let Some(key) = daemon_key(command) else {
+        return writeln!(writer, "error: unknown command: {}", command);
+    };
+    let input = match child_input(log_state, key) {
+        Ok(input) => input,
+        Err(reason) => return writeln!(writer, "error: {}: {}", command, reason),
+    };
+    let mut guard = child_stdin.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(ref mut stdin) = *guard else {
+        return writeln!(writer, "error: no command is running");
+    };
+    if stdin.write_all(&input).and_then(|_| stdin.flush()).is_err() {
+        return writeln!(writer, "error: failed to write to the command's stdin");
+    }
+    writeln!(writer, "ok")
+}
+
+/// `cmd-run send`: forwards one command to a running daemon.
+fn send_to_daemon(args: &[String]) -> Result<()> {
+    let mut control_path = PathBuf::from(DEFAULT_CONTROL_FILE);
+    let mut command: Option<&str> = None;
+    let mut i = 0;
+    while i < args.len() {
+        if let Some(path) = args[i].strip_prefix("--control=") {
+            control_path = PathBuf::from(path);
+        } else if args[i] == "--control" && i + 1 < args.len() {
+            control_path = PathBuf::from(&args[i + 1]);
+            i += 1; // Skip next argument
+        } else {
+            command = Some(&args[i]);
+        }
+        i += 1;
+    }
+    let command = command.ok_or_else(|| {
+        anyhow::anyhow!("Usage: cmd-run send [--control=FILE] <reload|restart|screenshot|quit>")
+    })?;
+    if daemon_key(command).is_none() {
+        anyhow::bail!("Unknown daemon command: {} (expected reload, restart, screenshot or quit)", command);
+    }
+
+    let content = fs::read_to_string(&control_path).with_context(|| {
+        format!("Failed to read control file (is the daemon running?): {}", control_path.display())
+    })?;
+    let control: serde_json::Value = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid control file: {}", control_path.display()))?;
+    let port = control["port"]
+        .as_u64()
+        .ok_or_else(|| anyhow::anyhow!("Missing port in control file: {}", control_path.display()))?;
+    let token = control["token"].as_str().unwrap_or_default();
+
+    let mut stream = TcpStream::connect(("127.0.0.1", port as u16))
+        .with_context(|| format!("Failed to connect to daemon on port {}", port))?;
+    writeln!(stream, "{} {}", token, command)?;
+    let mut response = String::new();
+    BufReader::new(&stream).read_line(&mut response)?;
+    let response = response.trim();
+    if response != "ok" {
+        anyhow::bail!("Daemon rejected `{}`: {}", command, response);
+    }
+    println!("✓ Sent {}", command);
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("send") {
+        return send_to_daemon(&args[2..]);
+    }
+    let daemon = args.get(1).map(String::as_str) == Some("daemon");
+    let mut control_path = PathBuf::from(DEFAULT_CONTROL_FILE);
 
     let mut log_path: Option<PathBuf> = None;
-    let mut working_dir: Option<PathBuf> = None;
+    let mut default_cwd: Option<PathBuf> = None;
+    let mut commands: Vec<CommandSpec> = Vec::new();
     let mut cmd_args: Vec<String> = Vec::new();
     let mut command_name: Option<String> = None;
+    let mut command_cwd: Option<PathBuf> = None;
+    let mut encoding_label = String::from("auto");
+    let mut log_format = LogFormat::Text;
+    let mut color = std::env::var_os("NO_COLOR").is_none();
+    let mut junit_path: Option<PathBuf> = None;
+    let mut flutter_run_machine = false;
+    let mut supervise = false;
+    let mut max_restarts: u32 = 5;
+    let mut restart_backoff: f64 = 2.0;
 
-    let mut i = 1; // Skip program name
+    let mut i = if daemon { 2 } else { 1 }; // Skip program name (and `daemon`)
     while i < args.len() {
         if args[i].starts_with("--log=") {
             let path_str = args[i].strip_prefix("--log=").unwrap();
@@ -89,10 +1301,69 @@ fn main() -> Result<()> {
             i += 1; // Skip next argument
         } else if args[i].starts_with("--cwd=") {
             let path_str = args[i].strip_prefix("--cwd=").unwrap();
-            working_dir = Some(PathBuf::from(path_str));
+            command_cwd = Some(PathBuf::from(path_str));
         } else if args[i] == "--cwd" && i + 1 < args.len() {
-            working_dir = Some(PathBuf::from(&args[i + 1]));
+            command_cwd = Some(PathBuf::from(&args[i + 1]));
+            i += 1; // Skip next argument
+        } else if args[i] == THEN_SEPARATOR {
+            // Close the current command; the first one's --cwd is the default for the rest
+            let Some(name) = command_name.take() else {
+                anyhow::bail!("Missing command before {}", THEN_SEPARATOR);
+            };
+            if commands.is_empty() {
+                default_cwd = command_cwd.clone();
+            }
+            commands.push(CommandSpec {
+                name,
+                args: std::mem::take(&mut cmd_args),
+                cwd: command_cwd.take().or_else(|| default_cwd.clone()),
+            });
+        } else if commands.is_empty() && command_name.is_none() && args[i].starts_with("--encoding=") {
+            encoding_label = args[i].strip_prefix("--encoding=").unwrap().to_string();
+        } else if commands.is_empty() && command_name.is_none() && args[i] == "--encoding" && i + 1 < args.len() {
+            encoding_label = args[i + 1].clone();
+            i += 1; // Skip next argument
+        } else if commands.is_empty() && command_name.is_none() && args[i].starts_with("--log-format=") {
+            log_format = match args[i].strip_prefix("--log-format=").unwrap() {
+                "text" => LogFormat::Text,
+                "jsonl" => LogFormat::Jsonl,
+                other => anyhow::bail!("Unknown log format: {} (expected text or jsonl)", other),
+            };
+        } else if commands.is_empty() && command_name.is_none() && args[i] == "--log-format" && i + 1 < args.len() {
+            log_format = match args[i + 1].as_str() {
+                "text" => LogFormat::Text,
+                "jsonl" => LogFormat::Jsonl,
+                other => anyhow::bail!("Unknown log format: {} (expected text or jsonl)", other),
+            };
+            i += 1; // Skip next argument
+        } else if daemon && commands.is_empty() && command_name.is_none() && args[i].starts_with("--control=") {
+            control_path = PathBuf::from(args[i].strip_prefix("--control=").unwrap());
+        } else if daemon && commands.is_empty() && command_name.is_none() && args[i] == "--control" && i + 1 < args.len() {
+            control_path = PathBuf::from(&args[i + 1]);
             i += 1; // Skip next argument
+        } else if commands.is_empty() && command_name.is_none() && args[i] == "--no-color" {
+            color = false;
+        } else if commands.is_empty() && command_name.is_none() && args[i].starts_with("--flutter-test-junit=") {
+            junit_path = Some(PathBuf::from(args[i].strip_prefix("--flutter-test-junit=").unwrap()));
+        } else if commands.is_empty() && command_name.is_none() && args[i] == "--flutter-test-junit" && i + 1 < args.len() {
+            junit_path = Some(PathBuf::from(&args[i + 1]));
+            i += 1; // Skip next argument
+        } else if commands.is_empty() && command_name.is_none() && args[i] == "--flutter-run-machine" {
+            flutter_run_machine = true;
+        } else if commands.is_empty() && command_name.is_none() && args[i] == "--supervise" {
+            supervise = true;
+        } else if commands.is_empty() && command_name.is_none() && args[i].starts_with("--max-restarts=") {
+            let value = args[i].strip_prefix("--max-restarts=").unwrap();
+            max_restarts = value
+                .parse()
+                .with_context(|| format!("Invalid --max-restarts: {}", value))?;
+        } else if commands.is_empty() && command_name.is_none() && args[i].starts_with("--restart-backoff=") {
+            let value = args[i].strip_prefix("--restart-backoff=").unwrap();
+            restart_backoff = value
+                .parse()
+                .ok()
+                .filter(|secs: &f64| secs.is_finite() && *secs > 0.0)
+                .ok_or_else(|| anyhow::anyhow!("Invalid --restart-backoff: {} (expected a positive number of seconds)", value))?;
         } else {
             // First non-log argument is the command
             if command_name.is_none() {
@@ -105,89 +1376,111 @@ fn main() -> Result<()> {
     }
 
     let command_name = command_name.ok_or_else(|| {
-        anyhow::anyhow!("Usage: cmd-run [--log=FILE] [--cwd=DIR] <command> [args...]\nExample: cmd-run --log=build.log --cwd=flutter flutter build apk --release")
+        anyhow::anyhow!("Usage: cmd-run [--log=FILE] [--cwd=DIR] [--encoding=auto|utf-8|gbk|...] [--log-format=text|jsonl] [--no-color] [--flutter-test-junit=FILE] [--flutter-run-machine] [--supervise [--max-restarts=N] [--restart-backoff=SECS]] <command> [args...] [--then [--cwd=DIR] <command> [args...]]...\nExample: cmd-run --log=build.log --cwd=flutter flutter build apk --release")
     })?;
+    commands.push(CommandSpec {
+        name: command_name,
+        args: cmd_args,
+        cwd: command_cwd.or(default_cwd),
+    });
 
-    // Resolve command path
-    let resolved_command = if command_name.contains(['/', '\\']) {
-        PathBuf::from(&command_name)
-    } else {
-        which(&command_name)
-            .with_context(|| format!("Command not found in PATH: {}", command_name))?
+    let output_options = OutputOptions {
+        encoding: OutputEncoding::from_label(&encoding_label)?,
+        color,
     };
+    #[cfg(windows)]
+    if output_options.color && (std::io::stdout().is_terminal() || std::io::stderr().is_terminal()) {
+        enable_vt_mode();
+    }
 
-    // Change to working directory if specified
-    if let Some(ref cwd) = working_dir {
-        std::env::set_current_dir(cwd)
-            .with_context(|| format!("Failed to change directory to: {}", cwd.display()))?;
+    // Resolve all commands up front so a typo in a later one fails before anything runs
+    let invocation_dir = std::env::current_dir().context("Failed to read current directory")?;
+    for spec in &commands {
+        spec.resolve_program(&invocation_dir)?;
     }
 
-    // Resolve log file path
+    // Log paths stay relative to where cmd-run was invoked, not to any --cwd
     let log_path = log_path.map(|p| {
         if p.is_absolute() {
             p
         } else {
-            std::env::current_dir().unwrap().join(p)
+            invocation_dir.join(p)
         }
     });
+    let junit_path = junit_path.map(|p| invocation_dir.join(p));
+    if junit_path.is_some() && !commands.iter().any(|spec| spec.args.iter().any(|a| a == "--machine")) {
+        eprintln!("Warning: --flutter-test-junit expects `flutter test --machine` output");
+    }
+    if flutter_run_machine && !commands.iter().any(|spec| spec.args.iter().any(|a| a == "--machine")) {
+        eprintln!("Warning: --flutter-run-machine expects `flutter run --machine` output");
+    }
 
-    let mut log_file_handle = if let Some(ref path) = log_path {
+    let log_file_handle = if let Some(ref path) = log_path {
         // Create log directory if needed
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)
                 .with_context(|| format!("Failed to create log directory: {}", parent.display()))?;
         }
 
-        let mut file = File::create(path)
+        let file = File::create(path)
             .with_context(|| format!("Failed to create log file: {}", path.display()))?;
 
         println!("Logging to: {}\n", path.display());
-
-        // Write log header
-        let timestamp = Local::now().to_rfc3339();
-        let cwd = std::env::current_dir().unwrap();
-        writeln!(file, "=== Command Log ===")?;
-        writeln!(file, "Timestamp: {}", timestamp)?;
-        writeln!(file, "Command: {} {}", command_name, cmd_args.join(" "))?;
-        writeln!(file, "Working Directory: {}", cwd.display())?;
-        writeln!(file, "===================\n")?;
-
         Some(file)
     } else {
         None
     };
 
+    let session = Session::new(log_path.clone());
+
+    let log_state = Arc::new(Mutex::new(LogState {
+        file: log_file_handle,
+        log_format,
+        line_no: 0,
+        issues: Vec::new(),
+        index: HashMap::new(),
+        reports: Vec::new(),
+        reports_from: 0,
+        junit: junit_path.as_ref().map(|_| JunitReport::default()),
+        flutter_run: flutter_run_machine.then(FlutterRunMachine::default),
+    }));
+
     // Enable raw mode so each keypress is available immediately (for r, R, q, etc.)
     #[cfg(windows)]
     let original_console_mode = enable_raw_mode();
 
-    // Spawn command process with piped stdin for graceful Ctrl-C handling
-    let mut child = Command::new(&resolved_command)
-        .args(&cmd_args)
-        .stdin(Stdio::piped())
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .with_context(|| format!("Failed to start command: {}", command_name))?;
+    // Stdin of the running child; None between commands
+    let child_stdin: Arc<Mutex<Option<ChildStdin>>> = Arc::new(Mutex::new(None));
+    let interrupted = Arc::new(AtomicBool::new(false));
 
-    // Take stdin handle, wrap in Arc<Mutex> so the Ctrl-C handler can access it
-    let child_stdin = Arc::new(Mutex::new(Some(child.stdin.take().expect("Failed to get stdin"))));
-    let child_stdin_for_ctrlc = Arc::clone(&child_stdin);
+    let control_path = invocation_dir.join(control_path);
+    let control_file = if daemon {
+        Some(start_daemon_listener(&control_path, &session, Arc::clone(&child_stdin), Arc::clone(&log_state))?)
+    } else {
+        None
+    };
 
-    // Set up Ctrl-C handler: send 'q' to child for graceful shutdown
+    // Set up Ctrl-C handler: send 'q' to child for graceful shutdown and skip the remaining commands
+    let child_stdin_for_ctrlc = Arc::clone(&child_stdin);
+    let interrupted_for_ctrlc = Arc::clone(&interrupted);
+    let log_for_ctrlc = Arc::clone(&log_state);
     ctrlc::set_handler(move || {
+        interrupted_for_ctrlc.store(true, Ordering::SeqCst);
+        let quit = child_input(&log_for_ctrlc, b"q\n").unwrap_or_default();
         if let Ok(mut guard) = child_stdin_for_ctrlc.lock() {
             if let Some(ref mut stdin) = *guard {
-                let _ = stdin.write_all(b"q\n");
+                let _ = stdin.write_all(&quit);
                 let _ = stdin.flush();
             }
-            // Drop the child stdin to signal EOF, so stdin_thread stops too
+            // Drop the child stdin to signal EOF
             *guard = None;
         }
     }).with_context(|| "Failed to set Ctrl-C handler")?;
 
     // Thread to forward parent stdin to child stdin (byte by byte for responsiveness)
     let child_stdin_for_fwd = Arc::clone(&child_stdin);
+    let interrupted_for_fwd = Arc::clone(&interrupted);
+    let log_for_fwd = Arc::clone(&log_state);
     let _stdin_thread = std::thread::spawn(move || {
         let stdin = std::io::stdin();
         let mut buf = [0u8; 1];
@@ -195,14 +1488,21 @@ fn main() -> Result<()> {
             match stdin.lock().read(&mut buf) {
                 Ok(0) => break, // EOF
                 Ok(1) => {
+                    if interrupted_for_fwd.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    // Keys without a machine-mode request are dropped
+                    let Ok(input) = child_input(&log_for_fwd, &buf) else {
+                        continue;
+                    };
                     if let Ok(mut guard) = child_stdin_for_fwd.lock() {
+                        // Input typed between commands is dropped
                         if let Some(ref mut child_in) = *guard {
-                            if child_in.write_all(&buf).is_err() {
-                                break;
+                            if child_in.write_all(&input).is_err() {
+                                *guard = None;
+                                continue;
                             }
                             let _ = child_in.flush();
-                        } else {
-                            break; // stdin was dropped by Ctrl-C handler
                         }
                     }
                 }
@@ -211,48 +1511,38 @@ fn main() -> Result<()> {
         }
     });
 
-    // Get stdout and stderr handles
-    let stdout = child.stdout.take().expect("Failed to capture stdout");
-    let stderr = child.stderr.take().expect("Failed to capture stderr");
-
-    // Create threads to handle output
-    let log_path_clone = log_path.clone();
-    let stdout_thread = std::thread::spawn(move || {
-        let reader = BufReader::new(stdout);
-        let mut log_file = log_path_clone.and_then(|p| File::options().append(true).open(p).ok());
-
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                println!("{}", line);
-                if let Some(ref mut file) = log_file {
-                    let _ = writeln!(file, "{}", line);
-                }
-            }
+    let mut exit_code = 0;
+    let mut failed: Option<&CommandSpec> = None;
+    for (index, spec) in commands.iter().enumerate() {
+        if interrupted.load(Ordering::SeqCst) {
+            break;
         }
-    });
-
-    let log_path_clone2 = log_path.clone();
-    let stderr_thread = std::thread::spawn(move || {
-        let reader = BufReader::new(stderr);
-        let mut log_file = log_path_clone2.and_then(|p| File::options().append(true).open(p).ok());
-
-        for line in reader.lines() {
-            if let Ok(line) = line {
-                eprintln!("{}", line);
-                if let Some(ref mut file) = log_file {
-                    let _ = writeln!(file, "{}", line);
-                }
+        if commands.len() > 1 {
+            println!("\n>>> [{}/{}] {}", index + 1, commands.len(), spec.display());
+        }
+        let mut restarts = 0;
+        loop {
+            exit_code = run_command(spec, &session, &invocation_dir, output_options, &log_state, &child_stdin)?;
+            if exit_code == 0 || !supervise || restarts >= max_restarts || interrupted.load(Ordering::SeqCst) {
+                break;
+            }
+            restarts += 1;
+            let delay = (restart_backoff * 2f64.powi(restarts as i32 - 1)).min(MAX_RESTART_BACKOFF_SECS);
+            log_restart(&log_state, spec, exit_code, restarts, max_restarts, delay);
+            // Sleep in small steps so Ctrl-C during the backoff stops the chain promptly
+            let deadline = Instant::now() + Duration::from_secs_f64(delay);
+            while Instant::now() < deadline && !interrupted.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            if interrupted.load(Ordering::SeqCst) {
+                break;
             }
         }
-    });
-
-    // Wait for process to complete first — this closes stdout/stderr pipes
-    let status = child.wait().with_context(|| format!("Failed to wait for command: {}", command_name))?;
-    let exit_code = status.code().unwrap_or(1);
-
-    // Now output threads can finish (pipes are closed)
-    stdout_thread.join().expect("stdout thread panicked");
-    stderr_thread.join().expect("stderr thread panicked");
+        if exit_code != 0 {
+            failed = Some(spec);
+            break;
+        }
+    }
 
     // Restore original console mode BEFORE any output
     #[cfg(windows)]
@@ -260,22 +1550,33 @@ fn main() -> Result<()> {
         restore_console_mode(mode);
     }
 
-    // Write log footer
-    if let Some(ref mut file) = log_file_handle {
-        writeln!(file, "\n===================")?;
-        writeln!(file, "Exit code: {}", exit_code)?;
-        writeln!(file, "Finished at: {}", Local::now().to_rfc3339())?;
+    let log_state = log_state.lock().unwrap_or_else(|e| e.into_inner());
+    log_state.print_summary(log_path.as_ref(), output_options.color && std::io::stdout().is_terminal());
+    log_state.print_reports();
+    if let (Some(path), Some(junit)) = (&junit_path, &log_state.junit) {
+        junit.write(path)?;
+        println!("JUnit report: {}", path.display());
     }
 
-    if !status.success() {
-        eprintln!("\nCommand failed with exit code {}", exit_code);
+    if let Some(spec) = failed {
+        if commands.len() > 1 {
+            eprintln!("\nCommand `{}` failed with exit code {}", spec.display(), exit_code);
+        } else {
+            eprintln!("\nCommand failed with exit code {}", exit_code);
+        }
         if let Some(ref path) = log_path {
             eprintln!("Check log file: {}", path.display());
         }
+    } else if interrupted.load(Ordering::SeqCst) {
+        eprintln!("\nInterrupted");
+        exit_code = 130;
     } else {
         println!("\n✓ Command completed successfully");
     }
 
-    // Use process::exit to force-terminate the blocked stdin thread
+    // process::exit skips destructors
+    drop(control_file);
+
+    // Use process::exit to force-terminate the blocked stdin and daemon threads
     std::process::exit(exit_code);
 }
@@ -0,0 +1,136 @@
+#!/usr/bin/env -S uv run --script
+# /// script
+# requires-python = ">=3.8"
+# dependencies = [
+#     "pillow",
+#     "cairosvg",
+# ]
+# ///
+"""Render resized/converted image variants described by a JSON job file.
+
+Used by flutter_gen_platforms for the asset pipeline and icon generation, but
+can be run standalone:
+
+    python flutter_gen_images.py --job job.json
+
+Job format:
+
+    {"images": [{"source": "logo.png",
+                 "outputs": [{"path": "out/logo.png", "width": 120,
+                              "height": null, "format": "png"}]}]}
+
+`height` defaults to keeping the source aspect ratio. `format` is one of png,
+webp, jpeg or ico (ico outputs take a `sizes` list of square sizes). SVG sources
+are rasterized with cairosvg, which needs the cairo library.
+"""
+
+import argparse
+import io
+import json
+import logging
+import sys
+from pathlib import Path
+
+from PIL import Image
+
+logging.basicConfig(level=logging.INFO, format='%(levelname)s: %(message)s')
+logger = logging.getLogger(__name__)
+
+
+def parse_color(value: str) -> tuple:
+    """Parse a #RRGGBB or #RRGGBBAA color string into an RGBA tuple."""
+    value = value.lstrip('#')
+    if len(value) == 6:
+        value += 'ff'
+    return tuple(int(value[i:i + 2], 16) for i in (0, 2, 4, 6))
+
+
+# Raster size for SVG sources; larger than any generated output.
+SVG_RENDER_SIZE = 1024
+
+
+def open_source(source: Path) -> Image.Image:
+    if source.suffix.lower() != '.svg':
+        try:
+            return Image.open(source).convert('RGBA')
+        except (OSError, SyntaxError) as e:
+            raise RuntimeError(f"Unsupported source image {source} (PNG, JPEG, WebP or SVG): {e}") from e
+    try:
+        import cairosvg
+    except (ImportError, OSError) as e:
+        raise RuntimeError(f"SVG source {source} needs cairosvg and the cairo library: {e}") from e
+    try:
+        png = cairosvg.svg2png(url=str(source), output_width=SVG_RENDER_SIZE)
+    except Exception as e:
+        raise RuntimeError(f"Failed to rasterize SVG source {source}: {e}") from e
+    return Image.open(io.BytesIO(png)).convert('RGBA')
+
+
+def resize(img: Image.Image, width: int, height, padding: float = 0.0, background=None) -> Image.Image:
+    """Resize keeping aspect ratio; with a fixed height the image is centered on a canvas."""
+    if height is None:
+        height = max(1, round(img.height * width / img.width))
+        return img.resize((width, height), Image.LANCZOS)
+
+    inner_w = max(1, round(width * (1 - 2 * padding)))
+    inner_h = max(1, round(height * (1 - 2 * padding)))
+    scale = min(inner_w / img.width, inner_h / img.height)
+    scaled = img.resize((max(1, round(img.width * scale)), max(1, round(img.height * scale))), Image.LANCZOS)
+
+    fill = parse_color(background) if background else (0, 0, 0, 0)
+    canvas = Image.new('RGBA', (width, height), fill)
+    offset = ((width - scaled.width) // 2, (height - scaled.height) // 2)
+    canvas.paste(scaled, offset, scaled)
+    return canvas
+
+
+def render_output(img: Image.Image, output: dict) -> None:
+    path = Path(output['path'])
+    path.parent.mkdir(parents=True, exist_ok=True)
+    fmt = output.get('format', 'png').lower()
+
+    if fmt == 'ico':
+        sizes = output.get('sizes') or [16, 32, 48, 64, 128, 256]
+        base = resize(img, max(sizes), max(sizes), output.get('padding', 0.0), output.get('background'))
+        base.save(path, format='ICO', sizes=[(s, s) for s in sizes])
+        return
+
+    out = resize(img, output['width'], output.get('height'), output.get('padding', 0.0), output.get('background'))
+    if fmt == 'webp':
+        out.save(path, format='WEBP', quality=output.get('quality', 90))
+    elif fmt in ('jpg', 'jpeg'):
+        out.convert('RGB').save(path, format='JPEG', quality=output.get('quality', 90))
+    else:
+        if output.get('opaque'):
+            out = out.convert('RGB')
+        out.save(path, format='PNG', optimize=True)
+
+
+def main():
+    parser = argparse.ArgumentParser(description='Render image variants from a JSON job file')
+    parser.add_argument('--job', type=Path, required=True, help='Path to the JSON job file')
+    args = parser.parse_args()
+
+    try:
+        job = json.loads(args.job.read_text(encoding='utf-8'))
+    except Exception as e:
+        logger.error(f"Failed to read job {args.job}: {e}")
+        sys.exit(1)
+
+    for image in job.get('images', []):
+        source = Path(image['source'])
+        if not source.exists():
+            logger.error(f"Source image not found: {source}")
+            sys.exit(1)
+        try:
+            img = open_source(source)
+        except RuntimeError as e:
+            logger.error(str(e))
+            sys.exit(1)
+        for output in image.get('outputs', []):
+            render_output(img, output)
+            logger.info(f"Generated {output['path']}")
+
+
+if __name__ == '__main__':
+    main()
@@ -1,12 +1,20 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use crate::config::AndroidConfig;
+use crate::config::{
+    AndroidBaselineProfileConfig, AndroidConfig, AndroidDynamicFeaturesConfig,
+    AndroidFileProviderConfig, AndroidFormFactor, AndroidPlayIntegrityConfig, AndroidGradleWrapperConfig, AndroidLocalPropertiesConfig,
+    AndroidNetworkSecurityConfig, BuildEnvValue, Config, RepositoriesMode,
+};
+use crate::manifest::apply_main_manifest;
+use crate::pubspec::{set_deferred_components, DeferredComponent};
+use crate::template::{OverlayRenderer, TEMPLATE_SUFFIX};
+use crate::utils::{resolve_cmd, run_gradle_wrapper};
+use crate::verify::verify_android_platform;
 
-fn copy_manifest_templates(project_dir: &Path, android_dir: &Path, templates_dir: &Path) -> Result<()> {
-    let src_dir = project_dir.join(templates_dir);
+fn copy_manifest_templates(android_dir: &Path, src_dir: &Path, renderer: &OverlayRenderer) -> Result<()> {
     if !src_dir.exists() {
         anyhow::bail!(
             "Android manifest templates directory not found: {}",
@@ -15,91 +23,255 @@ fn copy_manifest_templates(project_dir: &Path, android_dir: &Path, templates_dir
     }
 
     let main_src = src_dir.join("AndroidManifest.main.xml");
-    if !main_src.exists() {
+    if !main_src.exists() && !src_dir.join(format!("AndroidManifest.main.xml{}", TEMPLATE_SUFFIX)).exists() {
         anyhow::bail!(
             "Missing required manifest template: {}",
             main_src.display()
         );
     }
 
-    let mappings = [
-        (
-            src_dir.join("AndroidManifest.main.xml"),
-            android_dir.join("app/src/main/AndroidManifest.xml"),
-        ),
-        (
-            src_dir.join("AndroidManifest.debug.xml"),
-            android_dir.join("app/src/debug/AndroidManifest.xml"),
-        ),
-        (
-            src_dir.join("AndroidManifest.profile.xml"),
-            android_dir.join("app/src/profile/AndroidManifest.xml"),
-        ),
-    ];
+    copy_template_files(src_dir, android_dir, renderer)
+}
 
-    for (src, dst) in mappings {
-        // debug/profile templates are optional; main is validated above.
-        if !src.exists() {
+/// Manifest templates and where they land in the android directory.
+const MANIFEST_TEMPLATES: [(&str, &str); 3] = [
+    ("AndroidManifest.main.xml", "app/src/main/AndroidManifest.xml"),
+    ("AndroidManifest.debug.xml", "app/src/debug/AndroidManifest.xml"),
+    ("AndroidManifest.profile.xml", "app/src/profile/AndroidManifest.xml"),
+];
+
+/// Overlay entries with a place of their own: the manifests, res/ and src/ above, the
+/// MainActivity templates and the form factor directories layered separately.
+fn is_mapped_template(relative: &Path) -> bool {
+    let output = OverlayRenderer::output_path(relative);
+    let first = output.components().next().map(|component| component.as_os_str().to_string_lossy());
+    let name = output.to_string_lossy();
+    MANIFEST_TEMPLATES.iter().any(|(template, _)| name == *template)
+        || name == "MainActivity.kt"
+        || name == "MainActivity.java"
+        || first.is_some_and(|first| {
+            ["res", "src"].contains(&first.as_ref())
+                || [AndroidFormFactor::Tv, AndroidFormFactor::Wear]
+                    .iter()
+                    .any(|form_factor| form_factor.as_str() == first)
+        })
+}
+
+/// Renders the overlay found in `src_dir` into the android directory; used for the base
+/// templates and again for the form factor's directory layered on top. The manifests
+/// (each may also be a `.tera` template), res/ and source sets are mapped into app/src;
+/// any other file lands at the same path under android/, e.g. `app/proguard-rules.pro`.
+fn copy_template_files(src_dir: &Path, android_dir: &Path, renderer: &OverlayRenderer) -> Result<()> {
+    for (name, dst) in MANIFEST_TEMPLATES {
+        // debug/profile templates are optional; main is validated by the caller.
+        let Some(src) = [src_dir.join(format!("{}{}", name, TEMPLATE_SUFFIX)), src_dir.join(name)]
+            .into_iter()
+            .find(|src| src.exists())
+        else {
             continue;
-        }
-        if let Some(parent) = dst.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
-        }
-        fs::copy(&src, &dst)
-            .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+        };
+        renderer.copy_file(&src, &android_dir.join(dst))?;
     }
 
     // Copy extra resource files (e.g. res/xml/file_paths.xml) if present.
     let res_src = src_dir.join("res");
     if res_src.is_dir() {
         let res_dst = android_dir.join("app/src/main/res");
-        copy_dir_recursive(&res_src, &res_dst)
+        renderer
+            .copy_dir(&res_src, &res_dst)
             .with_context(|| format!("Failed to copy resource dir: {}", res_src.display()))?;
     }
 
+    // Copy flavor/build-type source sets (e.g. src/staging/res, src/release/google-services.json).
+    let source_sets_src = src_dir.join("src");
+    if source_sets_src.is_dir() {
+        let source_sets_dst = android_dir.join("app/src");
+        renderer.copy_dir(&source_sets_src, &source_sets_dst).with_context(|| {
+            format!("Failed to copy source sets dir: {}", source_sets_src.display())
+        })?;
+    }
+
+    renderer.copy_dir_except(src_dir, android_dir, is_mapped_template)?;
     Ok(())
 }
 
-fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
-    fs::create_dir_all(dst)
-        .with_context(|| format!("Failed to create dir: {}", dst.display()))?;
-    for entry in fs::read_dir(src)
-        .with_context(|| format!("Failed to read dir: {}", src.display()))?
+/// Enables the `tv`/`wear` config implied by `form_factor`, so the manifest gets the
+/// launcher intent and uses-feature declarations without repeating them.
+pub fn apply_form_factor_defaults(config: &mut AndroidConfig) -> Result<()> {
+    match config.form_factor {
+        Some(AndroidFormFactor::Tv) => {
+            if config.wear.is_some() {
+                bail!("android.form_factor = \"tv\" conflicts with android.wear");
+            }
+            config.tv.get_or_insert_with(Default::default);
+        }
+        Some(AndroidFormFactor::Wear) => {
+            if config.tv.is_some() {
+                bail!("android.form_factor = \"wear\" conflicts with android.tv");
+            }
+            config.wear.get_or_insert_with(Default::default);
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Replaces the generated MainActivity with `platforms/android/MainActivity.kt`
+/// (or `.java`), placed under the namespace package so `.MainActivity` in the
+/// manifest keeps resolving. The template's `package` line is rewritten.
+fn apply_main_activity_template(android_dir: &Path, templates_dir: &Path, namespace: &str) -> Result<()> {
+    let Some((template, lang, ext)) = [("kotlin", "kt"), ("java", "java")]
+        .into_iter()
+        .map(|(lang, ext)| (templates_dir.join(format!("MainActivity.{}", ext)), lang, ext))
+        .find(|(template, _, _)| template.exists())
+    else {
+        return Ok(());
+    };
+
+    let main_dir = android_dir.join("app/src/main");
+    for lang_dir in ["kotlin", "java"] {
+        let root = main_dir.join(lang_dir);
+        if !root.is_dir() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&root) {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy();
+            if name == "MainActivity.kt" || name == "MainActivity.java" {
+                fs::remove_file(entry.path()).with_context(|| {
+                    format!("Failed to remove file: {}", entry.path().display())
+                })?;
+            }
+        }
+    }
+
+    let content = fs::read_to_string(&template)
+        .with_context(|| format!("Failed to read file: {}", template.display()))?;
+    let terminator = if ext == "java" { ";" } else { "" };
+    let package_line = format!("package {}{}", namespace, terminator);
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    match lines.iter().position(|line| line.trim_start().starts_with("package ")) {
+        Some(i) => lines[i] = package_line,
+        None => {
+            lines.insert(0, String::new());
+            lines.insert(0, package_line);
+        }
+    }
+
+    let dst_dir = namespace
+        .split('.')
+        .fold(main_dir.join(lang), |dir, segment| dir.join(segment));
+    fs::create_dir_all(&dst_dir)
+        .with_context(|| format!("Failed to create dir: {}", dst_dir.display()))?;
+    let dst = dst_dir.join(format!("MainActivity.{}", ext));
+    fs::write(&dst, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", dst.display()))?;
+    println!("✓ MainActivity replaced from template: {}", template.display());
+    Ok(())
+}
+
+/// String resource behind the Play Integrity meta-data; a literal numeric
+/// `android:value` would be parsed as an int and overflow.
+fn write_play_integrity_resources(android_dir: &Path, config: &AndroidPlayIntegrityConfig) -> Result<()> {
+    if config.cloud_project_number.is_empty()
+        || !config.cloud_project_number.chars().all(|c| c.is_ascii_digit())
     {
-        let entry = entry?;
-        let src_path = entry.path();
-        let dst_path = dst.join(entry.file_name());
-        if src_path.is_dir() {
-            copy_dir_recursive(&src_path, &dst_path)?;
-        } else {
-            fs::copy(&src_path, &dst_path)
-                .with_context(|| format!("Failed to copy {} -> {}", src_path.display(), dst_path.display()))?;
+        bail!(
+            "android.play.integrity.cloud_project_number must be numeric: {}",
+            config.cloud_project_number
+        );
+    }
+    let dir = android_dir.join("app/src/main/res/values");
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create dir: {}", dir.display()))?;
+    let path = dir.join("play_integrity.xml");
+    let lines = [
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>".to_string(),
+        "<resources>".to_string(),
+        format!(
+            "    <string name=\"{}\" translatable=\"false\">{}</string>",
+            config.meta_data_name, config.cloud_project_number
+        ),
+        "</resources>".to_string(),
+    ];
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
+
+fn write_network_security_config(android_dir: &Path, config: &AndroidNetworkSecurityConfig) -> Result<()> {
+    let mut lines = vec![
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>".to_string(),
+        "<network-security-config>".to_string(),
+        format!(
+            "    <base-config cleartextTrafficPermitted=\"{}\">",
+            config.cleartext_traffic_permitted
+        ),
+        "        <trust-anchors>".to_string(),
+        "            <certificates src=\"system\" />".to_string(),
+    ];
+    if config.trust_user_certs {
+        lines.push("            <certificates src=\"user\" />".to_string());
+    }
+    for certificate in &config.certificates {
+        lines.push(format!("            <certificates src=\"{}\" />", certificate));
+    }
+    lines.push("        </trust-anchors>".to_string());
+    lines.push("    </base-config>".to_string());
+    if !config.cleartext_domains.is_empty() {
+        lines.push("    <domain-config cleartextTrafficPermitted=\"true\">".to_string());
+        for domain in &config.cleartext_domains {
+            lines.push(format!(
+                "        <domain includeSubdomains=\"true\">{}</domain>",
+                domain
+            ));
         }
+        lines.push("    </domain-config>".to_string());
     }
+    if config.debug_user_certs {
+        lines.push("    <debug-overrides>".to_string());
+        lines.push("        <trust-anchors>".to_string());
+        lines.push("            <certificates src=\"user\" />".to_string());
+        lines.push("        </trust-anchors>".to_string());
+        lines.push("    </debug-overrides>".to_string());
+    }
+    lines.push("</network-security-config>".to_string());
+
+    let path = android_dir.join("app/src/main/res/xml/network_security_config.xml");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
     Ok(())
 }
 
-pub fn apply_repositories(path: &Path, repos: &[String]) -> Result<()> {
+/// Adds `repos` at the top of the file's first `repositories {` block, or with `replace`
+/// makes them its only entries. The block ends at its matching brace, so multi-line
+/// entries like `maven {\n url = ...\n}` are kept or dropped whole.
+pub fn apply_repositories(path: &Path, repos: &[String], replace: bool) -> Result<()> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read file: {}", path.display()))?;
-    let lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
-
     let mut out = Vec::new();
-    let mut in_repos = false;
+    let mut depth = 0i32;
     let mut inserted = false;
 
-    for line in &lines {
-        out.push(line.clone());
+    for line in content.lines() {
+        if depth > 0 {
+            depth += line.matches('{').count() as i32 - line.matches('}').count() as i32;
+            if depth > 0 && replace {
+                continue;
+            }
+            out.push(line.to_string());
+            continue;
+        }
+        out.push(line.to_string());
         if line.trim() == "repositories {" && !inserted {
-            in_repos = true;
+            depth = 1;
             for repo in repos {
-                let insert = format!("        maven {{ url = uri(\"{}\") }}", repo);
-                out.push(insert);
+                out.push(format!("        maven {{ url = uri(\"{}\") }}", repo));
             }
             inserted = true;
-        } else if in_repos && line.trim() == "}" {
-            in_repos = false;
         }
     }
 
@@ -108,25 +280,27 @@ pub fn apply_repositories(path: &Path, repos: &[String]) -> Result<()> {
     Ok(())
 }
 
-pub fn apply_plugin_repositories(path: &Path, repos: &[String]) -> Result<()> {
+pub fn apply_settings_plugins(path: &Path, plugins: &[String]) -> Result<()> {
+    if plugins.is_empty() {
+        return Ok(());
+    }
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read file: {}", path.display()))?;
     let mut out = Vec::new();
-    let mut in_plugin_repos = false;
+    let mut in_plugins = false;
     let mut inserted = false;
 
     for line in content.lines() {
-        out.push(line.to_string());
-        if line.trim() == "repositories {" && !inserted {
-            in_plugin_repos = true;
-            for repo in repos {
-                let insert = format!("        maven {{ url = uri(\"{}\") }}", repo);
-                out.push(insert);
+        if line.trim() == "plugins {" && !inserted {
+            in_plugins = true;
+        } else if in_plugins && line.trim() == "}" {
+            for plugin in plugins {
+                out.push(format!("    {}", plugin));
             }
+            in_plugins = false;
             inserted = true;
-        } else if in_plugin_repos && line.trim() == "}" {
-            in_plugin_repos = false;
         }
+        out.push(line.to_string());
     }
 
     fs::write(path, out.join("\n") + "\n")
@@ -134,20 +308,450 @@ pub fn apply_plugin_repositories(path: &Path, repos: &[String]) -> Result<()> {
     Ok(())
 }
 
-pub fn apply_app_gradle(
-    path: &Path,
-    namespace: &str,
-    application_id: &str,
-    output_file_name: Option<&str>,
-    abi_filters: Option<&[String]>,
-    kotlin_incremental: Option<bool>,
-) -> Result<()> {
+/// Where the Flutter Gradle plugin fetches engine artifacts; settings-level repositories
+/// must include it since the plugin's own project repository may be ignored or rejected.
+const FLUTTER_ENGINE_REPOSITORY: &str = "https://storage.googleapis.com/download.flutter.io";
+
+pub fn dependency_resolution_lines(config: &AndroidConfig) -> Vec<String> {
+    let Some(resolution) = &config.settings.dependency_resolution else {
+        return Vec::new();
+    };
+    let mut lines = vec![
+        "dependencyResolutionManagement {".to_string(),
+        format!("    repositoriesMode.set(RepositoriesMode.{})", resolution.mode.as_gradle()),
+        "    repositories {".to_string(),
+    ];
+    if !resolution.replace {
+        lines.push("        google()".to_string());
+        lines.push("        mavenCentral()".to_string());
+        lines.push(format!("        maven {{ url = uri(\"{}\") }}", FLUTTER_ENGINE_REPOSITORY));
+    }
+    let mut repositories: Vec<&String> = resolution.repositories.iter().collect();
+    if resolution.mode == RepositoriesMode::FailOnProjectRepos {
+        // allprojects repositories are rejected in this mode, so they are declared here.
+        repositories.extend(
+            config
+                .build
+                .allprojects
+                .repositories
+                .iter()
+                .filter(|repo| !resolution.repositories.contains(repo)),
+        );
+    }
+    for repo in repositories {
+        lines.push(format!("        maven {{ url = uri(\"{}\") }}", repo));
+    }
+    lines.push("    }".to_string());
+    lines.push("}".to_string());
+    lines
+}
+
+/// Inserts `dependencyResolutionManagement {}` after the top-level `plugins {}` block:
+/// Gradle requires `pluginManagement {}` and `plugins {}` to come first in settings.
+fn apply_dependency_resolution(path: &Path, lines: &[String]) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    if content.contains("dependencyResolutionManagement {") {
+        return Ok(());
+    }
+    let mut out: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let plugins_start = out.iter().position(|line| line == "plugins {");
+    let plugins_end = plugins_start.and_then(|start| (start..out.len()).find(|&i| out[i] == "}"));
+    let Some(end) = plugins_end else {
+        bail!("plugins {{}} block not found in: {}", path.display());
+    };
+    let at = end + 1;
+    out.insert(at, String::new());
+    for (offset, line) in lines.iter().enumerate() {
+        out.insert(at + 1 + offset, line.clone());
+    }
+
+    fs::write(path, out.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
+
+/// Drops the `allprojects { repositories { ... } }` block, which FAIL_ON_PROJECT_REPOS
+/// rejects, and returns the repository entries it declared (multi-line ones included).
+fn remove_allprojects_repositories(path: &Path) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut out = Vec::new();
+    let mut repositories = Vec::new();
+    let mut depth = 0usize;
+    let mut removing = false;
+    for line in content.lines() {
+        if !removing && line.trim() == "allprojects {" {
+            removing = true;
+        }
+        if removing {
+            let before = depth;
+            depth += line.matches('{').count();
+            depth = depth.saturating_sub(line.matches('}').count());
+            // Inside `allprojects { repositories {`, without the block's own braces.
+            if before >= 2 && depth >= 2 {
+                repositories.push(line.to_string());
+            }
+            if depth == 0 {
+                removing = false;
+            }
+            continue;
+        }
+        out.push(line.to_string());
+    }
+    // Don't leave the blank line that separated the removed block.
+    while out.first().is_some_and(|line| line.trim().is_empty()) {
+        out.remove(0);
+    }
+
+    fs::write(path, out.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(repositories)
+}
+
+pub fn settings_plugins(config: &AndroidConfig) -> Vec<String> {
+    let mut plugins = Vec::new();
+    let crashlytics = &config.crashlytics;
+    if crashlytics.enabled {
+        plugins.push(format!(
+            "id(\"com.google.gms.google-services\") version \"{}\" apply false",
+            crashlytics.google_services_version.as_deref().unwrap_or("4.4.2")
+        ));
+        plugins.push(format!(
+            "id(\"com.google.firebase.crashlytics\") version \"{}\" apply false",
+            crashlytics.plugin_version.as_deref().unwrap_or("3.0.3")
+        ));
+    }
+    let baseline_profile = &config.baseline_profile;
+    if baseline_profile.enabled {
+        plugins.push(format!(
+            "id(\"androidx.baselineprofile\") version \"{}\" apply false",
+            baseline_profile.plugin_version.as_deref().unwrap_or("1.3.3")
+        ));
+    }
+    plugins
+}
+
+pub fn app_plugins(config: &AndroidConfig) -> Vec<String> {
+    let mut plugins = Vec::new();
+    if config.crashlytics.enabled {
+        plugins.push("id(\"com.google.gms.google-services\")".to_string());
+        plugins.push("id(\"com.google.firebase.crashlytics\")".to_string());
+    }
+    if config.baseline_profile.enabled {
+        plugins.push("id(\"androidx.baselineprofile\")".to_string());
+    }
+    plugins
+}
+
+/// Entries of the app module's top-level `dependencies {}` block.
+pub fn dependency_lines(config: &AndroidConfig) -> Vec<String> {
+    let mut lines = Vec::new();
+    let baseline_profile = &config.baseline_profile;
+    if baseline_profile.enabled {
+        lines.push(format!(
+            "    implementation(\"androidx.profileinstaller:profileinstaller:{}\")",
+            baseline_profile.profileinstaller_version.as_deref().unwrap_or("1.4.1")
+        ));
+        if let Some(producer) = &baseline_profile.producer {
+            lines.push(format!("    baselineProfile(project(\"{}\"))", producer));
+        }
+    }
+    if let Some(integrity) = &config.play.integrity {
+        lines.push(format!(
+            "    implementation(\"com.google.android.play:integrity:{}\")",
+            integrity.version.as_deref().unwrap_or("1.4.0")
+        ));
+    }
+    if let Some(update) = &config.play.in_app_update {
+        let version = update.version.as_deref().unwrap_or("2.1.0");
+        lines.push(format!("    implementation(\"com.google.android.play:app-update:{}\")", version));
+        if update.ktx {
+            lines.push(format!("    implementation(\"com.google.android.play:app-update-ktx:{}\")", version));
+        }
+    }
+    if let Some(features) = &config.dynamic_features
+        && !features.modules.is_empty()
+    {
+        // FlutterPlayStoreSplitApplication installs modules through the SplitInstall API.
+        lines.push(format!(
+            "    implementation(\"com.google.android.play:feature-delivery:{}\")",
+            features.feature_delivery_version.as_deref().unwrap_or("2.1.0")
+        ));
+    }
+    lines
+}
+
+pub fn release_build_type_lines(config: &AndroidConfig) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(level) = config.app.build.debug_symbol_level {
+        lines.push("            ndk {".to_string());
+        lines.push(format!("                debugSymbolLevel = \"{}\"", level.as_gradle()));
+        lines.push("            }".to_string());
+    }
+    let crashlytics = &config.crashlytics;
+    if crashlytics.enabled {
+        lines.push(
+            "            configure<com.google.firebase.crashlytics.buildtools.gradle.CrashlyticsExtension> {"
+                .to_string(),
+        );
+        lines.push(format!(
+            "                mappingFileUploadEnabled = {}",
+            crashlytics.mapping_file_upload
+        ));
+        lines.push(format!(
+            "                nativeSymbolUploadEnabled = {}",
+            crashlytics.native_symbol_upload
+        ));
+        lines.push("            }".to_string());
+    }
+    lines
+}
+
+/// Build types Flutter apps always have; other variant keys are product flavors.
+const BUILD_TYPES: [&str; 3] = ["debug", "profile", "release"];
+
+fn kotlin_string(value: &str) -> String {
+    format!(
+        "\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"").replace('$', "\\$")
+    )
+}
+
+fn build_config_field(name: &str, value: &BuildEnvValue) -> String {
+    let (ty, literal) = match value {
+        BuildEnvValue::Bool(v) => ("boolean", v.to_string()),
+        BuildEnvValue::Int(v) if i32::try_from(*v).is_ok() => ("int", v.to_string()),
+        BuildEnvValue::Int(v) => ("long", format!("{}L", v)),
+        BuildEnvValue::Float(v) => ("double", format!("{:?}", v)),
+        BuildEnvValue::String(v) => (
+            "String",
+            format!("\"{}\"", v.replace('\\', "\\\\").replace('"', "\\\"")),
+        ),
+    };
+    format!(
+        "buildConfigField({}, {}, {})",
+        kotlin_string(ty),
+        kotlin_string(name),
+        kotlin_string(&literal)
+    )
+}
+
+fn res_value(name: &str, value: &BuildEnvValue) -> String {
+    let (ty, literal) = match value {
+        BuildEnvValue::Bool(v) => ("bool", v.to_string()),
+        BuildEnvValue::Int(v) => ("integer", v.to_string()),
+        BuildEnvValue::Float(v) => ("string", v.to_string()),
+        BuildEnvValue::String(v) => ("string", android_string_escape(v)),
+    };
+    format!(
+        "resValue({}, {}, {})",
+        kotlin_string(ty),
+        kotlin_string(name),
+        kotlin_string(&literal)
+    )
+}
+
+fn lint_lines(config: &AndroidConfig) -> Vec<String> {
+    let Some(lint) = &config.app.build.lint else {
+        return Vec::new();
+    };
+    let mut lines = vec!["    lint {".to_string()];
+    if let Some(value) = lint.abort_on_error {
+        lines.push(format!("        abortOnError = {}", value));
+    }
+    if let Some(value) = lint.check_release_builds {
+        lines.push(format!("        checkReleaseBuilds = {}", value));
+    }
+    if !lint.disable.is_empty() {
+        let ids: Vec<String> = lint.disable.iter().map(|id| kotlin_string(id)).collect();
+        lines.push(format!("        disable += setOf({})", ids.join(", ")));
+    }
+    lines.push("    }".to_string());
+    lines
+}
+
+fn test_lines(config: &AndroidConfig) -> Vec<String> {
+    let Some(test) = &config.app.build.test else {
+        return Vec::new();
+    };
+    let mut lines = Vec::new();
+    if let Some(runner) = &test.instrumentation_runner {
+        lines.push("    defaultConfig {".to_string());
+        lines.push(format!("        testInstrumentationRunner = {}", kotlin_string(runner)));
+        lines.push("    }".to_string());
+    }
+    let mut options = Vec::new();
+    if let Some(value) = test.return_default_values {
+        options.push(format!("        unitTests.isReturnDefaultValues = {}", value));
+    }
+    if let Some(value) = test.include_android_resources {
+        options.push(format!("        unitTests.isIncludeAndroidResources = {}", value));
+    }
+    if let Some(value) = test.animations_disabled {
+        options.push(format!("        animationsDisabled = {}", value));
+    }
+    if !options.is_empty() {
+        lines.push("    testOptions {".to_string());
+        lines.extend(options);
+        lines.push("    }".to_string());
+    }
+    lines
+}
+
+/// Lines appended to the end of the `android {}` block.
+pub fn android_block_lines(config: &AndroidConfig) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(features) = &config.dynamic_features
+        && !features.modules.is_empty()
+    {
+        let modules: Vec<String> = features
+            .modules
+            .iter()
+            .map(|module| format!("\":{}\"", module.name))
+            .collect();
+        lines.push(format!("    dynamicFeatures += setOf({})", modules.join(", ")));
+    }
+    lines.extend(lint_lines(config));
+    lines.extend(test_lines(config));
+    lines.extend(variant_value_lines(config));
+    lines
+}
+
+/// `build_config_fields` / `res_values` blocks.
+fn variant_value_lines(config: &AndroidConfig) -> Vec<String> {
+    let build = &config.app.build;
+    let mut variants: Vec<&str> = build
+        .build_config_fields
+        .keys()
+        .chain(build.res_values.keys())
+        .map(String::as_str)
+        .collect();
+    variants.sort_by_key(|name| {
+        let rank = if *name == "default" {
+            0
+        } else {
+            BUILD_TYPES
+                .iter()
+                .position(|t| t == name)
+                .map_or(BUILD_TYPES.len() + 1, |i| i + 1)
+        };
+        (rank, *name)
+    });
+    variants.dedup();
+
+    let mut lines = Vec::new();
+    if build.build_config_fields.values().any(|fields| !fields.is_empty()) {
+        lines.push("    buildFeatures {".to_string());
+        lines.push("        buildConfig = true".to_string());
+        lines.push("    }".to_string());
+    }
+    for variant in variants {
+        let mut body = Vec::new();
+        if let Some(fields) = build.build_config_fields.get(variant) {
+            body.extend(fields.iter().map(|(name, value)| build_config_field(name, value)));
+        }
+        if let Some(values) = build.res_values.get(variant) {
+            body.extend(values.iter().map(|(name, value)| res_value(name, value)));
+        }
+        if body.is_empty() {
+            continue;
+        }
+        lines.push(if variant == "default" {
+            "    defaultConfig {".to_string()
+        } else if BUILD_TYPES.contains(&variant) {
+            format!("    buildTypes.getByName(\"{}\") {{", variant)
+        } else {
+            format!("    productFlavors.getByName(\"{}\") {{", variant)
+        });
+        lines.extend(body.into_iter().map(|line| format!("        {}", line)));
+        lines.push("    }".to_string());
+    }
+    lines
+}
+
+/// Maps output file name tokens to the Kotlin expressions available in `applicationVariants.all`.
+pub const OUTPUT_FILE_NAME_TOKENS: [(&str, &str); 6] = [
+    ("{abi}", "${abi}"),
+    ("{build_type}", "${variant.buildType.name}"),
+    ("{flavor}", "${variant.flavorName}"),
+    ("{variant}", "${variant.name}"),
+    ("{version_name}", "${variant.versionName}"),
+    ("{version_code}", "${variant.versionCode}"),
+];
+
+/// Kotlin string template for `pattern`: literal text is escaped first, so only the
+/// tokens become `${...}` interpolations.
+fn output_file_name_expr(pattern: &str) -> String {
+    OUTPUT_FILE_NAME_TOKENS
+        .iter()
+        .fold(kotlin_string(pattern), |acc, (token, expr)| acc.replace(token, expr))
+}
+
+/// `outputFileName` assignment lines inside `applicationVariants.all { outputs.all { ... } }`.
+pub fn output_file_name_lines(config: &AndroidConfig) -> Vec<String> {
+    let build = &config.app.build;
+    let default = build.output_file_name.as_deref();
+    if build.output_file_names.is_empty() {
+        return match default {
+            Some(pattern) => vec![format!("output.outputFileName = {}", output_file_name_expr(pattern))],
+            None => Vec::new(),
+        };
+    }
+
+    // Variant names and flavors are more specific than build types, so they match first.
+    let mut arms = Vec::new();
+    for (key, pattern) in &build.output_file_names {
+        if !BUILD_TYPES.contains(&key.as_str()) {
+            arms.push(format!(
+                "    variant.name == {key} || variant.flavorName == {key} -> {}",
+                output_file_name_expr(pattern),
+                key = kotlin_string(key)
+            ));
+        }
+    }
+    for (key, pattern) in &build.output_file_names {
+        if BUILD_TYPES.contains(&key.as_str()) {
+            arms.push(format!(
+                "    variant.buildType.name == {} -> {}",
+                kotlin_string(key),
+                output_file_name_expr(pattern)
+            ));
+        }
+    }
+    let fallback = default
+        .map(output_file_name_expr)
+        .unwrap_or_else(|| "output.outputFileName".to_string());
+
+    let mut lines = vec!["output.outputFileName = when {".to_string()];
+    lines.extend(arms);
+    lines.push(format!("    else -> {}", fallback));
+    lines.push("}".to_string());
+    lines
+}
+
+pub fn apply_app_gradle(path: &Path, config: &AndroidConfig) -> Result<()> {
+    let build = &config.app.build;
+    let namespace = &build.namespace;
+    let application_id = &build.application_id;
+    let output_lines = output_file_name_lines(config);
+    let abi_filters = build.abi_filters.as_deref();
+    let kotlin_incremental = config.build_performance.kotlin_incremental;
+    let plugins = app_plugins(config);
+    let release_lines = release_build_type_lines(config);
+    let android_lines = android_block_lines(config);
+    let dependencies = dependency_lines(config);
+
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read file: {}", path.display()))?;
     let mut out = Vec::new();
+    let mut in_plugins = false;
     let mut in_build_types = false;
+    let mut in_release = false;
     let mut in_default_config = false;
     let mut in_kotlin_options = false;
+    let mut added_plugins = false;
+    let mut added_release_lines = false;
     let mut added_output_config = false;
     let mut added_abi_filters = false;
     let mut added_kotlin_incremental = false;
@@ -155,12 +759,26 @@ pub fn apply_app_gradle(
     for line in content.lines() {
         if line.trim_start().starts_with("namespace = ") {
             out.push(format!("    namespace = \"{}\"", namespace));
+        } else if let Some(form_factor) = config.form_factor
+            && line.trim_start().starts_with("minSdk = ")
+        {
+            out.push(format!("        minSdk = maxOf(flutter.minSdkVersion, {})", form_factor.min_sdk()));
         } else if line.trim_start().starts_with("applicationId = ") {
             out.push(format!("        applicationId = \"{}\"", application_id));
         } else {
             out.push(line.to_string());
         }
 
+        if line.trim() == "plugins {" && !added_plugins {
+            in_plugins = true;
+        } else if in_plugins && line.trim() == "}" {
+            for plugin in &plugins {
+                out.insert(out.len() - 1, format!("    {}", plugin));
+            }
+            in_plugins = false;
+            added_plugins = true;
+        }
+
         if line.trim().starts_with("kotlinOptions {") {
             in_kotlin_options = true;
         }
@@ -185,14 +803,14 @@ pub fn apply_app_gradle(
         }
 
         if in_default_config && line.trim() == "}" && !added_abi_filters {
-            if let Some(abis) = abi_filters {
-                if !abis.is_empty() {
-                    out.insert(out.len() - 1, format!("        ndk {{"));
-                    for abi in abis {
-                        out.insert(out.len() - 1, format!("            abiFilters.add(\"{}\")", abi));
-                    }
-                    out.insert(out.len() - 1, format!("        }}"));
+            if let Some(abis) = abi_filters
+                && !abis.is_empty()
+            {
+                out.insert(out.len() - 1, "        ndk {".to_string());
+                for abi in abis {
+                    out.insert(out.len() - 1, format!("            abiFilters.add(\"{}\")", abi));
                 }
+                out.insert(out.len() - 1, "        }".to_string());
             }
             in_default_config = false;
             added_abi_filters = true;
@@ -202,33 +820,451 @@ pub fn apply_app_gradle(
             in_build_types = true;
         }
 
+        if in_build_types && line.trim().starts_with("release {") && !added_release_lines {
+            in_release = true;
+            continue;
+        }
+
+        if in_release && line.trim() == "}" {
+            for release_line in &release_lines {
+                out.insert(out.len() - 1, release_line.clone());
+            }
+            in_release = false;
+            added_release_lines = true;
+        }
+
         if in_build_types && line.trim() == "}" && !added_output_config {
             in_build_types = false;
-            if let Some(filename_pattern) = output_file_name {
+            if !output_lines.is_empty() {
                 out.push(String::new());
                 out.push("    applicationVariants.all {".to_string());
+                out.push("        val variant = this".to_string());
                 out.push("        outputs.all {".to_string());
                 out.push("            val output = this as com.android.build.gradle.internal.api.BaseVariantOutputImpl".to_string());
-                out.push(format!("            output.outputFileName = \"{}\"", filename_pattern));
+                out.push("            val abi = output.getFilter(com.android.build.OutputFile.ABI) ?: \"universal\"".to_string());
+                for line in &output_lines {
+                    out.push(format!("            {}", line));
+                }
                 out.push("        }".to_string());
                 out.push("    }".to_string());
                 added_output_config = true;
             }
         }
     }
+
+    if !android_lines.is_empty() {
+        let android_start = out.iter().position(|line| line == "android {");
+        let android_end = android_start
+            .and_then(|start| (start..out.len()).find(|&i| out[i] == "}"));
+        let Some(at) = android_end else {
+            bail!("android {{}} block not found in: {}", path.display());
+        };
+        out.insert(at, String::new());
+        for (offset, line) in android_lines.into_iter().enumerate() {
+            out.insert(at + 1 + offset, line);
+        }
+    }
+
+    if !dependencies.is_empty() {
+        // Extend the script's own top-level block (Flutter's template may already have
+        // one); a second `dependencies {}` block would work but reads like a mistake.
+        let block_start = out.iter().position(|line| line == "dependencies {");
+        let block_end = block_start.and_then(|start| (start..out.len()).find(|&i| out[i] == "}"));
+        match (block_start, block_end) {
+            (Some(start), Some(end)) => {
+                let mut dependencies: Vec<String> = dependencies
+                    .into_iter()
+                    .filter(|line| !out[start..end].iter().any(|existing| existing.trim() == line.trim()))
+                    .collect();
+                dependencies.reverse();
+                for line in dependencies {
+                    out.insert(end, line);
+                }
+            }
+            _ => {
+                out.push(String::new());
+                out.push("dependencies {".to_string());
+                out.extend(dependencies);
+                out.push("}".to_string());
+            }
+        }
+    }
+
     fs::write(path, out.join("\n") + "\n")
         .with_context(|| format!("Failed to write file: {}", path.display()))?;
     Ok(())
 }
 
-pub fn apply_gradle_wrapper_properties(path: &Path, distribution_url: &str) -> Result<()> {
+pub fn apply_gradle_wrapper_properties(path: &Path, config: &AndroidGradleWrapperConfig) -> Result<()> {
     let mut props = read_properties(path)?;
-    props.insert("distributionUrl".to_string(), distribution_url.to_string());
+    if let Some(distribution_url) = &config.distribution_url {
+        props.insert("distributionUrl".to_string(), distribution_url.clone());
+    }
+    if let Some(sum) = &config.distribution_sha256_sum {
+        props.insert("distributionSha256Sum".to_string(), sum.clone());
+    }
+    if let Some(timeout) = config.network_timeout {
+        props.insert("networkTimeout".to_string(), timeout.to_string());
+    }
     write_properties(path, &props)?;
     Ok(())
 }
 
-fn read_properties(path: &Path) -> Result<HashMap<String, String>> {
+/// Creates the baseline profile source set picked up by the `androidx.baselineprofile` plugin.
+fn write_baseline_profile_rules(
+    project_dir: &Path,
+    android_dir: &Path,
+    config: &AndroidBaselineProfileConfig,
+) -> Result<()> {
+    let dir = android_dir.join("app/src/main/baselineProfiles");
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create dir: {}", dir.display()))?;
+    let dst = dir.join("baseline-prof.txt");
+    if let Some(rules) = &config.rules {
+        let src = project_dir.join(rules);
+        fs::copy(&src, &dst).with_context(|| {
+            format!("Failed to copy baseline profile: {} -> {}", src.display(), dst.display())
+        })?;
+        println!("✓ Baseline profile rules copied: {}", dst.display());
+    } else if !dst.exists() {
+        // An empty rules file keeps the source set in version control until a producer fills it.
+        fs::write(&dst, "").with_context(|| format!("Failed to write file: {}", dst.display()))?;
+    }
+    Ok(())
+}
+
+/// Writes a `com.android.dynamic-feature` module per entry (build script, manifest
+/// with its delivery mode), the base module's title strings, the settings includes and
+/// the matching pubspec.yaml `deferred-components`. Modules take compileSdk, minSdk
+/// and Java level from `:app`, so they follow the Flutter defaults.
+fn write_dynamic_feature_modules(
+    project_dir: &Path,
+    android_dir: &Path,
+    config: &AndroidDynamicFeaturesConfig,
+    namespace: &str,
+) -> Result<()> {
+    for module in &config.modules {
+        let valid = module.name.chars().next().is_some_and(|c| c.is_ascii_lowercase())
+            && module.name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if !valid || module.name == "app" {
+            bail!(
+                "Invalid dynamic feature module name '{}': use lowercase letters, digits and '_', not 'app'",
+                module.name
+            );
+        }
+        if config.modules.iter().filter(|other| other.name == module.name).count() > 1 {
+            bail!("Duplicate dynamic feature module: {}", module.name);
+        }
+
+        let module_dir = android_dir.join(&module.name);
+        let main_dir = module_dir.join("src/main");
+        fs::create_dir_all(&main_dir)
+            .with_context(|| format!("Failed to create dir: {}", main_dir.display()))?;
+        let build_script = [
+            "plugins {".to_string(),
+            "    id(\"com.android.dynamic-feature\")".to_string(),
+            "}".to_string(),
+            String::new(),
+            "evaluationDependsOn(\":app\")".to_string(),
+            "val appAndroid = project(\":app\").extensions.getByType<com.android.build.api.dsl.ApplicationExtension>()".to_string(),
+            String::new(),
+            "android {".to_string(),
+            format!("    namespace = \"{}.{}\"", namespace, module.name),
+            "    compileSdk = appAndroid.compileSdk".to_string(),
+            String::new(),
+            "    defaultConfig {".to_string(),
+            "        minSdk = appAndroid.defaultConfig.minSdk".to_string(),
+            "    }".to_string(),
+            String::new(),
+            "    compileOptions {".to_string(),
+            "        sourceCompatibility = appAndroid.compileOptions.sourceCompatibility".to_string(),
+            "        targetCompatibility = appAndroid.compileOptions.targetCompatibility".to_string(),
+            "    }".to_string(),
+            "}".to_string(),
+            String::new(),
+            "dependencies {".to_string(),
+            "    implementation(project(\":app\"))".to_string(),
+            "}".to_string(),
+        ];
+        let path = module_dir.join("build.gradle.kts");
+        fs::write(&path, build_script.join("\n") + "\n")
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+
+        let delivery = if module.on_demand { "on-demand" } else { "install-time" };
+        let manifest = [
+            "<manifest xmlns:android=\"http://schemas.android.com/apk/res/android\"".to_string(),
+            "    xmlns:dist=\"http://schemas.android.com/apk/distribution\">".to_string(),
+            "    <dist:module".to_string(),
+            "        dist:instant=\"false\"".to_string(),
+            format!("        dist:title=\"@string/{}Name\">", module.name),
+            "        <dist:delivery>".to_string(),
+            format!("            <dist:{} />", delivery),
+            "        </dist:delivery>".to_string(),
+            "        <dist:fusing dist:include=\"true\" />".to_string(),
+            "    </dist:module>".to_string(),
+            "</manifest>".to_string(),
+        ];
+        let path = main_dir.join("AndroidManifest.xml");
+        fs::write(&path, manifest.join("\n") + "\n")
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+        println!("✓ Dynamic feature module generated: {} ({})", module_dir.display(), delivery);
+    }
+
+    // Module titles must be resources of the base module.
+    let values_dir = android_dir.join("app/src/main/res/values");
+    fs::create_dir_all(&values_dir)
+        .with_context(|| format!("Failed to create dir: {}", values_dir.display()))?;
+    let mut strings = vec![
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>".to_string(),
+        "<resources>".to_string(),
+    ];
+    for module in &config.modules {
+        strings.push(format!(
+            "    <string name=\"{}Name\">{}</string>",
+            module.name,
+            android_string_escape(module.title.as_deref().unwrap_or(&module.name))
+        ));
+    }
+    strings.push("</resources>".to_string());
+    let path = values_dir.join("dynamic_features.xml");
+    fs::write(&path, strings.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+
+    let path = android_dir.join("settings.gradle.kts");
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    let Some(app_include) = lines.iter().position(|line| line.trim() == "include(\":app\")") else {
+        bail!("include(\":app\") not found in: {}", path.display());
+    };
+    let includes: Vec<String> = config
+        .modules
+        .iter()
+        .map(|module| format!("include(\":{}\")", module.name))
+        .filter(|include| !lines.iter().any(|line| line.trim() == include))
+        .collect();
+    lines.splice(app_include + 1..app_include + 1, includes);
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+
+    let components: Vec<DeferredComponent> = config
+        .modules
+        .iter()
+        .map(|module| DeferredComponent {
+            name: module.name.clone(),
+            libraries: module.libraries.clone(),
+            assets: module.assets.clone(),
+        })
+        .collect();
+    set_deferred_components(&project_dir.join("pubspec.yaml"), &components)?;
+    Ok(())
+}
+
+/// Text for a `<string>` resource: XML-escaped, with the quotes and backslashes aapt2
+/// treats specially escaped and a leading `@`/`?` kept from reading as a reference.
+fn android_string_escape(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\'', "\\'")
+        .replace('"', "\\\"");
+    if escaped.starts_with(['@', '?']) {
+        format!("\\{}", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// Copies the TV launcher banner referenced by `android:banner="@drawable/banner"`.
+fn copy_tv_banner(project_dir: &Path, android_dir: &Path, banner: &str) -> Result<()> {
+    let src = project_dir.join(banner);
+    let ext = src.extension().and_then(|e| e.to_str()).unwrap_or("png");
+    let dst = android_dir.join(format!("app/src/main/res/drawable-xhdpi/banner.{}", ext));
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::copy(&src, &dst).with_context(|| {
+        format!("Failed to copy TV banner: {} -> {}", src.display(), dst.display())
+    })?;
+    println!("✓ TV banner copied: {}", dst.display());
+    Ok(())
+}
+
+fn env_path(keys: &[&str]) -> Option<String> {
+    keys.iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.trim().is_empty())
+}
+
+fn write_local_properties(android_dir: &Path, config: &AndroidLocalPropertiesConfig) -> Result<()> {
+    let Some(sdk_dir) = config
+        .sdk_dir
+        .clone()
+        .or_else(|| env_path(&["ANDROID_HOME", "ANDROID_SDK_ROOT"]))
+    else {
+        bail!("android.local_properties: set sdk_dir or ANDROID_HOME/ANDROID_SDK_ROOT");
+    };
+    let Some(flutter_sdk) = config.flutter_sdk.clone().or_else(|| env_path(&["FLUTTER_ROOT"])) else {
+        bail!("android.local_properties: set flutter_sdk or FLUTTER_ROOT");
+    };
+
+    // Merge so values flutter writes itself (flutter.versionName, ...) are kept.
+    let path = android_dir.join("local.properties");
+    let mut props = read_properties(&path)?;
+    props.insert("sdk.dir".to_string(), sdk_dir);
+    props.insert("flutter.sdk".to_string(), flutter_sdk);
+    write_properties(&path, &props)?;
+    println!("✓ local.properties written: {}", path.display());
+    Ok(())
+}
+
+/// Splits ".../gradle-8.7-all.zip" into ("8.7", "all").
+fn parse_distribution_url(url: &str) -> Option<(String, String)> {
+    let file = url.rsplit('/').next()?;
+    let stem = file.strip_prefix("gradle-")?.strip_suffix(".zip")?;
+    let (version, kind) = stem.rsplit_once('-')?;
+    Some((version.to_string(), kind.to_string()))
+}
+
+fn gradlew_name() -> &'static str {
+    if cfg!(windows) { "gradlew.bat" } else { "gradlew" }
+}
+
+/// Regenerates gradle-wrapper.jar and the gradlew scripts in a scratch project, then copies
+/// them into `android_dir`. `wrapper` runs twice, the second time through the new wrapper,
+/// so the files come from the requested Gradle version rather than the one running the task.
+fn regenerate_gradle_wrapper(android_dir: &Path, config: &AndroidGradleWrapperConfig) -> Result<()> {
+    let Some(url) = &config.distribution_url else {
+        bail!("android.gradle_wrapper.regenerate requires distribution_url");
+    };
+    let Some((version, distribution_type)) = parse_distribution_url(url) else {
+        bail!("Cannot parse Gradle version from distribution_url: {}", url);
+    };
+
+    let existing_wrapper = android_dir.join(gradlew_name());
+    let gradle_cmd = if existing_wrapper.exists() {
+        // Runs from a temp dir, so a relative project path would not resolve.
+        std::path::absolute(&existing_wrapper)
+            .with_context(|| format!("Failed to resolve path: {}", existing_wrapper.display()))?
+    } else {
+        resolve_cmd("gradle")
+            .context("android.gradle_wrapper.regenerate needs an existing gradlew or gradle in PATH")?
+    };
+
+    let work_dir = std::env::temp_dir().join(format!("flutter_gen_gradle_wrapper_{}", std::process::id()));
+    fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed to create dir: {}", work_dir.display()))?;
+    fs::write(work_dir.join("settings.gradle.kts"), "")
+        .with_context(|| format!("Failed to write file: {}", work_dir.display()))?;
+    let sum = config.distribution_sha256_sum.as_deref();
+    let result = run_gradle_wrapper(&gradle_cmd, &work_dir, &version, &distribution_type, sum)
+        .and_then(|_| {
+            run_gradle_wrapper(
+                &work_dir.join(gradlew_name()),
+                &work_dir,
+                &version,
+                &distribution_type,
+                sum,
+            )
+        })
+        .and_then(|_| {
+            for file in ["gradlew", "gradlew.bat", "gradle/wrapper/gradle-wrapper.jar"] {
+                let src = work_dir.join(file);
+                let dst = android_dir.join(file);
+                if let Some(parent) = dst.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+                }
+                fs::copy(&src, &dst)
+                    .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+            }
+            Ok(())
+        });
+    let _ = fs::remove_dir_all(&work_dir);
+    result?;
+    println!("✓ Gradle wrapper regenerated for Gradle {}", version);
+    Ok(())
+}
+
+/// Drive prefix (`C:`) of a path on Windows; None elsewhere.
+fn path_drive(path: &Path) -> Option<std::ffi::OsString> {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    match path.components().next() {
+        Some(std::path::Component::Prefix(prefix)) => {
+            Some(prefix.as_os_str().to_ascii_uppercase())
+        }
+        _ => None,
+    }
+}
+
+/// Fills in defaults that depend on the machine: Kotlin incremental compilation breaks
+/// when the project and the pub cache (plugin sources) are on different drives.
+pub fn apply_build_performance_defaults(config: &mut AndroidConfig, project_dir: &Path) {
+    let performance = &mut config.build_performance;
+    if performance.kotlin_incremental.is_some() {
+        return;
+    }
+    let pub_cache = std::env::var_os("PUB_CACHE")
+        .map(std::path::PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("LOCALAPPDATA").map(|dir| std::path::PathBuf::from(dir).join("Pub/Cache"))
+        });
+    if let Some(pub_cache) = pub_cache {
+        let project_drive = path_drive(project_dir);
+        if project_drive.is_some() && project_drive != path_drive(&pub_cache) {
+            println!("Project and pub cache are on different drives; disabling Kotlin incremental compilation");
+            performance.kotlin_incremental = Some(false);
+        }
+    }
+}
+
+/// gradle.properties entries from `android.build_performance`.
+pub fn gradle_property_entries(config: &AndroidConfig) -> Vec<(&'static str, String)> {
+    let performance = &config.build_performance;
+    let flags = [
+        ("kotlin.incremental", performance.kotlin_incremental),
+        ("org.gradle.caching", performance.build_cache),
+        ("org.gradle.parallel", performance.parallel),
+        ("org.gradle.configuration-cache", performance.configuration_cache),
+        ("kapt.use.worker.api", performance.kapt_workers),
+    ];
+    let mut entries: Vec<(&'static str, String)> = flags
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|v| (key, v.to_string())))
+        .collect();
+    if let Some(jvm_args) = &performance.jvm_args {
+        entries.push(("org.gradle.jvmargs", jvm_args.clone()));
+    }
+    entries
+}
+
+/// Sets keys in gradle.properties line by line, so the template's order and comments survive.
+fn apply_gradle_properties(path: &Path, entries: &[(&str, String)]) -> Result<()> {
+    let content = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read file: {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let mut lines: Vec<String> = content.lines().map(|line| line.to_string()).collect();
+    for (key, value) in entries {
+        let entry = format!("{}={}", key, value);
+        let existing = lines.iter().position(|line| {
+            line.split_once('=')
+                .is_some_and(|(k, _)| k.trim() == *key && !line.trim_start().starts_with('#'))
+        });
+        match existing {
+            Some(i) => lines[i] = entry,
+            None => lines.push(entry),
+        }
+    }
+    fs::write(path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
+
+pub fn read_properties(path: &Path) -> Result<HashMap<String, String>> {
     if !path.exists() {
         return Ok(HashMap::new());
     }
@@ -239,52 +1275,263 @@ fn read_properties(path: &Path) -> Result<HashMap<String, String>> {
     Ok(props)
 }
 
+/// Writes the properties sorted by key, so regenerating an unchanged file is a no-op.
 fn write_properties(path: &Path, props: &HashMap<String, String>) -> Result<()> {
     let file = fs::File::create(path)
         .with_context(|| format!("Failed to write file: {}", path.display()))?;
-    java_properties::write(std::io::BufWriter::new(file), props)
+    let mut writer = java_properties::PropertiesWriter::new(std::io::BufWriter::new(file));
+    let mut keys: Vec<&String> = props.keys().collect();
+    keys.sort();
+    for key in keys {
+        writer
+            .write(key, &props[key])
+            .with_context(|| format!("Failed to write properties: {}", path.display()))?;
+    }
+    writer
+        .finish()
         .with_context(|| format!("Failed to write properties: {}", path.display()))?;
     Ok(())
 }
 
-pub fn process_android_platform(
-    project_dir: &Path,
-    config: &AndroidConfig,
-    platforms_dir: Option<&str>,
-) -> Result<()> {
+fn write_file_paths(android_dir: &Path, config: &AndroidFileProviderConfig) -> Result<()> {
+    let mut lines = vec![
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>".to_string(),
+        "<paths>".to_string(),
+    ];
+    if config.paths.is_empty() {
+        lines.push("    <cache-path name=\"cache\" path=\".\" />".to_string());
+        lines.push("    <files-path name=\"files\" path=\".\" />".to_string());
+    }
+    for entry in &config.paths {
+        lines.push(format!(
+            "    <{} name=\"{}\" path=\"{}\" />",
+            entry.kind.as_element(),
+            entry.name,
+            entry.path
+        ));
+    }
+    lines.push("</paths>".to_string());
+
+    let path = android_dir.join("app/src/main/res/xml/file_paths.xml");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
+
+/// Gradle scripts edited line by line. Their flutter create versions are kept under
+/// `PRISTINE_DIR` so `--mode patch` re-applies the edits to them instead of stacking
+/// another copy on top of the previous run's output.
+const GRADLE_SCRIPTS: [&str; 3] = ["build.gradle.kts", "settings.gradle.kts", "app/build.gradle.kts"];
+
+pub const PRISTINE_DIR: &str = ".flutter_gen_pristine";
+
+/// Keeps the freshly created gradle scripts for later `--mode patch` runs.
+pub fn save_pristine_gradle_scripts(project_dir: &Path) -> Result<()> {
+    for script in GRADLE_SCRIPTS {
+        let src = project_dir.join("android").join(script);
+        if !src.exists() {
+            continue;
+        }
+        let dst = project_dir.join(PRISTINE_DIR).join("android").join(script);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+        }
+        fs::copy(&src, &dst)
+            .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+    }
+    Ok(())
+}
+
+/// Puts the flutter create gradle scripts back before the edits are re-applied.
+pub fn restore_pristine_gradle_scripts(project_dir: &Path) -> Result<()> {
+    let pristine_dir = project_dir.join(PRISTINE_DIR).join("android");
+    for script in GRADLE_SCRIPTS {
+        let src = pristine_dir.join(script);
+        if !src.exists() {
+            bail!(
+                "{} not found (run a full generation first)",
+                Path::new(PRISTINE_DIR).join("android").join(script).display()
+            );
+        }
+        let dst = project_dir.join("android").join(script);
+        fs::copy(&src, &dst)
+            .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+    }
+    println!("✓ Restored gradle scripts from {}", pristine_dir.display());
+    Ok(())
+}
+
+pub fn process_android_platform(project_dir: &Path, cfg: &Config) -> Result<()> {
     let android_dir = project_dir.join("android");
+    let config = &cfg.android;
+    let renderer = OverlayRenderer::new(cfg)?;
 
-    let platforms_root = platforms_dir
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty())
-        .unwrap_or("platforms");
-    let templates_dir = std::path::PathBuf::from(platforms_root).join("android");
-    copy_manifest_templates(project_dir, &android_dir, &templates_dir)?;
+    let templates_dir = cfg.platforms_root(project_dir).join("android");
+    copy_manifest_templates(&android_dir, &templates_dir, &renderer)?;
+    let mut main_activity_dir = templates_dir.clone();
+    if let Some(form_factor) = config.form_factor {
+        let form_factor_dir = templates_dir.join(form_factor.as_str());
+        if form_factor_dir.is_dir() {
+            copy_template_files(&form_factor_dir, &android_dir, &renderer)?;
+            println!("✓ {} templates applied: {}", form_factor.as_str(), form_factor_dir.display());
+            if ["kt", "java"]
+                .iter()
+                .any(|ext| form_factor_dir.join(format!("MainActivity.{}", ext)).exists())
+            {
+                main_activity_dir = form_factor_dir;
+            }
+        }
+    }
+    apply_main_activity_template(&android_dir, &main_activity_dir, &config.app.build.namespace)?;
+    if let Some(network_security) = &config.network_security {
+        write_network_security_config(&android_dir, network_security)?;
+    }
+    if let Some(integrity) = &config.play.integrity {
+        write_play_integrity_resources(&android_dir, integrity)?;
+    }
+    if let Some(file_provider) = &config.file_provider {
+        write_file_paths(&android_dir, file_provider)?;
+    }
+    if let Some(banner) = config.tv.as_ref().and_then(|tv| tv.banner.as_deref()) {
+        copy_tv_banner(project_dir, &android_dir, banner)?;
+    }
+    if config.baseline_profile.enabled {
+        write_baseline_profile_rules(project_dir, &android_dir, &config.baseline_profile)?;
+    }
+    if let Some(features) = &config.dynamic_features
+        && !features.modules.is_empty()
+    {
+        write_dynamic_feature_modules(project_dir, &android_dir, features, &config.app.build.namespace)?;
+    }
+    apply_main_manifest(&android_dir.join("app/src/main/AndroidManifest.xml"), config)?;
 
+    let fail_on_project_repos = config
+        .settings
+        .dependency_resolution
+        .as_ref()
+        .is_some_and(|resolution| resolution.mode == RepositoriesMode::FailOnProjectRepos);
+    let mut moved_repositories = Vec::new();
+    if fail_on_project_repos {
+        let declared = remove_allprojects_repositories(&android_dir.join("build.gradle.kts"))?;
+        if !config.build.allprojects.replace {
+            moved_repositories = declared;
+        }
+    } else {
+        apply_repositories(
+            &android_dir.join("build.gradle.kts"),
+            &config.build.allprojects.repositories,
+            config.build.allprojects.replace,
+        )?;
+    }
     apply_repositories(
-        &android_dir.join("build.gradle.kts"),
-        &config.build.allprojects.repositories,
-    )?;
-    apply_plugin_repositories(
         &android_dir.join("settings.gradle.kts"),
         &config.settings.plugin_management.repositories,
+        config.settings.plugin_management.replace,
     )?;
-    apply_app_gradle(
-        &android_dir.join("app/build.gradle.kts"),
-        &config.app.build.namespace,
-        &config.app.build.application_id,
-        config.app.build.output_file_name.as_deref(),
-        config.app.build.abi_filters.as_deref(),
-        config.app.build.kotlin_incremental,
+    let mut resolution_lines = dependency_resolution_lines(config);
+    if !resolution_lines.is_empty() {
+        // The template's allprojects repositories, before the closing `    }` and `}`.
+        for line in moved_repositories {
+            let single = line.matches('{').count() == line.matches('}').count();
+            if !(single && resolution_lines.iter().any(|existing| existing.trim() == line.trim())) {
+                resolution_lines.insert(resolution_lines.len() - 2, line);
+            }
+        }
+        apply_dependency_resolution(&android_dir.join("settings.gradle.kts"), &resolution_lines)?;
+    }
+    apply_settings_plugins(
+        &android_dir.join("settings.gradle.kts"),
+        &settings_plugins(config),
     )?;
+    apply_app_gradle(&android_dir.join("app/build.gradle.kts"), config)?;
     // Manifests are fully driven by template files under platforms/android.
-    if let Some(distribution_url) = &config.gradle_wrapper.distribution_url {
+    if config.gradle_wrapper.regenerate {
+        regenerate_gradle_wrapper(&android_dir, &config.gradle_wrapper)?;
+    }
+    let wrapper = &config.gradle_wrapper;
+    if wrapper.distribution_url.is_some()
+        || wrapper.distribution_sha256_sum.is_some()
+        || wrapper.network_timeout.is_some()
+    {
         apply_gradle_wrapper_properties(
             &android_dir.join("gradle/wrapper/gradle-wrapper.properties"),
-            distribution_url,
+            wrapper,
         )?;
     }
 
+    let gradle_properties = gradle_property_entries(config);
+    if !gradle_properties.is_empty() {
+        apply_gradle_properties(&android_dir.join("gradle.properties"), &gradle_properties)?;
+    }
+
+    if let Some(local_properties) = &config.local_properties {
+        write_local_properties(&android_dir, local_properties)?;
+    }
+
+    verify_android_platform(&android_dir, config)?;
+
     println!("Android directory generated at: {}", android_dir.display());
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_file_name_expr_escapes_literal_text() {
+        assert_eq!(
+            output_file_name_expr(r#"a"b$c\d-{version_name}.apk"#),
+            r#""a\"b\$c\\d-${variant.versionName}.apk""#
+        );
+    }
+
+    #[test]
+    fn android_string_escape_handles_quotes_markup_and_references() {
+        assert_eq!(android_string_escape(r#"It's "A" & <b>\n"#), r#"It\'s \"A\" &amp; &lt;b&gt;\\n"#);
+        assert_eq!(android_string_escape("@home"), r"\@home");
+        assert_eq!(android_string_escape("?attr"), r"\?attr");
+        assert_eq!(android_string_escape("a@b"), "a@b");
+    }
+
+    #[test]
+    fn apply_repositories_replaces_multi_line_entries_whole() {
+        let path = std::env::temp_dir().join(format!("flutter_gen_test_{}_repositories.kts", std::process::id()));
+        let original = [
+            "allprojects {",
+            "    repositories {",
+            "        google()",
+            "        maven {",
+            "            url = uri(\"https://example.com/maven\")",
+            "        }",
+            "        mavenCentral()",
+            "    }",
+            "}",
+        ];
+        fs::write(&path, original.join("\n")).unwrap();
+        let repos = vec!["https://mirror.example.com/maven".to_string()];
+
+        apply_repositories(&path, &repos, true).unwrap();
+        let replaced = fs::read_to_string(&path).unwrap();
+        let expected = [
+            "allprojects {",
+            "    repositories {",
+            "        maven { url = uri(\"https://mirror.example.com/maven\") }",
+            "    }",
+            "}",
+        ];
+        assert_eq!(replaced, expected.join("\n") + "\n");
+
+        fs::write(&path, original.join("\n")).unwrap();
+        apply_repositories(&path, &repos, false).unwrap();
+        let appended = fs::read_to_string(&path).unwrap();
+        assert_eq!(appended.lines().count(), original.len() + 1);
+        assert!(appended.contains("            url = uri(\"https://example.com/maven\")\n        }\n        mavenCentral()"));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use serde_json::json;
+use std::path::Path;
+
+use crate::config::AssetsConfig;
+use crate::images::run_image_job;
+use crate::pubspec::add_flutter_assets;
+
+/// Density variants generated for every declared image, following Flutter's
+/// `2.0x/`, `3.0x/` resolution-aware asset layout.
+const DENSITIES: [(u32, &str); 3] = [(1, ""), (2, "2.0x/"), (3, "3.0x/")];
+
+pub fn process_assets(project_dir: &Path, config: &AssetsConfig) -> Result<()> {
+    if config.images.is_empty() {
+        return Ok(());
+    }
+
+    let output_dir = config.output_dir.trim_end_matches('/');
+    let mut images = Vec::new();
+    let mut entries = Vec::new();
+
+    for image in &config.images {
+        let source = project_dir.join(&image.source);
+        if !source.exists() {
+            bail!("Asset source image not found: {}", source.display());
+        }
+        let name = match &image.name {
+            Some(name) => name.clone(),
+            None => Path::new(&image.source)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        };
+        let webp = image.webp.unwrap_or(config.webp);
+        let (format, ext) = if webp { ("webp", "webp") } else { ("png", "png") };
+
+        let outputs: Vec<_> = DENSITIES
+            .iter()
+            .map(|(scale, subdir)| {
+                json!({
+                    "path": format!("{}/{}{}.{}", output_dir, subdir, name, ext),
+                    "width": image.width * scale,
+                    "height": image.height.map(|h| h * scale),
+                    "format": format,
+                })
+            })
+            .collect();
+        images.push(json!({ "source": image.source, "outputs": outputs }));
+        entries.push(format!("{}/{}.{}", output_dir, name, ext));
+    }
+
+    run_image_job(project_dir, &json!({ "images": images }))?;
+    add_flutter_assets(&project_dir.join("pubspec.yaml"), &entries)?;
+    println!("✓ Generated {} asset images under {}", config.images.len(), output_dir);
+    Ok(())
+}
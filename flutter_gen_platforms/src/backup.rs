@@ -0,0 +1,154 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::releases::utc_timestamp;
+use crate::utils::{copy_dir_recursive, remove_dir_all_with_retry, remove_platform_dir};
+
+pub const BACKUP_DIR: &str = ".flutter_gen_backup";
+
+/// Build caches inside platform directories, recreated by the next build.
+pub const CACHE_DIRS: [&str; 5] = [".gradle", ".cxx", "build", "Pods", "ephemeral"];
+
+/// Names of the existing backups, oldest first (timestamps sort chronologically).
+fn backup_names(project_dir: &Path) -> Result<Vec<String>> {
+    let root = project_dir.join(BACKUP_DIR);
+    if !root.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&root).with_context(|| format!("Failed to read dir: {}", root.display()))? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Platform directories saved in a backup.
+fn backup_platforms(backup: &Path) -> Result<Vec<String>> {
+    let mut platforms = Vec::new();
+    for entry in fs::read_dir(backup).with_context(|| format!("Failed to read dir: {}", backup.display()))? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            platforms.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    platforms.sort();
+    Ok(platforms)
+}
+
+/// Copies `src` to `dst`, leaving out build caches and symlinks.
+fn copy_platform_dir(src: &Path, dst: &Path) -> Result<()> {
+    let walker = walkdir::WalkDir::new(src)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !CACHE_DIRS.iter().any(|dir| entry.file_name() == *dir));
+    for entry in walker {
+        let entry = entry.with_context(|| format!("Failed to walk directory: {}", src.display()))?;
+        let relative = entry.path().strip_prefix(src).unwrap_or(entry.path());
+        let target = dst.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create dir: {}", target.display()))?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target).with_context(|| {
+                format!("Failed to copy {} -> {}", entry.path().display(), target.display())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the existing platform directories among `names` to a new
+/// `.flutter_gen_backup/<timestamp>/`, then deletes all but the newest `keep` backups
+/// (0 keeps all). Returns the backup's directory, or `None` when there was nothing to save.
+pub fn backup_platform_dirs(project_dir: &Path, names: &[&str], keep: usize, dry_run: bool) -> Result<Option<PathBuf>> {
+    let existing: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| {
+            fs::symlink_metadata(project_dir.join(name)).is_ok_and(|meta| meta.file_type().is_dir())
+        })
+        .collect();
+    if existing.is_empty() {
+        return Ok(None);
+    }
+
+    // Colons aren't allowed in Windows file names.
+    let stamp = utc_timestamp().replace(':', "-");
+    let taken = backup_names(project_dir)?;
+    let mut name = stamp.clone();
+    let mut suffix = 2;
+    // Zero-padded so backups made within the same second still sort in creation order.
+    while taken.contains(&name) {
+        name = format!("{}-{:03}", stamp, suffix);
+        suffix += 1;
+    }
+    let backup = project_dir.join(BACKUP_DIR).join(&name);
+    if dry_run {
+        println!("[DRY RUN] Would back up {} to: {}", existing.join(", "), backup.display());
+        return Ok(None);
+    }
+
+    for platform in &existing {
+        copy_platform_dir(&project_dir.join(platform), &backup.join(platform))?;
+    }
+    println!("✓ Backed up {} to: {}", existing.join(", "), backup.display());
+
+    if keep > 0 {
+        let names = backup_names(project_dir)?;
+        for old in names.iter().take(names.len().saturating_sub(keep)) {
+            remove_dir_all_with_retry(&project_dir.join(BACKUP_DIR).join(old))?;
+        }
+    }
+    Ok(Some(backup))
+}
+
+/// Prints the backups, newest first.
+pub fn list_backups(project_dir: &Path) -> Result<()> {
+    let names = backup_names(project_dir)?;
+    if names.is_empty() {
+        println!("No backups in: {}", project_dir.join(BACKUP_DIR).display());
+        return Ok(());
+    }
+    for name in names.iter().rev() {
+        let platforms = backup_platforms(&project_dir.join(BACKUP_DIR).join(name))?;
+        println!("{}  {}", name, platforms.join(", "));
+    }
+    Ok(())
+}
+
+/// Puts the platform directories of a backup (the newest unless `from` names one) back
+/// in place. The directories being replaced are backed up first, so a restore can be
+/// undone by restoring again.
+pub fn restore_backup(project_dir: &Path, from: Option<&str>, dry_run: bool) -> Result<()> {
+    let names = backup_names(project_dir)?;
+    let name = match from {
+        Some(from) if names.iter().any(|name| name == from) => from.to_string(),
+        Some(from) => bail!("Backup not found: {}", project_dir.join(BACKUP_DIR).join(from).display()),
+        None => match names.last() {
+            Some(name) => name.clone(),
+            None => bail!("No backups in: {}", project_dir.join(BACKUP_DIR).display()),
+        },
+    };
+    let backup = project_dir.join(BACKUP_DIR).join(&name);
+    let platforms = backup_platforms(&backup)?;
+    if platforms.is_empty() {
+        bail!("Backup is empty: {}", backup.display());
+    }
+
+    let current: Vec<&str> = platforms.iter().map(String::as_str).collect();
+    backup_platform_dirs(project_dir, &current, 0, dry_run)?;
+    if dry_run {
+        println!("[DRY RUN] Would restore {} from: {}", platforms.join(", "), backup.display());
+        return Ok(());
+    }
+    for platform in &platforms {
+        remove_platform_dir(project_dir, platform, &[])?;
+        copy_dir_recursive(&backup.join(platform), &project_dir.join(platform))?;
+    }
+    println!("✓ Restored {} from: {}", platforms.join(", "), backup.display());
+    Ok(())
+}
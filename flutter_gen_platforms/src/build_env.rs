@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::config::{BuildEnvValue, Config};
+
+const DEFAULT_OUTPUT: &str = "lib/generated/build_env.dart";
+const PRODUCTION_PROFILES: [&str; 3] = ["prod", "production", "release"];
+
+fn dart_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'").replace('$', "\\$"))
+}
+
+fn dart_identifier(key: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    for c in key.chars() {
+        if c == '_' || c == '-' || c == '.' {
+            upper = !out.is_empty();
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn dart_const(value: &BuildEnvValue) -> (&'static str, String) {
+    match value {
+        BuildEnvValue::Bool(v) => ("bool", v.to_string()),
+        BuildEnvValue::Int(v) => ("int", v.to_string()),
+        BuildEnvValue::Float(v) => ("double", format!("{:?}", v)),
+        BuildEnvValue::String(v) => ("String", dart_string(v)),
+    }
+}
+
+/// Writes the `BuildEnv` Dart class (profile, version, banner, flags) that apps
+/// use to show environment watermarks.
+pub fn write_build_env(project_dir: &Path, cfg: &Config) -> Result<()> {
+    let Some(config) = &cfg.build_env else {
+        return Ok(());
+    };
+    let profile = config.profile.as_deref().unwrap_or("dev");
+    let banner = config.banner.clone().or_else(|| {
+        (!PRODUCTION_PROFILES.contains(&profile)).then(|| profile.to_uppercase())
+    });
+
+    let mut lines = vec![
+        "// GENERATED by flutter_gen_platforms. Do not edit by hand.".to_string(),
+        String::new(),
+        "class BuildEnv {".to_string(),
+        "  BuildEnv._();".to_string(),
+        String::new(),
+        format!("  static const String profile = {};", dart_string(profile)),
+        format!(
+            "  static const String version = {};",
+            dart_string(cfg.version.as_deref().unwrap_or(""))
+        ),
+        format!(
+            "  static const String? banner = {};",
+            banner.as_deref().map(dart_string).unwrap_or_else(|| "null".to_string())
+        ),
+        format!(
+            "  static const bool isProduction = {};",
+            PRODUCTION_PROFILES.contains(&profile)
+        ),
+    ];
+    for (key, value) in &config.flags {
+        let (ty, literal) = dart_const(value);
+        lines.push(format!("  static const {} {} = {};", ty, dart_identifier(key), literal));
+    }
+    lines.push("}".to_string());
+
+    let output = project_dir.join(config.output.as_deref().unwrap_or(DEFAULT_OUTPUT));
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::write(&output, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", output.display()))?;
+    println!("✓ Build environment ({}) written to {}", profile, output.display());
+    Ok(())
+}
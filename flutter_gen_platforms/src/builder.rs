@@ -0,0 +1,543 @@
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+use which::which;
+
+use crate::config::{AndroidBuildTarget, BuildConfig};
+use crate::preview::copy_project;
+
+/// Flutter `--target-platform` name for an Android ABI.
+pub fn target_platform(abi: &str) -> Result<&'static str> {
+    match abi {
+        "arm64-v8a" => Ok("android-arm64"),
+        "armeabi-v7a" => Ok("android-arm"),
+        "x86_64" => Ok("android-x64"),
+        other => bail!("Unknown ABI: {} (expected arm64-v8a, armeabi-v7a or x86_64)", other),
+    }
+}
+
+pub const DESKTOP_PLATFORMS: [&str; 3] = ["windows", "macos", "linux"];
+
+/// Tokens of `build.desktop_file_name` filled in per build.
+pub const DESKTOP_FILE_NAME_TOKENS: [&str; 3] = ["platform", "arch", "build_type"];
+
+/// One build: a flavor × build type combination on Android, or a build type on desktop.
+/// Android ABIs are folded into a single build (`--split-per-abi` for APKs).
+#[derive(Clone)]
+struct Variant {
+    platform: String,
+    flavor: Option<String>,
+    build_type: String,
+}
+
+impl Variant {
+    fn is_android(&self) -> bool {
+        self.platform == "android"
+    }
+
+    fn name(&self) -> String {
+        match &self.flavor {
+            _ if !self.is_android() => format!("{}-{}", self.platform, self.build_type),
+            Some(flavor) => format!("{}-{}", flavor, self.build_type),
+            None => self.build_type.clone(),
+        }
+    }
+
+    /// Capitalized build type, as used in Gradle and Xcode/MSBuild output dirs.
+    fn build_type_title(&self) -> String {
+        let mut chars = self.build_type.chars();
+        chars
+            .next()
+            .map(|c| c.to_uppercase().chain(chars).collect())
+            .unwrap_or_default()
+    }
+
+    /// Gradle variant directory, e.g. "freeRelease" or "release".
+    fn gradle_name(&self) -> String {
+        match &self.flavor {
+            Some(flavor) => format!("{}{}", flavor, self.build_type_title()),
+            None => self.build_type.clone(),
+        }
+    }
+}
+
+fn expand_matrix(config: &BuildConfig) -> Result<Vec<Variant>> {
+    let mut variants = Vec::new();
+    for platform in &config.platforms {
+        if platform == "android" {
+            let flavors: Vec<Option<String>> = if config.matrix.flavors.is_empty() {
+                vec![None]
+            } else {
+                config.matrix.flavors.iter().cloned().map(Some).collect()
+            };
+            for flavor in &flavors {
+                for build_type in &config.matrix.build_types {
+                    variants.push(Variant {
+                        platform: platform.clone(),
+                        flavor: flavor.clone(),
+                        build_type: build_type.clone(),
+                    });
+                }
+            }
+        } else if DESKTOP_PLATFORMS.contains(&platform.as_str()) {
+            for build_type in &config.matrix.build_types {
+                variants.push(Variant {
+                    platform: platform.clone(),
+                    flavor: None,
+                    build_type: build_type.clone(),
+                });
+            }
+        } else {
+            bail!(
+                "Unknown build platform: {} (expected android, windows, macos or linux)",
+                platform
+            );
+        }
+    }
+    Ok(variants)
+}
+
+fn flutter_build_args(config: &BuildConfig, variant: &Variant) -> Result<Vec<String>> {
+    if !variant.is_android() {
+        return Ok(vec![
+            "build".to_string(),
+            variant.platform.clone(),
+            format!("--{}", variant.build_type),
+        ]);
+    }
+    let matrix = &config.matrix;
+    let mut args = vec![
+        "build".to_string(),
+        matrix.target.as_str().to_string(),
+        format!("--{}", variant.build_type),
+    ];
+    if let Some(flavor) = &variant.flavor {
+        args.push("--flavor".to_string());
+        args.push(flavor.clone());
+    }
+    if !matrix.abis.is_empty() {
+        let platforms = matrix
+            .abis
+            .iter()
+            .map(|abi| target_platform(abi))
+            .collect::<Result<Vec<_>>>()?;
+        args.push("--target-platform".to_string());
+        args.push(platforms.join(","));
+        if matrix.target == AndroidBuildTarget::Apk {
+            args.push("--split-per-abi".to_string());
+        }
+    }
+    args.extend(matrix.extra_args.iter().cloned());
+    Ok(args)
+}
+
+/// Flutter's build dir name for the host architecture; desktop builds are never cross-compiled.
+fn host_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "aarch64" => "arm64",
+        _ => "x64",
+    }
+}
+
+/// The release bundle a desktop build leaves behind, and the arch used in its zip name.
+fn desktop_bundle(project_dir: &Path, variant: &Variant) -> Result<(PathBuf, &'static str)> {
+    let (bundle, arch) = match variant.platform.as_str() {
+        "windows" => (
+            project_dir
+                .join("build/windows")
+                .join(host_arch())
+                .join("runner")
+                .join(variant.build_type_title()),
+            host_arch(),
+        ),
+        "linux" => (
+            project_dir
+                .join("build/linux")
+                .join(host_arch())
+                .join(&variant.build_type)
+                .join("bundle"),
+            host_arch(),
+        ),
+        _ => {
+            // macOS builds are universal binaries; ship the .app itself.
+            let products = project_dir
+                .join("build/macos/Build/Products")
+                .join(variant.build_type_title());
+            let app = fs::read_dir(&products)
+                .with_context(|| format!("Failed to read dir: {}", products.display()))?
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .find(|path| path.extension().is_some_and(|ext| ext == "app"));
+            let Some(app) = app else {
+                bail!("No .app bundle found in: {}", products.display());
+            };
+            (app, "universal")
+        }
+    };
+    if !bundle.exists() {
+        bail!("Desktop bundle not found: {}", bundle.display());
+    }
+    Ok((bundle, arch))
+}
+
+/// Zips a bundle with the platform's own tool: `ditto` keeps .app symlinks and
+/// metadata intact, Windows 10+ ships bsdtar, and Linux uses Info-ZIP.
+fn zip_dir(bundle: &Path, zip_path: &Path) -> Result<()> {
+    if zip_path.exists() {
+        fs::remove_file(zip_path)
+            .with_context(|| format!("Failed to remove file: {}", zip_path.display()))?;
+    }
+    // zip runs inside the bundle dir, so the archive path must not be relative.
+    let zip_path = std::path::absolute(zip_path)?;
+    let mut command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("ditto");
+        command.args(["-c", "-k", "--sequesterRsrc", "--keepParent"]).arg(bundle).arg(&zip_path);
+        command
+    } else if cfg!(windows) {
+        let mut command = Command::new("tar");
+        command.args(["-a", "-c", "-f"]).arg(&zip_path).arg("-C").arg(bundle).arg(".");
+        command
+    } else {
+        let mut command = Command::new("zip");
+        command.args(["-q", "-r", "-y"]).arg(&zip_path).arg(".").current_dir(bundle);
+        command
+    };
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run zip for: {}", bundle.display()))?;
+    if !status.success() {
+        bail!("Zipping {} failed with exit code: {:?}", bundle.display(), status.code());
+    }
+    Ok(())
+}
+
+fn zip_desktop_bundle(project_dir: &Path, build_dir: &Path, config: &BuildConfig, variant: &Variant) -> Result<PathBuf> {
+    let (bundle, arch) = desktop_bundle(build_dir, variant)?;
+    let name = config
+        .desktop_file_name
+        .replace("{platform}", &variant.platform)
+        .replace("{arch}", arch)
+        .replace("{build_type}", &variant.build_type);
+    let dist_dir = project_dir.join(&config.dist_dir);
+    fs::create_dir_all(&dist_dir)
+        .with_context(|| format!("Failed to create dir: {}", dist_dir.display()))?;
+    let zip_path = dist_dir.join(name);
+    zip_dir(&bundle, &zip_path)?;
+    Ok(zip_path)
+}
+
+/// Build outputs flutter leaves for a variant, relative to the project dir.
+fn expected_artifacts(config: &BuildConfig, variant: &Variant) -> Vec<PathBuf> {
+    let suffix = match &variant.flavor {
+        Some(flavor) => format!("{}-{}", flavor, variant.build_type),
+        None => variant.build_type.clone(),
+    };
+    match config.matrix.target {
+        AndroidBuildTarget::Apk => {
+            let dir = Path::new("build/app/outputs/flutter-apk");
+            if config.matrix.abis.is_empty() {
+                vec![dir.join(format!("app-{}.apk", suffix))]
+            } else {
+                config
+                    .matrix
+                    .abis
+                    .iter()
+                    .map(|abi| dir.join(format!("app-{}-{}.apk", abi, suffix)))
+                    .collect()
+            }
+        }
+        AndroidBuildTarget::Appbundle => vec![
+            Path::new("build/app/outputs/bundle")
+                .join(variant.gradle_name())
+                .join(format!("app-{}.aab", suffix)),
+        ],
+    }
+}
+
+/// How builds are launched: the configured cmd_run command, else a cmd_run on PATH.
+pub fn cmd_run_launcher(config: &BuildConfig) -> Option<Vec<String>> {
+    if let Some(command) = &config.cmd_run
+        && !command.is_empty()
+    {
+        return Some(command.clone());
+    }
+    ["cmd_run", "cmd-run"]
+        .iter()
+        .find_map(|name| which(name).ok())
+        .map(|path| vec![path.display().to_string()])
+}
+
+struct BuildResult {
+    variant: Variant,
+    success: bool,
+    error: Option<String>,
+    duration: Duration,
+    log: PathBuf,
+    artifacts: Vec<PathBuf>,
+}
+
+/// Builds `variant` in `build_dir` (the project or a job's copy of it); logs and
+/// collected artifacts go to the project itself.
+fn run_variant(
+    project_dir: &Path,
+    build_dir: &Path,
+    flutter_cmd: &Path,
+    launcher: Option<&[String]>,
+    config: &BuildConfig,
+    variant: &Variant,
+) -> Result<(bool, Vec<PathBuf>)> {
+    let args = flutter_build_args(config, variant)?;
+    let log = project_dir.join(&config.log_dir).join(format!("{}.log", variant.name()));
+    if let Some(parent) = log.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+
+    let started = SystemTime::now();
+    let status = match launcher {
+        Some(launcher) => Command::new(&launcher[0])
+            .args(&launcher[1..])
+            .arg(format!("--log={}", log.display()))
+            .arg("--no-color")
+            .arg(flutter_cmd)
+            .args(&args)
+            .current_dir(build_dir)
+            .status()
+            .with_context(|| format!("Failed to run cmd_run: {}", launcher.join(" ")))?,
+        None => {
+            let file = fs::File::create(&log)
+                .with_context(|| format!("Failed to create log file: {}", log.display()))?;
+            let stderr = file
+                .try_clone()
+                .with_context(|| format!("Failed to open log file: {}", log.display()))?;
+            Command::new(flutter_cmd)
+                .args(&args)
+                .current_dir(build_dir)
+                .stdout(file)
+                .stderr(stderr)
+                .status()
+                .context("Failed to run flutter build")?
+        }
+    };
+
+    if !variant.is_android() {
+        let artifacts = if status.success() {
+            vec![zip_desktop_bundle(project_dir, build_dir, config, variant)?]
+        } else {
+            Vec::new()
+        };
+        return Ok((status.success(), artifacts));
+    }
+
+    // Only collect outputs written by this build, never stale ones from an earlier run.
+    let dist_dir = project_dir.join(&config.dist_dir);
+    let mut artifacts = Vec::new();
+    for relative in expected_artifacts(config, variant) {
+        let src = build_dir.join(&relative);
+        let fresh = fs::metadata(&src)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified >= started);
+        if !fresh {
+            continue;
+        }
+        fs::create_dir_all(&dist_dir)
+            .with_context(|| format!("Failed to create dir: {}", dist_dir.display()))?;
+        let dst = dist_dir.join(src.file_name().unwrap_or_default());
+        fs::copy(&src, &dst)
+            .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+        artifacts.push(dst);
+    }
+    Ok((status.success(), artifacts))
+}
+
+fn format_size(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+fn print_report(project_dir: &Path, results: &[BuildResult]) {
+    let relative = |path: &Path| {
+        path.strip_prefix(project_dir)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let width = results
+        .iter()
+        .map(|result| result.variant.name().len())
+        .max()
+        .unwrap_or(0)
+        .max("Variant".len());
+    println!("\n{:<width$}  {:<6}  {:>7}  Artifacts", "Variant", "Status", "Time");
+    for result in results {
+        let status = if result.success { "ok" } else { "FAILED" };
+        let time = format!("{:.0}s", result.duration.as_secs_f64());
+        let mut artifacts: Vec<String> = result
+            .artifacts
+            .iter()
+            .map(|path| {
+                let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+                format!("{} ({})", relative(path), format_size(size))
+            })
+            .collect();
+        if let Some(error) = &result.error {
+            artifacts.push(error.clone());
+        } else if !result.success {
+            artifacts.push(format!("see {}", relative(&result.log)));
+        }
+        let mut lines = artifacts.into_iter();
+        println!(
+            "{:<width$}  {:<6}  {:>7}  {}",
+            result.variant.name(),
+            status,
+            time,
+            lines.next().unwrap_or_else(|| "-".to_string())
+        );
+        for line in lines {
+            println!("{:<width$}  {:<6}  {:>7}  {}", "", "", "", line);
+        }
+    }
+}
+
+fn write_report(project_dir: &Path, config: &BuildConfig, results: &[BuildResult]) -> Result<()> {
+    let entries: Vec<_> = results
+        .iter()
+        .map(|result| {
+            json!({
+                "variant": result.variant.name(),
+                "platform": result.variant.platform,
+                "flavor": result.variant.flavor,
+                "build_type": result.variant.build_type,
+                "success": result.success,
+                "error": result.error,
+                "duration_secs": result.duration.as_secs_f64(),
+                "log": result.log.strip_prefix(project_dir).unwrap_or(&result.log),
+                "artifacts": result.artifacts.iter().map(|path| json!({
+                    "path": path.strip_prefix(project_dir).unwrap_or(path),
+                    "size": fs::metadata(path).map(|meta| meta.len()).unwrap_or(0),
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    let dist_dir = project_dir.join(&config.dist_dir);
+    fs::create_dir_all(&dist_dir)
+        .with_context(|| format!("Failed to create dir: {}", dist_dir.display()))?;
+    let path = dist_dir.join("build-report.json");
+    fs::write(&path, serde_json::to_string_pretty(&json!({ "builds": entries }))? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("\nBuild report written to: {}", path.display());
+    Ok(())
+}
+
+/// Where job `job` builds: the project for the first, a copy beside it for the others.
+/// Builds sharing a tree would race on build/, .dart_tool/ and Gradle's project state;
+/// a sibling copy keeps `../` path dependencies in pubspec.yaml resolving.
+fn job_dir(project_dir: &Path, job: usize) -> Result<PathBuf> {
+    if job == 0 {
+        return Ok(project_dir.to_path_buf());
+    }
+    let project = std::path::absolute(project_dir)
+        .with_context(|| format!("Failed to resolve project dir: {}", project_dir.display()))?;
+    let name = project.file_name().unwrap_or_default().to_string_lossy().to_string();
+    Ok(project.with_file_name(format!(".{}.job{}", name, job + 1)))
+}
+
+/// Builds every flavor × build type of the configured matrix (and each desktop platform),
+/// `config.jobs` at a time, collects the outputs into the dist dir and prints one
+/// consolidated table. Each job past the first builds in its own copy of the project.
+pub fn build_matrix(project_dir: &Path, flutter_cmd: &Path, config: &BuildConfig, dry_run: bool) -> Result<()> {
+    let variants = expand_matrix(config)?;
+    if variants.is_empty() {
+        bail!("build.matrix expands to no variants (build_types is empty)");
+    }
+    if variants.iter().any(|variant| !variant.is_android())
+        && config.matrix.build_types.len() > 1
+        && !config.desktop_file_name.contains("{build_type}")
+    {
+        bail!("build.desktop_file_name must contain {{build_type}} when building several build types");
+    }
+    let launcher = cmd_run_launcher(config);
+    if launcher.is_none() {
+        println!("cmd_run not found (set build.cmd_run); running flutter directly with log files");
+    }
+
+    if dry_run {
+        for variant in &variants {
+            println!(
+                "[DRY RUN] Would build {}: {} {}",
+                variant.name(),
+                flutter_cmd.display(),
+                flutter_build_args(config, variant)?.join(" ")
+            );
+        }
+        return Ok(());
+    }
+
+    let jobs = config.jobs.clamp(1, variants.len());
+    println!("Building {} variant(s) with {} job(s)", variants.len(), jobs);
+    let build_dirs = (0..jobs).map(|job| job_dir(project_dir, job)).collect::<Result<Vec<_>>>()?;
+    let copied = build_dirs.iter().skip(1).try_for_each(|dir| {
+        if dir.exists() {
+            fs::remove_dir_all(dir).with_context(|| format!("Failed to remove dir: {}", dir.display()))?;
+        }
+        println!("Copying the project for a parallel job: {}", dir.display());
+        copy_project(project_dir, dir)
+    });
+    if let Err(err) = copied {
+        for dir in build_dirs.iter().skip(1) {
+            let _ = fs::remove_dir_all(dir);
+        }
+        return Err(err);
+    }
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<(usize, BuildResult)>> = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for build_dir in &build_dirs {
+            let (next, results, variants, launcher) = (&next, &results, &variants, &launcher);
+            scope.spawn(move || {
+                loop {
+                    let index = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(variant) = variants.get(index) else {
+                        break;
+                    };
+                    println!(">>> Building {}", variant.name());
+                    let started = Instant::now();
+                    let outcome = run_variant(project_dir, build_dir, flutter_cmd, launcher.as_deref(), config, variant);
+                    let (success, artifacts, error) = match outcome {
+                        Ok((success, artifacts)) => (success, artifacts, None),
+                        Err(err) => (false, Vec::new(), Some(format!("{:#}", err))),
+                    };
+                    let result = BuildResult {
+                        variant: variant.clone(),
+                        success,
+                        error,
+                        duration: started.elapsed(),
+                        log: project_dir.join(&config.log_dir).join(format!("{}.log", variant.name())),
+                        artifacts,
+                    };
+                    results.lock().unwrap_or_else(|e| e.into_inner()).push((index, result));
+                }
+            });
+        }
+    });
+
+    for dir in build_dirs.iter().skip(1) {
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by_key(|(index, _)| *index);
+    let results: Vec<BuildResult> = results.into_iter().map(|(_, result)| result).collect();
+    print_report(project_dir, &results);
+    write_report(project_dir, config, &results)?;
+
+    let failed = results.iter().filter(|result| !result.success).count();
+    if failed > 0 {
+        bail!("{} of {} build(s) failed", failed, results.len());
+    }
+    println!("✓ All {} build(s) succeeded", results.len());
+    Ok(())
+}
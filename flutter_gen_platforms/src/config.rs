@@ -1,12 +1,14 @@
 use anyhow::{bail, Context, Result};
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::path::Path;
 use std::process::Command;
 use which::which;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
 #[allow(dead_code)]
 pub struct Config {
     pub project_name: String,
@@ -26,123 +28,1960 @@ pub struct Config {
     pub create: FlutterCreateConfig,
     pub android: AndroidConfig,
     pub ios: Option<IosConfig>,
+    #[serde(default)]
+    pub macos: Option<MacosConfig>,
+    #[serde(default)]
+    pub linux: Option<LinuxConfig>,
     pub windows: Option<WindowsConfig>,
+    #[serde(default)]
+    pub web: Option<WebConfig>,
+    #[serde(default)]
+    pub assets: Option<AssetsConfig>,
+    #[serde(default)]
+    pub fonts: Option<FontsConfig>,
+    #[serde(default)]
+    pub build_env: Option<BuildEnvConfig>,
+    #[serde(default)]
+    pub clean: CleanConfig,
+    #[serde(default)]
+    pub gitignore: Option<GitignoreConfig>,
+    #[serde(default)]
+    pub line_endings: Option<LineEndingsConfig>,
+    #[serde(default)]
+    pub build: BuildConfig,
+    #[serde(default)]
+    pub publish: PublishConfig,
+    #[serde(default)]
+    pub preflight: PreflightConfig,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+    /// Overlays deep-merged over the rest of the config by `--profile <name>`, e.g.
+    /// `[profiles.staging.android.app.build] application_id = "com.example.app.staging"`.
+    #[serde(default)]
+    pub profiles: BTreeMap<String, serde_json::Value>,
+    /// Unknown top-level sections, handed to external `flutter-gen-platform-<name>` processors.
+    #[serde(flatten)]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+impl Config {
+    /// Root of the per-platform overlay directories: `platforms_dir`, or `platforms/`.
+    pub fn platforms_root(&self, project_dir: &Path) -> std::path::PathBuf {
+        let root = self
+            .platforms_dir
+            .as_deref()
+            .map(str::trim)
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or("platforms");
+        project_dir.join(root)
+    }
+}
+
+/// Checks run before platform directories are deleted.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PreflightConfig {
+    #[serde(default)]
+    pub skip: bool,
+    /// Minimum free space on the project's volume.
+    #[serde(default = "default_preflight_min_free_mb")]
+    pub min_free_mb: u64,
+    /// Enforce Windows' 260-character path limit on every OS, for projects shared
+    /// with Windows contributors.
+    #[serde(default)]
+    pub windows_path_limit: bool,
+}
+
+impl Default for PreflightConfig {
+    fn default() -> Self {
+        Self {
+            skip: false,
+            min_free_mb: default_preflight_min_free_mb(),
+            windows_path_limit: false,
+        }
+    }
+}
+
+/// Shell commands run in the project directory around generation (`sh -c`, or `cmd /C` on
+/// Windows), e.g. `post_generate = ["flutter pub get", "dart run build_runner build"]`.
+/// Commands reach the shell verbatim, so `$VAR` (or `%VAR%` under cmd) is the shell's to
+/// expand; a failing command stops the generation.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct HooksConfig {
+    /// Run after the preflight checks, before the platform directories are replaced.
+    #[serde(default)]
+    pub pre_generate: Vec<String>,
+    /// Run after every platform is processed, before the lockfile is written.
+    #[serde(default)]
+    pub post_generate: Vec<String>,
+    /// Hooks run only when the platform is generated, after the shared ones of the same
+    /// stage, e.g. `[hooks.platforms.ios] post_generate = ["cd ios && pod install"]`.
+    #[serde(default)]
+    pub platforms: BTreeMap<String, PlatformHooksConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct PlatformHooksConfig {
+    #[serde(default)]
+    pub pre_generate: Vec<String>,
+    #[serde(default)]
+    pub post_generate: Vec<String>,
+}
+
+/// `publish artifacts`: where dist/ is uploaded, under a `<version>/` prefix.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PublishConfig {
+    /// Destination: `s3://bucket/path`, `http(s)://...` (HTTP PUT) or a local directory.
+    #[serde(default)]
+    pub to: Option<String>,
+    /// Extra HTTP headers for PUT uploads, e.g. Authorization = "Bearer ${UPLOAD_TOKEN}";
+    /// env vars here and in `to` are only expanded when publishing.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Release index appended on each publish, relative to the project dir.
+    #[serde(default = "default_releases_file")]
+    pub releases_file: String,
+    /// Source of the changelog excerpt stored with each release.
+    #[serde(default = "default_changelog")]
+    pub changelog: String,
+}
+
+impl Default for PublishConfig {
+    fn default() -> Self {
+        Self {
+            to: None,
+            headers: BTreeMap::new(),
+            releases_file: default_releases_file(),
+            changelog: default_changelog(),
+        }
+    }
+}
+
+/// `build` subcommand: Android variant matrix built through cmd_run.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct BuildConfig {
+    /// Builds run at the same time. Every job past the first builds in a copy of the
+    /// project next to it (`.<dir>.job2`, ...), removed afterwards.
+    #[serde(default = "default_build_jobs")]
+    pub jobs: usize,
+    /// How to invoke cmd_run, e.g. ["rust-script", "tools/cmd_run.rs"]; defaults to a
+    /// `cmd_run`/`cmd-run` executable on PATH, falling back to plain flutter with a log file.
+    #[serde(default)]
+    pub cmd_run: Option<Vec<String>>,
+    #[serde(default = "default_build_log_dir")]
+    pub log_dir: String,
+    /// Collected artifacts and build-report.json go here.
+    #[serde(default = "default_build_dist_dir")]
+    pub dist_dir: String,
+    /// Platforms to build: "android" (expanded by `matrix`), "windows", "macos", "linux".
+    #[serde(default = "default_build_platforms")]
+    pub platforms: Vec<String>,
+    /// Zip name for desktop bundles; takes the output_file_name_pattern tokens
+    /// ({project_name}, {version}, {build_number}, {date}, {git_sha}) plus {platform},
+    /// {arch} and {build_type}.
+    #[serde(default = "default_desktop_file_name")]
+    pub desktop_file_name: String,
+    #[serde(default)]
+    pub matrix: BuildMatrixConfig,
+}
+
+impl Default for BuildConfig {
+    fn default() -> Self {
+        Self {
+            jobs: default_build_jobs(),
+            cmd_run: None,
+            log_dir: default_build_log_dir(),
+            dist_dir: default_build_dist_dir(),
+            platforms: default_build_platforms(),
+            desktop_file_name: default_desktop_file_name(),
+            matrix: BuildMatrixConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct BuildMatrixConfig {
+    #[serde(default)]
+    pub target: AndroidBuildTarget,
+    /// Product flavors; empty builds without `--flavor`.
+    #[serde(default)]
+    pub flavors: Vec<String>,
+    #[serde(default = "default_build_types")]
+    pub build_types: Vec<String>,
+    /// ABIs (arm64-v8a, armeabi-v7a, x86_64); APKs are split per ABI, bundles include them all.
+    #[serde(default)]
+    pub abis: Vec<String>,
+    /// Extra arguments for every `flutter build` invocation.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+}
+
+impl Default for BuildMatrixConfig {
+    fn default() -> Self {
+        Self {
+            target: AndroidBuildTarget::default(),
+            flavors: Vec::new(),
+            build_types: default_build_types(),
+            abis: Vec::new(),
+            extra_args: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AndroidBuildTarget {
+    #[default]
+    Apk,
+    Appbundle,
+}
+
+impl AndroidBuildTarget {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AndroidBuildTarget::Apk => "apk",
+            AndroidBuildTarget::Appbundle => "appbundle",
+        }
+    }
+}
+
+/// Workspace file for `--all`: several apps generated in order with one template root.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WorkspaceConfig {
+    /// App directories, relative to the workspace file.
+    pub apps: Vec<String>,
+    /// Config file name inside each app directory.
+    #[serde(default = "default_workspace_config")]
+    pub config: String,
+    /// Template root shared by the apps, relative to the workspace file; an app's own
+    /// `platforms_dir` takes precedence.
+    #[serde(default = "default_workspace_platforms_dir")]
+    pub platforms_dir: String,
+}
+
+/// Controls the removal of platform directories before `flutter create`.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CleanConfig {
+    /// Globs relative to the project dir (`*`, `?`, `**`) of files kept when a platform
+    /// directory is regenerated, e.g. "android/key.properties" or "windows/runner/resources/**".
+    #[serde(default)]
+    pub preserve: Vec<String>,
+    /// Copy platform directories to .flutter_gen_backup/<timestamp>/ before removing them.
+    #[serde(default = "default_true")]
+    pub backup: bool,
+    /// Backups kept; older ones are deleted after a new one is made (0 keeps all).
+    #[serde(default = "default_clean_keep_backups")]
+    pub keep_backups: usize,
+}
+
+impl Default for CleanConfig {
+    fn default() -> Self {
+        Self {
+            preserve: Vec::new(),
+            backup: true,
+            keep_backups: default_clean_keep_backups(),
+        }
+    }
+}
+
+/// Entries kept in a marked block of the project's .gitignore.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct GitignoreConfig {
+    /// Ignore the generated platform directories entirely (they can be recreated from config).
+    #[serde(default)]
+    pub platforms: bool,
+    /// Extra paths, e.g. "android/key.properties", "android/local.properties".
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+/// Line endings of the generated platform files.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct LineEndingsConfig {
+    /// Applied to platforms without their own entry; unset leaves files as generated.
+    #[serde(default)]
+    pub default: Option<LineEnding>,
+    /// Per platform directory, e.g. { windows = "crlf" }.
+    #[serde(default)]
+    pub platforms: BTreeMap<String, LineEnding>,
+    /// Keep a matching `eol=` block in .gitattributes.
+    #[serde(default = "default_true")]
+    pub gitattributes: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "lf",
+            LineEnding::Crlf => "crlf",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+#[allow(dead_code)]
+pub struct PubspecConfig {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub version: Option<String>,
+    #[serde(default)]
+    pub homepage: Option<String>,
+    #[serde(default)]
+    pub repository: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FlutterCreateConfig {
+    /// Platforms created and customized; unset or empty means `default_platforms`.
+    /// `--platforms` on the command line overrides it.
+    #[serde(default)]
+    pub platforms: Option<Vec<String>>,
+    /// Platforms used when `platforms` is unset or empty.
+    #[serde(default = "default_create_default_platforms")]
+    pub default_platforms: Vec<String>,
+    #[serde(default)]
+    pub android_language: Option<String>,
+}
+
+impl Default for FlutterCreateConfig {
+    fn default() -> Self {
+        Self {
+            platforms: None,
+            default_platforms: default_create_default_platforms(),
+            android_language: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AndroidConfig {
+    #[serde(default)]
+    pub gradle_wrapper: AndroidGradleWrapperConfig,
+    #[serde(default)]
+    pub app: AndroidAppConfig,
+    pub build: AndroidBuildConfig,
+    #[serde(default)]
+    pub settings: AndroidSettingsConfig,
+    #[serde(default)]
+    pub crashlytics: AndroidCrashlyticsConfig,
+    #[serde(default)]
+    pub baseline_profile: AndroidBaselineProfileConfig,
+    #[serde(default)]
+    pub build_performance: AndroidBuildPerformanceConfig,
+    #[serde(default)]
+    pub manifest: AndroidManifestConfig,
+    #[serde(default)]
+    pub network_security: Option<AndroidNetworkSecurityConfig>,
+    #[serde(default)]
+    pub deep_links: Option<AndroidDeepLinksConfig>,
+    #[serde(default)]
+    pub file_provider: Option<AndroidFileProviderConfig>,
+    #[serde(default)]
+    pub local_properties: Option<AndroidLocalPropertiesConfig>,
+    #[serde(default)]
+    pub tv: Option<AndroidTvConfig>,
+    #[serde(default)]
+    pub wear: Option<AndroidWearConfig>,
+    #[serde(default)]
+    pub dynamic_features: Option<AndroidDynamicFeaturesConfig>,
+    /// Preset enabling `tv`/`wear` with their defaults, raising minSdk and layering
+    /// `platforms/android/<form_factor>/` templates over the base templates.
+    #[serde(default)]
+    pub form_factor: Option<AndroidFormFactor>,
+    #[serde(default)]
+    pub play: AndroidPlayConfig,
+}
+
+/// Google Play Core libraries wired into the app module.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidPlayConfig {
+    #[serde(default)]
+    pub integrity: Option<AndroidPlayIntegrityConfig>,
+    #[serde(default)]
+    pub in_app_update: Option<AndroidInAppUpdateConfig>,
+}
+
+/// Play Integrity API; the cloud project number is exposed to the app as
+/// `<meta-data android:name="{meta_data_name}" android:value="@string/{meta_data_name}">`.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AndroidPlayIntegrityConfig {
+    /// Google Cloud project number linked in the Play Console (a string, it can exceed 32 bits).
+    pub cloud_project_number: String,
+    #[serde(default = "default_play_integrity_meta_data_name")]
+    pub meta_data_name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidInAppUpdateConfig {
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Also add the Kotlin extensions (app-update-ktx).
+    #[serde(default)]
+    pub ktx: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AndroidFormFactor {
+    Tv,
+    Wear,
+}
+
+impl AndroidFormFactor {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AndroidFormFactor::Tv => "tv",
+            AndroidFormFactor::Wear => "wear",
+        }
+    }
+
+    /// Lowest API level the form factor's launcher supports (Wear OS 3 starts at 30).
+    pub fn min_sdk(self) -> u32 {
+        match self {
+            AndroidFormFactor::Tv => 21,
+            AndroidFormFactor::Wear => 30,
+        }
+    }
+}
+
+/// Play Feature Delivery modules backing Flutter deferred components.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidDynamicFeaturesConfig {
+    #[serde(default)]
+    pub feature_delivery_version: Option<String>,
+    #[serde(default)]
+    pub modules: Vec<AndroidDynamicFeatureModule>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AndroidDynamicFeatureModule {
+    /// Gradle module name, also the deferred component name in pubspec.yaml.
+    pub name: String,
+    /// Shown by Play while the module downloads; defaults to the name.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Downloaded on request (`loadLibrary()`) rather than at install time.
+    #[serde(default = "default_true")]
+    pub on_demand: bool,
+    /// Dart libraries of the component, e.g. "package:app/boost.dart".
+    #[serde(default)]
+    pub libraries: Vec<String>,
+    #[serde(default)]
+    pub assets: Vec<String>,
+    /// Loading unit ids reported by `flutter build appbundle`, for the
+    /// loadingUnitMapping manifest entry.
+    #[serde(default)]
+    pub loading_units: Vec<u32>,
+}
+
+/// Android TV (leanback) launcher support.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidTvConfig {
+    /// 320x180 launcher banner image, relative to the project dir; copied to res/drawable-xhdpi.
+    #[serde(default)]
+    pub banner: Option<String>,
+    /// Mark leanback as required, making the app TV-only on Play.
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// Wear OS support.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AndroidWearConfig {
+    /// `com.google.android.wearable.standalone`: the app works without a paired phone.
+    #[serde(default = "default_true")]
+    pub standalone: bool,
+}
+
+impl Default for AndroidWearConfig {
+    fn default() -> Self {
+        Self { standalone: true }
+    }
+}
+
+/// Writes android/local.properties so CI builds don't depend on an IDE having created it.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidLocalPropertiesConfig {
+    /// `sdk.dir`; defaults to $ANDROID_HOME, then $ANDROID_SDK_ROOT.
+    #[serde(default)]
+    pub sdk_dir: Option<String>,
+    /// `flutter.sdk`; defaults to $FLUTTER_ROOT.
+    #[serde(default)]
+    pub flutter_sdk: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidFileProviderConfig {
+    /// Provider authority; defaults to "${applicationId}.fileprovider" so flavor suffixes apply.
+    #[serde(default)]
+    pub authority: Option<String>,
+    /// Shared roots; defaults to the app cache and files directories.
+    #[serde(default)]
+    pub paths: Vec<AndroidFileProviderPathConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AndroidFileProviderPathConfig {
+    pub kind: FileProviderPathKind,
+    pub name: String,
+    #[serde(default = "default_file_provider_path")]
+    pub path: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FileProviderPathKind {
+    Files,
+    Cache,
+    External,
+    ExternalFiles,
+    ExternalCache,
+    ExternalMedia,
+}
+
+impl FileProviderPathKind {
+    pub fn as_element(self) -> &'static str {
+        match self {
+            Self::Files => "files-path",
+            Self::Cache => "cache-path",
+            Self::External => "external-path",
+            Self::ExternalFiles => "external-files-path",
+            Self::ExternalCache => "external-cache-path",
+            Self::ExternalMedia => "external-media-path",
+        }
+    }
+}
+
+fn default_file_provider_path() -> String {
+    ".".to_string()
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidDeepLinksConfig {
+    #[serde(default)]
+    pub schemes: Vec<String>,
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub path_prefix: Option<String>,
+    /// Marks the filter as verified App Links (requires http/https schemes).
+    #[serde(default)]
+    pub auto_verify: bool,
+    /// Signing certificate fingerprints; when set, web/.well-known/assetlinks.json is written.
+    #[serde(default)]
+    pub sha256_cert_fingerprints: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidNetworkSecurityConfig {
+    #[serde(default)]
+    pub cleartext_traffic_permitted: bool,
+    /// Domains (including subdomains) allowed to use cleartext HTTP.
+    #[serde(default)]
+    pub cleartext_domains: Vec<String>,
+    /// Trust user-installed CAs in all builds.
+    #[serde(default)]
+    pub trust_user_certs: bool,
+    /// Trust user-installed CAs in debuggable builds only (e.g. for proxies).
+    #[serde(default)]
+    pub debug_user_certs: bool,
+    /// Extra trust anchors, e.g. "@raw/company_ca".
+    #[serde(default)]
+    pub certificates: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidManifestConfig {
+    /// Permission names; bare names like "CAMERA" are prefixed with `android.permission.`.
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub queries: AndroidQueriesConfig,
+}
+
+/// Package visibility declarations (`<queries>`) required since Android 11.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidQueriesConfig {
+    #[serde(default)]
+    pub packages: Vec<String>,
+    #[serde(default)]
+    pub intents: Vec<AndroidQueryIntentConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AndroidQueryIntentConfig {
+    /// Full action name; bare names like "VIEW" are prefixed with `android.intent.action.`.
+    pub action: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub scheme: Option<String>,
+    #[serde(default)]
+    pub host: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidBuildConfig {
+    pub allprojects: RepositoryList,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidSettingsConfig {
+    pub plugin_management: RepositoryList,
+    /// Emits `dependencyResolutionManagement {}` in settings.gradle.kts.
+    #[serde(default)]
+    pub dependency_resolution: Option<AndroidDependencyResolutionConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidDependencyResolutionConfig {
+    #[serde(default)]
+    pub mode: RepositoriesMode,
+    /// Extra maven repositories, after google(), mavenCentral() and the Flutter engine repository.
+    #[serde(default)]
+    pub repositories: Vec<String>,
+    /// Emit only `repositories`, without the defaults.
+    #[serde(default)]
+    pub replace: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RepositoriesMode {
+    PreferProject,
+    #[default]
+    PreferSettings,
+    /// Also drops the root build.gradle.kts `allprojects { repositories }` block, which would fail the build.
+    FailOnProjectRepos,
+}
+
+impl RepositoriesMode {
+    pub fn as_gradle(self) -> &'static str {
+        match self {
+            RepositoriesMode::PreferProject => "PREFER_PROJECT",
+            RepositoriesMode::PreferSettings => "PREFER_SETTINGS",
+            RepositoriesMode::FailOnProjectRepos => "FAIL_ON_PROJECT_REPOS",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidCrashlyticsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub plugin_version: Option<String>,
+    #[serde(default)]
+    pub google_services_version: Option<String>,
+    #[serde(default = "default_true")]
+    pub mapping_file_upload: bool,
+    #[serde(default)]
+    pub native_symbol_upload: bool,
+}
+
+/// Gradle/Kotlin build tuning, written to android/gradle.properties.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidBuildPerformanceConfig {
+    /// `kotlin.incremental`; defaults to false when the project and the pub cache are on
+    /// different Windows drives, where incremental compilation fails on relative paths.
+    #[serde(default)]
+    pub kotlin_incremental: Option<bool>,
+    /// `org.gradle.caching`
+    #[serde(default)]
+    pub build_cache: Option<bool>,
+    /// `org.gradle.parallel`
+    #[serde(default)]
+    pub parallel: Option<bool>,
+    /// `org.gradle.configuration-cache`
+    #[serde(default)]
+    pub configuration_cache: Option<bool>,
+    /// `kapt.use.worker.api`
+    #[serde(default)]
+    pub kapt_workers: Option<bool>,
+    /// `org.gradle.jvmargs`, replacing the Flutter template's value.
+    #[serde(default)]
+    pub jvm_args: Option<String>,
+}
+
+/// Applies `androidx.baselineprofile` and profileinstaller for startup-optimized release builds.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidBaselineProfileConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub plugin_version: Option<String>,
+    #[serde(default)]
+    pub profileinstaller_version: Option<String>,
+    /// Producer module generating the profile (e.g. ":baselineprofile").
+    #[serde(default)]
+    pub producer: Option<String>,
+    /// Checked-in profile rules copied to app/src/main/baselineProfiles/baseline-prof.txt.
+    #[serde(default)]
+    pub rules: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidGradleWrapperConfig {
+    pub distribution_url: Option<String>,
+    #[serde(default)]
+    pub distribution_sha256_sum: Option<String>,
+    /// Download timeout in milliseconds (`networkTimeout`).
+    #[serde(default)]
+    pub network_timeout: Option<u32>,
+    /// Regenerate gradle-wrapper.jar and the gradlew scripts for the distribution's version.
+    #[serde(default)]
+    pub regenerate: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidAppConfig {
+    #[serde(default)]
+    pub build: AndroidAppBuildConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidAppBuildConfig {
+    #[serde(default)]
+    pub namespace: String,
+    #[serde(default)]
+    pub application_id: String,
+    #[serde(default)]
+    pub output_file_name: Option<String>,
+    /// Output file name patterns keyed by build type, flavor or variant name
+    /// (e.g. "freeRelease"); `output_file_name` is used for variants that match none.
+    /// Besides {project_name}, {version}, {build_number}, {date} and {git_sha} (filled in
+    /// at generation time): {abi}, {build_type}, {flavor}, {variant}, {version_name} and
+    /// {version_code}. Unknown tokens are rejected.
+    #[serde(default)]
+    pub output_file_names: BTreeMap<String, String>,
+    #[serde(default)]
+    pub abi_filters: Option<Vec<String>>,
+    /// Deprecated: use `android.build_performance.kotlin_incremental`.
+    #[serde(default)]
+    pub kotlin_incremental: Option<bool>,
+    #[serde(default)]
+    pub debug_symbol_level: Option<DebugSymbolLevel>,
+    /// `buildConfigField`s keyed by "default" (defaultConfig), a build type or a product flavor.
+    #[serde(default)]
+    pub build_config_fields: BTreeMap<String, BTreeMap<String, BuildEnvValue>>,
+    /// `resValue`s, keyed like `build_config_fields`.
+    #[serde(default)]
+    pub res_values: BTreeMap<String, BTreeMap<String, BuildEnvValue>>,
+    #[serde(default)]
+    pub lint: Option<AndroidLintConfig>,
+    #[serde(default)]
+    pub test: Option<AndroidTestConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidTestConfig {
+    /// `testInstrumentationRunner`, e.g. "androidx.test.runner.AndroidJUnitRunner".
+    #[serde(default)]
+    pub instrumentation_runner: Option<String>,
+    /// `testOptions.unitTests.isReturnDefaultValues`
+    #[serde(default)]
+    pub return_default_values: Option<bool>,
+    /// `testOptions.unitTests.isIncludeAndroidResources`
+    #[serde(default)]
+    pub include_android_resources: Option<bool>,
+    /// `testOptions.animationsDisabled`
+    #[serde(default)]
+    pub animations_disabled: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct AndroidLintConfig {
+    #[serde(default)]
+    pub abort_on_error: Option<bool>,
+    /// `false` skips the lint run that `assembleRelease` triggers.
+    #[serde(default)]
+    pub check_release_builds: Option<bool>,
+    /// Lint issue ids to disable, e.g. "InvalidPackage".
+    #[serde(default)]
+    pub disable: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DebugSymbolLevel {
+    None,
+    SymbolTable,
+    Full,
+}
+
+impl DebugSymbolLevel {
+    pub fn as_gradle(self) -> &'static str {
+        match self {
+            DebugSymbolLevel::None => "NONE",
+            DebugSymbolLevel::SymbolTable => "SYMBOL_TABLE",
+            DebugSymbolLevel::Full => "FULL",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct IosConfig {
+    /// IPHONEOS_DEPLOYMENT_TARGET of the project and the Podfile platform, e.g. "13.0".
+    #[serde(default)]
+    pub deployment_target: Option<String>,
+    /// SWIFT_VERSION of the Runner and RunnerTests targets, e.g. "5.0".
+    #[serde(default)]
+    pub swift_version: Option<String>,
+    #[serde(default)]
+    pub signing: Option<IosSigningConfig>,
+    #[serde(default)]
+    pub pods: Option<IosPodsConfig>,
+    /// Top-level keys set in ios/Runner/Info.plist (usage descriptions, NSAppTransportSecurity,
+    /// UIBackgroundModes, CFBundleURLTypes, ...). Tables become dicts; an existing key's
+    /// value is replaced as a whole.
+    #[serde(default)]
+    pub info_plist: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub entitlements: Option<IosEntitlementsConfig>,
+    #[serde(default)]
+    pub icons: Option<IosIconsConfig>,
+    #[serde(default)]
+    pub privacy: Option<IosPrivacyConfig>,
+    #[serde(default)]
+    pub launch_screen: Option<IosLaunchScreenConfig>,
+}
+
+/// Runner/Base.lproj/LaunchScreen.storyboard: a solid background with a centered image.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct IosLaunchScreenConfig {
+    /// "#RRGGBB" or "#RRGGBBAA".
+    #[serde(default)]
+    pub background_color: Option<String>,
+    /// Center image, rendered at @1x/@2x/@3x into the LaunchImage image set.
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Image width in points; the height follows the source's aspect ratio.
+    #[serde(default = "default_launch_image_width")]
+    pub image_width: u32,
+}
+
+fn default_launch_image_width() -> u32 {
+    200
+}
+
+/// Runner/PrivacyInfo.xcprivacy, required for App Store submissions.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct IosPrivacyConfig {
+    /// NSPrivacyTracking: whether the app's data is used for tracking (ATT).
+    #[serde(default)]
+    pub tracking: bool,
+    /// NSPrivacyTrackingDomains contacted for tracking.
+    #[serde(default)]
+    pub tracking_domains: Vec<String>,
+    /// NSPrivacyAccessedAPITypes: required-reason APIs the app uses.
+    #[serde(default)]
+    pub accessed_apis: Vec<IosPrivacyAccessedApi>,
+    /// NSPrivacyCollectedDataTypes entries, written as-is (keys as in Apple's docs).
+    #[serde(default)]
+    pub collected_data_types: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct IosPrivacyAccessedApi {
+    pub category: IosPrivacyApiCategory,
+    /// Approved reason codes, e.g. ["CA92.1"].
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IosPrivacyApiCategory {
+    FileTimestamp,
+    SystemBootTime,
+    DiskSpace,
+    ActiveKeyboards,
+    UserDefaults,
+}
+
+impl IosPrivacyApiCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IosPrivacyApiCategory::FileTimestamp => "NSPrivacyAccessedAPICategoryFileTimestamp",
+            IosPrivacyApiCategory::SystemBootTime => "NSPrivacyAccessedAPICategorySystemBootTime",
+            IosPrivacyApiCategory::DiskSpace => "NSPrivacyAccessedAPICategoryDiskSpace",
+            IosPrivacyApiCategory::ActiveKeyboards => "NSPrivacyAccessedAPICategoryActiveKeyboards",
+            IosPrivacyApiCategory::UserDefaults => "NSPrivacyAccessedAPICategoryUserDefaults",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct IosIconsConfig {
+    /// Source image for every AppIcon.appiconset size (1024x1024 or larger, or SVG).
+    pub source: String,
+    /// Fill behind transparent areas; App Store icons must not have an alpha channel.
+    #[serde(default)]
+    pub background: Option<String>,
+}
+
+/// Capabilities written to ios/Runner/Runner.entitlements (CODE_SIGN_ENTITLEMENTS).
+/// Each one must also be enabled for the App ID in the developer portal.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct IosEntitlementsConfig {
+    /// com.apple.developer.associated-domains, e.g. "applinks:example.com".
+    #[serde(default)]
+    pub associated_domains: Vec<String>,
+    /// aps-environment for push notifications.
+    #[serde(default)]
+    pub push: Option<IosPushEnvironment>,
+    /// com.apple.security.application-groups, e.g. "group.com.example.shared".
+    #[serde(default)]
+    pub app_groups: Vec<String>,
+    /// keychain-access-groups, e.g. "$(AppIdentifierPrefix)com.example.shared".
+    #[serde(default)]
+    pub keychain_groups: Vec<String>,
+    /// Any other entitlement keys, written as-is.
+    #[serde(default)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IosPushEnvironment {
+    Development,
+    Production,
+}
+
+impl IosPushEnvironment {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IosPushEnvironment::Development => "development",
+            IosPushEnvironment::Production => "production",
+        }
+    }
+}
+
+/// CocoaPods settings written into ios/Podfile, the iOS side of the Android repository mirrors.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct IosPodsConfig {
+    /// Spec repositories emitted as `source` lines, e.g. a CDN or git mirror of the trunk.
+    /// Any source replaces the default trunk, so list it too if it should stay.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// `platform :ios, '<version>'`, e.g. "13.0"; defaults to ios.deployment_target.
+    #[serde(default)]
+    pub platform: Option<String>,
+    /// Build settings forced on every pod target in post_install,
+    /// e.g. { IPHONEOS_DEPLOYMENT_TARGET = "13.0" } to silence old pods' deployment target warnings.
+    #[serde(default)]
+    pub build_settings: BTreeMap<String, String>,
+    /// Raw Ruby lines run at the end of the post_install block (`installer` is in scope).
+    #[serde(default)]
+    pub post_install: Vec<String>,
+}
+
+/// Code signing build settings of the Runner target, so `flutter build ipa` works on CI
+/// without opening Xcode.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct IosSigningConfig {
+    /// Apple developer team id (DEVELOPMENT_TEAM), e.g. "ABCDE12345".
+    pub development_team: String,
+    #[serde(default = "default_code_sign_style")]
+    pub code_sign_style: IosCodeSignStyle,
+    /// PROVISIONING_PROFILE_SPECIFIER for every configuration (manual signing).
+    #[serde(default)]
+    pub provisioning_profile: Option<String>,
+    /// Per-configuration profiles (Debug, Release, Profile), overriding provisioning_profile.
+    #[serde(default)]
+    pub provisioning_profiles: BTreeMap<String, String>,
+    /// CODE_SIGN_IDENTITY for device builds, e.g. "Apple Distribution".
+    #[serde(default)]
+    pub code_sign_identity: Option<String>,
+}
+
+fn default_code_sign_style() -> IosCodeSignStyle {
+    IosCodeSignStyle::Automatic
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IosCodeSignStyle {
+    Automatic,
+    Manual,
+}
+
+impl IosCodeSignStyle {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IosCodeSignStyle::Automatic => "Automatic",
+            IosCodeSignStyle::Manual => "Manual",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct LinuxConfig {
+    /// GTK application id (APPLICATION_ID), e.g. "com.example.demo"; also names the .desktop file.
+    #[serde(default)]
+    pub application_id: Option<String>,
+    /// Window title; defaults to the project name set by flutter create.
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub window_width: Option<u32>,
+    #[serde(default)]
+    pub window_height: Option<u32>,
+    #[serde(default)]
+    pub icon: Option<LinuxIconConfig>,
+    /// Writes linux/<application_id>.desktop and installs it into the bundle.
+    #[serde(default)]
+    pub desktop: Option<LinuxDesktopConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct LinuxIconConfig {
+    /// PNG or SVG relative to the project dir, rendered into runner/resources/app_icon.png,
+    /// installed as data/app_icon.png and set as the window icon. With `desktop` it is also
+    /// installed as share/icons/hicolor/256x256/apps/<application_id>.png for the entry.
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct LinuxDesktopConfig {
+    /// Name=; defaults to the window title, then the project name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Comment=, shown as a tooltip by most launchers.
+    #[serde(default)]
+    pub comment: Option<String>,
+    /// Categories=, e.g. ["Utility"] (freedesktop.org main categories).
+    #[serde(default)]
+    pub categories: Vec<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// MimeType= entries the app opens, e.g. ["application/x-demo"].
+    #[serde(default)]
+    pub mime_types: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct MacosConfig {
+    /// PRODUCT_BUNDLE_IDENTIFIER; flutter create derives it from `org` and the project name.
+    #[serde(default)]
+    pub bundle_id: Option<String>,
+    /// PRODUCT_NAME: the .app name and the window title.
+    #[serde(default)]
+    pub product_name: Option<String>,
+    /// PRODUCT_COPYRIGHT shown in the About panel.
+    #[serde(default)]
+    pub copyright: Option<String>,
+    /// LSApplicationCategoryType, e.g. "public.app-category.productivity"; the Mac App Store requires one.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// MACOSX_DEPLOYMENT_TARGET and the Podfile platform, e.g. "10.15".
+    #[serde(default)]
+    pub deployment_target: Option<String>,
+    #[serde(default)]
+    pub icon: Option<MacosIconConfig>,
+    /// Rewrites Runner/DebugProfile.entitlements and Runner/Release.entitlements.
+    #[serde(default)]
+    pub entitlements: Option<MacosEntitlementsConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MacosIconConfig {
+    /// PNG or SVG relative to the project dir, rendered into every app_icon_<size>.png.
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MacosEntitlementsConfig {
+    /// com.apple.security.app-sandbox; required for the Mac App Store.
+    #[serde(default = "default_true")]
+    pub sandbox: bool,
+    /// Outgoing connections; a sandboxed app needs this for any HTTP request.
+    #[serde(default)]
+    pub network_client: bool,
+    /// Incoming connections (always on in debug/profile for the Dart VM service).
+    #[serde(default)]
+    pub network_server: bool,
+    /// Files the user picks in open/save panels.
+    #[serde(default)]
+    pub user_selected_files: Option<MacosFileAccess>,
+    /// The Downloads folder.
+    #[serde(default)]
+    pub downloads: Option<MacosFileAccess>,
+    #[serde(default)]
+    pub camera: bool,
+    #[serde(default)]
+    pub microphone: bool,
+    /// Any other entitlement keys, written as-is to both files.
+    #[serde(default)]
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MacosFileAccess {
+    ReadOnly,
+    ReadWrite,
+}
+
+impl MacosFileAccess {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MacosFileAccess::ReadOnly => "read-only",
+            MacosFileAccess::ReadWrite => "read-write",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+#[allow(dead_code)]
+pub struct WindowsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub window_width: Option<u32>,
+    #[serde(default)]
+    pub window_height: Option<u32>,
+    /// Minimum window size in logical pixels.
+    #[serde(default)]
+    pub min_width: Option<u32>,
+    #[serde(default)]
+    pub min_height: Option<u32>,
+    #[serde(default = "default_true")]
+    pub resizable: bool,
+    /// Show the window maximized on startup.
+    #[serde(default)]
+    pub maximized: bool,
+    /// Window title; defaults to the project name set by flutter create.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Allow one running instance; starting another activates the existing window and
+    /// hands it the new command line (see `protocols`).
+    /// Instances are told apart by android.app.build.application_id, so profiles with
+    /// their own id (`com.example.app.staging`) run side by side.
+    #[serde(default)]
+    pub single_instance: bool,
+    /// Runner.rc VERSIONINFO strings.
+    #[serde(default)]
+    pub company: Option<String>,
+    #[serde(default)]
+    pub copyright: Option<String>,
+    /// ProductName and FileDescription.
+    #[serde(default)]
+    pub product_name: Option<String>,
+    #[serde(default)]
+    pub icon: Option<WindowsIconConfig>,
+    #[serde(default)]
+    pub cmake: WindowsCmakeConfig,
+    /// Rewrites runner/runner.exe.manifest; unset keeps Flutter's template.
+    #[serde(default)]
+    pub manifest: Option<WindowsManifestConfig>,
+    /// URL schemes (e.g. "myapp" for myapp://) registered for the current user at startup;
+    /// the URL reaches Dart as the first entrypoint argument, like a deep link on Android.
+    /// With single_instance a link opened while the app runs activates its window and
+    /// arrives as `arguments` on the `flutter_gen_platforms/single_instance` method channel.
+    #[serde(default)]
+    pub protocols: Vec<String>,
+    /// File types opened with the app (the file path is passed the same way).
+    #[serde(default)]
+    pub file_associations: Vec<WindowsFileAssociation>,
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct PubspecConfig {
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WindowsFileAssociation {
+    /// e.g. ".demo"; the leading dot is optional.
+    pub extension: String,
+    /// Shown in Explorer's "Type" column.
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Defaults to `<exe name>.<extension>`.
+    #[serde(default)]
+    pub prog_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WindowsIconConfig {
+    /// PNG or SVG relative to the project dir, rendered into runner/resources/app_icon.ico.
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WindowsManifestConfig {
+    #[serde(default = "default_dpi_awareness")]
+    pub dpi_awareness: WindowsDpiAwareness,
+    /// Opt in to paths longer than MAX_PATH (also needs LongPathsEnabled on the machine).
+    #[serde(default)]
+    pub long_path_aware: bool,
+    /// UAC level; unset leaves it to Windows (asInvoker).
+    #[serde(default)]
+    pub execution_level: Option<WindowsExecutionLevel>,
+}
+
+impl Default for WindowsManifestConfig {
+    fn default() -> Self {
+        Self {
+            dpi_awareness: default_dpi_awareness(),
+            long_path_aware: false,
+            execution_level: None,
+        }
+    }
+}
+
+fn default_dpi_awareness() -> WindowsDpiAwareness {
+    WindowsDpiAwareness::PerMonitorV2
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowsDpiAwareness {
+    Unaware,
+    System,
+    PerMonitor,
+    PerMonitorV2,
+}
+
+impl WindowsDpiAwareness {
+    /// `<dpiAwareness>` value (Windows 10 1607+).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WindowsDpiAwareness::Unaware => "unaware",
+            WindowsDpiAwareness::System => "system",
+            WindowsDpiAwareness::PerMonitor => "PerMonitor",
+            WindowsDpiAwareness::PerMonitorV2 => "PerMonitorV2, PerMonitor",
+        }
+    }
+
+    /// Legacy `<dpiAware>` value read by older Windows versions.
+    pub fn legacy_value(self) -> &'static str {
+        match self {
+            WindowsDpiAwareness::Unaware => "false",
+            WindowsDpiAwareness::System => "true",
+            WindowsDpiAwareness::PerMonitor | WindowsDpiAwareness::PerMonitorV2 => "true/pm",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowsExecutionLevel {
+    AsInvoker,
+    HighestAvailable,
+    RequireAdministrator,
+}
+
+impl WindowsExecutionLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WindowsExecutionLevel::AsInvoker => "asInvoker",
+            WindowsExecutionLevel::HighestAvailable => "highestAvailable",
+            WindowsExecutionLevel::RequireAdministrator => "requireAdministrator",
+        }
+    }
+}
+
+/// Additions to windows/runner/CMakeLists.txt, spliced in after every regeneration
+/// together with `<platforms_dir>/windows/runner.cmake` when present.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct WindowsCmakeConfig {
+    /// Extra C/C++ sources, relative to the project dir: outside windows/, or copied into
+    /// it by the platforms/windows overlay.
+    #[serde(default)]
+    pub sources: Vec<String>,
+    /// Include directories, relative to the project dir.
+    #[serde(default)]
+    pub include_dirs: Vec<String>,
+    /// Preprocessor definitions, e.g. "WIN32_LEAN_AND_MEAN" or "APP_FEATURE=1".
+    #[serde(default)]
+    pub defines: Vec<String>,
+    /// Libraries to link, e.g. "shell32.lib" or "comctl32.lib".
+    #[serde(default)]
+    pub libraries: Vec<String>,
+    /// C++ standard for the runner (17, 20, 23); Flutter's default is 17.
+    #[serde(default)]
+    pub cpp_standard: Option<u32>,
+}
+
+impl Default for WindowsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_width: None,
+            window_height: None,
+            min_width: None,
+            min_height: None,
+            resizable: true,
+            maximized: false,
+            title: None,
+            single_instance: false,
+            company: None,
+            copyright: None,
+            product_name: None,
+            icon: None,
+            cmake: WindowsCmakeConfig::default(),
+            manifest: None,
+            protocols: Vec::new(),
+            file_associations: Vec::new(),
+        }
+    }
+}
+
+/// PWA settings patched into web/manifest.json; unset fields keep the Flutter template's values.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct WebConfig {
     #[serde(default)]
     pub name: Option<String>,
     #[serde(default)]
+    pub short_name: Option<String>,
+    #[serde(default)]
     pub description: Option<String>,
     #[serde(default)]
-    pub version: Option<String>,
+    pub start_url: Option<String>,
     #[serde(default)]
-    pub homepage: Option<String>,
+    pub theme_color: Option<String>,
     #[serde(default)]
-    pub repository: Option<String>,
+    pub background_color: Option<String>,
+    #[serde(default)]
+    pub display: Option<WebDisplay>,
+    /// e.g. "portrait-primary", "landscape", "any"
+    #[serde(default)]
+    pub orientation: Option<String>,
+    #[serde(default)]
+    pub icons: Option<WebIconsConfig>,
+    /// index.html `<title>` and apple-mobile-web-app-title.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Fixed `<base href>`, e.g. "/app/"; must start and end with "/".
+    #[serde(default)]
+    pub base_href: Option<String>,
+    /// Extra `<meta name=... content=...>` tags, replacing same-named ones.
+    #[serde(default)]
+    pub meta: BTreeMap<String, String>,
+    /// Content-Security-Policy meta tag, e.g. "default-src 'self'; script-src 'self' 'wasm-unsafe-eval'".
+    #[serde(default)]
+    pub csp: Option<String>,
+    /// Extra `<script>` tags added to the index.html head, in order.
+    #[serde(default)]
+    pub scripts: Vec<WebScriptConfig>,
+    /// Extra `<link>` tags (fonts, preconnect) added to the index.html head.
+    #[serde(default)]
+    pub links: Vec<WebLinkConfig>,
+    /// Loading indicator shown in index.html until Flutter renders its first frame.
+    #[serde(default)]
+    pub loading: Option<WebLoadingConfig>,
+    /// Writes web/flutter_bootstrap.js with these `_flutter.loader.load` options.
+    #[serde(default)]
+    pub loader: Option<WebLoaderConfig>,
+    #[serde(default)]
+    pub hosting: Option<WebHostingConfig>,
+    /// Open Graph and Twitter card tags for link previews.
+    #[serde(default)]
+    pub social: Option<WebSocialConfig>,
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct FlutterCreateConfig {
+/// Values may use `{{project_name}}`, `{{version}}` and `{{description}}`; title and
+/// description fall back to `web.title`/`web.name`/project_name and
+/// `web.description`/description.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct WebSocialConfig {
     #[serde(default)]
-    pub platforms: Option<Vec<String>>,
+    pub title: Option<String>,
     #[serde(default)]
-    pub android_language: Option<String>,
+    pub description: Option<String>,
+    /// Absolute URL, or a path resolved against `url`.
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub image_alt: Option<String>,
+    /// Canonical URL of the deployed app.
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub site_name: Option<String>,
+    /// Twitter card type; defaults to "summary_large_image" with an image, else "summary".
+    #[serde(default)]
+    pub twitter_card: Option<String>,
+    /// e.g. "@handle"
+    #[serde(default)]
+    pub twitter_site: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
-pub struct AndroidConfig {
+/// Hosting configs written to the project root: SPA rewrite to index.html, cache
+/// headers and the wasm MIME type.
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WebHostingConfig {
+    pub providers: Vec<HostingProvider>,
+    /// Directory that gets deployed.
+    #[serde(default = "default_hosting_public_dir")]
+    pub public_dir: String,
+    /// Globs (relative to public_dir) served with a long max-age.
+    #[serde(default = "default_hosting_cached_paths")]
+    pub cached_paths: Vec<String>,
+    #[serde(default = "default_hosting_max_age")]
+    pub max_age: u64,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HostingProvider {
+    Firebase,
+    Netlify,
+    Nginx,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct WebScriptConfig {
     #[serde(default)]
-    pub gradle_wrapper: AndroidGradleWrapperConfig,
+    pub src: Option<String>,
+    /// Inline script body, used when `src` is not set.
     #[serde(default)]
-    pub app: AndroidAppConfig,
-    pub build: AndroidBuildConfig,
+    pub content: Option<String>,
+    #[serde(default, rename = "async")]
+    pub is_async: bool,
     #[serde(default)]
-    pub settings: AndroidSettingsConfig,
+    pub defer: bool,
+    /// Other attributes, e.g. { type = "module", crossorigin = "anonymous" }.
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct AndroidBuildConfig {
-    pub allprojects: RepositoryList,
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct WebLinkConfig {
+    pub rel: String,
+    pub href: String,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct AndroidSettingsConfig {
-    pub plugin_management: RepositoryList,
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct WebLoadingConfig {
+    /// File under `platforms/web/` with the indicator markup (may include its own `<style>`).
+    #[serde(default)]
+    pub template: Option<String>,
+    /// Inline markup, used when no template is set.
+    #[serde(default)]
+    pub html: Option<String>,
+    #[serde(default)]
+    pub css: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct AndroidGradleWrapperConfig {
-    pub distribution_url: Option<String>,
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct WebLoaderConfig {
+    #[serde(default)]
+    pub renderer: Option<WebRenderer>,
+    /// Where CanvasKit is fetched from instead of the gstatic CDN, e.g. "canvaskit/"
+    /// for the copy bundled with the build.
+    #[serde(default)]
+    pub canvas_kit_base_url: Option<String>,
+    /// "auto", "full" or "chromium".
+    #[serde(default)]
+    pub canvas_kit_variant: Option<String>,
+    #[serde(default)]
+    pub force_single_threaded_skwasm: Option<bool>,
+    #[serde(default)]
+    pub entrypoint_base_url: Option<String>,
+    /// Register the service worker (deprecated by Flutter; off unless enabled).
+    #[serde(default)]
+    pub service_worker: bool,
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct AndroidAppConfig {
+/// `html` only exists in Flutter < 3.29; `skwasm` needs `flutter build web --wasm`.
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebRenderer {
+    Canvaskit,
+    Skwasm,
+    Html,
+}
+
+impl WebRenderer {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebRenderer::Canvaskit => "canvaskit",
+            WebRenderer::Skwasm => "skwasm",
+            WebRenderer::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WebIconsConfig {
+    /// Source image for favicon.png, icons/Icon-{192,512}.png and their maskable variants.
+    pub source: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebDisplay {
+    Fullscreen,
+    Standalone,
+    MinimalUi,
+    Browser,
+}
+
+impl WebDisplay {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebDisplay::Fullscreen => "fullscreen",
+            WebDisplay::Standalone => "standalone",
+            WebDisplay::MinimalUi => "minimal-ui",
+            WebDisplay::Browser => "browser",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AssetsConfig {
+    #[serde(default = "default_assets_output_dir")]
+    pub output_dir: String,
     #[serde(default)]
-    pub build: AndroidAppBuildConfig,
+    pub webp: bool,
+    #[serde(default)]
+    pub images: Vec<AssetImageConfig>,
 }
 
-#[derive(Debug, Deserialize, Default)]
-pub struct AndroidAppBuildConfig {
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AssetImageConfig {
+    pub source: String,
     #[serde(default)]
-    pub namespace: String,
+    pub name: Option<String>,
+    /// Logical (1x) width in pixels; 2x/3x variants are scaled from it.
+    pub width: u32,
     #[serde(default)]
-    pub application_id: String,
+    pub height: Option<u32>,
     #[serde(default)]
-    pub output_file_name: Option<String>,
+    pub webp: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FontsConfig {
+    #[serde(default = "default_fonts_output_dir")]
+    pub output_dir: String,
     #[serde(default)]
-    pub abi_filters: Option<Vec<String>>,
+    pub families: Vec<FontFamilyConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FontFamilyConfig {
+    pub family: String,
+    pub files: Vec<FontFileConfig>,
+    /// Subset every file to these ranges (e.g. "U+0000-00FF"); fonts are copied verbatim when unset.
     #[serde(default)]
-    pub kotlin_incremental: Option<bool>,
+    pub unicode_ranges: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize)]
-#[allow(dead_code)]
-pub struct IosConfig {}
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FontFileConfig {
+    pub source: String,
+    #[serde(default)]
+    pub weight: Option<u16>,
+    #[serde(default)]
+    pub style: Option<String>,
+}
 
-#[derive(Debug, Deserialize, Default)]
-#[allow(dead_code)]
-pub struct WindowsConfig {
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
+pub struct BuildEnvConfig {
     #[serde(default)]
-    pub enabled: bool,
+    pub output: Option<String>,
     #[serde(default)]
-    pub window_width: Option<u32>,
+    pub profile: Option<String>,
+    /// Watermark text; defaults to the upper-cased profile for non-production profiles.
     #[serde(default)]
-    pub window_height: Option<u32>,
+    pub banner: Option<String>,
+    #[serde(default)]
+    pub flags: BTreeMap<String, BuildEnvValue>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone)]
+#[serde(untagged)]
+pub enum BuildEnvValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Default)]
 pub struct RepositoryList {
     pub repositories: Vec<String>,
+    /// Drop the template's default repositories (google(), mavenCentral(), ...) and keep
+    /// only `repositories`, for networks where the defaults are unreachable.
+    #[serde(default)]
+    pub replace: bool,
+}
+
+fn default_preflight_min_free_mb() -> u64 {
+    500
+}
+
+fn default_create_default_platforms() -> Vec<String> {
+    vec!["android".to_string()]
+}
+
+fn default_workspace_config() -> String {
+    "app.pkl".to_string()
+}
+
+fn default_workspace_platforms_dir() -> String {
+    "platforms".to_string()
+}
+
+fn default_clean_keep_backups() -> usize {
+    5
+}
+
+fn default_play_integrity_meta_data_name() -> String {
+    "play_integrity_cloud_project_number".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_build_jobs() -> usize {
+    1
+}
+
+fn default_build_log_dir() -> String {
+    "build/logs".to_string()
+}
+
+fn default_build_dist_dir() -> String {
+    "dist".to_string()
+}
+
+fn default_build_types() -> Vec<String> {
+    vec!["release".to_string()]
+}
+
+fn default_hosting_public_dir() -> String {
+    "build/web".to_string()
+}
+
+fn default_hosting_cached_paths() -> Vec<String> {
+    ["assets/**", "canvaskit/**", "icons/**"].iter().map(|s| s.to_string()).collect()
+}
+
+fn default_hosting_max_age() -> u64 {
+    31_536_000
+}
+
+fn default_releases_file() -> String {
+    "releases.json".to_string()
+}
+
+fn default_changelog() -> String {
+    "CHANGELOG.md".to_string()
+}
+
+fn default_build_platforms() -> Vec<String> {
+    vec!["android".to_string()]
+}
+
+fn default_desktop_file_name() -> String {
+    "{project_name}-v{version}-{platform}-{arch}.zip".to_string()
+}
+
+fn default_assets_output_dir() -> String {
+    "assets/images".to_string()
+}
+
+fn default_fonts_output_dir() -> String {
+    "assets/fonts".to_string()
+}
+
+/// `read_only` (dry runs, `validate`) leaves the `app.pkl.json` cache untouched.
+pub fn load_config(path: &Path, read_only: bool) -> Result<Config> {
+    let cfg: Config = from_config_value(load_config_value(path, read_only)?)
+        .with_context(|| format!("Failed to parse config: {}", path.display()))?;
+    Ok(cfg)
+}
+
+/// Every field of a config that failed to deserialize, as `path: message`.
+#[derive(Debug)]
+pub struct FieldErrors(pub Vec<String>);
+
+impl std::fmt::Display for FieldErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0.as_slice() {
+            [] => Ok(()),
+            [only] => write!(f, "{}", only),
+            [first, rest @ ..] => write!(f, "{} (and {} more)", first, rest.len()),
+        }
+    }
 }
 
-pub fn load_config(path: &Path) -> Result<Config> {
-    match path.extension().and_then(|ext| ext.to_str()) {
-        Some("pkl") => load_pkl_config(path),
-        Some("toml") => {
-            let content = fs::read_to_string(path)
-                .with_context(|| format!("Failed to read config: {}", path.display()))?;
-            let cfg: Config = toml::from_str(&content).context("Failed to parse config")?;
-            Ok(cfg)
+impl std::error::Error for FieldErrors {}
+
+/// Deserializes a merged config value. Errors name the offending field
+/// (`android.app.build.min_sdk: invalid type: ...`) whatever format the files were in, and
+/// carry every failing field, not just the first: each one is dropped and the value retried.
+fn from_config_value<T: serde::de::DeserializeOwned>(mut value: serde_json::Value) -> Result<T> {
+    let mut errors = Vec::new();
+    let mut removed: Vec<String> = Vec::new();
+    loop {
+        let err = match serde_path_to_error::deserialize::<_, T>(value.clone()) {
+            Ok(parsed) if errors.is_empty() => return Ok(parsed),
+            Ok(_) => break,
+            Err(err) => err,
+        };
+        let path = err.path().to_string();
+        // A parent of a dropped field failing next is fallout of the drop, not a new problem.
+        let follow_on = removed
+            .iter()
+            .any(|done| done == &path || done.starts_with(&format!("{}.", path)) || done.starts_with(&format!("{}[", path)));
+        if follow_on {
+            break;
         }
-        _ => {
-            bail!("Unsupported config format: {}", path.display());
+        errors.push(err.to_string());
+        if errors.len() >= 50 || !remove_value_at(&mut value, err.path()) {
+            break;
         }
+        removed.push(path);
     }
+    Err(FieldErrors(errors).into())
 }
 
-fn load_pkl_config(path: &Path) -> Result<Config> {
-    let pkl_cmd = resolve_cmd("pkl")?;
-    let output = run_pkl_eval(&pkl_cmd, path, ["-f", "json"])
-        .or_else(|_| run_pkl_eval(&pkl_cmd, path, ["--format", "json"]))
-        .with_context(|| format!("Failed to run pkl eval for: {}", path.display()))?;
+/// Removes the map entry holding the value at `path`. A failing list element takes its whole
+/// list with it, so the indices of later errors stay those of the file. False when there is
+/// no such entry to drop.
+fn remove_value_at(value: &mut serde_json::Value, path: &serde_path_to_error::Path) -> bool {
+    use serde_path_to_error::Segment;
+    let mut segments: Vec<&Segment> = path.iter().collect();
+    while let Some(Segment::Seq { .. }) = segments.last() {
+        segments.pop();
+    }
+    let Some((Segment::Map { key }, parents)) = segments.split_last() else {
+        return false;
+    };
+    let mut current = value;
+    for segment in parents {
+        let next = match segment {
+            Segment::Map { key } => current.get_mut(key.as_str()),
+            Segment::Seq { index } => current.get_mut(*index),
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return false,
+        }
+    }
+    current.as_object_mut().is_some_and(|map| map.remove(key.as_str()).is_some())
+}
 
-    let cfg: Config = serde_json::from_slice(&output)
-        .with_context(|| format!("Failed to parse pkl output: {}", path.display()))?;
+/// The config file as an untyped JSON value, for merging before deserialization. Files
+/// named by `extends` are loaded first and the file's own keys deep-merged over them.
+pub fn load_config_value(path: &Path, read_only: bool) -> Result<serde_json::Value> {
+    resolve_extends(path, read_only, &mut Vec::new(), &mut Vec::new())
+}
+
+/// Base files pulled in through `extends`, recursively, in merge order.
+pub fn config_base_paths(path: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut bases = Vec::new();
+    resolve_extends(path, true, &mut Vec::new(), &mut bases)?;
+    Ok(bases)
+}
+
+fn resolve_extends(
+    path: &Path,
+    read_only: bool,
+    chain: &mut Vec<std::path::PathBuf>,
+    bases: &mut Vec<std::path::PathBuf>,
+) -> Result<serde_json::Value> {
+    let canonical = fs::canonicalize(path).with_context(|| format!("Failed to read config: {}", path.display()))?;
+    if chain.contains(&canonical) {
+        bail!("Circular extends in config: {}", path.display());
+    }
+    let mut value = load_config_file_value(path, read_only)?;
+    let extends = match value.as_object_mut().and_then(|map| map.remove("extends")) {
+        None => Vec::new(),
+        Some(serde_json::Value::String(base)) => vec![base],
+        Some(serde_json::Value::Array(items)) => items
+            .into_iter()
+            .map(|item| match item {
+                serde_json::Value::String(base) => Ok(base),
+                _ => bail!("extends must list file paths: {}", path.display()),
+            })
+            .collect::<Result<_>>()?,
+        Some(_) => bail!("extends must be a file path or a list of them: {}", path.display()),
+    };
+    if extends.is_empty() {
+        return Ok(value);
+    }
+
+    chain.push(canonical);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut merged = serde_json::Value::Object(serde_json::Map::new());
+    for base in extends {
+        let base_path = dir.join(expand_env_vars(&base)?);
+        let base_value = resolve_extends(&base_path, read_only, chain, bases)
+            .with_context(|| format!("Failed to load base config: {}", base_path.display()))?;
+        bases.push(base_path);
+        merge_values(&mut merged, base_value);
+    }
+    chain.pop();
+    merge_values(&mut merged, value);
+    Ok(merged)
+}
+
+fn load_config_file_value(path: &Path, read_only: bool) -> Result<serde_json::Value> {
+    let read = || fs::read_to_string(path).with_context(|| format!("Failed to read config: {}", path.display()));
+    let value = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("pkl") => load_pkl_value(path, read_only)?,
+        Some("toml") => toml::from_str(&read()?).context("Failed to parse config")?,
+        // Same schema as app.pkl's JSON output, for setups without the pkl binary.
+        Some("yaml" | "yml") => serde_saphyr::from_str(&read()?).context("Failed to parse config")?,
+        Some("json") => serde_json::from_str(&read()?).context("Failed to parse config")?,
+        _ => bail!("Unsupported config format: {}", path.display()),
+    };
+    Ok(value)
+}
+
+pub fn load_workspace_config(path: &Path, read_only: bool) -> Result<WorkspaceConfig> {
+    let mut workspace: WorkspaceConfig = from_config_value(load_config_value(path, read_only)?)
+        .with_context(|| format!("Failed to parse workspace: {}", path.display()))?;
+    for app in workspace.apps.iter_mut() {
+        *app = expand_env_vars(app)?;
+    }
+    workspace.platforms_dir = expand_env_vars(&workspace.platforms_dir)?;
+    if workspace.apps.is_empty() {
+        bail!("No apps listed in workspace: {}", path.display());
+    }
+    Ok(workspace)
+}
+
+/// `app.pkl` + `staging` -> `app.staging.pkl`.
+pub fn profile_config_path(path: &Path, profile: &str) -> std::path::PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}.{}.{}", stem, profile, ext.to_string_lossy()),
+        None => format!("{}.{}", stem, profile),
+    };
+    path.with_file_name(name)
+}
+
+/// Deep merge: objects are merged key by key, anything else in `overlay` replaces the
+/// value in `base` (lists are replaced, not appended).
+pub fn merge_values(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Loads the config with the `profile` overlays merged in: `profiles.<profile>` from the
+/// config itself, then `<name>.<profile>.<ext>` next to it when that file exists.
+pub fn load_profile_config(path: &Path, profile: &str, read_only: bool) -> Result<Config> {
+    let mut value = load_config_value(path, read_only)?;
+    let section = value.get("profiles").and_then(|profiles| profiles.get(profile)).cloned();
+    let has_profiles = value
+        .get("profiles")
+        .and_then(serde_json::Value::as_object)
+        .is_some_and(|profiles| !profiles.is_empty());
+    let overlay_path = profile_config_path(path, profile);
+    if section.is_none() && !overlay_path.exists() {
+        if has_profiles {
+            println!(
+                "Warning: profile '{}' has no profiles.{} section and no {}",
+                profile,
+                profile,
+                overlay_path.display()
+            );
+        }
+        return load_config(path, read_only);
+    }
+    if let Some(section) = section {
+        merge_values(&mut value, section);
+        println!("✓ Profile overlay applied: profiles.{}", profile);
+    }
+    if overlay_path.exists() {
+        merge_values(&mut value, load_config_value(&overlay_path, read_only)?);
+        println!("✓ Profile overlay applied: {}", overlay_path.display());
+    }
+    let cfg: Config = from_config_value(value)
+        .with_context(|| format!("Failed to parse config with profile '{}': {}", profile, path.display()))?;
     Ok(cfg)
 }
 
+/// `app.pkl` -> `app.pkl.json`: the last `pkl eval` output, refreshed on every run with pkl
+/// installed (except read-only loads). Committing it lets CI images without the pkl CLI run
+/// the generator.
+pub fn pkl_json_cache(path: &Path) -> std::path::PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".json");
+    name.into()
+}
+
+fn load_pkl_value(path: &Path, read_only: bool) -> Result<serde_json::Value> {
+    let cache = pkl_json_cache(path);
+    let output = match resolve_cmd("pkl") {
+        Ok(pkl_cmd) => {
+            let output = run_pkl_eval(&pkl_cmd, path, ["-f", "json"])
+                .or_else(|_| run_pkl_eval(&pkl_cmd, path, ["--format", "json"]))
+                .with_context(|| format!("Failed to run pkl eval for: {}", path.display()))?;
+            if !read_only && fs::read(&cache).ok().as_deref() != Some(output.as_slice()) {
+                fs::write(&cache, &output)
+                    .with_context(|| format!("Failed to write file: {}", cache.display()))?;
+            }
+            output
+        }
+        Err(err) => {
+            if !cache.exists() {
+                return Err(err).with_context(|| {
+                    format!("pkl is required for {} (or a cached {})", path.display(), cache.display())
+                });
+            }
+            let modified = |p: &Path| fs::metadata(p).and_then(|m| m.modified()).ok();
+            if modified(path) > modified(&cache) {
+                println!(
+                    "Warning: {} is newer than {}; run with pkl installed to refresh it",
+                    path.display(),
+                    cache.display()
+                );
+            }
+            println!("✓ pkl not found, using cached {}", cache.display());
+            fs::read(&cache).with_context(|| format!("Failed to read file: {}", cache.display()))?
+        }
+    };
+
+    // Optional properties of the `schema --format pkl` module render as null; dropping them
+    // lets serde apply the same defaults as for a missing key.
+    let mut value: serde_json::Value = serde_json::from_slice(&output)
+        .with_context(|| format!("Failed to parse pkl output: {}", path.display()))?;
+    drop_nulls(&mut value);
+    Ok(value)
+}
+
+fn drop_nulls(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|_, item| !item.is_null());
+            map.values_mut().for_each(drop_nulls);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(drop_nulls),
+        _ => {}
+    }
+}
+
 fn run_pkl_eval(pkl_cmd: &Path, path: &Path, format_args: [&str; 2]) -> Result<Vec<u8>> {
     let output = Command::new(pkl_cmd)
         .arg("eval")
@@ -170,6 +2009,14 @@ pub fn expand_config(cfg: &mut Config) -> Result<()> {
     if let Some(value) = cfg.platforms_dir.as_ref() {
         cfg.platforms_dir = Some(expand_env_vars(value)?);
     }
+    if let Some(build_env) = cfg.build_env.as_mut() {
+        if let Some(value) = build_env.profile.as_ref() {
+            build_env.profile = Some(expand_env_vars(value)?);
+        }
+        if let Some(value) = build_env.banner.as_ref() {
+            build_env.banner = Some(expand_env_vars(value)?);
+        }
+    }
     expand_flutter_create_config(&mut cfg.create)?;
     expand_android_config(&mut cfg.android)?;
     if cfg.android.app.build.application_id.trim().is_empty() {
@@ -195,6 +2042,14 @@ fn expand_android_config(cfg: &mut AndroidConfig) -> Result<()> {
     if let Some(value) = cfg.gradle_wrapper.distribution_url.as_ref() {
         cfg.gradle_wrapper.distribution_url = Some(expand_env_vars(value)?);
     }
+    if let Some(local) = cfg.local_properties.as_mut() {
+        if let Some(value) = local.sdk_dir.as_ref() {
+            local.sdk_dir = Some(expand_env_vars(value)?);
+        }
+        if let Some(value) = local.flutter_sdk.as_ref() {
+            local.flutter_sdk = Some(expand_env_vars(value)?);
+        }
+    }
     cfg.build.allprojects.repositories = cfg
         .build
         .allprojects
@@ -209,6 +2064,13 @@ fn expand_android_config(cfg: &mut AndroidConfig) -> Result<()> {
         .iter()
         .map(|value| expand_env_vars(value))
         .collect::<Result<Vec<_>>>()?;
+    if let Some(resolution) = cfg.settings.dependency_resolution.as_mut() {
+        resolution.repositories = resolution
+            .repositories
+            .iter()
+            .map(|value| expand_env_vars(value))
+            .collect::<Result<Vec<_>>>()?;
+    }
     Ok(())
 }
 
@@ -227,7 +2089,7 @@ fn expand_flutter_create_config(cfg: &mut FlutterCreateConfig) -> Result<()> {
     Ok(())
 }
 
-fn expand_env_vars(input: &str) -> Result<String> {
+pub fn expand_env_vars(input: &str) -> Result<String> {
     let mut out = String::new();
     let chars: Vec<char> = input.chars().collect();
     let mut i = 0;
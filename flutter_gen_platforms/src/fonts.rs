@@ -0,0 +1,71 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::FontsConfig;
+use crate::pubspec::{set_flutter_fonts, FontAsset, FontFamily};
+use crate::utils::resolve_cmd;
+
+fn subset_font(src: &Path, dst: &Path, unicode_ranges: &[String]) -> Result<()> {
+    let pyftsubset = resolve_cmd("pyftsubset")
+        .context("pyftsubset (from fonttools) is required for font subsetting")?;
+    let status = Command::new(pyftsubset)
+        .arg(src)
+        .arg(format!("--unicodes={}", unicode_ranges.join(",")))
+        .arg(format!("--output-file={}", dst.display()))
+        .arg("--layout-features=*")
+        .status()
+        .context("Failed to run pyftsubset")?;
+    if !status.success() {
+        bail!("pyftsubset failed for {} with status: {status}", src.display());
+    }
+    Ok(())
+}
+
+pub fn process_fonts(project_dir: &Path, config: &FontsConfig) -> Result<()> {
+    if config.families.is_empty() {
+        return Ok(());
+    }
+
+    let output_dir = config.output_dir.trim_end_matches('/');
+    let dst_dir = project_dir.join(output_dir);
+    fs::create_dir_all(&dst_dir)
+        .with_context(|| format!("Failed to create dir: {}", dst_dir.display()))?;
+
+    let mut families = Vec::new();
+    for family in &config.families {
+        let mut fonts = Vec::new();
+        for file in &family.files {
+            let src = project_dir.join(&file.source);
+            if !src.exists() {
+                bail!("Font file not found: {}", src.display());
+            }
+            let file_name = src
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let dst = dst_dir.join(&file_name);
+            match family.unicode_ranges.as_deref() {
+                Some(ranges) if !ranges.is_empty() => subset_font(&src, &dst, ranges)?,
+                _ => {
+                    fs::copy(&src, &dst).with_context(|| {
+                        format!("Failed to copy {} -> {}", src.display(), dst.display())
+                    })?;
+                }
+            }
+            fonts.push(FontAsset {
+                asset: format!("{}/{}", output_dir, file_name),
+                weight: file.weight,
+                style: file.style.clone(),
+            });
+        }
+        families.push(FontFamily {
+            family: family.family.clone(),
+            fonts,
+        });
+    }
+
+    set_flutter_fonts(&project_dir.join("pubspec.yaml"), &families)?;
+    Ok(())
+}
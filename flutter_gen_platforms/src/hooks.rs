@@ -0,0 +1,88 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::HooksConfig;
+
+#[derive(Debug, Clone, Copy)]
+pub enum HookStage {
+    PreGenerate,
+    PostGenerate,
+}
+
+impl HookStage {
+    fn as_str(self) -> &'static str {
+        match self {
+            HookStage::PreGenerate => "pre_generate",
+            HookStage::PostGenerate => "post_generate",
+        }
+    }
+
+    fn select<'a>(self, pre_generate: &'a [String], post_generate: &'a [String]) -> &'a [String] {
+        match self {
+            HookStage::PreGenerate => pre_generate,
+            HookStage::PostGenerate => post_generate,
+        }
+    }
+}
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+/// Runs the stage's shared hooks, then those of each generated platform in `platforms`
+/// order. Hooks see the generated platforms as `FLUTTER_GEN_PLATFORMS` (comma-separated)
+/// and, for per-platform hooks, their platform as `FLUTTER_GEN_PLATFORM`; commands are
+/// passed to the shell unexpanded, so `$` means what it means there.
+pub fn run_hooks(
+    project_dir: &Path,
+    hooks: &HooksConfig,
+    stage: HookStage,
+    platforms: &[&str],
+    dry_run: bool,
+) -> Result<()> {
+    let mut commands: Vec<(Option<&str>, &String)> = stage
+        .select(&hooks.pre_generate, &hooks.post_generate)
+        .iter()
+        .map(|command| (None, command))
+        .collect();
+    for platform in platforms {
+        if let Some(platform_hooks) = hooks.platforms.get(*platform) {
+            commands.extend(
+                stage
+                    .select(&platform_hooks.pre_generate, &platform_hooks.post_generate)
+                    .iter()
+                    .map(|command| (Some(*platform), command)),
+            );
+        }
+    }
+
+    let platform_list = platforms.join(",");
+    for (platform, command) in commands {
+        if dry_run {
+            println!("[DRY RUN] Would run {} hook: {}", stage.as_str(), command);
+            continue;
+        }
+        println!("Running {} hook: {}", stage.as_str(), command);
+        let mut shell = shell_command(command);
+        shell.current_dir(project_dir).env("FLUTTER_GEN_PLATFORMS", &platform_list);
+        if let Some(platform) = platform {
+            shell.env("FLUTTER_GEN_PLATFORM", platform);
+        }
+        let status = shell
+            .status()
+            .with_context(|| format!("Failed to run {} hook: {}", stage.as_str(), command))?;
+        if !status.success() {
+            bail!("{} hook failed with exit code {:?}: {}", stage.as_str(), status.code(), command);
+        }
+    }
+    Ok(())
+}
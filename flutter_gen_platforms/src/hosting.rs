@@ -0,0 +1,211 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::config::{HostingProvider, WebConfig, WebHostingConfig, WebRenderer};
+
+/// Flutter web entry files: not content-hashed, so browsers must revalidate them.
+const NO_CACHE_FILES: [&str; 6] = [
+    "index.html",
+    "flutter_bootstrap.js",
+    "flutter_service_worker.js",
+    "main.dart.js",
+    "version.json",
+    "manifest.json",
+];
+
+/// Header rules shared by every provider; paths are globs relative to the site root.
+struct HeaderRule {
+    glob: String,
+    headers: Vec<(&'static str, String)>,
+}
+
+fn header_rules(config: &WebHostingConfig, cross_origin_isolated: bool) -> Vec<HeaderRule> {
+    let mut rules = Vec::new();
+    if cross_origin_isolated {
+        // skwasm's multi-threaded build needs SharedArrayBuffer.
+        rules.push(HeaderRule {
+            glob: "**".to_string(),
+            headers: vec![
+                ("Cross-Origin-Opener-Policy", "same-origin".to_string()),
+                ("Cross-Origin-Embedder-Policy", "require-corp".to_string()),
+            ],
+        });
+    }
+    rules.push(HeaderRule {
+        glob: "**/*.wasm".to_string(),
+        headers: vec![("Content-Type", "application/wasm".to_string())],
+    });
+    for path in &config.cached_paths {
+        rules.push(HeaderRule {
+            glob: path.clone(),
+            headers: vec![("Cache-Control", format!("public, max-age={}", config.max_age))],
+        });
+    }
+    for file in NO_CACHE_FILES {
+        rules.push(HeaderRule {
+            glob: file.to_string(),
+            headers: vec![("Cache-Control", "no-cache".to_string())],
+        });
+    }
+    rules
+}
+
+/// Replaces only the `hosting` key, so other firebase.json settings survive.
+fn write_firebase(project_dir: &Path, config: &WebHostingConfig, rules: &[HeaderRule]) -> Result<()> {
+    let path = project_dir.join("firebase.json");
+    let mut firebase: Value = if path.exists() {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse JSON: {}", path.display()))?
+    } else {
+        json!({})
+    };
+    let Some(fields) = firebase.as_object_mut() else {
+        bail!("Expected a JSON object in: {}", path.display());
+    };
+    let headers: Vec<Value> = rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "source": rule.glob,
+                "headers": rule.headers.iter().map(|(key, value)| json!({ "key": key, "value": value })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    fields.insert(
+        "hosting".to_string(),
+        json!({
+            "public": config.public_dir,
+            "ignore": ["firebase.json", "**/.*"],
+            "rewrites": [{ "source": "**", "destination": "/index.html" }],
+            "headers": headers,
+        }),
+    );
+    fs::write(&path, serde_json::to_string_pretty(&firebase)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Firebase hosting config written to {}", path.display());
+    Ok(())
+}
+
+fn write_netlify(project_dir: &Path, config: &WebHostingConfig, rules: &[HeaderRule]) -> Result<()> {
+    let mut lines = vec![
+        "# Generated by flutter_gen_platforms from [web.hosting]".to_string(),
+        "[build]".to_string(),
+        format!("  publish = {}", json!(config.public_dir)),
+        String::new(),
+        "[[redirects]]".to_string(),
+        "  from = \"/*\"".to_string(),
+        "  to = \"/index.html\"".to_string(),
+        "  status = 200".to_string(),
+    ];
+    for rule in rules {
+        // Netlify's `*` already spans directories.
+        let path = format!("/{}", rule.glob.replace("**/", "").replace("**", "*"));
+        lines.push(String::new());
+        lines.push("[[headers]]".to_string());
+        lines.push(format!("  for = {}", json!(path)));
+        lines.push("  [headers.values]".to_string());
+        for (key, value) in &rule.headers {
+            lines.push(format!("    {} = {}", key, json!(value)));
+        }
+    }
+    let path = project_dir.join("netlify.toml");
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Netlify config written to {}", path.display());
+    Ok(())
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches zero directories.
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '.' | '+' | '(' | ')' | '[' | ']' | '{' | '}' | '^' | '$' | '|' | '\\' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            _ => regex.push(c),
+        }
+    }
+    regex
+}
+
+/// nginx drops inherited `add_header`s in any location that sets its own, so the
+/// site-wide headers are repeated in every location.
+fn write_nginx(project_dir: &Path, config: &WebHostingConfig, rules: &[HeaderRule]) -> Result<()> {
+    let (global, specific): (Vec<&HeaderRule>, Vec<&HeaderRule>) =
+        rules.iter().partition(|rule| rule.glob == "**");
+    let add_headers = |rule: Option<&HeaderRule>, indent: &str| -> Vec<String> {
+        global
+            .iter()
+            .copied()
+            .chain(rule)
+            .flat_map(|rule| rule.headers.iter())
+            .filter(|(key, _)| *key != "Content-Type")
+            .map(|(key, value)| format!("{}add_header {} \"{}\" always;", indent, key, value))
+            .collect()
+    };
+
+    let mut lines = vec![
+        "# Generated by flutter_gen_platforms from [web.hosting]".to_string(),
+        format!("# Serve the contents of {} as the document root.", config.public_dir),
+        "server {".to_string(),
+        "    listen 80;".to_string(),
+        "    root /usr/share/nginx/html;".to_string(),
+        "    index index.html;".to_string(),
+        String::new(),
+        "    location / {".to_string(),
+        "        try_files $uri $uri/ /index.html;".to_string(),
+    ];
+    lines.extend(add_headers(None, "        "));
+    lines.push("    }".to_string());
+    for rule in specific {
+        lines.push(String::new());
+        lines.push(format!("    location ~ ^/{}$ {{", glob_to_regex(&rule.glob)));
+        if let Some((_, mime)) = rule.headers.iter().find(|(key, _)| *key == "Content-Type") {
+            lines.push("        types { }".to_string());
+            lines.push(format!("        default_type {};", mime));
+        }
+        lines.extend(add_headers(Some(rule), "        "));
+        lines.push("    }".to_string());
+    }
+    lines.push("}".to_string());
+
+    let path = project_dir.join("nginx.conf");
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ nginx config written to {}", path.display());
+    Ok(())
+}
+
+pub fn write_hosting_configs(project_dir: &Path, web: &WebConfig, config: &WebHostingConfig) -> Result<()> {
+    let cross_origin_isolated = web
+        .loader
+        .as_ref()
+        .is_some_and(|loader| loader.renderer == Some(WebRenderer::Skwasm));
+    let rules = header_rules(config, cross_origin_isolated);
+    for provider in &config.providers {
+        match provider {
+            HostingProvider::Firebase => write_firebase(project_dir, config, &rules)?,
+            HostingProvider::Netlify => write_netlify(project_dir, config, &rules)?,
+            HostingProvider::Nginx => write_nginx(project_dir, config, &rules)?,
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use which::which;
+
+const IMAGES_SCRIPT: &str = include_str!("../../flutter_gen_images.py");
+
+/// Runs `flutter_gen_images.py` with the given job from inside `project_dir`,
+/// so relative source/output paths in the job resolve against the project.
+pub fn run_image_job(project_dir: &Path, job: &Value) -> Result<()> {
+    let work_dir = std::env::temp_dir().join(format!("flutter_gen_images_{}", std::process::id()));
+    fs::create_dir_all(&work_dir)
+        .with_context(|| format!("Failed to create dir: {}", work_dir.display()))?;
+    let script_path = work_dir.join("flutter_gen_images.py");
+    let job_path = work_dir.join("job.json");
+    fs::write(&script_path, IMAGES_SCRIPT)
+        .with_context(|| format!("Failed to write file: {}", script_path.display()))?;
+    fs::write(&job_path, serde_json::to_string_pretty(job)?)
+        .with_context(|| format!("Failed to write file: {}", job_path.display()))?;
+
+    // Prefer uv so the script's inline Pillow dependency is resolved automatically.
+    let mut command = if let Ok(uv) = which("uv") {
+        let mut command = Command::new(uv);
+        command.arg("run").arg("--script").arg(&script_path);
+        command
+    } else {
+        let python_cmd = which("python")
+            .or_else(|_| which("python3"))
+            .context("Neither uv nor python found in PATH (needed for image generation)")?;
+        let mut command = Command::new(python_cmd);
+        command.arg(&script_path);
+        command
+    };
+
+    let status = command
+        .arg("--job")
+        .arg(&job_path)
+        .current_dir(project_dir)
+        .status()
+        .context("Failed to run image generation script")?;
+    let _ = fs::remove_dir_all(&work_dir);
+    if !status.success() {
+        bail!("image generation failed with status: {status}");
+    }
+    Ok(())
+}
@@ -0,0 +1,379 @@
+use anyhow::{bail, Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use crate::validate::{check_id, check_project_name, Problems, PLATFORMS};
+
+/// Maven mirrors written into the starter config's repository lists.
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum InitMirror {
+    None,
+    Aliyun,
+}
+
+impl InitMirror {
+    /// (allprojects, plugin_management) repositories, tried before the template's defaults.
+    fn repositories(self) -> (Vec<&'static str>, Vec<&'static str>) {
+        match self {
+            InitMirror::None => (Vec::new(), Vec::new()),
+            InitMirror::Aliyun => (
+                vec![
+                    "https://maven.aliyun.com/repository/google",
+                    "https://maven.aliyun.com/repository/public",
+                ],
+                vec![
+                    "https://maven.aliyun.com/repository/gradle-plugin",
+                    "https://maven.aliyun.com/repository/google",
+                    "https://maven.aliyun.com/repository/public",
+                ],
+            ),
+        }
+    }
+}
+
+/// What `init` asks for; flags fill these in, prompts the rest.
+pub struct InitOptions {
+    pub name: Option<String>,
+    pub org: Option<String>,
+    pub platforms: Option<Vec<String>>,
+    pub mirror: Option<InitMirror>,
+    /// Take the defaults instead of prompting.
+    pub yes: bool,
+    /// Overwrite an existing config and manifest templates.
+    pub force: bool,
+}
+
+struct Answers {
+    name: String,
+    org: String,
+    platforms: Vec<String>,
+    mirror: InitMirror,
+}
+
+impl Answers {
+    fn application_id(&self) -> String {
+        format!("{}.{}", self.org, self.name)
+    }
+}
+
+const MANIFEST_MAIN: &str = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android">
+    <application
+        android:label="{{label}}"
+        android:name="${applicationName}"
+        android:icon="@mipmap/ic_launcher">
+        <activity
+            android:name=".MainActivity"
+            android:exported="true"
+            android:launchMode="singleTop"
+            android:taskAffinity=""
+            android:theme="@style/LaunchTheme"
+            android:configChanges="orientation|keyboardHidden|keyboard|screenSize|smallestScreenSize|locale|layoutDirection|fontScale|screenLayout|density|uiMode"
+            android:hardwareAccelerated="true"
+            android:windowSoftInputMode="adjustResize">
+            <meta-data
+              android:name="io.flutter.embedding.android.NormalTheme"
+              android:resource="@style/NormalTheme"
+              />
+            <intent-filter>
+                <action android:name="android.intent.action.MAIN"/>
+                <category android:name="android.intent.category.LAUNCHER"/>
+            </intent-filter>
+        </activity>
+        <meta-data
+            android:name="flutterEmbedding"
+            android:value="2" />
+    </application>
+    <queries>
+        <intent>
+            <action android:name="android.intent.action.PROCESS_TEXT"/>
+            <data android:mimeType="text/plain"/>
+        </intent>
+    </queries>
+</manifest>
+"#;
+
+/// debug/ and profile/ manifests: the Flutter tool talks to the app over the network.
+const MANIFEST_DEBUG: &str = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android">
+    <uses-permission android:name="android.permission.INTERNET"/>
+</manifest>
+"#;
+
+fn ask(question: &str, default: &str) -> Result<String> {
+    print!("{} [{}]: ", question, default);
+    io::stdout().flush()?;
+    let mut line = String::new();
+    io::stdin().read_line(&mut line).context("Failed to read answer")?;
+    let answer = line.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
+
+/// Dart package name derived from the project directory, e.g. "My App" -> "my_app".
+fn default_name(project_dir: &Path) -> String {
+    let dir = fs::canonicalize(project_dir).unwrap_or_else(|_| project_dir.to_path_buf());
+    let raw = dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let name: String = raw
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.trim_matches('_') {
+        "" => "app".to_string(),
+        trimmed if trimmed.starts_with(|c: char| c.is_ascii_digit()) => format!("app_{}", trimmed),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+fn collect_answers(project_dir: &Path, options: &InitOptions) -> Result<Answers> {
+    let interactive = !options.yes && io::stdin().is_terminal();
+    let name = match &options.name {
+        Some(name) => name.clone(),
+        None if interactive => ask("Project name", &default_name(project_dir))?,
+        None => default_name(project_dir),
+    };
+    let org = match &options.org {
+        Some(org) => org.clone(),
+        None if interactive => ask("Organization (reverse domain)", "com.example")?,
+        None => "com.example".to_string(),
+    };
+    let platforms = match &options.platforms {
+        Some(platforms) => platforms.clone(),
+        None if interactive => ask("Platforms", "android,ios")?
+            .split(',')
+            .map(|p| p.trim().to_string())
+            .filter(|p| !p.is_empty())
+            .collect(),
+        None => vec!["android".to_string(), "ios".to_string()],
+    };
+    let mirror = match options.mirror {
+        Some(mirror) => mirror,
+        None if interactive => {
+            let answer = ask("Maven mirror (none, aliyun)", "none")?;
+            InitMirror::from_str(&answer, true).map_err(|err| anyhow::anyhow!("Invalid mirror: {}", err))?
+        }
+        None => InitMirror::None,
+    };
+
+    let mut problems = Problems::new();
+    check_project_name(&mut problems, "name", &name);
+    check_id(&mut problems, "org", &org, false);
+    if problems.is_empty() && platforms.iter().any(|p| p == "android") {
+        check_id(&mut problems, "android.app.id", &format!("{}.{}", org, name), true);
+    }
+    if let Some((path, message)) = problems.first() {
+        bail!("Invalid {}: {}", path, message);
+    }
+    if let Some(unknown) = platforms.iter().find(|p| !PLATFORMS.contains(&p.as_str())) {
+        bail!("Unknown platform: {} (expected {})", unknown, PLATFORMS.join(", "));
+    }
+    if platforms.is_empty() {
+        bail!("At least one platform is required");
+    }
+    Ok(Answers {
+        name,
+        org,
+        platforms,
+        mirror,
+    })
+}
+
+/// A double-quoted string literal; JSON's escaping is valid in pkl, TOML and YAML alike.
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn quote_list(values: &[impl AsRef<str>]) -> Vec<String> {
+    values.iter().map(|value| quote(value.as_ref())).collect()
+}
+
+/// A `new Listing { ... }` with one element per line, closed at `indent`.
+fn pkl_listing(values: &[impl AsRef<str>], indent: &str) -> String {
+    if values.is_empty() {
+        return "new Listing {}".to_string();
+    }
+    let elements: String = quote_list(values)
+        .iter()
+        .map(|value| format!("{}  {}\n", indent, value))
+        .collect();
+    format!("new Listing {{\n{}{}}}", elements, indent)
+}
+
+fn pkl_config(answers: &Answers) -> String {
+    let (allprojects, plugins) = answers.mirror.repositories();
+    let id = quote(&answers.application_id());
+    format!(
+        r#"// Starter config written by `flutter_gen_platforms init`; the README lists every option.
+project_name = {name}
+org = {org}
+description = "A new Flutter project."
+version = "1.0.0+1"
+
+create {{
+  // Passed to `flutter create --platforms`.
+  platforms = {platforms}
+}}
+
+android {{
+  app {{
+    build {{
+      namespace = {id}
+      application_id = {id}
+    }}
+  }}
+  build {{
+    allprojects {{
+      // Maven mirrors, tried before google() and mavenCentral(); `replace` drops those.
+      repositories = {allprojects}
+      replace = false
+    }}
+  }}
+  settings {{
+    plugin_management {{
+      repositories = {plugins}
+      replace = false
+    }}
+  }}
+}}
+
+// ios {{
+//   deployment_target = "13.0"
+// }}
+"#,
+        name = quote(&answers.name),
+        org = quote(&answers.org),
+        platforms = pkl_listing(&answers.platforms, "  "),
+        id = id,
+        allprojects = pkl_listing(&allprojects, "      "),
+        plugins = pkl_listing(&plugins, "      "),
+    )
+}
+
+fn toml_config(answers: &Answers) -> String {
+    let (allprojects, plugins) = answers.mirror.repositories();
+    let id = quote(&answers.application_id());
+    format!(
+        r#"# Starter config written by `flutter_gen_platforms init`; the README lists every option.
+project_name = {name}
+org = {org}
+description = "A new Flutter project."
+version = "1.0.0+1"
+
+[create]
+# Passed to `flutter create --platforms`.
+platforms = [{platforms}]
+
+[android.app.build]
+namespace = {id}
+application_id = {id}
+
+[android.build.allprojects]
+# Maven mirrors, tried before google() and mavenCentral(); `replace` drops those.
+repositories = [{allprojects}]
+replace = false
+
+[android.settings.plugin_management]
+repositories = [{plugins}]
+replace = false
+
+# [ios]
+# deployment_target = "13.0"
+"#,
+        name = quote(&answers.name),
+        org = quote(&answers.org),
+        platforms = quote_list(&answers.platforms).join(", "),
+        id = id,
+        allprojects = quote_list(&allprojects).join(", "),
+        plugins = quote_list(&plugins).join(", "),
+    )
+}
+
+fn yaml_config(answers: &Answers) -> String {
+    let (allprojects, plugins) = answers.mirror.repositories();
+    let id = quote(&answers.application_id());
+    format!(
+        r#"# Starter config written by `flutter_gen_platforms init`; the README lists every option.
+project_name: {name}
+org: {org}
+description: "A new Flutter project."
+version: "1.0.0+1"
+
+create:
+  # Passed to `flutter create --platforms`.
+  platforms: [{platforms}]
+
+android:
+  app:
+    build:
+      namespace: {id}
+      application_id: {id}
+  build:
+    allprojects:
+      # Maven mirrors, tried before google() and mavenCentral(); `replace` drops those.
+      repositories: [{allprojects}]
+      replace: false
+  settings:
+    plugin_management:
+      repositories: [{plugins}]
+      replace: false
+
+# ios:
+#   deployment_target: "13.0"
+"#,
+        name = quote(&answers.name),
+        org = quote(&answers.org),
+        platforms = quote_list(&answers.platforms).join(", "),
+        id = id,
+        allprojects = quote_list(&allprojects).join(", "),
+        plugins = quote_list(&plugins).join(", "),
+    )
+}
+
+fn write_new_file(path: &Path, content: &str, force: bool) -> Result<bool> {
+    if path.exists() && !force {
+        println!("Warning: {} already exists, left unchanged (use --force to overwrite)", path.display());
+        return Ok(false);
+    }
+    if let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+    {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Written: {}", path.display());
+    Ok(true)
+}
+
+/// Writes a commented starter config (format from the file extension) and, for Android,
+/// the platforms/android manifest templates the generator requires.
+pub fn init_project(project_dir: &Path, config_path: &Path, options: &InitOptions) -> Result<()> {
+    let render = match config_path.extension().and_then(|ext| ext.to_str()) {
+        Some("pkl") => pkl_config,
+        Some("toml") => toml_config,
+        Some("yaml" | "yml") => yaml_config,
+        _ => bail!("Unsupported config format for init: {} (use .pkl, .toml or .yaml)", config_path.display()),
+    };
+    if config_path.exists() && !options.force {
+        bail!("Config already exists: {} (use --force to overwrite)", config_path.display());
+    }
+    let answers = collect_answers(project_dir, options)?;
+    write_new_file(config_path, &render(&answers), options.force)?;
+
+    if answers.platforms.iter().any(|p| p == "android") {
+        let templates_dir = project_dir.join("platforms/android");
+        let label = answers.name.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;");
+        write_new_file(
+            &templates_dir.join("AndroidManifest.main.xml"),
+            &MANIFEST_MAIN.replace("{{label}}", &label),
+            options.force,
+        )?;
+        for file in ["AndroidManifest.debug.xml", "AndroidManifest.profile.xml"] {
+            write_new_file(&templates_dir.join(file), MANIFEST_DEBUG, options.force)?;
+        }
+    }
+    println!(
+        "✓ Project initialized; run `flutter_gen_platforms --config {}` to generate the platforms",
+        config_path.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,568 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{
+    Config, IosCodeSignStyle, IosEntitlementsConfig, IosIconsConfig, IosLaunchScreenConfig, IosPodsConfig,
+    IosPrivacyConfig, IosSigningConfig,
+};
+use crate::images::run_image_job;
+use crate::plist;
+use crate::template::OverlayRenderer;
+use crate::utils::remove_marked_block;
+use crate::xcode::{
+    add_runner_file, build_settings_blocks, check_version, edit_file, is_runner_target, ruby_quote,
+    set_build_settings, set_podfile_platform, setting_key, skip_xcode_project, PBXPROJ,
+};
+
+const PODS_BEGIN: &str = "# flutter_gen_platforms:pods:begin";
+const PODS_END: &str = "# flutter_gen_platforms:pods:end";
+
+/// Flutter's ios/Podfile template. `flutter create` leaves it out until a plugin needs
+/// CocoaPods; writing it up front is equivalent, the tool only fills in xcconfig includes.
+const PODFILE_TEMPLATE: &str = r##"# Uncomment this line to define a global platform for your project
+# platform :ios, '12.0'
+
+# CocoaPods analytics sends network stats synchronously affecting flutter build latency.
+ENV['COCOAPODS_DISABLE_STATS'] = 'true'
+
+project 'Runner', {
+  'Debug' => :debug,
+  'Profile' => :release,
+  'Release' => :release,
+}
+
+def flutter_root
+  generated_xcode_build_settings_path = File.expand_path(File.join('..', 'Flutter', 'Generated.xcconfig'), __FILE__)
+  unless File.exist?(generated_xcode_build_settings_path)
+    raise "#{generated_xcode_build_settings_path} must exist. If you're running pod install manually, make sure flutter pub get is executed first"
+  end
+
+  File.foreach(generated_xcode_build_settings_path) do |line|
+    matches = line.match(/FLUTTER_ROOT\=(.*)/)
+    return matches[1].strip if matches
+  end
+  raise "FLUTTER_ROOT not found in #{generated_xcode_build_settings_path}. Try deleting Generated.xcconfig, then run flutter pub get"
+end
+
+require File.expand_path(File.join('packages', 'flutter_tools', 'bin', 'podhelper'), flutter_root)
+
+flutter_ios_podfile_setup
+
+target 'Runner' do
+  use_frameworks!
+
+  flutter_install_all_ios_pods File.dirname(File.realpath(__FILE__))
+  target 'RunnerTests' do
+    inherit! :search_paths
+  end
+end
+
+post_install do |installer|
+  installer.pods_project.targets.each do |target|
+    flutter_additional_ios_build_settings(target)
+  end
+end
+"##;
+
+fn apply_signing(ios_dir: &Path, signing: &IosSigningConfig) -> Result<()> {
+    let team = &signing.development_team;
+    if team.len() != 10 || !team.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+        bail!("Invalid ios.signing.development_team '{}': expected a 10-character team id", team);
+    }
+    let uses_profiles = signing.provisioning_profile.is_some() || !signing.provisioning_profiles.is_empty();
+    if uses_profiles && signing.code_sign_style != IosCodeSignStyle::Manual {
+        bail!("ios.signing provisioning profiles require code_sign_style = \"manual\"");
+    }
+
+    edit_file(&ios_dir.join(PBXPROJ), |lines| {
+        let configurations: Vec<String> = build_settings_blocks(lines)
+            .into_iter()
+            .filter(|block| is_runner_target(lines, block))
+            .map(|block| block.name)
+            .collect();
+        if let Some(unknown) = signing
+            .provisioning_profiles
+            .keys()
+            .find(|name| !configurations.contains(name))
+        {
+            bail!(
+                "ios.signing.provisioning_profiles: unknown configuration '{}' (expected one of {})",
+                unknown,
+                configurations.join(", ")
+            );
+        }
+        set_build_settings(lines, is_runner_target, |configuration| {
+            let profile = signing
+                .provisioning_profiles
+                .get(configuration)
+                .or(signing.provisioning_profile.as_ref());
+            vec![
+                ("DEVELOPMENT_TEAM".to_string(), Some(team.clone())),
+                (
+                    "CODE_SIGN_STYLE".to_string(),
+                    Some(signing.code_sign_style.as_str().to_string()),
+                ),
+                ("PROVISIONING_PROFILE_SPECIFIER".to_string(), profile.cloned()),
+                (
+                    "CODE_SIGN_IDENTITY[sdk=iphoneos*]".to_string(),
+                    signing.code_sign_identity.clone(),
+                ),
+            ]
+        });
+        Ok(())
+    })?;
+    println!(
+        "✓ iOS signing configured (team {}, {} signing)",
+        team,
+        signing.code_sign_style.as_str().to_lowercase()
+    );
+    Ok(())
+}
+
+fn apply_info_plist(ios_dir: &Path, values: &BTreeMap<String, Value>) -> Result<()> {
+    let path = ios_dir.join("Runner/Info.plist");
+    edit_file(&path, |lines| {
+        plist::set_entries(lines, values)
+            .with_context(|| format!("Failed to update property list: {}", path.display()))
+    })?;
+    println!("✓ Info.plist keys set: {}", values.keys().cloned().collect::<Vec<_>>().join(", "));
+    Ok(())
+}
+
+const ENTITLEMENTS: &str = "Runner/Runner.entitlements";
+
+fn apply_entitlements(ios_dir: &Path, entitlements: &IosEntitlementsConfig) -> Result<()> {
+    let mut values = entitlements.extra.clone();
+    let lists = [
+        ("com.apple.developer.associated-domains", &entitlements.associated_domains),
+        ("com.apple.security.application-groups", &entitlements.app_groups),
+        ("keychain-access-groups", &entitlements.keychain_groups),
+    ];
+    for (key, list) in lists {
+        if !list.is_empty() {
+            values.insert(key.to_string(), Value::from(list.clone()));
+        }
+    }
+    if let Some(push) = entitlements.push {
+        values.insert("aps-environment".to_string(), Value::from(push.as_str()));
+    }
+
+    let path = ios_dir.join(ENTITLEMENTS);
+    fs::write(&path, plist::document(&values)?)
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    edit_file(&ios_dir.join(PBXPROJ), |lines| {
+        set_build_settings(lines, is_runner_target, |_| {
+            vec![("CODE_SIGN_ENTITLEMENTS".to_string(), Some(ENTITLEMENTS.to_string()))]
+        });
+        Ok(())
+    })?;
+    println!("✓ Entitlements written: {}", path.display());
+    Ok(())
+}
+
+const APP_ICON_SET: &str = "Runner/Assets.xcassets/AppIcon.appiconset";
+
+/// (idiom, point size, scale) of every slot in Flutter's AppIcon.appiconset.
+const APP_ICONS: [(&str, f64, u32); 19] = [
+    ("iphone", 20.0, 2),
+    ("iphone", 20.0, 3),
+    ("iphone", 29.0, 1),
+    ("iphone", 29.0, 2),
+    ("iphone", 29.0, 3),
+    ("iphone", 40.0, 2),
+    ("iphone", 40.0, 3),
+    ("iphone", 60.0, 2),
+    ("iphone", 60.0, 3),
+    ("ipad", 20.0, 1),
+    ("ipad", 20.0, 2),
+    ("ipad", 29.0, 1),
+    ("ipad", 29.0, 2),
+    ("ipad", 40.0, 1),
+    ("ipad", 40.0, 2),
+    ("ipad", 76.0, 1),
+    ("ipad", 76.0, 2),
+    ("ipad", 83.5, 2),
+    ("ios-marketing", 1024.0, 1),
+];
+
+/// Regenerates every AppIcon.appiconset image and its Contents.json.
+fn generate_ios_icons(project_dir: &Path, icons: &IosIconsConfig) -> Result<()> {
+    if !project_dir.join(&icons.source).exists() {
+        bail!("iOS icon source image not found: {}", project_dir.join(&icons.source).display());
+    }
+    let mut images = Vec::new();
+    let mut outputs = Vec::new();
+    for (idiom, size, scale) in APP_ICONS {
+        let filename = format!("Icon-App-{0}x{0}@{1}x.png", size, scale);
+        images.push(json!({
+            "size": format!("{0}x{0}", size),
+            "idiom": idiom,
+            "filename": filename,
+            "scale": format!("{}x", scale),
+        }));
+        let path = format!("ios/{}/{}", APP_ICON_SET, filename);
+        if outputs.iter().any(|output: &Value| output["path"] == path) {
+            continue;
+        }
+        let pixels = (size * scale as f64).round() as u32;
+        outputs.push(json!({
+            "path": path,
+            "width": pixels,
+            "height": pixels,
+            "background": icons.background.as_deref().unwrap_or("#ffffff"),
+            "opaque": true,
+        }));
+    }
+    run_image_job(project_dir, &json!({ "images": [{ "source": icons.source, "outputs": outputs }] }))?;
+
+    let contents = json!({
+        "images": images,
+        "info": { "version": 1, "author": "xcode" },
+    });
+    let path = project_dir.join("ios").join(APP_ICON_SET).join("Contents.json");
+    fs::write(&path, serde_json::to_string_pretty(&contents)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Generated iOS app icons from {}", icons.source);
+    Ok(())
+}
+
+const LAUNCH_SCREEN: &str = "Runner/Base.lproj/LaunchScreen.storyboard";
+const LAUNCH_IMAGE_SET: &str = "Runner/Assets.xcassets/LaunchImage.imageset";
+
+/// Interface Builder color attributes for "#RRGGBB" or "#RRGGBBAA".
+fn storyboard_color(key: &str, value: &str) -> Result<String> {
+    let hex = value.trim_start_matches('#');
+    let channels: Option<Vec<u8>> = if (hex.len() == 6 || hex.len() == 8) && hex.is_ascii() {
+        (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect()
+    } else {
+        None
+    };
+    let Some(mut channels) = channels else {
+        bail!("Invalid ios.launch_screen.background_color '{}': expected #RRGGBB or #RRGGBBAA", value);
+    };
+    channels.resize(4, 255);
+    let [red, green, blue, alpha] = [0, 1, 2, 3].map(|i| channels[i] as f64 / 255.0);
+    Ok(format!(
+        "<color key=\"{}\" red=\"{}\" green=\"{}\" blue=\"{}\" alpha=\"{}\" colorSpace=\"custom\" customColorSpace=\"sRGB\"/>",
+        key, red, green, blue, alpha
+    ))
+}
+
+fn apply_launch_screen(project_dir: &Path, ios_dir: &Path, launch: &IosLaunchScreenConfig) -> Result<()> {
+    if let Some(color) = &launch.background_color {
+        let replacement = storyboard_color("backgroundColor", color)?;
+        let path = ios_dir.join(LAUNCH_SCREEN);
+        edit_file(&path, |lines| {
+            let Some(line) = lines
+                .iter_mut()
+                .find(|line| line.trim_start().starts_with("<color key=\"backgroundColor\""))
+            else {
+                bail!("No backgroundColor found in: {}", path.display());
+            };
+            let indent = &line[..line.len() - line.trim_start().len()];
+            *line = format!("{}{}", indent, replacement);
+            Ok(())
+        })?;
+    }
+
+    if let Some(image) = &launch.image {
+        if !project_dir.join(image).exists() {
+            bail!("iOS launch image not found: {}", project_dir.join(image).display());
+        }
+        let mut images = Vec::new();
+        let mut outputs = Vec::new();
+        for scale in 1..=3u32 {
+            let filename = if scale == 1 {
+                "LaunchImage.png".to_string()
+            } else {
+                format!("LaunchImage@{}x.png", scale)
+            };
+            images.push(json!({ "idiom": "universal", "filename": filename, "scale": format!("{}x", scale) }));
+            outputs.push(json!({
+                "path": format!("ios/{}/{}", LAUNCH_IMAGE_SET, filename),
+                "width": launch.image_width * scale,
+            }));
+        }
+        run_image_job(project_dir, &json!({ "images": [{ "source": image, "outputs": outputs }] }))?;
+        let contents = json!({
+            "images": images,
+            "info": { "version": 1, "author": "xcode" },
+        });
+        let image_set = ios_dir.join(LAUNCH_IMAGE_SET);
+        fs::create_dir_all(&image_set)
+            .with_context(|| format!("Failed to create dir: {}", image_set.display()))?;
+        let path = image_set.join("Contents.json");
+        fs::write(&path, serde_json::to_string_pretty(&contents)? + "\n")
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    }
+    println!("✓ iOS launch screen configured");
+    Ok(())
+}
+
+const PRIVACY_MANIFEST: &str = "PrivacyInfo.xcprivacy";
+
+fn apply_privacy_manifest(ios_dir: &Path, privacy: &IosPrivacyConfig) -> Result<()> {
+    let accessed_apis: Vec<Value> = privacy
+        .accessed_apis
+        .iter()
+        .map(|api| {
+            json!({
+                "NSPrivacyAccessedAPIType": api.category.as_str(),
+                "NSPrivacyAccessedAPITypeReasons": api.reasons,
+            })
+        })
+        .collect();
+    let values = BTreeMap::from([
+        ("NSPrivacyTracking".to_string(), Value::from(privacy.tracking)),
+        ("NSPrivacyTrackingDomains".to_string(), Value::from(privacy.tracking_domains.clone())),
+        ("NSPrivacyAccessedAPITypes".to_string(), Value::from(accessed_apis)),
+        (
+            "NSPrivacyCollectedDataTypes".to_string(),
+            Value::from(privacy.collected_data_types.clone()),
+        ),
+    ]);
+    if privacy.tracking && privacy.tracking_domains.is_empty() {
+        println!("Warning: ios.privacy.tracking is set but tracking_domains is empty");
+    }
+
+    let path = ios_dir.join("Runner").join(PRIVACY_MANIFEST);
+    fs::write(&path, plist::document(&values)?)
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    edit_file(&ios_dir.join(PBXPROJ), |lines| {
+        add_runner_file(lines, PRIVACY_MANIFEST, "text.xml", Some("Resources"))?;
+        Ok(())
+    })?;
+    println!("✓ Privacy manifest written: {}", path.display());
+    Ok(())
+}
+
+/// Sets IPHONEOS_DEPLOYMENT_TARGET on the project and every configuration that overrides
+/// it, plus the engine's MinimumOSVersion where the template still has it.
+fn apply_deployment_target(ios_dir: &Path, version: &str) -> Result<()> {
+    check_version("ios.deployment_target", version)?;
+    edit_file(&ios_dir.join(PBXPROJ), |lines| {
+        set_build_settings(
+            lines,
+            |lines, block| {
+                lines[block.start..block.end].iter().any(|line| {
+                    line.trim() == "SDKROOT = iphoneos;"
+                        || setting_key(line) == Some("IPHONEOS_DEPLOYMENT_TARGET")
+                })
+            },
+            |_| vec![("IPHONEOS_DEPLOYMENT_TARGET".to_string(), Some(version.to_string()))],
+        );
+        Ok(())
+    })?;
+
+    let framework_info = ios_dir.join("Flutter/AppFrameworkInfo.plist");
+    if framework_info.exists() {
+        edit_file(&framework_info, |lines| {
+            if lines.iter().any(|line| line.trim() == "<key>MinimumOSVersion</key>") {
+                let values = BTreeMap::from([("MinimumOSVersion".to_string(), Value::from(version))]);
+                plist::set_entries(lines, &values)?;
+            }
+            Ok(())
+        })?;
+    }
+    println!("✓ iOS deployment target set to {}", version);
+    Ok(())
+}
+
+fn apply_swift_version(ios_dir: &Path, version: &str) -> Result<()> {
+    check_version("ios.swift_version", version)?;
+    edit_file(&ios_dir.join(PBXPROJ), |lines| {
+        set_build_settings(
+            lines,
+            |lines, block| {
+                lines[block.start..block.end]
+                    .iter()
+                    .any(|line| setting_key(line) == Some("SWIFT_VERSION"))
+            },
+            |_| vec![("SWIFT_VERSION".to_string(), Some(version.to_string()))],
+        );
+        Ok(())
+    })?;
+    println!("✓ Swift version set to {}", version);
+    Ok(())
+}
+
+fn apply_pods(ios_dir: &Path, pods: &IosPodsConfig, deployment_target: Option<&str>) -> Result<()> {
+    let path = ios_dir.join("Podfile");
+    if !path.exists() {
+        fs::write(&path, PODFILE_TEMPLATE)
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    }
+    edit_file(&path, |lines| {
+        // The top-level sources block is followed by a separator line of its own.
+        if let Some(start) = lines.iter().position(|line| line == PODS_BEGIN)
+            && let Some(stop) = lines[start..].iter().position(|line| line == PODS_END)
+            && lines.get(start + stop + 1).is_some_and(|line| line.is_empty())
+        {
+            lines.remove(start + stop + 1);
+        }
+        remove_marked_block(lines, PODS_BEGIN, PODS_END);
+
+        if let Some(version) = pods.platform.as_deref().or(deployment_target) {
+            set_podfile_platform(lines, "ios", version);
+        }
+
+        if !pods.sources.is_empty() {
+            // Sources go before the first statement, below the leading comments.
+            let pos = lines
+                .iter()
+                .position(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+                .unwrap_or(lines.len());
+            let mut block = vec![PODS_BEGIN.to_string()];
+            block.extend(pods.sources.iter().map(|source| format!("source {}", ruby_quote(source))));
+            block.push(PODS_END.to_string());
+            block.push(String::new());
+            lines.splice(pos..pos, block);
+        }
+
+        if !pods.build_settings.is_empty() || !pods.post_install.is_empty() {
+            let Some(start) = lines.iter().position(|line| line.trim() == "post_install do |installer|") else {
+                bail!("post_install block not found in {}", path.display());
+            };
+            // The block's own `end` is the first one back at its indentation.
+            let Some(end) = lines[start + 1..]
+                .iter()
+                .position(|line| line == "end")
+                .map(|offset| start + 1 + offset)
+            else {
+                bail!("Unterminated post_install block in {}", path.display());
+            };
+            let mut block = vec![format!("  {}", PODS_BEGIN)];
+            if !pods.build_settings.is_empty() {
+                block.push("  installer.pods_project.targets.each do |target|".to_string());
+                block.push("    target.build_configurations.each do |config|".to_string());
+                for (key, value) in &pods.build_settings {
+                    block.push(format!(
+                        "      config.build_settings[{}] = {}",
+                        ruby_quote(key),
+                        ruby_quote(value)
+                    ));
+                }
+                block.push("    end".to_string());
+                block.push("  end".to_string());
+            }
+            block.extend(pods.post_install.iter().map(|line| format!("  {}", line)));
+            block.push(format!("  {}", PODS_END));
+            lines.splice(end..end, block);
+        }
+        Ok(())
+    })?;
+    println!("✓ Podfile configured: {}", path.display());
+    Ok(())
+}
+
+/// Xcode file type and Runner build phase of an overlay file under Runner/, by extension.
+fn overlay_file_kind(relative: &Path) -> (&'static str, Option<&'static str>) {
+    let extension = relative.extension().and_then(|e| e.to_str()).unwrap_or("");
+    match extension {
+        "swift" => ("sourcecode.swift", Some("Sources")),
+        "m" => ("sourcecode.c.objc", Some("Sources")),
+        "mm" => ("sourcecode.cpp.objcpp", Some("Sources")),
+        "c" => ("sourcecode.c.c", Some("Sources")),
+        "cpp" => ("sourcecode.cpp.cpp", Some("Sources")),
+        "h" => ("sourcecode.c.h", None),
+        "entitlements" => ("text.plist.entitlements", None),
+        // The target's Info.plist is processed through INFOPLIST_FILE, never copied as a resource.
+        "plist" if relative == Path::new("Info.plist") => ("text.plist.xml", None),
+        "plist" => ("text.plist.xml", Some("Resources")),
+        "xcprivacy" => ("text.xml", Some("Resources")),
+        "json" => ("text.json", Some("Resources")),
+        "storyboard" => ("file.storyboard", Some("Resources")),
+        "xib" => ("file.xib", Some("Resources")),
+        "png" => ("image.png", Some("Resources")),
+        "jpg" | "jpeg" => ("image.jpeg", Some("Resources")),
+        _ => ("file", Some("Resources")),
+    }
+}
+
+/// Copies `<platforms_dir>/ios/` over ios/, expanding `{{name}}` variables in text files.
+///
+/// The project file is never overwritten; new files under Runner/ are registered in it
+/// instead (sources in the Sources phase, plists and assets in Resources). Files inside
+/// bundle-like directories (`Foo.xcassets/`, `en.lproj/`) are copied but left to the
+/// reference Xcode already has for their directory.
+fn apply_ios_overlay(templates_dir: &Path, ios_dir: &Path, cfg: &Config) -> Result<()> {
+    if !templates_dir.is_dir() {
+        return Ok(());
+    }
+    let copied = OverlayRenderer::new(cfg)?.copy_dir_except(templates_dir, ios_dir, skip_xcode_project)?;
+    let mut registered = Vec::new();
+    for relative in &copied {
+        if let Ok(in_runner) = relative.strip_prefix("Runner") {
+            let bundled = in_runner
+                .parent()
+                .is_some_and(|dir| dir.components().any(|c| Path::new(c.as_os_str()).extension().is_some()));
+            if !bundled {
+                registered.push(in_runner.to_string_lossy().replace('\\', "/"));
+            }
+        }
+    }
+    if !copied.is_empty() {
+        println!("✓ iOS overlay applied: {} file(s) from {}", copied.len(), templates_dir.display());
+    }
+    let mut added = Vec::new();
+    edit_file(&ios_dir.join(PBXPROJ), |lines| {
+        for path in &registered {
+            let (file_type, phase) = overlay_file_kind(Path::new(path));
+            if add_runner_file(lines, path, file_type, phase)? {
+                added.push(path.clone());
+            }
+        }
+        Ok(())
+    })?;
+    for path in added {
+        println!("✓ Added Runner/{} to {}", path, PBXPROJ);
+    }
+    Ok(())
+}
+
+pub fn process_ios_platform(project_dir: &Path, cfg: &Config) -> Result<()> {
+    let ios_dir = project_dir.join("ios");
+    if !ios_dir.join(PBXPROJ).exists() {
+        bail!("Generated iOS project not found at: {}", ios_dir.join(PBXPROJ).display());
+    }
+    let default_config = Default::default();
+    let config = cfg.ios.as_ref().unwrap_or(&default_config);
+
+    apply_ios_overlay(&cfg.platforms_root(project_dir).join("ios"), &ios_dir, cfg)?;
+
+    if let Some(signing) = &config.signing {
+        apply_signing(&ios_dir, signing)?;
+    }
+    if !config.info_plist.is_empty() {
+        apply_info_plist(&ios_dir, &config.info_plist)?;
+    }
+    if let Some(entitlements) = &config.entitlements {
+        apply_entitlements(&ios_dir, entitlements)?;
+    }
+    if let Some(privacy) = &config.privacy {
+        apply_privacy_manifest(&ios_dir, privacy)?;
+    }
+    if let Some(launch) = &config.launch_screen {
+        apply_launch_screen(project_dir, &ios_dir, launch)?;
+    }
+    if let Some(icons) = &config.icons {
+        generate_ios_icons(project_dir, icons)?;
+    }
+    if let Some(version) = &config.deployment_target {
+        apply_deployment_target(&ios_dir, version)?;
+    }
+    if let Some(version) = &config.swift_version {
+        apply_swift_version(&ios_dir, version)?;
+    }
+    if config.pods.is_some() || config.deployment_target.is_some() {
+        let default_pods = IosPodsConfig::default();
+        let pods = config.pods.as_ref().unwrap_or(&default_pods);
+        apply_pods(&ios_dir, pods, config.deployment_target.as_deref())?;
+    }
+
+    println!("✓ iOS platform directory configured");
+    Ok(())
+}
@@ -0,0 +1,291 @@
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{Config, LinuxConfig, LinuxDesktopConfig};
+use crate::images::run_image_job;
+use crate::template::OverlayRenderer;
+use crate::utils::remove_marked_block;
+
+const ICON_BEGIN: &str = "// flutter_gen_platforms:icon:begin";
+const ICON_END: &str = "// flutter_gen_platforms:icon:end";
+
+const INSTALL_BEGIN: &str = "# flutter_gen_platforms:install:begin";
+const INSTALL_END: &str = "# flutter_gen_platforms:install:end";
+
+/// Rendered window icon, relative to linux/; installed into the bundle's data directory.
+const ICON_PATH: &str = "runner/resources/app_icon.png";
+
+const ICON_SIZE: u32 = 256;
+
+/// Rewrites a runner source file line by line; writes only when something changed.
+fn edit_source_file(path: &Path, edit: impl FnOnce(&mut Vec<String>) -> Result<()>) -> Result<bool> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    edit(&mut lines)?;
+    let updated = lines.join("\n") + "\n";
+    if updated == content {
+        return Ok(false);
+    }
+    fs::write(path, updated).with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(true)
+}
+
+/// my_application.cc lives in runner/ since Flutter 3.22 and directly in linux/ before.
+fn application_source(linux_dir: &Path) -> PathBuf {
+    let current = linux_dir.join("runner/my_application.cc");
+    if current.exists() { current } else { linux_dir.join("my_application.cc") }
+}
+
+/// C string literal contents (GTK takes UTF-8, so only quotes and escapes need care).
+fn c_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Value of a `set(NAME "value")` line in linux/CMakeLists.txt.
+fn cmake_variable(lines: &[String], name: &str) -> Option<String> {
+    let prefix = format!("set({} \"", name);
+    lines.iter().find_map(|line| {
+        let value = line.trim().strip_prefix(&prefix)?.strip_suffix("\")")?;
+        Some(value.to_string())
+    })
+}
+
+fn apply_application_id(linux_dir: &Path, application_id: &str) -> Result<()> {
+    let valid = application_id.split('.').count() >= 2
+        && application_id.split('.').all(|part| {
+            !part.is_empty()
+                && !part.starts_with(|c: char| c.is_ascii_digit())
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+    if !valid {
+        bail!("Invalid linux.application_id '{}': expected a reverse-DNS id like com.example.app", application_id);
+    }
+    let path = linux_dir.join("CMakeLists.txt");
+    edit_source_file(&path, |lines| {
+        let Some(line) = lines.iter_mut().find(|line| line.trim().starts_with("set(APPLICATION_ID ")) else {
+            bail!("No APPLICATION_ID found in: {}", path.display());
+        };
+        *line = format!("set(APPLICATION_ID \"{}\")", application_id);
+        Ok(())
+    })?;
+    println!("✓ Linux application id set to {}", application_id);
+    Ok(())
+}
+
+/// The header bar title (GNOME) and the plain window title (other window managers).
+fn apply_window_title(source: &Path, title: &str) -> Result<()> {
+    edit_source_file(source, |lines| {
+        let mut found = false;
+        for call in ["gtk_header_bar_set_title(header_bar, \"", "gtk_window_set_title(window, \""] {
+            for line in lines.iter_mut().filter(|line| line.contains(call)) {
+                let indent = &line[..line.len() - line.trim_start().len()];
+                *line = format!("{}{}{}\");", indent, call, c_string(title));
+                found = true;
+            }
+        }
+        if !found {
+            bail!("No window title found in: {}", source.display());
+        }
+        Ok(())
+    })?;
+    println!("✓ Linux window title set to {:?}", title);
+    Ok(())
+}
+
+fn apply_window_size(source: &Path, width: u32, height: u32) -> Result<()> {
+    edit_source_file(source, |lines| {
+        let Some(line) = lines.iter_mut().find(|line| line.contains("gtk_window_set_default_size(window, ")) else {
+            bail!("No gtk_window_set_default_size call found in: {}", source.display());
+        };
+        let indent = &line[..line.len() - line.trim_start().len()];
+        *line = format!("{}gtk_window_set_default_size(window, {}, {});", indent, width, height);
+        Ok(())
+    })?;
+    println!("✓ Linux window size set to {}x{}", width, height);
+    Ok(())
+}
+
+/// Renders the icon and sets it on the window from the bundle's data directory, which
+/// sits next to the executable.
+fn apply_icon(project_dir: &Path, source: &Path, icon_source: Option<&str>) -> Result<()> {
+    if let Some(icon_source) = icon_source {
+        if !project_dir.join(icon_source).exists() {
+            bail!("Linux icon source image not found: {}", project_dir.join(icon_source).display());
+        }
+        let outputs = [json!({
+            "path": format!("linux/{}", ICON_PATH),
+            "width": ICON_SIZE,
+            "height": ICON_SIZE,
+        })];
+        run_image_job(project_dir, &json!({ "images": [{ "source": icon_source, "outputs": outputs }] }))?;
+        println!("✓ Generated Linux app icon from {}", icon_source);
+    }
+    edit_source_file(source, |lines| {
+        remove_marked_block(lines, ICON_BEGIN, ICON_END);
+        if icon_source.is_none() {
+            return Ok(());
+        }
+        let Some(pos) = lines.iter().position(|line| line.contains("gtk_window_set_default_size(window, ")) else {
+            bail!("No gtk_window_set_default_size call found in: {}", source.display());
+        };
+        let block = [
+            ICON_BEGIN,
+            "g_autofree gchar* exe_path = g_file_read_link(\"/proc/self/exe\", nullptr);",
+            "if (exe_path != nullptr) {",
+            "  g_autofree gchar* exe_dir = g_path_get_dirname(exe_path);",
+            "  g_autofree gchar* icon_path =",
+            "      g_build_filename(exe_dir, \"data\", \"app_icon.png\", nullptr);",
+            "  gtk_window_set_icon_from_file(window, icon_path, nullptr);",
+            "}",
+            ICON_END,
+        ];
+        let lines_to_insert = block.iter().map(|line| format!("  {}", line));
+        lines.splice(pos + 1..pos + 1, lines_to_insert);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// `;`-terminated desktop entry list, e.g. `Utility;Development;`.
+fn desktop_list(values: &[String]) -> String {
+    values.iter().map(|value| format!("{};", value.replace(';', "\\;"))).collect()
+}
+
+fn write_desktop_file(
+    linux_dir: &Path,
+    desktop: &LinuxDesktopConfig,
+    application_id: &str,
+    binary_name: &str,
+    name: &str,
+) -> Result<PathBuf> {
+    let mut lines = vec![
+        "[Desktop Entry]".to_string(),
+        "Type=Application".to_string(),
+        format!("Name={}", desktop.name.as_deref().unwrap_or(name)),
+    ];
+    if let Some(comment) = &desktop.comment {
+        lines.push(format!("Comment={}", comment));
+    }
+    let exec_arg = if desktop.mime_types.is_empty() { "" } else { " %U" };
+    lines.push(format!("Exec={}{}", binary_name, exec_arg));
+    // apply_install puts linux.icon into the hicolor theme under the application id.
+    lines.push(format!("Icon={}", application_id));
+    lines.push("Terminal=false".to_string());
+    if !desktop.categories.is_empty() {
+        lines.push(format!("Categories={}", desktop_list(&desktop.categories)));
+    }
+    if !desktop.keywords.is_empty() {
+        lines.push(format!("Keywords={}", desktop_list(&desktop.keywords)));
+    }
+    if !desktop.mime_types.is_empty() {
+        lines.push(format!("MimeType={}", desktop_list(&desktop.mime_types)));
+    }
+    // GTK sets the window's WM_CLASS to the application id; launchers match on it.
+    lines.push(format!("StartupWMClass={}", application_id));
+
+    let path = linux_dir.join(format!("{}.desktop", application_id));
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Desktop entry written: {}", path.display());
+    Ok(path)
+}
+
+/// Installs the icon and desktop entry into the bundle's data directory, as one marked
+/// block at the end of linux/CMakeLists.txt. With a desktop entry the icon is also
+/// installed as share/icons/hicolor/256x256/apps/<application_id>.png under the install
+/// prefix, where the entry's `Icon=<application_id>` finds it once packaged.
+fn apply_install(linux_dir: &Path, files: &[String], themed_icon: Option<&str>) -> Result<()> {
+    edit_source_file(&linux_dir.join("CMakeLists.txt"), |lines| {
+        remove_marked_block(lines, INSTALL_BEGIN, INSTALL_END);
+        if files.is_empty() {
+            return Ok(());
+        }
+        lines.push(INSTALL_BEGIN.to_string());
+        for file in files {
+            lines.push(format!(
+                "install(FILES \"${{CMAKE_CURRENT_SOURCE_DIR}}/{}\" DESTINATION \"${{INSTALL_BUNDLE_DATA_DIR}}\"",
+                file
+            ));
+            lines.push("  COMPONENT Runtime)".to_string());
+        }
+        if let Some(application_id) = themed_icon {
+            lines.push(format!(
+                "install(FILES \"${{CMAKE_CURRENT_SOURCE_DIR}}/{}\" DESTINATION \"${{CMAKE_INSTALL_PREFIX}}/share/icons/hicolor/{}x{}/apps\"",
+                ICON_PATH, ICON_SIZE, ICON_SIZE
+            ));
+            lines.push(format!("  RENAME \"{}.png\" COMPONENT Runtime)", application_id));
+        }
+        lines.push(INSTALL_END.to_string());
+        Ok(())
+    })?;
+    Ok(())
+}
+
+/// Copies `<platforms_dir>/linux/` over linux/, rendering templates, so custom runner
+/// code survives regeneration. Runs before the config-driven edits.
+fn apply_linux_overlay(templates_dir: &Path, linux_dir: &Path, cfg: &Config) -> Result<()> {
+    if !templates_dir.is_dir() {
+        return Ok(());
+    }
+    let copied = OverlayRenderer::new(cfg)?.copy_dir(templates_dir, linux_dir)?;
+    if !copied.is_empty() {
+        println!("✓ Linux overlay applied: {} file(s) from {}", copied.len(), templates_dir.display());
+    }
+    Ok(())
+}
+
+pub fn process_linux_platform(project_dir: &Path, cfg: &Config) -> Result<()> {
+    let linux_dir = project_dir.join("linux");
+    if !linux_dir.join("CMakeLists.txt").exists() {
+        bail!("Linux directory not found. Run 'flutter create --platforms=linux .' first.");
+    }
+    let default_config = LinuxConfig::default();
+    let config = cfg.linux.as_ref().unwrap_or(&default_config);
+    apply_linux_overlay(&cfg.platforms_root(project_dir).join("linux"), &linux_dir, cfg)?;
+    let source = application_source(&linux_dir);
+
+    if let Some(application_id) = &config.application_id {
+        apply_application_id(&linux_dir, application_id)?;
+    }
+    if let Some(title) = &config.title {
+        apply_window_title(&source, title)?;
+    }
+    match (config.window_width, config.window_height) {
+        (Some(width), Some(height)) => apply_window_size(&source, width, height)?,
+        (None, None) => {}
+        _ => bail!("linux.window_width and linux.window_height must be set together"),
+    }
+    apply_icon(project_dir, &source, config.icon.as_ref().map(|icon| icon.source.as_str()))?;
+
+    let mut installed = Vec::new();
+    let mut themed_icon = None;
+    if config.icon.is_some() {
+        installed.push(ICON_PATH.to_string());
+    }
+    if let Some(desktop) = &config.desktop {
+        let cmake_path = linux_dir.join("CMakeLists.txt");
+        let content = fs::read_to_string(&cmake_path)
+            .with_context(|| format!("Failed to read file: {}", cmake_path.display()))?;
+        let lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+        let (Some(application_id), Some(binary_name)) =
+            (cmake_variable(&lines, "APPLICATION_ID"), cmake_variable(&lines, "BINARY_NAME"))
+        else {
+            bail!("APPLICATION_ID or BINARY_NAME not found in: {}", cmake_path.display());
+        };
+        let name = config.title.as_deref().unwrap_or(&cfg.project_name);
+        let path = write_desktop_file(&linux_dir, desktop, &application_id, &binary_name, name)?;
+        if let Some(file_name) = path.file_name() {
+            installed.push(file_name.to_string_lossy().to_string());
+        }
+        if config.icon.is_some() {
+            themed_icon = Some(application_id);
+        }
+    }
+    apply_install(&linux_dir, &installed, themed_icon.as_deref())?;
+
+    println!("✓ Linux platform directory configured");
+    Ok(())
+}
@@ -0,0 +1,263 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::backup::CACHE_DIRS;
+use crate::config::{config_base_paths, profile_config_path, Config};
+use crate::releases::utc_timestamp;
+use crate::utils::{glob_match, sha256_file};
+
+pub const LOCK_FILE: &str = ".flutter_gen_lock.json";
+
+/// Files Flutter and CocoaPods rewrite on `pub get`, builds and `pod install`; their hashes
+/// say nothing about hand edits.
+const TOOL_MANAGED_FILES: [&str; 7] = [
+    "local.properties",
+    "Generated.xcconfig",
+    "flutter_export_environment.sh",
+    "Podfile.lock",
+    "project.pbxproj",
+    "contents.xcworkspacedata",
+    "generated_plugins.cmake",
+];
+
+/// IDE and tool state directories inside platform directories, next to the build caches.
+const UNTRACKED_DIRS: [&str; 3] = [".idea", ".kotlin", "xcuserdata"];
+
+fn is_tracked(name: &str) -> bool {
+    !TOOL_MANAGED_FILES.contains(&name)
+        && !name.starts_with("GeneratedPluginRegistrant.")
+        && !name.starts_with("generated_plugin_registrant.")
+        && name != ".DS_Store"
+        && !name.ends_with(".iml")
+}
+
+/// SHA-256 of the tracked files under the platform directories, keyed by their
+/// `/`-separated path relative to the project.
+fn platform_file_hashes(project_dir: &Path, platforms: &[&str]) -> Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    for platform in platforms {
+        let dir = project_dir.join(platform);
+        if !dir.is_dir() {
+            continue;
+        }
+        let walker = walkdir::WalkDir::new(&dir).into_iter().filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0 || !(CACHE_DIRS.contains(&name.as_ref()) || UNTRACKED_DIRS.contains(&name.as_ref()))
+        });
+        for entry in walker {
+            let entry = entry.with_context(|| format!("Failed to walk directory: {}", dir.display()))?;
+            if !entry.file_type().is_file() || !is_tracked(&entry.file_name().to_string_lossy()) {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(project_dir).unwrap_or(entry.path());
+            files.insert(relative.to_string_lossy().replace('\\', "/"), sha256_file(entry.path())?);
+        }
+    }
+    Ok(files)
+}
+
+fn read_lockfile(project_dir: &Path) -> Result<Option<Value>> {
+    let path = project_dir.join(LOCK_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let lock = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse lockfile: {}", path.display()))?;
+    Ok(Some(lock))
+}
+
+/// Feeds one length-prefixed field, so adjacent fields can't run into each other.
+fn feed(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_le_bytes());
+    hasher.update(bytes);
+}
+
+fn feed_file(hasher: &mut Sha256, path: &Path) -> Result<()> {
+    let content = fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))?;
+    feed(hasher, &content);
+    Ok(())
+}
+
+/// Project-relative files the config points generation at: icon and image sources, fonts,
+/// the TV banner and the launch screen image.
+fn config_source_files(cfg: &Config) -> Vec<&str> {
+    let mut files = Vec::new();
+    files.extend(cfg.android.tv.as_ref().and_then(|tv| tv.banner.as_deref()));
+    if let Some(ios) = &cfg.ios {
+        files.extend(ios.icons.as_ref().map(|icons| icons.source.as_str()));
+        files.extend(ios.launch_screen.as_ref().and_then(|launch| launch.image.as_deref()));
+    }
+    files.extend(cfg.macos.as_ref().and_then(|macos| macos.icon.as_ref()).map(|icon| icon.source.as_str()));
+    files.extend(cfg.linux.as_ref().and_then(|linux| linux.icon.as_ref()).map(|icon| icon.source.as_str()));
+    files.extend(cfg.windows.as_ref().and_then(|windows| windows.icon.as_ref()).map(|icon| icon.source.as_str()));
+    files.extend(cfg.web.as_ref().and_then(|web| web.icons.as_ref()).map(|icons| icons.source.as_str()));
+    files.extend(cfg.assets.iter().flat_map(|assets| &assets.images).map(|image| image.source.as_str()));
+    files.extend(
+        cfg.fonts
+            .iter()
+            .flat_map(|fonts| &fonts.families)
+            .flat_map(|family| &family.files)
+            .map(|file| file.source.as_str()),
+    );
+    files
+}
+
+/// SHA-256 of everything generation reads from the project: the config file (with its
+/// `extends` bases, the selected profile and its overlay file), the source files it points
+/// to, the web loading template and the platform template directory, plus the tool version
+/// so upgrades regenerate.
+pub fn inputs_hash(project_dir: &Path, config_path: &Path, profile: Option<&str>, cfg: &Config) -> Result<String> {
+    let mut hasher = Sha256::new();
+    feed(&mut hasher, env!("CARGO_PKG_VERSION").as_bytes());
+    feed_file(&mut hasher, config_path)?;
+    for base in config_base_paths(config_path)? {
+        feed_file(&mut hasher, &base)?;
+    }
+    if let Some(profile) = profile {
+        feed(&mut hasher, profile.as_bytes());
+        let overlay = profile_config_path(config_path, profile);
+        if overlay.exists() {
+            feed_file(&mut hasher, &overlay)?;
+        }
+    }
+
+    // A missing source is hashed as absent; generation reports it.
+    for file in config_source_files(cfg) {
+        feed(&mut hasher, file.as_bytes());
+        let path = project_dir.join(file);
+        if path.is_file() {
+            feed_file(&mut hasher, &path)?;
+        }
+    }
+
+    let templates_dir = cfg.platforms_root(project_dir);
+    // Usually inside the template directory below, but the path may lead out of it.
+    if let Some(template) = cfg
+        .web
+        .as_ref()
+        .and_then(|web| web.loading.as_ref())
+        .and_then(|loading| loading.template.as_deref())
+    {
+        let path = templates_dir.join("web").join(template);
+        if path.is_file() {
+            feed_file(&mut hasher, &path)?;
+        }
+    }
+    if templates_dir.is_dir() {
+        for entry in walkdir::WalkDir::new(&templates_dir).sort_by_file_name() {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                let relative = entry.path().strip_prefix(&templates_dir).unwrap_or(entry.path());
+                feed(&mut hasher, relative.to_string_lossy().replace('\\', "/").as_bytes());
+                feed_file(&mut hasher, entry.path())?;
+            }
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Records a successful generation so later runs can tell whether it is stale, with the
+/// hash of every file in the generated platform directories so hand edits can be spotted.
+/// Only platform directories are tracked: pubspec.yaml, assets, fonts and the marked
+/// .gitignore/.gitattributes blocks are shared with the user's own edits.
+///
+/// Files of platforms not generated this time are carried over; so are the platforms
+/// themselves while the inputs are unchanged, as they are still up to date.
+pub fn write_lockfile(project_dir: &Path, inputs_hash: &str, platforms: &[&str]) -> Result<()> {
+    let path = project_dir.join(LOCK_FILE);
+    let mut files = platform_file_hashes(project_dir, platforms)?;
+    let mut locked: Vec<String> = platforms.iter().map(|name| name.to_string()).collect();
+    let previous = read_lockfile(project_dir)?;
+    if let Some(previous) = &previous
+        && previous["inputs_hash"].as_str() == Some(inputs_hash)
+        && let Some(previous) = previous["platforms"].as_array()
+    {
+        for platform in previous.iter().filter_map(Value::as_str) {
+            if !locked.iter().any(|name| name == platform) && project_dir.join(platform).is_dir() {
+                locked.push(platform.to_string());
+            }
+        }
+    }
+    if let Some(previous) = &previous
+        && let Some(previous) = previous["files"].as_object()
+    {
+        for (file, hash) in previous {
+            let platform = file.split('/').next().unwrap_or_default();
+            if !platforms.contains(&platform)
+                && project_dir.join(file).is_file()
+                && let Some(hash) = hash.as_str()
+            {
+                files.insert(file.clone(), hash.to_string());
+            }
+        }
+    }
+    let lock = json!({
+        "tool_version": env!("CARGO_PKG_VERSION"),
+        "inputs_hash": inputs_hash,
+        "platforms": locked,
+        "generated_at": utc_timestamp(),
+        "files": files,
+    });
+    fs::write(&path, serde_json::to_string_pretty(&lock)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Lockfile written: {}", path.display());
+    Ok(())
+}
+
+/// Files under `platforms` changed or added since the last generation, as
+/// `("modified" | "added", path)`. Files matching `preserve` survive regeneration and are
+/// left out; so is any platform the lockfile has no hashes for.
+pub fn hand_edits(project_dir: &Path, platforms: &[&str], preserve: &[String]) -> Result<Vec<(&'static str, String)>> {
+    let Some(lock) = read_lockfile(project_dir)? else {
+        return Ok(Vec::new());
+    };
+    let Some(locked) = lock["files"].as_object() else {
+        return Ok(Vec::new());
+    };
+    let recorded: Vec<&str> = platforms
+        .iter()
+        .copied()
+        .filter(|platform| locked.keys().any(|file| file.starts_with(&format!("{}/", platform))))
+        .collect();
+
+    let mut edits = Vec::new();
+    for (file, hash) in platform_file_hashes(project_dir, &recorded)? {
+        if preserve.iter().any(|pattern| glob_match(pattern, &file)) {
+            continue;
+        }
+        match locked.get(&file).and_then(Value::as_str) {
+            Some(locked_hash) if locked_hash == hash => {}
+            Some(_) => edits.push(("modified", file)),
+            None => edits.push(("added", file)),
+        }
+    }
+    Ok(edits)
+}
+
+/// Why the generated platforms need regenerating, or `None` when they are up to date.
+pub fn stale_reason(project_dir: &Path, inputs_hash: &str, platforms: &[&str]) -> Result<Option<String>> {
+    let Some(lock) = read_lockfile(project_dir)? else {
+        return Ok(Some(format!("{} not found", LOCK_FILE)));
+    };
+    if lock["inputs_hash"].as_str() != Some(inputs_hash) {
+        return Ok(Some("config, templates or tool version changed".to_string()));
+    }
+    for platform in platforms {
+        let locked = lock["platforms"]
+            .as_array()
+            .is_some_and(|locked| locked.iter().any(|name| name.as_str() == Some(platform)));
+        if !locked {
+            return Ok(Some(format!("{} was not generated", platform)));
+        }
+        if !project_dir.join(platform).is_dir() {
+            return Ok(Some(format!("{}/ is missing", platform)));
+        }
+    }
+    Ok(None)
+}
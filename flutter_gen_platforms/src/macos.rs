@@ -0,0 +1,182 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::config::{Config, MacosConfig, MacosEntitlementsConfig};
+use crate::images::run_image_job;
+use crate::plist;
+use crate::template::OverlayRenderer;
+use crate::xcode::{
+    check_version, edit_file, set_build_settings, set_podfile_platform, setting_key, skip_xcode_project, PBXPROJ,
+};
+
+const APP_INFO: &str = "Runner/Configs/AppInfo.xcconfig";
+
+/// Pixel sizes of the app_icon_<size>.png files referenced by Flutter's macOS AppIcon.appiconset.
+const ICON_SIZES: [u32; 7] = [16, 32, 64, 128, 256, 512, 1024];
+
+/// Sets the Runner's identity in Configs/AppInfo.xcconfig, which every configuration includes.
+fn apply_app_info(macos_dir: &Path, config: &MacosConfig) -> Result<()> {
+    let values = [
+        ("PRODUCT_NAME", &config.product_name),
+        ("PRODUCT_BUNDLE_IDENTIFIER", &config.bundle_id),
+        ("PRODUCT_COPYRIGHT", &config.copyright),
+    ];
+    if values.iter().all(|(_, value)| value.is_none()) {
+        return Ok(());
+    }
+    edit_file(&macos_dir.join(APP_INFO), |lines| {
+        for (key, value) in values {
+            let Some(value) = value else {
+                continue;
+            };
+            let line = format!("{} = {}", key, value);
+            match lines
+                .iter()
+                .position(|l| l.split_once('=').is_some_and(|(name, _)| name.trim() == key))
+            {
+                Some(pos) => lines[pos] = line,
+                None => lines.push(line),
+            }
+        }
+        Ok(())
+    })?;
+    println!("✓ macOS AppInfo.xcconfig updated");
+    Ok(())
+}
+
+fn apply_category(macos_dir: &Path, category: &str) -> Result<()> {
+    if !category.starts_with("public.app-category.") {
+        bail!("Invalid macos.category '{}': expected e.g. \"public.app-category.productivity\"", category);
+    }
+    let values = BTreeMap::from([("LSApplicationCategoryType".to_string(), Value::from(category))]);
+    edit_file(&macos_dir.join("Runner/Info.plist"), |lines| plist::set_entries(lines, &values))?;
+    println!("✓ macOS app category set to {}", category);
+    Ok(())
+}
+
+/// Entitlement keys for one of the two files; debug and profile builds also need JIT and
+/// an open port for the Dart VM service.
+fn entitlement_values(entitlements: &MacosEntitlementsConfig, debug: bool) -> BTreeMap<String, Value> {
+    let mut values = entitlements.extra.clone();
+    let flags = [
+        ("com.apple.security.app-sandbox", entitlements.sandbox),
+        ("com.apple.security.cs.allow-jit", debug),
+        ("com.apple.security.network.client", entitlements.network_client),
+        ("com.apple.security.network.server", entitlements.network_server || debug),
+        ("com.apple.security.device.camera", entitlements.camera),
+        ("com.apple.security.device.audio-input", entitlements.microphone),
+    ];
+    for (key, enabled) in flags {
+        if enabled {
+            values.insert(key.to_string(), Value::Bool(true));
+        }
+    }
+    if let Some(access) = entitlements.user_selected_files {
+        values.insert(format!("com.apple.security.files.user-selected.{}", access.as_str()), Value::Bool(true));
+    }
+    if let Some(access) = entitlements.downloads {
+        values.insert(format!("com.apple.security.files.downloads.{}", access.as_str()), Value::Bool(true));
+    }
+    values
+}
+
+fn write_entitlements(macos_dir: &Path, entitlements: &MacosEntitlementsConfig) -> Result<()> {
+    for (file, debug) in [("DebugProfile.entitlements", true), ("Release.entitlements", false)] {
+        let path = macos_dir.join("Runner").join(file);
+        let content = plist::document(&entitlement_values(entitlements, debug))?;
+        fs::write(&path, content).with_context(|| format!("Failed to write file: {}", path.display()))?;
+    }
+    println!("✓ macOS entitlements written");
+    Ok(())
+}
+
+/// Sets MACOSX_DEPLOYMENT_TARGET on the project and every configuration overriding it,
+/// and the Podfile platform when plugins brought one in.
+fn apply_deployment_target(macos_dir: &Path, version: &str) -> Result<()> {
+    check_version("macos.deployment_target", version)?;
+    edit_file(&macos_dir.join(PBXPROJ), |lines| {
+        set_build_settings(
+            lines,
+            |lines, block| {
+                lines[block.start..block.end].iter().any(|line| {
+                    line.trim() == "SDKROOT = macosx;" || setting_key(line) == Some("MACOSX_DEPLOYMENT_TARGET")
+                })
+            },
+            |_| vec![("MACOSX_DEPLOYMENT_TARGET".to_string(), Some(version.to_string()))],
+        );
+        Ok(())
+    })?;
+    let podfile = macos_dir.join("Podfile");
+    if podfile.exists() {
+        edit_file(&podfile, |lines| {
+            set_podfile_platform(lines, "osx", version);
+            Ok(())
+        })?;
+    }
+    println!("✓ macOS deployment target set to {}", version);
+    Ok(())
+}
+
+/// Replaces the app_icon_<size>.png images; the template's Contents.json already lists them.
+fn generate_macos_icons(project_dir: &Path, source: &str) -> Result<()> {
+    if !project_dir.join(source).exists() {
+        bail!("macOS icon source image not found: {}", project_dir.join(source).display());
+    }
+    let outputs: Vec<Value> = ICON_SIZES
+        .iter()
+        .map(|size| {
+            json!({
+                "path": format!("macos/Runner/Assets.xcassets/AppIcon.appiconset/app_icon_{}.png", size),
+                "width": size,
+                "height": size,
+            })
+        })
+        .collect();
+    run_image_job(project_dir, &json!({ "images": [{ "source": source, "outputs": outputs }] }))?;
+    println!("✓ Generated macOS app icons from {}", source);
+    Ok(())
+}
+
+/// Copies `<platforms_dir>/macos/` over macos/, rendering templates; the Xcode project
+/// is skipped. Unlike on iOS, new files are not added to the project, so overlays suit
+/// replacements of the generated sources, plists and entitlements.
+fn apply_macos_overlay(templates_dir: &Path, macos_dir: &Path, cfg: &Config) -> Result<()> {
+    if !templates_dir.is_dir() {
+        return Ok(());
+    }
+    let copied = OverlayRenderer::new(cfg)?.copy_dir_except(templates_dir, macos_dir, skip_xcode_project)?;
+    if !copied.is_empty() {
+        println!("✓ macOS overlay applied: {} file(s) from {}", copied.len(), templates_dir.display());
+    }
+    Ok(())
+}
+
+pub fn process_macos_platform(project_dir: &Path, cfg: &Config) -> Result<()> {
+    let macos_dir = project_dir.join("macos");
+    if !macos_dir.join(PBXPROJ).exists() {
+        bail!("Generated macOS project not found at: {}", macos_dir.join(PBXPROJ).display());
+    }
+    let default_config = MacosConfig::default();
+    let config = cfg.macos.as_ref().unwrap_or(&default_config);
+    apply_macos_overlay(&cfg.platforms_root(project_dir).join("macos"), &macos_dir, cfg)?;
+
+    apply_app_info(&macos_dir, config)?;
+    if let Some(category) = &config.category {
+        apply_category(&macos_dir, category)?;
+    }
+    if let Some(entitlements) = &config.entitlements {
+        write_entitlements(&macos_dir, entitlements)?;
+    }
+    if let Some(version) = &config.deployment_target {
+        apply_deployment_target(&macos_dir, version)?;
+    }
+    if let Some(icon) = &config.icon {
+        generate_macos_icons(project_dir, &icon.source)?;
+    }
+
+    println!("✓ macOS platform directory configured");
+    Ok(())
+}
@@ -1,129 +1,657 @@
+#[cfg_attr(not(feature = "android"), allow(dead_code))]
 mod android;
+mod assets;
+mod backup;
+mod build_env;
+mod builder;
 mod config;
+mod fonts;
+mod hooks;
+mod hosting;
+mod images;
+mod init;
+#[cfg_attr(not(feature = "ios"), allow(dead_code))]
+mod ios;
+#[cfg_attr(not(feature = "linux"), allow(dead_code))]
+mod linux;
+mod lockfile;
+#[cfg_attr(not(feature = "macos"), allow(dead_code))]
+mod macos;
+mod manifest;
+mod migrate;
+#[cfg_attr(not(any(feature = "ios", feature = "macos")), allow(dead_code))]
+mod plist;
+mod preflight;
+mod preview;
+mod processor;
+mod publish;
+mod pubspec;
+mod releases;
+mod run;
+mod schema;
+#[cfg_attr(
+    not(any(
+        feature = "android",
+        feature = "ios",
+        feature = "linux",
+        feature = "macos",
+        feature = "web",
+        feature = "windows"
+    )),
+    allow(dead_code)
+)]
+mod template;
 mod utils;
+mod validate;
+mod verify;
+#[cfg_attr(not(feature = "web"), allow(dead_code))]
 mod web;
+#[cfg_attr(not(feature = "windows"), allow(dead_code))]
 mod windows;
+#[cfg_attr(not(any(feature = "ios", feature = "macos")), allow(dead_code))]
+mod xcode;
 
-use anyhow::{bail, Result};
-use clap::Parser;
+use anyhow::{Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
-use config::{expand_config, load_config};
+use hooks::HookStage;
+use config::{expand_config, load_config, load_profile_config, load_workspace_config, Config};
 use utils::{
-    remove_dir_all_with_retry, resolve_cmd, run_flutter_create,
+    apply_line_endings, fill_name_pattern, remove_platform_dir, resolve_cmd, run_flutter_create,
+    update_gitignore,
 };
 
 #[derive(Parser, Debug)]
 #[command(name = "flutter-gen-platform", about = "Generate Flutter platform directories")]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     #[arg(long, value_name = "FILE", default_value = "app.pkl")]
     config: PathBuf,
 
     #[arg(long, value_name = "CMD", default_value = "flutter")]
     flutter_cmd: String,
 
-    #[arg(long, value_name = "DIR", default_value = ".")]
+    /// Flutter project directory (default: `.`; `run` defaults to the directory of --config)
+    #[arg(long, value_name = "DIR", global = true)]
     project_dir: Option<PathBuf>,
 
-    #[arg(long, help = "Preview changes without writing files")]
+    #[arg(long, help = "Preview changes without writing files", global = true)]
     dry_run: bool,
+
+    #[arg(long, value_enum, default_value = "full")]
+    mode: GenerateMode,
+
+    /// Regenerate even when platform files were changed by hand since the last generation
+    #[arg(long)]
+    force: bool,
+
+    /// Merge the profiles.<NAME> section and app.<NAME>.<ext> over the config; also the
+    /// build environment's profile
+    #[arg(long, value_name = "NAME", global = true)]
+    profile: Option<String>,
+
+    /// Generate only these platforms, e.g. android,web (overrides create.platforms)
+    #[arg(long, value_name = "LIST", value_delimiter = ',')]
+    platforms: Option<Vec<String>>,
+
+    /// Generate every app listed in the workspace file, in order
+    #[arg(long)]
+    all: bool,
+
+    /// Workspace file for --all: app directories, their config name and the shared templates
+    #[arg(long, value_name = "FILE", default_value = "flutter_gen_workspace.pkl")]
+    workspace: PathBuf,
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-    let config_path = args.config;
-    let flutter_cmd = args.flutter_cmd;
-    let project_dir = args.project_dir;
-    let dry_run = args.dry_run;
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum GenerateMode {
+    /// Delete the platform directories and recreate them with flutter create
+    Full,
+    /// Keep the existing platform directories and re-apply only the config-driven edits
+    Patch,
+}
 
-    if dry_run {
-        println!("[DRY RUN] Preview mode - no files will be modified\n");
-    }
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Move the generated platforms to a new application id / bundle id
+    MigrateId {
+        #[arg(long, value_name = "ID")]
+        to: String,
+    },
+    /// Regenerate only the build environment Dart file (for use from build scripts)
+    BuildEnv,
+    /// Build the Android variant matrix from [build] config through cmd_run
+    Build {
+        /// Builds run at the same time (overrides build.jobs)
+        #[arg(long, value_name = "N")]
+        jobs: Option<usize>,
+    },
+    /// Upload build outputs with checksums and a release manifest
+    Publish {
+        #[command(subcommand)]
+        target: PublishTarget,
+    },
+    /// Query the release index written by `publish artifacts`
+    Releases {
+        #[command(subcommand)]
+        action: ReleasesAction,
+    },
+    /// Regenerate stale platforms, then `flutter run` through cmd_run (logging, daemon control)
+    Run {
+        #[arg(long, short = 'd', value_name = "ID")]
+        device: Option<String>,
+        #[arg(long, value_name = "NAME")]
+        flavor: Option<String>,
+        /// Regenerate even when the lockfile says the platforms are up to date
+        #[arg(long)]
+        regenerate: bool,
+        /// Extra arguments for flutter run (after `--`)
+        #[arg(last = true, value_name = "ARGS")]
+        flutter_args: Vec<String>,
+    },
+    /// Load the config and report every problem found in it, without generating anything
+    Validate,
+    /// Print the config structure as JSON Schema or a pkl module, for editor validation and completion
+    Schema {
+        #[arg(long, value_enum, default_value = "json")]
+        format: schema::SchemaFormat,
+        /// Write to this file instead of stdout
+        #[arg(long, short = 'o', value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Write a commented starter config (at --config) and the platforms/android manifest templates
+    Init {
+        #[arg(long, value_name = "NAME")]
+        name: Option<String>,
+        /// Reverse domain, e.g. com.example
+        #[arg(long, value_name = "ORG")]
+        org: Option<String>,
+        #[arg(long, value_name = "LIST", value_delimiter = ',')]
+        platforms: Option<Vec<String>>,
+        #[arg(long, value_enum)]
+        mirror: Option<init::InitMirror>,
+        /// Use defaults for everything not given as a flag instead of prompting
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// Overwrite an existing config and manifest templates
+        #[arg(long)]
+        force: bool,
+    },
+    /// Put platform directories back from a .flutter_gen_backup/ snapshot (the newest by default)
+    Restore {
+        /// Backup name, as shown by --list
+        #[arg(long, value_name = "BACKUP")]
+        from: Option<String>,
+        /// Show the backups instead of restoring
+        #[arg(long)]
+        list: bool,
+    },
+}
 
-    let mut cfg = load_config(&config_path)?;
+#[derive(Subcommand, Debug)]
+enum ReleasesAction {
+    /// Show the newest releases from publish.releases_file
+    List {
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>,
+    },
+}
 
-    let project_dir = project_dir.unwrap_or_else(|| {
-        config_path
-            .parent()
-            .unwrap_or_else(|| Path::new("."))
-            .to_path_buf()
-    });
+#[derive(Subcommand, Debug)]
+enum PublishTarget {
+    /// Upload the files in build.dist_dir to <destination>/<version>/
+    Artifacts {
+        /// s3://bucket/path, http(s)://... (HTTP PUT) or a local directory (overrides publish.to)
+        #[arg(long, value_name = "DEST")]
+        to: Option<String>,
+    },
+}
 
-    // Use version from app.pkl's pubspec config
-    if cfg.version.is_none() {
-        if let Some(pubspec_config) = &cfg.pubspec {
-            cfg.version = pubspec_config.version.clone();
-            if let Some(version) = &cfg.version {
-                println!("Using version from config: {}", version);
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.all && args.command.is_some() {
+        anyhow::bail!("--all only applies to generation, not to subcommands");
+    }
+    match &args.command {
+        Some(Commands::MigrateId { to }) => {
+            let project_dir = project_dir(&args);
+            migrate::migrate_id(&project_dir, to, args.dry_run)
+        }
+        Some(Commands::BuildEnv) => {
+            let project_dir = project_dir(&args);
+            let mut cfg = prepare_config(&args.config, &project_dir, args.profile.as_deref(), args.dry_run)?;
+            let build_env_config = cfg.build_env.get_or_insert_with(Default::default);
+            if let Some(profile) = &args.profile {
+                build_env_config.profile = Some(profile.clone());
+            }
+            build_env::write_build_env(&project_dir, &cfg)
+        }
+        Some(Commands::Build { jobs }) => {
+            let project_dir = project_dir(&args);
+            let mut cfg = prepare_config(&args.config, &project_dir, args.profile.as_deref(), args.dry_run)?;
+            if let Some(jobs) = jobs {
+                cfg.build.jobs = *jobs;
+            }
+            let flutter_cmd = resolve_cmd(&args.flutter_cmd)?;
+            builder::build_matrix(&project_dir, &flutter_cmd, &cfg.build, args.dry_run)
+        }
+        Some(Commands::Publish {
+            target: PublishTarget::Artifacts { to },
+        }) => {
+            let project_dir = project_dir(&args);
+            let cfg = prepare_config(&args.config, &project_dir, args.profile.as_deref(), args.dry_run)?;
+            publish::publish_artifacts(&project_dir, &cfg, to.as_deref(), args.dry_run)
+        }
+        Some(Commands::Releases {
+            action: ReleasesAction::List { limit },
+        }) => {
+            let project_dir = project_dir(&args);
+            let cfg = prepare_config(&args.config, &project_dir, args.profile.as_deref(), args.dry_run)?;
+            releases::list_releases(&project_dir, &cfg, *limit)
+        }
+        Some(Commands::Run {
+            device,
+            flavor,
+            regenerate,
+            flutter_args,
+        }) => {
+            let project_dir = run_project_dir(&args);
+            let mut cfg = prepare_config(&args.config, &project_dir, args.profile.as_deref(), args.dry_run)?;
+            select_platforms(&mut cfg, args.platforms.as_deref())?;
+            let inputs_hash = lockfile::inputs_hash(&project_dir, &args.config, args.profile.as_deref(), &cfg)?;
+            match lockfile::stale_reason(&project_dir, &inputs_hash, &generated_platforms(&cfg))? {
+                Some(reason) => {
+                    println!("Platforms are out of date ({}), regenerating...\n", reason);
+                    generate_project(&args, &args.config, &project_dir, None)?;
+                }
+                None if *regenerate => generate_project(&args, &args.config, &project_dir, None)?,
+                None => println!("✓ Platforms up to date ({})", lockfile::LOCK_FILE),
+            }
+            let flutter_cmd = resolve_cmd(&args.flutter_cmd)?;
+            run::run_app(
+                &project_dir,
+                &flutter_cmd,
+                &cfg.build,
+                device.as_deref(),
+                flavor.as_deref(),
+                flutter_args,
+                args.dry_run,
+            )
+        }
+        Some(Commands::Init {
+            name,
+            org,
+            platforms,
+            mirror,
+            yes,
+            force,
+        }) => {
+            let project_dir = project_dir(&args);
+            let options = init::InitOptions {
+                name: name.clone(),
+                org: org.clone(),
+                platforms: platforms.clone(),
+                mirror: *mirror,
+                yes: *yes,
+                force: *force,
+            };
+            init::init_project(&project_dir, &args.config, &options)
+        }
+        Some(Commands::Restore { from, list }) => {
+            let project_dir = project_dir(&args);
+            if *list {
+                backup::list_backups(&project_dir)
+            } else {
+                backup::restore_backup(&project_dir, from.as_deref(), args.dry_run)
             }
         }
+        Some(Commands::Schema { format, output }) => schema::write_schema(*format, output.as_deref()),
+        Some(Commands::Validate) => {
+            let project_dir = project_dir(&args);
+            let cfg = match prepare_config(&args.config, &project_dir, args.profile.as_deref(), true) {
+                Ok(cfg) => cfg,
+                Err(err) => return validate::report_load_error(&args.config, err),
+            };
+            validate::validate_config(&project_dir, &args.config, &cfg, &generated_platforms(&cfg))
+        }
+        None => generate(&args),
     }
+}
 
-    // Set output_file_name if not already configured (backward compatibility)
-    if cfg.android.app.build.output_file_name.is_none() {
+/// Loads the config and fills in derived values (version, output file name, env vars).
+/// `read_only` skips refreshing the pkl JSON cache.
+fn prepare_config(config_path: &Path, project_dir: &Path, profile: Option<&str>, read_only: bool) -> Result<Config> {
+    let mut cfg = match profile {
+        Some(profile) => load_profile_config(config_path, profile, read_only)?,
+        None => load_config(config_path, read_only)?,
+    };
+
+    // Use version from app.pkl's pubspec config
+    if cfg.version.is_none()
+        && let Some(pubspec_config) = &cfg.pubspec
+    {
+        cfg.version = pubspec_config.version.clone();
         if let Some(version) = &cfg.version {
-            let pattern = cfg
-                .output_file_name_pattern
-                .as_deref()
-                .unwrap_or("{project_name}-v{version}-${name}.apk");
+            println!("Using version from config: {}", version);
+        }
+    }
 
-            let output_pattern = pattern
-                .replace("{version}", version)
-                .replace("{project_name}", &cfg.project_name);
+    // Set output_file_name if not already configured (backward compatibility)
+    if cfg.android.app.build.output_file_name.is_none() && cfg.version.is_some() {
+        let pattern = cfg
+            .output_file_name_pattern
+            .as_deref()
+            .unwrap_or("{project_name}-v{version}-${name}.apk");
+        cfg.android.app.build.output_file_name = Some(pattern.to_string());
+    }
+    let build_performance = &mut cfg.android.build_performance;
+    if build_performance.kotlin_incremental.is_none() {
+        build_performance.kotlin_incremental = cfg.android.app.build.kotlin_incremental;
+    }
 
-            cfg.android.app.build.output_file_name = Some(output_pattern);
-        }
+    let values = name_pattern_values(&cfg, project_dir);
+    let android_tokens: Vec<&str> = android::OUTPUT_FILE_NAME_TOKENS
+        .iter()
+        .map(|(token, _)| token.trim_matches(|c| c == '{' || c == '}'))
+        .collect();
+    let build = &mut cfg.android.app.build;
+    if let Some(pattern) = &build.output_file_name {
+        build.output_file_name = Some(fill_name_pattern(
+            "android.app.build.output_file_name",
+            pattern,
+            &values,
+            &android_tokens,
+        )?);
+    }
+    for (key, pattern) in build.output_file_names.iter_mut() {
+        let field = format!("android.app.build.output_file_names.{}", key);
+        *pattern = fill_name_pattern(&field, pattern, &values, &android_tokens)?;
+    }
+    if cfg.build.platforms.iter().any(|platform| platform != "android") {
+        cfg.build.desktop_file_name = fill_name_pattern(
+            "build.desktop_file_name",
+            &cfg.build.desktop_file_name,
+            &values,
+            &builder::DESKTOP_FILE_NAME_TOKENS,
+        )?;
     }
 
     expand_config(&mut cfg)?;
+    // The selected profile is also the one the build environment reports.
+    if let Some(profile) = profile
+        && let Some(build_env_config) = cfg.build_env.as_mut()
+    {
+        build_env_config.profile = Some(profile.to_string());
+    }
+    Ok(cfg)
+}
 
-    // Determine which platforms to process based on config
-    let platforms = cfg
-        .create
-        .platforms
-        .as_ref()
-        .map(|p| p.as_slice())
-        .unwrap_or(&[]);
-    let process_android = platforms.is_empty() || platforms.contains(&"android".to_string());
-    let process_web = platforms.contains(&"web".to_string());
-    let process_windows = platforms.contains(&"windows".to_string());
-
-    // Remove existing platform directories
-    if process_android {
-        let android_dir = project_dir.join("android");
-        if android_dir.exists() {
-            if dry_run {
-                println!("[DRY RUN] Would remove directory: {}", android_dir.display());
-            } else {
-                remove_dir_all_with_retry(&android_dir)?;
-            }
+/// Values of the file name tokens known at generation time; `None` when unavailable.
+/// git only runs when a pattern uses `{git_sha}`.
+fn name_pattern_values(cfg: &Config, project_dir: &Path) -> BTreeMap<&'static str, Option<String>> {
+    let build_number = cfg
+        .version
+        .as_deref()
+        .and_then(|version| version.split_once('+'))
+        .map(|(_, build)| build.to_string());
+    let build = &cfg.android.app.build;
+    let uses_git_sha = build
+        .output_file_name
+        .iter()
+        .chain(build.output_file_names.values())
+        .chain(std::iter::once(&cfg.build.desktop_file_name))
+        .any(|pattern| pattern.contains("{git_sha}"));
+    let git_sha = uses_git_sha
+        .then(|| {
+            std::process::Command::new("git")
+                .args(["rev-parse", "--short", "HEAD"])
+                .current_dir(project_dir)
+                .output()
+                .ok()
+        })
+        .flatten()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string());
+    let date: String = releases::utc_timestamp()
+        .chars()
+        .take(10)
+        .filter(|c| *c != '-')
+        .collect();
+    BTreeMap::from([
+        ("project_name", Some(cfg.project_name.clone())),
+        ("version", cfg.version.clone()),
+        ("build_number", build_number),
+        ("date", Some(date)),
+        ("git_sha", git_sha),
+    ])
+}
+
+/// Platforms created and customized: `create.platforms`, or `create.default_platforms`
+/// when that is unset or empty.
+fn generated_platforms(cfg: &Config) -> Vec<&'static str> {
+    let platforms = match cfg.create.platforms.as_deref() {
+        Some(platforms) if !platforms.is_empty() => platforms,
+        _ => &cfg.create.default_platforms,
+    };
+    validate::PLATFORMS
+        .into_iter()
+        .filter(|name| platforms.iter().any(|platform| platform == name))
+        .collect()
+}
+
+/// Applies `--platforms` over `create.platforms`.
+fn select_platforms(cfg: &mut Config, platforms: Option<&[String]>) -> Result<()> {
+    let Some(platforms) = platforms else {
+        return Ok(());
+    };
+    for platform in platforms {
+        if !validate::PLATFORMS.contains(&platform.as_str()) {
+            anyhow::bail!(
+                "Unknown platform in --platforms: {} (expected one of {})",
+                platform,
+                validate::PLATFORMS.join(", ")
+            );
         }
     }
+    cfg.create.platforms = Some(platforms.to_vec());
+    Ok(())
+}
 
-    if process_web {
-        let web_dir = project_dir.join("web");
-        if web_dir.exists() {
-            if dry_run {
-                println!("[DRY RUN] Would remove directory: {}", web_dir.display());
-            } else {
-                remove_dir_all_with_retry(&web_dir)?;
-            }
-        }
+fn generate(args: &Args) -> Result<()> {
+    if args.all {
+        return generate_workspace(args);
     }
+    generate_project(args, &args.config, &project_dir(args), None)
+}
 
-    if process_windows {
-        let windows_dir = project_dir.join("windows");
-        if windows_dir.exists() {
-            if dry_run {
-                println!("[DRY RUN] Would remove directory: {}", windows_dir.display());
-            } else {
-                remove_dir_all_with_retry(&windows_dir)?;
+/// `--project-dir`, or the current directory.
+fn project_dir(args: &Args) -> PathBuf {
+    args.project_dir.clone().unwrap_or_else(|| PathBuf::from("."))
+}
+
+/// `--project-dir`, or the directory holding the config file, so `run` finds the lockfile
+/// and regenerates next to the config wherever it is started from.
+fn run_project_dir(args: &Args) -> PathBuf {
+    match &args.project_dir {
+        Some(dir) => dir.clone(),
+        None => match args.config.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        },
+    }
+}
+
+/// `--all`: generates the workspace's apps in order, stopping at the first failure.
+fn generate_workspace(args: &Args) -> Result<()> {
+    let workspace = load_workspace_config(&args.workspace, args.dry_run)?;
+    let root = args.workspace.parent().unwrap_or_else(|| Path::new("."));
+    let platforms_dir = std::path::absolute(root.join(&workspace.platforms_dir))
+        .with_context(|| format!("Failed to resolve path: {}", workspace.platforms_dir))?;
+    for (i, app) in workspace.apps.iter().enumerate() {
+        let project_dir = root.join(app);
+        let config_path = project_dir.join(&workspace.config);
+        println!("\n[{}/{}] {}\n", i + 1, workspace.apps.len(), project_dir.display());
+        generate_project(args, &config_path, &project_dir, Some(&platforms_dir))
+            .with_context(|| format!("Failed to generate app: {}", project_dir.display()))?;
+    }
+    if args.dry_run {
+        println!("\n[DRY RUN] Previewed {} app(s) from {}", workspace.apps.len(), args.workspace.display());
+    } else {
+        println!("\n✓ Generated {} app(s) from {}", workspace.apps.len(), args.workspace.display());
+    }
+    Ok(())
+}
+
+/// Generates one app; `platforms_dir` is the workspace's shared template root.
+fn generate_project(args: &Args, config_path: &Path, project_dir: &Path, platforms_dir: Option<&Path>) -> Result<()> {
+    let cfg = prepare_project_config(args, config_path, project_dir, platforms_dir)?;
+    if args.dry_run {
+        return preview_generate(args, config_path, project_dir, cfg);
+    }
+    generate_platforms(args, config_path, project_dir, cfg, project_dir)
+}
+
+/// Loads the config for generation, falling back to the workspace's template root, and
+/// fills in the defaults that depend on where the project lives.
+fn prepare_project_config(
+    args: &Args,
+    config_path: &Path,
+    project_dir: &Path,
+    platforms_dir: Option<&Path>,
+) -> Result<Config> {
+    let mut cfg = prepare_config(config_path, project_dir, args.profile.as_deref(), args.dry_run)?;
+    if let Some(platforms_dir) = platforms_dir
+        && cfg.platforms_dir.is_none()
+    {
+        cfg.platforms_dir = Some(platforms_dir.to_string_lossy().into_owned());
+    }
+    android::apply_build_performance_defaults(&mut cfg.android, project_dir);
+    android::apply_form_factor_defaults(&mut cfg.android)?;
+    Ok(cfg)
+}
+
+/// `--dry-run`: generates into a temporary copy of the project and prints a unified diff
+/// of every file that would change. The config is the one prepared for the real project,
+/// with paths leading out of it made absolute so they resolve the same from the copy.
+fn preview_generate(args: &Args, config_path: &Path, project_dir: &Path, cfg: Config) -> Result<()> {
+    println!("[DRY RUN] Preview mode - no files will be modified\n");
+    let cfg = preview::rebase_outside_paths(cfg, project_dir)?;
+
+    let preview_dir = std::env::temp_dir().join(format!("flutter_gen_preview_{}", std::process::id()));
+    if preview_dir.exists() {
+        utils::remove_dir_all_with_retry(&preview_dir)?;
+    }
+    preview::copy_project(project_dir, &preview_dir)?;
+
+    println!("[DRY RUN] Generating into: {}\n", preview_dir.display());
+    let result = generate_platforms(args, config_path, &preview_dir, cfg, project_dir).and_then(|_| {
+        println!("\n[DRY RUN] Changes to {}:\n", project_dir.display());
+        preview::print_tree_diff(project_dir, &preview_dir)
+    });
+    let _ = std::fs::remove_dir_all(&preview_dir);
+    result
+}
+
+/// Stops before platform files changed by hand since the last generation are overwritten,
+/// unless --force; a dry run only warns.
+fn check_hand_edits(args: &Args, project_dir: &Path, cfg: &Config, platforms: &[&str]) -> Result<()> {
+    const SHOWN: usize = 20;
+    let edits = lockfile::hand_edits(project_dir, platforms, &cfg.clean.preserve)?;
+    if edits.is_empty() {
+        return Ok(());
+    }
+    if args.force {
+        println!("Warning: Overwriting {} file(s) changed by hand (--force)", edits.len());
+        return Ok(());
+    }
+    let mut lines: Vec<String> = edits
+        .iter()
+        .take(SHOWN)
+        .map(|(kind, file)| format!("  {}: {}", kind, file))
+        .collect();
+    if edits.len() > SHOWN {
+        lines.push(format!("  ... and {} more", edits.len() - SHOWN));
+    }
+    let message = format!(
+        "{} file(s) changed by hand since the last generation ({}):\n{}",
+        edits.len(),
+        lockfile::LOCK_FILE,
+        lines.join("\n")
+    );
+    if args.dry_run {
+        println!("Warning: {}\n", message);
+        return Ok(());
+    }
+    anyhow::bail!(
+        "{}\nMove the changes into the config or the platform overlays, or re-run with --force to overwrite them",
+        message
+    );
+}
+
+/// Generates into `project_dir`. Preflight and hand-edit checks look at `real_project_dir`:
+/// the same directory, or the project behind a --dry-run copy.
+fn generate_platforms(
+    args: &Args,
+    config_path: &Path,
+    project_dir: &Path,
+    mut cfg: Config,
+    real_project_dir: &Path,
+) -> Result<()> {
+    let flutter_cmd = &args.flutter_cmd;
+    let project_dir = project_dir.to_path_buf();
+
+    // Determine which platforms to process based on config
+    let configured = generated_platforms(&cfg);
+    select_platforms(&mut cfg, args.platforms.as_deref())?;
+    let generated = generated_platforms(&cfg);
+    if generated.is_empty() {
+        anyhow::bail!("No platforms selected (create.platforms, create.default_platforms or --platforms)");
+    }
+    // flutter create without --platforms would create every platform.
+    cfg.create.platforms = Some(generated.iter().map(|name| name.to_string()).collect());
+
+    preflight::run_preflight(real_project_dir, &cfg, &generated, args.dry_run)?;
+    check_hand_edits(args, real_project_dir, &cfg, &generated)?;
+    hooks::run_hooks(&project_dir, &cfg.hooks, HookStage::PreGenerate, &generated, args.dry_run)?;
+
+    if args.mode == GenerateMode::Patch {
+        let missing: Vec<&str> = generated
+            .iter()
+            .copied()
+            .filter(|name| !project_dir.join(name).is_dir())
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "Platform directories missing for --mode patch: {} (run a full generation first)",
+                missing.join(", ")
+            );
+        }
+        if generated.contains(&"android") {
+            android::restore_pristine_gradle_scripts(&project_dir)?;
+            // The template's MainActivity sits in the package flutter create was given.
+            let namespace = &cfg.android.app.build.namespace;
+            if !namespace.is_empty() {
+                migrate::move_package_sources(&project_dir.join("android"), namespace, false)?;
             }
         }
+        println!("✓ Patching existing platform directories: {}\n", generated.join(", "));
+    } else {
+        if cfg.clean.backup {
+            backup::backup_platform_dirs(&project_dir, &generated, cfg.clean.keep_backups, false)?;
+        }
+        // Remove existing platform directories
+        for name in &generated {
+            remove_platform_dir(&project_dir, name, &cfg.clean.preserve)?;
+        }
     }
 
-    let flutter_cmd = resolve_cmd(&flutter_cmd)?;
-    if !dry_run {
+    // Patch mode works on the existing tree, so flutter is not needed.
+    if args.mode == GenerateMode::Full {
+        let flutter_cmd = resolve_cmd(flutter_cmd)?;
         run_flutter_create(
             &project_dir,
             &flutter_cmd,
@@ -132,53 +660,39 @@ fn main() -> Result<()> {
             cfg.description.as_deref(),
             &cfg.create,
         )?;
-    } else {
-        println!("[DRY RUN] Would run flutter create with:");
-        println!("  project_name: {}", cfg.project_name);
-        if let Some(org) = &cfg.org {
-            println!("  org: {}", org);
-        }
-        if let Some(desc) = &cfg.description {
-            println!("  description: {}", desc);
+        if generated.contains(&"android") {
+            android::save_pristine_gradle_scripts(&project_dir)?;
         }
-        println!("  platforms: {:?}", cfg.create.platforms);
-        println!("  android_language: {:?}\n", cfg.create.android_language);
-        return Ok(());
     }
 
-    // Process Android platform
-    if process_android {
-        let android_dir = project_dir.join("android");
-        if !android_dir.exists() {
-            bail!(
-                "Generated android directory not found at: {}",
-                android_dir.display()
-            );
-        }
-        android::process_android_platform(&project_dir, &cfg.android, cfg.platforms_dir.as_deref())?;
+    // Customize the platform directories, then run external processors
+    let processors = processor::processors(&cfg, &generated);
+    processor::run_processors(&project_dir, &cfg, &processors)?;
+
+    if cfg.build_env.is_some() {
+        build_env::write_build_env(&project_dir, &cfg)?;
     }
 
-    // Process Web platform
-    if process_web {
-        let web_dir = project_dir.join("web");
-        if !web_dir.exists() {
-            bail!("Generated web directory not found at: {}", web_dir.display());
-        }
-        web::process_web_platform(&project_dir)?;
+    if let Some(assets_config) = &cfg.assets {
+        assets::process_assets(&project_dir, assets_config)?;
     }
 
-    // Process Windows platform
-    if process_windows {
-        let windows_dir = project_dir.join("windows");
-        if !windows_dir.exists() {
-            bail!("Generated windows directory not found at: {}", windows_dir.display());
-        }
-        if let Some(windows_config) = &cfg.windows {
-            windows::process_windows_platform(&project_dir, windows_config)?;
-        } else {
-            windows::process_windows_platform(&project_dir, &Default::default())?;
-        }
+    if let Some(fonts_config) = &cfg.fonts {
+        fonts::process_fonts(&project_dir, fonts_config)?;
+    }
+
+    if let Some(line_endings_config) = &cfg.line_endings {
+        apply_line_endings(&project_dir, line_endings_config, &configured)?;
     }
+    // A --platforms subset keeps the other platforms' .gitattributes/.gitignore entries.
+    if let Some(gitignore_config) = &cfg.gitignore {
+        update_gitignore(&project_dir, gitignore_config, &configured)?;
+    }
+
+    hooks::run_hooks(&project_dir, &cfg.hooks, HookStage::PostGenerate, &generated, args.dry_run)?;
+
+    let inputs_hash = lockfile::inputs_hash(&project_dir, config_path, args.profile.as_deref(), &cfg)?;
+    lockfile::write_lockfile(&project_dir, &inputs_hash, &generated)?;
 
     println!("Platform directories generated successfully!");
     Ok(())
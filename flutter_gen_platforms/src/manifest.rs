@@ -0,0 +1,367 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::config::{
+    AndroidConfig, AndroidDeepLinksConfig, AndroidFileProviderConfig, AndroidQueriesConfig,
+    AndroidTvConfig, AndroidWearConfig,
+};
+
+/// Minimal text-based editor for the copied AndroidManifest.xml.
+///
+/// Edits are line-oriented (like the gradle edits) so the template's formatting
+/// and comments survive; xmltree would drop the `android:` attribute prefixes.
+pub struct ManifestEditor {
+    lines: Vec<String>,
+}
+
+impl ManifestEditor {
+    pub fn new(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(|l| l.to_string()).collect(),
+        }
+    }
+
+    pub fn contains(&self, needle: &str) -> bool {
+        self.lines.iter().any(|line| line.contains(needle))
+    }
+
+    fn find_line(&self, from: usize, pred: impl Fn(&str) -> bool) -> Option<usize> {
+        (from..self.lines.len()).find(|&i| pred(self.lines[i].trim()))
+    }
+
+    /// Index of the line holding the closing `>` of the start tag beginning at `start`.
+    fn start_tag_end(&self, start: usize) -> Option<usize> {
+        (start..self.lines.len()).find(|&i| {
+            let line = &self.lines[i];
+            let from = if i == start { line.find('<').unwrap_or(0) } else { 0 };
+            line[from..].contains('>')
+        })
+    }
+
+    fn insert_lines(&mut self, at: usize, indent: &str, lines: &[String]) {
+        for (offset, line) in lines.iter().enumerate() {
+            self.lines.insert(at + offset, format!("{}{}", indent, line));
+        }
+    }
+
+    /// Inserts top-level `<manifest>` children (uses-permission, queries, ...)
+    /// just before the `<application` element.
+    pub fn add_to_manifest(&mut self, lines: &[String]) -> Result<()> {
+        let Some(at) = self.find_line(0, |l| l.starts_with("<application")) else {
+            bail!("<application> element not found in manifest");
+        };
+        self.insert_lines(at, "    ", lines);
+        Ok(())
+    }
+
+    /// Inserts children at the end of the `<application>` element.
+    pub fn add_to_application(&mut self, lines: &[String]) -> Result<()> {
+        let Some(at) = self.find_line(0, |l| l.starts_with("</application>")) else {
+            bail!("</application> not found in manifest");
+        };
+        self.insert_lines(at, "        ", lines);
+        Ok(())
+    }
+
+    /// Inserts children at the end of the `.MainActivity` `<activity>` element.
+    pub fn add_to_main_activity(&mut self, lines: &[String]) -> Result<()> {
+        let mut from = 0;
+        while let Some(start) = self.find_line(from, |l| l.starts_with("<activity")) {
+            let Some(tag_end) = self.start_tag_end(start) else {
+                break;
+            };
+            let is_main = self.lines[start..=tag_end]
+                .iter()
+                .any(|line| line.contains(".MainActivity\""));
+            if is_main {
+                let Some(close) = self.find_line(tag_end, |l| l.starts_with("</activity>")) else {
+                    bail!("</activity> not found for MainActivity in manifest");
+                };
+                self.insert_lines(close, "            ", lines);
+                return Ok(());
+            }
+            from = tag_end + 1;
+        }
+        bail!("MainActivity <activity> element not found in manifest");
+    }
+
+    /// Sets (or replaces) an attribute on the `<application>` start tag.
+    pub fn set_application_attribute(&mut self, name: &str, value: &str) -> Result<()> {
+        self.set_attribute("<application", name, value)
+    }
+
+    fn set_attribute(&mut self, tag: &str, name: &str, value: &str) -> Result<()> {
+        let Some(start) = self.find_line(0, |l| l.starts_with(tag)) else {
+            bail!("{}> element not found in manifest", tag);
+        };
+        let Some(end) = self.start_tag_end(start) else {
+            bail!("Unterminated {}> start tag in manifest", tag);
+        };
+        let prefix = format!("{}=\"", name);
+        for i in start..=end {
+            if let Some(pos) = self.lines[i].find(&prefix) {
+                let value_start = pos + prefix.len();
+                if let Some(len) = self.lines[i][value_start..].find('"') {
+                    self.lines[i].replace_range(value_start..value_start + len, value);
+                    return Ok(());
+                }
+            }
+        }
+        // Add the attribute on its own line, matching the one-attribute-per-line template style.
+        let indent = self.lines[start].len() - self.lines[start].trim_start().len() + 4;
+        let line = &mut self.lines[end];
+        let close = if line.trim_end().ends_with("/>") { "/>" } else { ">" };
+        let trimmed = line.trim_end().trim_end_matches(close).to_string();
+        *line = trimmed;
+        self.lines.insert(
+            end + 1,
+            format!("{}{}=\"{}\"{}", " ".repeat(indent), name, value, close),
+        );
+        Ok(())
+    }
+
+    pub fn into_string(self) -> String {
+        self.lines.join("\n") + "\n"
+    }
+}
+
+fn qualified_name(name: &str, prefix: &str) -> String {
+    if name.contains('.') {
+        name.to_string()
+    } else {
+        format!("{}{}", prefix, name)
+    }
+}
+
+fn queries_lines(config: &AndroidQueriesConfig) -> Vec<String> {
+    if config.packages.is_empty() && config.intents.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec!["<queries>".to_string()];
+    for package in &config.packages {
+        lines.push(format!("    <package android:name=\"{}\" />", package));
+    }
+    for intent in &config.intents {
+        lines.push("    <intent>".to_string());
+        lines.push(format!(
+            "        <action android:name=\"{}\" />",
+            qualified_name(&intent.action, "android.intent.action.")
+        ));
+        if let Some(category) = &intent.category {
+            lines.push(format!(
+                "        <category android:name=\"{}\" />",
+                qualified_name(category, "android.intent.category.")
+            ));
+        }
+        let mut data = Vec::new();
+        if let Some(scheme) = &intent.scheme {
+            data.push(format!("android:scheme=\"{}\"", scheme));
+        }
+        if let Some(host) = &intent.host {
+            data.push(format!("android:host=\"{}\"", host));
+        }
+        if let Some(mime_type) = &intent.mime_type {
+            data.push(format!("android:mimeType=\"{}\"", mime_type));
+        }
+        if !data.is_empty() {
+            lines.push(format!("        <data {} />", data.join(" ")));
+        }
+        lines.push("    </intent>".to_string());
+    }
+    lines.push("</queries>".to_string());
+    lines
+}
+
+fn deep_link_lines(config: &AndroidDeepLinksConfig) -> Vec<String> {
+    if config.schemes.is_empty() && config.hosts.is_empty() {
+        return Vec::new();
+    }
+    let mut lines = vec![
+        if config.auto_verify {
+            "<intent-filter android:autoVerify=\"true\">".to_string()
+        } else {
+            "<intent-filter>".to_string()
+        },
+        "    <action android:name=\"android.intent.action.VIEW\" />".to_string(),
+        "    <category android:name=\"android.intent.category.DEFAULT\" />".to_string(),
+        "    <category android:name=\"android.intent.category.BROWSABLE\" />".to_string(),
+    ];
+    for scheme in &config.schemes {
+        lines.push(format!("    <data android:scheme=\"{}\" />", scheme));
+    }
+    for host in &config.hosts {
+        lines.push(format!("    <data android:host=\"{}\" />", host));
+    }
+    if let Some(path_prefix) = &config.path_prefix {
+        lines.push(format!("    <data android:pathPrefix=\"{}\" />", path_prefix));
+    }
+    lines.push("</intent-filter>".to_string());
+    lines
+}
+
+const FILE_PROVIDER_CLASS: &str = "androidx.core.content.FileProvider";
+
+fn file_provider_lines(config: &AndroidFileProviderConfig) -> Vec<String> {
+    let authority = config
+        .authority
+        .as_deref()
+        .unwrap_or("${applicationId}.fileprovider");
+    vec![
+        "<provider".to_string(),
+        format!("    android:name=\"{}\"", FILE_PROVIDER_CLASS),
+        format!("    android:authorities=\"{}\"", authority),
+        "    android:exported=\"false\"".to_string(),
+        "    android:grantUriPermissions=\"true\">".to_string(),
+        "    <meta-data".to_string(),
+        "        android:name=\"android.support.FILE_PROVIDER_PATHS\"".to_string(),
+        "        android:resource=\"@xml/file_paths\" />".to_string(),
+        "</provider>".to_string(),
+    ]
+}
+
+fn uses_feature_line(name: &str, required: bool) -> String {
+    format!(
+        "<uses-feature android:name=\"{}\" android:required=\"{}\" />",
+        name, required
+    )
+}
+
+fn tv_feature_lines(config: &AndroidTvConfig) -> Vec<String> {
+    vec![
+        uses_feature_line("android.software.leanback", config.required),
+        // TV remotes have no touchscreen; without this Play filters the app out on TVs.
+        uses_feature_line("android.hardware.touchscreen", false),
+    ]
+}
+
+fn leanback_launcher_lines() -> Vec<String> {
+    vec![
+        "<intent-filter>".to_string(),
+        "    <action android:name=\"android.intent.action.MAIN\" />".to_string(),
+        "    <category android:name=\"android.intent.category.LEANBACK_LAUNCHER\" />".to_string(),
+        "</intent-filter>".to_string(),
+    ]
+}
+
+fn wear_application_lines(config: &AndroidWearConfig) -> Vec<String> {
+    vec![
+        "<uses-library".to_string(),
+        "    android:name=\"com.google.android.wearable\"".to_string(),
+        "    android:required=\"false\" />".to_string(),
+        "<meta-data".to_string(),
+        "    android:name=\"com.google.android.wearable.standalone\"".to_string(),
+        format!("    android:value=\"{}\" />", config.standalone),
+    ]
+}
+
+/// Applies config-driven additions to the main manifest copied from the templates.
+const DEFERRED_COMPONENTS_APPLICATION: &str =
+    "io.flutter.embedding.android.FlutterPlayStoreSplitApplication";
+const LOADING_UNIT_MAPPING: &str =
+    "io.flutter.embedding.engine.deferredcomponents.DeferredComponentManager.loadingUnitMapping";
+
+pub fn apply_main_manifest(path: &Path, config: &AndroidConfig) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut editor = ManifestEditor::new(&content);
+
+    let permissions: Vec<String> = config
+        .manifest
+        .permissions
+        .iter()
+        .map(|p| qualified_name(p, "android.permission."))
+        .filter(|name| !editor.contains(&format!("android:name=\"{}\"", name)))
+        .map(|name| format!("<uses-permission android:name=\"{}\" />", name))
+        .collect();
+    if !permissions.is_empty() {
+        editor.add_to_manifest(&permissions)?;
+    }
+
+    let queries = queries_lines(&config.manifest.queries);
+    if !queries.is_empty() {
+        editor.add_to_manifest(&queries)?;
+    }
+
+    if let Some(deep_links) = &config.deep_links {
+        let lines = deep_link_lines(deep_links);
+        if !lines.is_empty() {
+            editor.add_to_main_activity(&lines)?;
+        }
+    }
+
+    if let Some(file_provider) = &config.file_provider
+        && !editor.contains(&format!("android:name=\"{}\"", FILE_PROVIDER_CLASS))
+    {
+        editor.add_to_application(&file_provider_lines(file_provider))?;
+    }
+
+    if let Some(tv) = &config.tv {
+        let features: Vec<String> = tv_feature_lines(tv)
+            .into_iter()
+            .filter(|line| !editor.contains(line))
+            .collect();
+        if !features.is_empty() {
+            editor.add_to_manifest(&features)?;
+        }
+        if !editor.contains("android.intent.category.LEANBACK_LAUNCHER") {
+            editor.add_to_main_activity(&leanback_launcher_lines())?;
+        }
+        if tv.banner.is_some() {
+            editor.set_application_attribute("android:banner", "@drawable/banner")?;
+        }
+    }
+
+    if let Some(wear) = &config.wear {
+        let feature = uses_feature_line("android.hardware.type.watch", true);
+        if !editor.contains(&feature) {
+            editor.add_to_manifest(&[feature])?;
+        }
+        if !editor.contains("com.google.android.wearable.standalone") {
+            editor.add_to_application(&wear_application_lines(wear))?;
+        }
+    }
+
+    if let Some(features) = &config.dynamic_features
+        && !features.modules.is_empty()
+    {
+        // Keep a custom Application class; it must then extend FlutterPlayStoreSplitApplication.
+        if editor.contains("android:name=\"${applicationName}\"") {
+            editor.set_application_attribute("android:name", DEFERRED_COMPONENTS_APPLICATION)?;
+        }
+        let mapping: Vec<String> = features
+            .modules
+            .iter()
+            .flat_map(|module| module.loading_units.iter().map(move |unit| format!("{}:{}", unit, module.name)))
+            .collect();
+        if !mapping.is_empty() && !editor.contains(LOADING_UNIT_MAPPING) {
+            editor.add_to_application(&[
+                "<meta-data".to_string(),
+                format!("    android:name=\"{}\"", LOADING_UNIT_MAPPING),
+                format!("    android:value=\"{}\" />", mapping.join(",")),
+            ])?;
+        }
+    }
+
+    if let Some(integrity) = &config.play.integrity {
+        let name = format!("android:name=\"{}\"", integrity.meta_data_name);
+        if !editor.contains(&name) {
+            editor.add_to_application(&[
+                "<meta-data".to_string(),
+                format!("    {}", name),
+                format!("    android:value=\"@string/{}\" />", integrity.meta_data_name),
+            ])?;
+        }
+    }
+
+    if config.network_security.is_some() {
+        editor.set_application_attribute(
+            "android:networkSecurityConfig",
+            "@xml/network_security_config",
+        )?;
+    }
+
+    fs::write(path, editor.into_string())
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const ID_HISTORY_FILE: &str = ".flutter_gen_id_history.json";
+
+pub fn validate_application_id(id: &str) -> Result<()> {
+    let segments: Vec<&str> = id.split('.').collect();
+    if segments.len() < 2 {
+        bail!("Invalid application id '{id}': expected at least two dot-separated segments");
+    }
+    for segment in segments {
+        let mut chars = segment.chars();
+        let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            bail!("Invalid application id '{id}': segment '{segment}' must start with a letter and contain only [A-Za-z0-9_]");
+        }
+    }
+    Ok(())
+}
+
+fn read_gradle_application_id(path: &Path) -> Result<Option<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok(content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("applicationId = "))
+        .map(|value| value.trim().trim_matches('"').to_string()))
+}
+
+fn apply_gradle_id(path: &Path, new_id: &str) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut out = Vec::new();
+    for line in content.lines() {
+        if line.trim_start().starts_with("namespace = ") {
+            out.push(format!("    namespace = \"{}\"", new_id));
+        } else if line.trim_start().starts_with("applicationId = ") {
+            out.push(format!("        applicationId = \"{}\"", new_id));
+        } else {
+            out.push(line.to_string());
+        }
+    }
+    fs::write(path, out.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
+
+fn package_dir(root: &Path, id: &str) -> PathBuf {
+    id.split('.').fold(root.to_path_buf(), |dir, segment| dir.join(segment))
+}
+
+/// Finds the package declared by `MainActivity` under a source root, which can
+/// differ from the application id when the id was changed after `flutter create`.
+pub fn find_main_activity_package(root: &Path) -> Result<Option<String>> {
+    if !root.is_dir() {
+        return Ok(None);
+    }
+    for entry in walkdir::WalkDir::new(root) {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy();
+        if name != "MainActivity.kt" && name != "MainActivity.java" {
+            continue;
+        }
+        let content = fs::read_to_string(entry.path())
+            .with_context(|| format!("Failed to read file: {}", entry.path().display()))?;
+        let package = content
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("package "))
+            .map(|value| value.trim_end_matches(';').trim().to_string());
+        return Ok(package);
+    }
+    Ok(None)
+}
+
+/// Moves the kotlin/java sources from MainActivity's package to `new_id`'s directory.
+pub fn move_package_sources(android_dir: &Path, new_id: &str, dry_run: bool) -> Result<()> {
+    for lang in ["kotlin", "java"] {
+        let root = android_dir.join("app/src/main").join(lang);
+        let Some(old_id) = find_main_activity_package(&root)? else {
+            continue;
+        };
+        let old_id = old_id.as_str();
+        if old_id == new_id {
+            continue;
+        }
+        let src = package_dir(&root, old_id);
+        if !src.is_dir() {
+            continue;
+        }
+        let dst = package_dir(&root, new_id);
+        if dry_run {
+            println!("[DRY RUN] Would move {} -> {}", src.display(), dst.display());
+            continue;
+        }
+        if dst.exists() {
+            bail!("Target package directory already exists: {}", dst.display());
+        }
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+        }
+        fs::rename(&src, &dst)
+            .with_context(|| format!("Failed to move {} -> {}", src.display(), dst.display()))?;
+
+        // Drop the now-empty parents of the old package, stopping at the source root.
+        let mut parent = src.parent();
+        while let Some(dir) = parent {
+            if dir == root || fs::remove_dir(dir).is_err() {
+                break;
+            }
+            parent = dir.parent();
+        }
+
+        for entry in walkdir::WalkDir::new(&dst) {
+            let entry = entry?;
+            let path = entry.path();
+            let is_source = matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("kt") | Some("java")
+            );
+            if !is_source {
+                continue;
+            }
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read file: {}", path.display()))?;
+            let updated = content
+                .lines()
+                .map(|line| {
+                    let trimmed = line.trim_end_matches(';');
+                    if trimmed == format!("package {}", old_id) {
+                        line.replacen(old_id, new_id, 1)
+                    } else {
+                        line.to_string()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            fs::write(path, updated + "\n")
+                .with_context(|| format!("Failed to write file: {}", path.display()))?;
+        }
+        println!("Moved {} sources to package {}", lang, new_id);
+    }
+    Ok(())
+}
+
+fn read_ios_bundle_id(content: &str) -> Option<String> {
+    content
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("PRODUCT_BUNDLE_IDENTIFIER = "))
+        .map(|value| value.trim_end_matches(';').trim_matches('"').to_string())
+        .find(|value| !value.ends_with(".RunnerTests"))
+}
+
+fn apply_ios_bundle_id(pbxproj: &Path, new_id: &str, dry_run: bool) -> Result<Option<String>> {
+    let content = fs::read_to_string(pbxproj)
+        .with_context(|| format!("Failed to read file: {}", pbxproj.display()))?;
+    let Some(old_id) = read_ios_bundle_id(&content) else {
+        return Ok(None);
+    };
+    if old_id == new_id {
+        println!("iOS bundle id is already {}", new_id);
+        return Ok(Some(old_id));
+    }
+    if dry_run {
+        println!("[DRY RUN] Would change iOS bundle id {} -> {}", old_id, new_id);
+        return Ok(Some(old_id));
+    }
+    let updated = content
+        .lines()
+        .map(|line| {
+            if line.trim().starts_with("PRODUCT_BUNDLE_IDENTIFIER = ") {
+                line.replace(&old_id, new_id)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    fs::write(pbxproj, updated + "\n")
+        .with_context(|| format!("Failed to write file: {}", pbxproj.display()))?;
+    println!("iOS bundle id changed: {} -> {}", old_id, new_id);
+    Ok(Some(old_id))
+}
+
+fn record_id_change(project_dir: &Path, platform: &str, from: &str, to: &str) -> Result<()> {
+    let path = project_dir.join(ID_HISTORY_FILE);
+    let mut history = if path.exists() {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        serde_json::from_str::<Vec<Value>>(&content)
+            .with_context(|| format!("Failed to parse id history: {}", path.display()))?
+    } else {
+        Vec::new()
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    history.push(json!({
+        "platform": platform,
+        "from": from,
+        "to": to,
+        "timestamp": timestamp,
+    }));
+    fs::write(&path, serde_json::to_string_pretty(&history)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
+
+pub fn migrate_id(project_dir: &Path, new_id: &str, dry_run: bool) -> Result<()> {
+    validate_application_id(new_id)?;
+
+    let mut migrated = Vec::new();
+
+    let android_dir = project_dir.join("android");
+    let app_gradle = android_dir.join("app/build.gradle.kts");
+    if app_gradle.exists() {
+        match read_gradle_application_id(&app_gradle)? {
+            Some(old_id) if old_id == new_id => {
+                println!("Android application id is already {}", new_id);
+            }
+            Some(old_id) => {
+                if dry_run {
+                    println!("[DRY RUN] Would change Android application id {} -> {}", old_id, new_id);
+                } else {
+                    apply_gradle_id(&app_gradle, new_id)?;
+                    println!("Android application id changed: {} -> {}", old_id, new_id);
+                }
+                move_package_sources(&android_dir, new_id, dry_run)?;
+                migrated.push(("android", old_id));
+            }
+            None => bail!("applicationId not found in: {}", app_gradle.display()),
+        }
+    }
+
+    let pbxproj = project_dir.join("ios/Runner.xcodeproj/project.pbxproj");
+    if pbxproj.exists()
+        && let Some(old_id) = apply_ios_bundle_id(&pbxproj, new_id, dry_run)?
+        && old_id != new_id
+    {
+        migrated.push(("ios", old_id));
+    }
+
+    if migrated.is_empty() {
+        println!("Nothing to migrate");
+        return Ok(());
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    for (platform, old_id) in &migrated {
+        record_id_change(project_dir, platform, old_id, new_id)?;
+    }
+    println!("\nRecorded previous ids in {}", project_dir.join(ID_HISTORY_FILE).display());
+    println!("Note: Google Play and App Store Connect key listings by id; {} will be published as a new app.", new_id);
+    println!("Keep the old listing (or its signing key) if existing users must receive updates,");
+    println!("and update android.app.build.application_id in your config so regeneration keeps the new id.");
+    Ok(())
+}
@@ -0,0 +1,133 @@
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// XML lines of a config value at `indent`, nesting by `unit`.
+fn value_lines(value: &Value, indent: &str, unit: &str) -> Result<Vec<String>> {
+    let nested = format!("{}{}", indent, unit);
+    let lines = match value {
+        Value::Null => bail!("null is not a property list value"),
+        Value::Bool(flag) => vec![format!("{}<{}/>", indent, flag)],
+        Value::Number(number) if number.is_f64() => vec![format!("{}<real>{}</real>", indent, number)],
+        Value::Number(number) => vec![format!("{}<integer>{}</integer>", indent, number)],
+        Value::String(text) => vec![format!("{}<string>{}</string>", indent, escape(text))],
+        Value::Array(items) if items.is_empty() => vec![format!("{}<array/>", indent)],
+        Value::Array(items) => {
+            let mut lines = vec![format!("{}<array>", indent)];
+            for item in items {
+                lines.extend(value_lines(item, &nested, unit)?);
+            }
+            lines.push(format!("{}</array>", indent));
+            lines
+        }
+        Value::Object(map) if map.is_empty() => vec![format!("{}<dict/>", indent)],
+        Value::Object(map) => {
+            // Sorted like Xcode writes them, whatever order the config format kept.
+            let mut items: Vec<_> = map.iter().collect();
+            items.sort_by_key(|(key, _)| *key);
+            let mut lines = vec![format!("{}<dict>", indent)];
+            for (key, item) in items {
+                lines.push(format!("{}<key>{}</key>", nested, escape(key)));
+                lines.extend(value_lines(item, &nested, unit)?);
+            }
+            lines.push(format!("{}</dict>", indent));
+            lines
+        }
+    };
+    Ok(lines)
+}
+
+/// Top-level entries as (escaped key, first line, end line exclusive).
+fn entries(lines: &[String], indent: &str) -> Vec<(String, usize, usize)> {
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        let key = line
+            .strip_prefix(indent)
+            .and_then(|rest| rest.strip_prefix("<key>"))
+            .and_then(|rest| rest.trim_end().strip_suffix("</key>"));
+        let Some(key) = key else {
+            i += 1;
+            continue;
+        };
+        let value = lines.get(i + 1).map(|l| l.trim()).unwrap_or("");
+        let close = match value {
+            "<array>" => Some(format!("{}</array>", indent)),
+            "<dict>" => Some(format!("{}</dict>", indent)),
+            _ => None,
+        };
+        let end = match close {
+            Some(close) => (i + 2..lines.len())
+                .find(|&j| lines[j].trim_end() == close)
+                .map(|j| j + 1)
+                .unwrap_or(lines.len()),
+            None => (i + 2).min(lines.len()),
+        };
+        found.push((key.to_string(), i, end));
+        i = end;
+    }
+    found
+}
+
+/// Sets top-level keys of the property list in `lines`, replacing whole existing values
+/// and keeping the keys sorted.
+///
+/// Line-based so the file's formatting survives: top-level entries are `<key>` lines one
+/// level into the root `<dict>` (a tab in Xcode's files, two spaces in some of Flutter's),
+/// each followed by a one-line value or an `<array>`/`<dict>` block closed at the same indent.
+pub fn set_entries(lines: &mut Vec<String>, values: &BTreeMap<String, Value>) -> Result<()> {
+    let Some(root) = lines.iter().position(|line| line.trim() == "<dict>") else {
+        bail!("<dict> not found in property list");
+    };
+    let outer = &lines[root][..lines[root].len() - lines[root].trim_start().len()];
+    let indent = lines[root + 1..]
+        .iter()
+        .find(|line| line.trim_start().starts_with("<key>"))
+        .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+        .unwrap_or_else(|| format!("{}\t", outer));
+    let unit = indent.strip_prefix(outer).unwrap_or("\t").to_string();
+
+    for (key, value) in values {
+        let escaped = escape(key);
+        if let Some(&(_, start, end)) = entries(lines, &indent).iter().find(|(name, _, _)| *name == escaped) {
+            lines.drain(start..end);
+        }
+        let Some(close) = lines.iter().rposition(|line| line.trim() == "</dict>") else {
+            bail!("</dict> not found in property list");
+        };
+        let insert_at = entries(lines, &indent)
+            .iter()
+            .find(|(name, _, _)| *name > escaped)
+            .map(|&(_, start, _)| start)
+            .unwrap_or(close);
+        let mut block = vec![format!("{}<key>{}</key>", indent, escaped)];
+        block.extend(value_lines(value, &indent, &unit)?);
+        lines.splice(insert_at..insert_at, block);
+    }
+    Ok(())
+}
+
+/// A complete property list document holding `values`.
+pub fn document(values: &BTreeMap<String, Value>) -> Result<String> {
+    let mut lines: Vec<String> = [
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>",
+        "<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">",
+        "<plist version=\"1.0\">",
+        "<dict>",
+        "</dict>",
+        "</plist>",
+    ]
+    .iter()
+    .map(|line| line.to_string())
+    .collect();
+    set_entries(&mut lines, values)?;
+    Ok(lines.join("\n") + "\n")
+}
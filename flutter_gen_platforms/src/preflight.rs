@@ -0,0 +1,155 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{Config, PreflightConfig};
+
+/// Windows' MAX_PATH, which Gradle and MSBuild still hit without long path support.
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// Room left for build intermediates below the deepest generated file
+/// (e.g. build/app/intermediates/... or windows/flutter/ephemeral/...).
+const BUILD_PATH_HEADROOM: usize = 80;
+
+/// Free space in MiB on the volume holding `dir`, or None when it cannot be determined.
+fn free_space_mb(dir: &Path) -> Option<u64> {
+    if cfg!(windows) {
+        let script = format!(
+            "(Get-Item -LiteralPath '{}').PSDrive.Free",
+            dir.display().to_string().replace('\'', "''")
+        );
+        let output = Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .output()
+            .ok()?;
+        let bytes: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+        return Some(bytes / (1024 * 1024));
+    }
+    // POSIX format: header line, then "fs blocks used available capacity mount".
+    let output = Command::new("df").arg("-Pk").arg(dir).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let available_kb: u64 = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(available_kb / 1024)
+}
+
+/// Deepest path the generator writes, so the path length check covers the package
+/// directories derived from the application id.
+fn deepest_generated_path(project_dir: &Path, cfg: &Config, platforms: &[&str]) -> PathBuf {
+    let mut deepest = project_dir.join("pubspec.yaml");
+    let mut consider = |path: PathBuf| {
+        if path.as_os_str().len() > deepest.as_os_str().len() {
+            deepest = path;
+        }
+    };
+    if platforms.contains(&"android") {
+        let mut path = project_dir.join("android/app/src/main/kotlin");
+        for part in cfg.android.app.build.application_id.split('.') {
+            path.push(part);
+        }
+        consider(path.join("MainActivity.kt"));
+    }
+    if platforms.contains(&"web") {
+        consider(project_dir.join("web/icons/Icon-maskable-512.png"));
+    }
+    if platforms.contains(&"windows") {
+        consider(project_dir.join("windows/flutter/ephemeral/cpp_client_wrapper/include/flutter/flutter_view_controller.h"));
+    }
+    deepest
+}
+
+/// Directories (and on Windows read-only files) that would stop a platform dir
+/// from being removed halfway through.
+fn undeletable_entries(dir: &Path) -> Vec<PathBuf> {
+    walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir() || cfg!(windows))
+        .filter(|entry| {
+            entry
+                .metadata()
+                .map(|meta| meta.permissions().readonly())
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+fn check_writable(project_dir: &Path) -> Result<()> {
+    let probe = project_dir.join(format!(".flutter_gen_preflight_{}", std::process::id()));
+    fs::write(&probe, b"")
+        .with_context(|| format!("Project dir is not writable: {}", project_dir.display()))?;
+    fs::remove_file(&probe)
+        .with_context(|| format!("Failed to remove file: {}", probe.display()))?;
+    Ok(())
+}
+
+/// Checks free space, path lengths and permissions before any platform directory is
+/// deleted, reporting every problem at once.
+pub fn run_preflight(project_dir: &Path, cfg: &Config, platforms: &[&str], dry_run: bool) -> Result<()> {
+    let config: &PreflightConfig = &cfg.preflight;
+    if config.skip {
+        return Ok(());
+    }
+    if !project_dir.is_dir() {
+        bail!("Project dir not found: {}", project_dir.display());
+    }
+    let mut problems = Vec::new();
+
+    match free_space_mb(project_dir) {
+        Some(free) if free < config.min_free_mb => problems.push(format!(
+            "only {} MiB free on the volume of {} (preflight.min_free_mb = {})",
+            free,
+            project_dir.display(),
+            config.min_free_mb
+        )),
+        Some(_) => {}
+        None => println!("⚠ Could not determine free disk space for {}", project_dir.display()),
+    }
+
+    let absolute = std::path::absolute(project_dir)
+        .with_context(|| format!("Failed to resolve project dir: {}", project_dir.display()))?;
+    let deepest = deepest_generated_path(&absolute, cfg, platforms);
+    let length = deepest.as_os_str().len() + BUILD_PATH_HEADROOM;
+    if (cfg!(windows) || config.windows_path_limit) && length > WINDOWS_MAX_PATH {
+        problems.push(format!(
+            "generated paths reach {} characters (with {} for build output) at {}, over the Windows limit of {}; move the project to a shorter path or enable long paths",
+            length,
+            BUILD_PATH_HEADROOM,
+            deepest.display(),
+            WINDOWS_MAX_PATH
+        ));
+    }
+
+    if !dry_run && let Err(err) = check_writable(project_dir) {
+        problems.push(format!("{:#}", err));
+    }
+    for name in platforms {
+        let dir = project_dir.join(name);
+        if !dir.is_dir() {
+            continue;
+        }
+        let blocked = undeletable_entries(&dir);
+        if let Some(first) = blocked.first() {
+            problems.push(format!(
+                "{} read-only entr{} under {}/ would stop its removal, e.g. {}",
+                blocked.len(),
+                if blocked.len() == 1 { "y" } else { "ies" },
+                name,
+                first.display()
+            ));
+        }
+    }
+
+    if !problems.is_empty() {
+        bail!(
+            "Preflight checks failed, nothing was changed:\n  - {}",
+            problems.join("\n  - ")
+        );
+    }
+    println!("✓ Preflight checks passed");
+    Ok(())
+}
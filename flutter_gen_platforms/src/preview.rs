@@ -0,0 +1,133 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use similar::TextDiff;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::backup::{BACKUP_DIR, CACHE_DIRS};
+use crate::config::Config;
+use crate::lockfile::LOCK_FILE;
+
+/// Project-level entries neither copied into the preview nor compared: VCS data, Dart
+/// tooling output, build output and backups.
+const SKIPPED_ROOT_ENTRIES: [&str; 5] = [".git", ".dart_tool", "build", BACKUP_DIR, LOCK_FILE];
+
+fn is_skipped(relative: &Path) -> bool {
+    let first = relative.components().next().map(|component| component.as_os_str());
+    first.is_some_and(|first| SKIPPED_ROOT_ENTRIES.iter().any(|entry| first == *entry))
+        || relative.components().any(|component| CACHE_DIRS.iter().any(|dir| component.as_os_str() == *dir))
+}
+
+/// Regular files under `root`, relative to it, leaving out skipped entries and symlinks.
+fn tree_files(root: &Path) -> Result<BTreeSet<PathBuf>> {
+    let mut files = BTreeSet::new();
+    let walker = walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| !is_skipped(entry.path().strip_prefix(root).unwrap_or(entry.path())));
+    for entry in walker {
+        let entry = entry.with_context(|| format!("Failed to walk directory: {}", root.display()))?;
+        if entry.file_type().is_file() {
+            files.insert(entry.path().strip_prefix(root).unwrap_or(entry.path()).to_path_buf());
+        }
+    }
+    Ok(files)
+}
+
+/// Makes relative paths in the config that lead out of the project (`../shared`) absolute
+/// against the real project, so they resolve to the same files from the preview copy.
+/// Paths inside the project are left alone; the copy has them too.
+pub fn rebase_outside_paths(cfg: Config, project_dir: &Path) -> Result<Config> {
+    let project = std::path::absolute(project_dir)
+        .with_context(|| format!("Failed to resolve project dir: {}", project_dir.display()))?;
+    let mut value = serde_json::to_value(&cfg).context("Failed to serialize config")?;
+    rebase_strings(&mut value, &project);
+    serde_json::from_value(value).context("Failed to parse config with rebased paths")
+}
+
+fn rebase_strings(value: &mut Value, project: &Path) {
+    match value {
+        Value::String(text) if text.starts_with("../") || text.starts_with("..\\") => {
+            *text = project.join(text.as_str()).to_string_lossy().into_owned();
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| rebase_strings(item, project)),
+        Value::Object(map) => map.values_mut().for_each(|item| rebase_strings(item, project)),
+        _ => {}
+    }
+}
+
+/// Copies the project to `preview_dir` for a throwaway generation.
+pub fn copy_project(project_dir: &Path, preview_dir: &Path) -> Result<()> {
+    for relative in tree_files(project_dir)? {
+        let src = project_dir.join(&relative);
+        let dst = preview_dir.join(&relative);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+        }
+        fs::copy(&src, &dst).with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+    }
+    Ok(())
+}
+
+/// Text of a file for diffing, or `None` when it looks binary (NUL bytes or invalid UTF-8).
+fn text_content(bytes: &[u8]) -> Option<&str> {
+    if bytes.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(bytes).ok()
+}
+
+/// Prints a unified diff of every file that differs between the project and the
+/// generated preview, then a summary of added, modified and removed files.
+pub fn print_tree_diff(project_dir: &Path, preview_dir: &Path) -> Result<()> {
+    let before = tree_files(project_dir)?;
+    let after = tree_files(preview_dir)?;
+    let (mut added, mut modified, mut removed) = (0, 0, 0);
+
+    for relative in before.union(&after) {
+        let name = relative.to_string_lossy().replace('\\', "/");
+        let read = |root: &Path| -> Result<Option<Vec<u8>>> {
+            let path = root.join(relative);
+            if !path.exists() {
+                return Ok(None);
+            }
+            fs::read(&path)
+                .map(Some)
+                .with_context(|| format!("Failed to read file: {}", path.display()))
+        };
+        let old = read(project_dir)?;
+        let new = read(preview_dir)?;
+        if old == new {
+            continue;
+        }
+        let old_name = if old.is_some() { format!("a/{}", name) } else { "/dev/null".to_string() };
+        let new_name = if new.is_some() { format!("b/{}", name) } else { "/dev/null".to_string() };
+        match (&old, &new) {
+            (None, _) => added += 1,
+            (_, None) => removed += 1,
+            _ => modified += 1,
+        }
+
+        let old_bytes = old.unwrap_or_default();
+        let new_bytes = new.unwrap_or_default();
+        match (text_content(&old_bytes), text_content(&new_bytes)) {
+            (Some(old_text), Some(new_text)) => {
+                let diff = TextDiff::from_lines(old_text, new_text);
+                print!("{}", diff.unified_diff().context_radius(3).header(&old_name, &new_name));
+            }
+            _ => println!("Binary files {} and {} differ", old_name, new_name),
+        }
+    }
+
+    let changed = added + modified + removed;
+    if changed == 0 {
+        println!("[DRY RUN] No files would change");
+    } else {
+        println!(
+            "[DRY RUN] {} file(s) would change: {} added, {} modified, {} removed",
+            changed, added, modified, removed
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use which::which;
+
+use crate::config::Config;
+
+/// Prefix of external processor executables on PATH: `flutter-gen-platform-<name>`.
+const EXTERNAL_PREFIX: &str = "flutter-gen-platform-";
+
+/// One generation step for a platform directory or a config section.
+pub trait PlatformProcessor {
+    fn name(&self) -> &str;
+    /// Top-level config section the processor reads; it only runs when the section is set.
+    fn config_section(&self) -> Option<&str> {
+        None
+    }
+    /// `value` is the raw config section (`Value::Null` when the processor has none).
+    fn process(&self, project_dir: &Path, cfg: &Config, value: &Value) -> Result<()>;
+}
+
+/// Fails early when `flutter create` didn't produce the platform directory.
+#[cfg(any(
+    feature = "android",
+    feature = "ios",
+    feature = "linux",
+    feature = "macos",
+    feature = "web",
+    feature = "windows"
+))]
+fn require_platform_dir(project_dir: &Path, name: &str) -> Result<()> {
+    let dir = project_dir.join(name);
+    if !dir.exists() {
+        bail!("Generated {} directory not found at: {}", name, dir.display());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "android")]
+struct AndroidProcessor;
+
+#[cfg(feature = "android")]
+impl PlatformProcessor for AndroidProcessor {
+    fn name(&self) -> &str {
+        "android"
+    }
+
+    fn process(&self, project_dir: &Path, cfg: &Config, _value: &Value) -> Result<()> {
+        require_platform_dir(project_dir, "android")?;
+        crate::android::process_android_platform(project_dir, cfg)
+    }
+}
+
+#[cfg(feature = "ios")]
+struct IosProcessor;
+
+#[cfg(feature = "ios")]
+impl PlatformProcessor for IosProcessor {
+    fn name(&self) -> &str {
+        "ios"
+    }
+
+    fn process(&self, project_dir: &Path, cfg: &Config, _value: &Value) -> Result<()> {
+        require_platform_dir(project_dir, "ios")?;
+        crate::ios::process_ios_platform(project_dir, cfg)
+    }
+}
+
+#[cfg(feature = "linux")]
+struct LinuxProcessor;
+
+#[cfg(feature = "linux")]
+impl PlatformProcessor for LinuxProcessor {
+    fn name(&self) -> &str {
+        "linux"
+    }
+
+    fn process(&self, project_dir: &Path, cfg: &Config, _value: &Value) -> Result<()> {
+        require_platform_dir(project_dir, "linux")?;
+        crate::linux::process_linux_platform(project_dir, cfg)
+    }
+}
+
+#[cfg(feature = "macos")]
+struct MacosProcessor;
+
+#[cfg(feature = "macos")]
+impl PlatformProcessor for MacosProcessor {
+    fn name(&self) -> &str {
+        "macos"
+    }
+
+    fn process(&self, project_dir: &Path, cfg: &Config, _value: &Value) -> Result<()> {
+        require_platform_dir(project_dir, "macos")?;
+        crate::macos::process_macos_platform(project_dir, cfg)
+    }
+}
+
+#[cfg(feature = "web")]
+struct WebProcessor;
+
+#[cfg(feature = "web")]
+impl PlatformProcessor for WebProcessor {
+    fn name(&self) -> &str {
+        "web"
+    }
+
+    fn process(&self, project_dir: &Path, cfg: &Config, _value: &Value) -> Result<()> {
+        require_platform_dir(project_dir, "web")?;
+        crate::web::process_web_platform(project_dir, cfg)
+    }
+}
+
+#[cfg(feature = "windows")]
+struct WindowsProcessor;
+
+#[cfg(feature = "windows")]
+impl PlatformProcessor for WindowsProcessor {
+    fn name(&self) -> &str {
+        "windows"
+    }
+
+    fn process(&self, project_dir: &Path, cfg: &Config, _value: &Value) -> Result<()> {
+        require_platform_dir(project_dir, "windows")?;
+        crate::windows::process_windows_platform(project_dir, cfg)
+    }
+}
+
+/// A `flutter-gen-platform-<name>` executable, run for the `[<name>]` config section.
+///
+/// It is called as `<exe> --project-dir <dir>` from the project dir and gets
+/// `{"project_name", "version", "config"}` as JSON on stdin; a non-zero exit fails
+/// generation.
+struct ExternalProcessor {
+    name: String,
+    executable: PathBuf,
+}
+
+impl PlatformProcessor for ExternalProcessor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn config_section(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn process(&self, project_dir: &Path, cfg: &Config, value: &Value) -> Result<()> {
+        let input = json!({
+            "project_name": cfg.project_name,
+            "version": cfg.version,
+            "config": value,
+        });
+        let mut child = Command::new(&self.executable)
+            .arg("--project-dir")
+            .arg(project_dir)
+            .current_dir(project_dir)
+            .stdin(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run processor: {}", self.executable.display()))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(serde_json::to_string(&input)?.as_bytes())
+                .with_context(|| format!("Failed to write to processor: {}", self.executable.display()))?;
+        }
+        let status = child.wait().context("Failed to wait for processor")?;
+        if !status.success() {
+            bail!("Processor {} failed with status: {}", self.executable.display(), status);
+        }
+        println!("✓ {} processed by {}", self.name, self.executable.display());
+        Ok(())
+    }
+}
+
+/// Built-in processors for the generated platforms (those compiled in), followed by
+/// an external processor for each config section this crate doesn't know about.
+pub fn processors(cfg: &Config, generated: &[&str]) -> Vec<Box<dyn PlatformProcessor>> {
+    let builtins: Vec<Box<dyn PlatformProcessor>> = vec![
+        #[cfg(feature = "android")]
+        Box::new(AndroidProcessor),
+        #[cfg(feature = "ios")]
+        Box::new(IosProcessor),
+        #[cfg(feature = "linux")]
+        Box::new(LinuxProcessor),
+        #[cfg(feature = "macos")]
+        Box::new(MacosProcessor),
+        #[cfg(feature = "web")]
+        Box::new(WebProcessor),
+        #[cfg(feature = "windows")]
+        Box::new(WindowsProcessor),
+    ];
+
+    for name in generated {
+        if !builtins.iter().any(|processor| processor.name() == *name) {
+            println!("Warning: {} support is not compiled in; {}/ is left as flutter created it", name, name);
+        }
+    }
+    let mut processors: Vec<Box<dyn PlatformProcessor>> = builtins
+        .into_iter()
+        .filter(|processor| generated.contains(&processor.name()))
+        .collect();
+
+    for section in cfg.extensions.keys() {
+        match which(format!("{}{}", EXTERNAL_PREFIX, section)) {
+            Ok(executable) => processors.push(Box::new(ExternalProcessor {
+                name: section.clone(),
+                executable,
+            })),
+            Err(_) => println!(
+                "Warning: unknown config section [{}] (no {}{} on PATH)",
+                section, EXTERNAL_PREFIX, section
+            ),
+        }
+    }
+    processors
+}
+
+/// Runs each processor whose config section (if any) is set.
+pub fn run_processors(project_dir: &Path, cfg: &Config, processors: &[Box<dyn PlatformProcessor>]) -> Result<()> {
+    for processor in processors {
+        let value = match processor.config_section() {
+            Some(section) => match cfg.extensions.get(section) {
+                Some(value) => value,
+                None => continue,
+            },
+            None => &Value::Null,
+        };
+        processor
+            .process(project_dir, cfg, value)
+            .with_context(|| format!("Processor {} failed", processor.name()))?;
+    }
+    Ok(())
+}
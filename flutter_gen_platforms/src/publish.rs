@@ -0,0 +1,252 @@
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+use crate::config::{expand_env_vars, Config};
+use crate::releases::{read_releases, record_release, utc_timestamp, RELEASES_FILE};
+use crate::utils::{resolve_cmd, sha256_file};
+
+const CHECKSUMS_FILE: &str = "SHA256SUMS";
+const MANIFEST_FILE: &str = "release.json";
+
+/// Where artifacts go; remote backends shell out to the usual CLIs so their
+/// credentials and config (AWS profiles, netrc) keep working.
+enum Backend {
+    Local(PathBuf),
+    Http { url: String, headers: Vec<String> },
+    S3(String),
+}
+
+impl Backend {
+    fn parse(to: &str, headers: &BTreeMap<String, String>) -> Result<Self> {
+        let to = to.trim_end_matches('/');
+        if to.starts_with("s3://") {
+            Ok(Backend::S3(to.to_string()))
+        } else if to.starts_with("http://") || to.starts_with("https://") {
+            Ok(Backend::Http {
+                url: to.to_string(),
+                headers: headers
+                    .iter()
+                    .map(|(key, value)| Ok(format!("{}: {}", key, expand_env_vars(value)?)))
+                    .collect::<Result<_>>()?,
+            })
+        } else if let Some((scheme, _)) = to.split_once("://")
+            && scheme != "file"
+        {
+            bail!("Unsupported publish destination: {} (use s3://, http(s):// or a directory)", to);
+        } else {
+            let dir = to.strip_prefix("file://").unwrap_or(to);
+            Ok(Backend::Local(PathBuf::from(dir)))
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Backend::Local(dir) => dir.display().to_string(),
+            Backend::Http { url, .. } => url.clone(),
+            Backend::S3(url) => url.clone(),
+        }
+    }
+
+    /// Uploads `file` to `<destination>/<key>`, where key is a `/`-separated relative path.
+    fn upload(&self, file: &Path, key: &str) -> Result<()> {
+        let mut command = match self {
+            Backend::Local(dir) => {
+                let dst = dir.join(key);
+                if let Some(parent) = dst.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+                }
+                fs::copy(file, &dst).with_context(|| {
+                    format!("Failed to copy {} -> {}", file.display(), dst.display())
+                })?;
+                return Ok(());
+            }
+            Backend::Http { url, headers } => {
+                let mut command = Command::new(resolve_cmd("curl")?);
+                command.args(["--fail", "--silent", "--show-error", "--upload-file"]).arg(file);
+                if !headers.is_empty() {
+                    // Read from stdin: headers often carry tokens, and argv is visible to
+                    // every user in the process list.
+                    command.args(["--header", "@-"]).stdin(Stdio::piped());
+                }
+                command.arg(format!("{}/{}", url, key));
+                command
+            }
+            Backend::S3(url) => {
+                let mut command = Command::new(resolve_cmd("aws")?);
+                command
+                    .args(["s3", "cp", "--only-show-errors"])
+                    .arg(file)
+                    .arg(format!("{}/{}", url, key));
+                command
+            }
+        };
+        let status = self
+            .run(&mut command)
+            .with_context(|| format!("Failed to upload: {}", file.display()))?
+            .status;
+        if !status.success() {
+            bail!("Uploading {} failed with exit code: {:?}", key, status.code());
+        }
+        Ok(())
+    }
+
+    /// Downloads `<destination>/<key>` to `dst`; `false` when there is no such file yet.
+    fn download(&self, key: &str, dst: &Path) -> Result<bool> {
+        let mut command = match self {
+            Backend::Local(dir) => {
+                let src = dir.join(key);
+                if !src.is_file() {
+                    return Ok(false);
+                }
+                fs::copy(&src, dst)
+                    .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+                return Ok(true);
+            }
+            Backend::Http { url, headers } => {
+                let mut command = Command::new(resolve_cmd("curl")?);
+                command
+                    .args(["--silent", "--show-error", "--write-out", "%{http_code}", "--output"])
+                    .arg(dst);
+                if !headers.is_empty() {
+                    command.args(["--header", "@-"]).stdin(Stdio::piped());
+                }
+                command.arg(format!("{}/{}", url, key));
+                command
+            }
+            Backend::S3(url) => {
+                let mut command = Command::new(resolve_cmd("aws")?);
+                command
+                    .args(["s3", "cp", "--only-show-errors"])
+                    .arg(format!("{}/{}", url, key))
+                    .arg(dst);
+                command
+            }
+        };
+        let output = self
+            .run(command.stdout(Stdio::piped()).stderr(Stdio::piped()))
+            .with_context(|| format!("Failed to download: {}", key))?;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match self {
+            Backend::Http { .. } if output.status.success() => {
+                match String::from_utf8_lossy(&output.stdout).trim() {
+                    "200" => Ok(true),
+                    "404" => Ok(false),
+                    code => bail!("Downloading {} failed with HTTP status {}", key, code),
+                }
+            }
+            Backend::S3(_) if output.status.success() => Ok(true),
+            Backend::S3(_) if stderr.contains("404") || stderr.contains("Not Found") => Ok(false),
+            _ => bail!("Downloading {} failed: {}", key, stderr.trim()),
+        }
+    }
+
+    /// Runs a transfer command, feeding the HTTP headers on stdin when it reads them there.
+    fn run(&self, command: &mut Command) -> std::io::Result<Output> {
+        let mut child = command.spawn()?;
+        if let (Backend::Http { headers, .. }, Some(mut stdin)) = (self, child.stdin.take()) {
+            stdin.write_all((headers.join("\n") + "\n").as_bytes())?;
+        }
+        child.wait_with_output()
+    }
+}
+
+/// Uploads every file in the dist dir to `<to>/<version>/`, followed by a SHA256SUMS
+/// file and a release.json manifest; the manifest goes last so its presence marks a
+/// complete upload. The release is then recorded in the releases index, merged with the
+/// published `<to>/releases.json` so releases made elsewhere are kept, and uploaded there.
+pub fn publish_artifacts(project_dir: &Path, cfg: &Config, to: Option<&str>, dry_run: bool) -> Result<()> {
+    let Some(to) = to.or(cfg.publish.to.as_deref()) else {
+        bail!("No publish destination: pass --to or set publish.to");
+    };
+    let to = expand_env_vars(to)?;
+    let Some(version) = &cfg.version else {
+        bail!("Publishing needs a version (set version or pubspec.version)");
+    };
+    let backend = Backend::parse(&to, &cfg.publish.headers)?;
+
+    let dist_dir = project_dir.join(&cfg.build.dist_dir);
+    let mut files: Vec<PathBuf> = fs::read_dir(&dist_dir)
+        .with_context(|| format!("Failed to read dir: {}", dist_dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default();
+            name != CHECKSUMS_FILE && name != MANIFEST_FILE
+        })
+        .collect();
+    files.sort();
+    if files.is_empty() {
+        bail!("No artifacts found in: {}", dist_dir.display());
+    }
+
+    let mut checksums = String::new();
+    let mut artifacts = Vec::new();
+    for file in &files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let sha256 = sha256_file(file)?;
+        let size = fs::metadata(file)
+            .with_context(|| format!("Failed to read metadata: {}", file.display()))?
+            .len();
+        checksums.push_str(&format!("{}  {}\n", sha256, name));
+        artifacts.push(json!({ "name": name, "size": size, "sha256": sha256 }));
+    }
+    let manifest = json!({
+        "project_name": cfg.project_name,
+        "version": version,
+        "published_at": utc_timestamp(),
+        "artifacts": artifacts,
+    });
+
+    if dry_run {
+        for file in &files {
+            println!(
+                "[DRY RUN] Would upload {} to {}/{}/",
+                file.display(),
+                backend.describe(),
+                version
+            );
+        }
+        print!("{}", checksums);
+        return Ok(());
+    }
+
+    let checksums_path = dist_dir.join(CHECKSUMS_FILE);
+    fs::write(&checksums_path, &checksums)
+        .with_context(|| format!("Failed to write file: {}", checksums_path.display()))?;
+    let manifest_path = dist_dir.join(MANIFEST_FILE);
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", manifest_path.display()))?;
+
+    files.push(checksums_path);
+    files.push(manifest_path);
+    for file in &files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+        backend.upload(file, &format!("{}/{}", version, name))?;
+        println!("  uploaded {}", name);
+    }
+    println!(
+        "✓ Published {} artifact(s) to {}/{}",
+        artifacts.len(),
+        backend.describe(),
+        version
+    );
+
+    let remote_index = std::env::temp_dir().join(format!("flutter_gen_releases_{}.json", std::process::id()));
+    let remote = if backend.download(RELEASES_FILE, &remote_index)? {
+        let releases = read_releases(&remote_index);
+        let _ = fs::remove_file(&remote_index);
+        releases?
+    } else {
+        Vec::new()
+    };
+    let index = record_release(project_dir, cfg, version, &artifacts, remote)?;
+    backend.upload(&index, RELEASES_FILE)?;
+    println!("✓ Release index uploaded to {}/{}", backend.describe(), RELEASES_FILE);
+    Ok(())
+}
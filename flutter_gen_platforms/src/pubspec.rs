@@ -0,0 +1,235 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Returns the `[start, end)` line range of the top-level `flutter:` block.
+fn flutter_block(lines: &[String]) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|line| line.trim_end() == "flutter:")?;
+    let end = lines[start + 1..]
+        .iter()
+        .position(|line| !line.trim().is_empty() && indent_of(line) == 0 && !line.starts_with('#'))
+        .map(|offset| start + 1 + offset)
+        .unwrap_or(lines.len());
+    Some((start, end))
+}
+
+/// The top-level `version:` of pubspec.yaml, if any.
+pub fn read_pubspec_version(path: &Path) -> Result<Option<String>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok(content
+        .lines()
+        .find_map(|line| line.strip_prefix("version:"))
+        .map(|value| value.split('#').next().unwrap_or("").trim().trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|value| !value.is_empty()))
+}
+
+/// Adds missing entries to the `flutter: assets:` list, keeping the rest of
+/// pubspec.yaml (comments, ordering) untouched.
+pub fn add_flutter_assets(path: &Path, entries: &[String]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    let (start, end) = match flutter_block(&lines) {
+        Some(range) => range,
+        None => {
+            lines.push(String::new());
+            lines.push("flutter:".to_string());
+            (lines.len() - 1, lines.len())
+        }
+    };
+
+    let assets_line = (start + 1..end).find(|&i| lines[i].trim_end() == "  assets:");
+    let (mut insert_at, existing) = match assets_line {
+        Some(i) => {
+            let mut last = i;
+            let mut existing = Vec::new();
+            for (j, line) in lines.iter().enumerate().take(end).skip(i + 1) {
+                if let Some(entry) = line.trim().strip_prefix("- ") {
+                    existing.push(entry.trim().to_string());
+                    last = j;
+                } else if !line.trim().is_empty() && !line.trim().starts_with('#') {
+                    break;
+                }
+            }
+            (last + 1, existing)
+        }
+        None => {
+            lines.insert(start + 1, "  assets:".to_string());
+            (start + 2, Vec::new())
+        }
+    };
+
+    let mut added = 0;
+    for entry in entries {
+        if existing.iter().any(|e| e == entry) {
+            continue;
+        }
+        lines.insert(insert_at, format!("    - {}", entry));
+        insert_at += 1;
+        added += 1;
+    }
+
+    if added > 0 {
+        fs::write(path, lines.join("\n") + "\n")
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+        println!("✓ Added {} asset entries to {}", added, path.display());
+    }
+    Ok(())
+}
+
+pub struct FontAsset {
+    pub asset: String,
+    pub weight: Option<u16>,
+    pub style: Option<String>,
+}
+
+pub struct FontFamily {
+    pub family: String,
+    pub fonts: Vec<FontAsset>,
+}
+
+fn font_family_lines(family: &FontFamily) -> Vec<String> {
+    let mut lines = vec![
+        format!("    - family: {}", family.family),
+        "      fonts:".to_string(),
+    ];
+    for font in &family.fonts {
+        lines.push(format!("        - asset: {}", font.asset));
+        if let Some(weight) = font.weight {
+            lines.push(format!("          weight: {}", weight));
+        }
+        if let Some(style) = &font.style {
+            lines.push(format!("          style: {}", style));
+        }
+    }
+    lines
+}
+
+/// Rewrites the `flutter: fonts:` entries for the given families, leaving
+/// families that are not managed by the generator in place.
+pub fn set_flutter_fonts(path: &Path, families: &[FontFamily]) -> Result<()> {
+    if families.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&str> = families.iter().map(|f| f.family.as_str()).collect();
+    let entries: Vec<String> = families.iter().flat_map(font_family_lines).collect();
+    set_flutter_list(path, "fonts", "family", &names, entries)?;
+    println!("✓ Updated {} font families in {}", families.len(), path.display());
+    Ok(())
+}
+
+pub struct DeferredComponent {
+    pub name: String,
+    pub libraries: Vec<String>,
+    pub assets: Vec<String>,
+}
+
+fn deferred_component_lines(component: &DeferredComponent) -> Vec<String> {
+    let mut lines = vec![format!("    - name: {}", component.name)];
+    if !component.libraries.is_empty() {
+        lines.push("      libraries:".to_string());
+        lines.extend(component.libraries.iter().map(|library| format!("        - {}", library)));
+    }
+    if !component.assets.is_empty() {
+        lines.push("      assets:".to_string());
+        lines.extend(component.assets.iter().map(|asset| format!("        - {}", asset)));
+    }
+    lines
+}
+
+/// Rewrites the `flutter: deferred-components:` entries for the given components,
+/// leaving components that are not managed by the generator in place.
+pub fn set_deferred_components(path: &Path, components: &[DeferredComponent]) -> Result<()> {
+    if components.is_empty() {
+        return Ok(());
+    }
+    let names: Vec<&str> = components.iter().map(|c| c.name.as_str()).collect();
+    let entries: Vec<String> = components.iter().flat_map(deferred_component_lines).collect();
+    set_flutter_list(path, "deferred-components", "name", &names, entries)?;
+    println!("✓ Updated {} deferred components in {}", components.len(), path.display());
+    Ok(())
+}
+
+/// Replaces the entries of the `flutter: <key>:` list whose `<item_key>` is one of
+/// `names` with `entries`, keeping the other entries.
+fn set_flutter_list(path: &Path, key: &str, item_key: &str, names: &[&str], entries: Vec<String>) -> Result<()> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    let (start, end) = match flutter_block(&lines) {
+        Some(range) => range,
+        None => {
+            lines.push(String::new());
+            lines.push("flutter:".to_string());
+            (lines.len() - 1, lines.len())
+        }
+    };
+
+    let header = format!("  {}:", key);
+    let list_line = (start + 1..end).find(|&i| lines[i].trim_end() == header);
+    let (list_start, list_end) = match list_line {
+        Some(i) => {
+            let mut last = i;
+            for (j, line) in lines.iter().enumerate().take(end).skip(i + 1) {
+                if line.trim().is_empty() || line.trim().starts_with('#') {
+                    continue;
+                }
+                if indent_of(line) <= 2 {
+                    break;
+                }
+                last = j;
+            }
+            (i, last + 1)
+        }
+        None => {
+            // Place the list after the last non-blank line of the flutter block.
+            let insert_at = (start..end)
+                .rev()
+                .find(|&i| !lines[i].trim().is_empty())
+                .map(|i| i + 1)
+                .unwrap_or(end);
+            lines.insert(insert_at, header.clone());
+            (insert_at, insert_at + 1)
+        }
+    };
+
+    // Split the existing list into per-entry chunks, dropping the managed ones.
+    let item_prefix = format!("- {}:", item_key);
+    let mut kept: Vec<Vec<String>> = Vec::new();
+    for line in &lines[list_start + 1..list_end] {
+        if line.trim_start().starts_with(&item_prefix) || kept.is_empty() {
+            kept.push(Vec::new());
+        }
+        if let Some(chunk) = kept.last_mut() {
+            chunk.push(line.clone());
+        }
+    }
+    kept.retain(|chunk| {
+        let name = chunk
+            .first()
+            .and_then(|line| line.trim().strip_prefix(item_prefix.as_str()))
+            .map(|name| name.trim().to_string());
+        match name {
+            Some(name) => !names.contains(&name.as_str()),
+            None => chunk.iter().any(|line| !line.trim().is_empty()),
+        }
+    });
+
+    let mut block: Vec<String> = kept.into_iter().flatten().collect();
+    block.extend(entries);
+    lines.splice(list_start + 1..list_end, block);
+
+    fs::write(path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+
+/// Name of the releases index in the artifact store.
+pub const RELEASES_FILE: &str = "releases.json";
+
+/// Changelog excerpts longer than this are cut off.
+const MAX_EXCERPT_CHARS: usize = 1000;
+
+/// Current UTC time as RFC 3339 (`2024-01-31T12:00:00Z`).
+pub fn utc_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    format_utc(secs)
+}
+
+/// `secs` since the Unix epoch as RFC 3339 in UTC.
+fn format_utc(secs: u64) -> String {
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+fn git_sha(project_dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(project_dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Body of the `## <version>` section (also `## [1.2.3]`, `## v1.2.3 - 2024-01-01`);
+/// a `+build` suffix on the version is optional in the heading.
+fn changelog_excerpt(changelog: &str, version: &str) -> Option<String> {
+    let core = version.split('+').next().unwrap_or(version);
+    let mut section = Vec::new();
+    let mut in_section = false;
+    let mut found = false;
+    for line in changelog.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if found {
+                break;
+            }
+            let name = heading
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim_start_matches('v');
+            in_section = name == version || name == core;
+            found = in_section;
+            continue;
+        }
+        if in_section {
+            section.push(line);
+        }
+    }
+    if !found {
+        return None;
+    }
+    let text = section.join("\n").trim().to_string();
+    if text.chars().count() > MAX_EXCERPT_CHARS {
+        let cut: String = text.chars().take(MAX_EXCERPT_CHARS).collect();
+        return Some(format!("{}…", cut.trim_end()));
+    }
+    Some(text)
+}
+
+fn releases_path(project_dir: &Path, cfg: &Config) -> PathBuf {
+    project_dir.join(&cfg.publish.releases_file)
+}
+
+pub fn read_releases(path: &Path) -> Result<Vec<Value>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let index: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse JSON: {}", path.display()))?;
+    match index.get("releases").and_then(Value::as_array) {
+        Some(releases) => Ok(releases.clone()),
+        None => bail!("Expected a \"releases\" array in: {}", path.display()),
+    }
+}
+
+fn release_version(release: &Value) -> Option<&str> {
+    release.get("version").and_then(Value::as_str)
+}
+
+/// The remote index with the local releases it lacks, oldest first: whoever published
+/// from another machine since this checkout last did is not dropped from the index.
+fn merge_releases(remote: Vec<Value>, local: Vec<Value>) -> Vec<Value> {
+    let mut merged = remote;
+    for release in local {
+        if !merged.iter().any(|known| release_version(known) == release_version(&release)) {
+            merged.push(release);
+        }
+    }
+    // RFC 3339 UTC dates sort chronologically as text; the sort is stable for ties.
+    merged.sort_by(|a, b| {
+        let date = |release: &Value| release.get("date").and_then(Value::as_str).unwrap_or("").to_string();
+        date(a).cmp(&date(b))
+    });
+    merged
+}
+
+/// Appends this release to the releases index (replacing an entry with the same
+/// version, so a re-publish does not duplicate it) and returns the index path.
+/// `remote` holds the releases of the index already published, merged in first.
+pub fn record_release(
+    project_dir: &Path,
+    cfg: &Config,
+    version: &str,
+    artifacts: &[Value],
+    remote: Vec<Value>,
+) -> Result<PathBuf> {
+    let changelog_path = project_dir.join(&cfg.publish.changelog);
+    let changelog = if changelog_path.exists() {
+        let content = fs::read_to_string(&changelog_path)
+            .with_context(|| format!("Failed to read file: {}", changelog_path.display()))?;
+        changelog_excerpt(&content, version)
+    } else {
+        None
+    };
+
+    let path = releases_path(project_dir, cfg);
+    let mut releases = merge_releases(remote, read_releases(&path)?);
+    releases.retain(|release| release_version(release) != Some(version));
+    releases.push(json!({
+        "version": version,
+        "date": utc_timestamp(),
+        "git_sha": git_sha(project_dir),
+        "artifacts": artifacts,
+        "changelog": changelog,
+    }));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    let index = json!({ "project_name": cfg.project_name, "releases": releases });
+    fs::write(&path, serde_json::to_string_pretty(&index)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Release {} recorded in {}", version, path.display());
+    Ok(path)
+}
+
+/// Prints the newest `limit` releases from the releases index.
+pub fn list_releases(project_dir: &Path, cfg: &Config, limit: Option<usize>) -> Result<()> {
+    let path = releases_path(project_dir, cfg);
+    if !path.exists() {
+        bail!("No releases index found at: {}", path.display());
+    }
+    let releases = read_releases(&path)?;
+    let shown: Vec<&Value> = releases
+        .iter()
+        .rev()
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    let text = |release: &Value, key: &str| {
+        release.get(key).and_then(Value::as_str).unwrap_or("-").to_string()
+    };
+    let width = shown
+        .iter()
+        .map(|release| text(release, "version").len())
+        .max()
+        .unwrap_or(0)
+        .max("Version".len());
+    println!("{:<width$}  {:<20}  {:<8}  Artifacts", "Version", "Date", "Commit");
+    for release in shown {
+        let sha = text(release, "git_sha");
+        let artifacts = release
+            .get("artifacts")
+            .and_then(Value::as_array)
+            .map(|artifacts| artifacts.len())
+            .unwrap_or(0);
+        println!(
+            "{:<width$}  {:<20}  {:<8}  {}",
+            text(release, "version"),
+            text(release, "date"),
+            &sha[..sha.len().min(8)],
+            artifacts
+        );
+    }
+    println!("{} release(s) in {}", releases.len(), path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_utc_handles_epoch_leap_days_and_century_rules() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_utc(1_709_251_199), "2024-02-29T23:59:59Z");
+        assert_eq!(format_utc(1_735_689_599), "2024-12-31T23:59:59Z");
+        assert_eq!(format_utc(4_107_542_400), "2100-03-01T00:00:00Z");
+    }
+
+    #[test]
+    fn merge_releases_keeps_remote_entries_missing_locally() {
+        let release = |version: &str, date: &str| json!({ "version": version, "date": date });
+        let remote = vec![release("1.0.0", "2024-01-01T00:00:00Z"), release("1.1.0", "2024-03-01T00:00:00Z")];
+        let local = vec![release("1.0.0", "2024-01-01T00:00:00Z"), release("1.0.1", "2024-02-01T00:00:00Z")];
+        let versions: Vec<_> = merge_releases(remote, local)
+            .iter()
+            .map(|release| release_version(release).unwrap().to_string())
+            .collect();
+        assert_eq!(versions, ["1.0.0", "1.0.1", "1.1.0"]);
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+use crate::builder::cmd_run_launcher;
+use crate::config::BuildConfig;
+use crate::releases::utc_timestamp;
+
+/// Control file of the cmd_run daemon, so `cmd_run send reload` works from the project dir.
+const DAEMON_CONTROL_FILE: &str = ".cmd-run-daemon.json";
+
+/// Launches `flutter run` for daily development.
+///
+/// With cmd_run available the session runs as a cmd_run daemon in machine mode: output
+/// is logged to `<build.log_dir>/run-<timestamp>.log`, r/R/q still work from the terminal
+/// and editors can drive it with `cmd_run send`. Without cmd_run, plain `flutter run`.
+pub fn run_app(
+    project_dir: &Path,
+    flutter_cmd: &Path,
+    config: &BuildConfig,
+    device: Option<&str>,
+    flavor: Option<&str>,
+    extra_args: &[String],
+    dry_run: bool,
+) -> Result<()> {
+    let mut flutter_args = vec!["run".to_string()];
+    if let Some(device) = device {
+        flutter_args.extend(["-d".to_string(), device.to_string()]);
+    }
+    if let Some(flavor) = flavor {
+        flutter_args.extend(["--flavor".to_string(), flavor.to_string()]);
+    }
+    flutter_args.extend(extra_args.iter().cloned());
+
+    let mut command = match cmd_run_launcher(config) {
+        Some(launcher) => {
+            let stamp: String = utc_timestamp().chars().filter(char::is_ascii_digit).collect();
+            let log = project_dir
+                .join(&config.log_dir)
+                .join(format!("run-{}-{}.log", &stamp[..8], &stamp[8..]));
+            let mut command = Command::new(&launcher[0]);
+            command
+                .args(&launcher[1..])
+                .arg("daemon")
+                .arg(format!("--control={}", project_dir.join(DAEMON_CONTROL_FILE).display()))
+                .arg(format!("--log={}", log.display()))
+                .arg(format!("--cwd={}", project_dir.display()))
+                .arg("--flutter-run-machine")
+                .arg(flutter_cmd)
+                .arg(&flutter_args[0])
+                .arg("--machine")
+                .args(&flutter_args[1..]);
+            command
+        }
+        None => {
+            println!("cmd_run not found; running flutter without logging or daemon control");
+            let mut command = Command::new(flutter_cmd);
+            command.args(&flutter_args).current_dir(project_dir);
+            command
+        }
+    };
+
+    let display = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if dry_run {
+        println!("[DRY RUN] Would run: {}", display);
+        return Ok(());
+    }
+    println!("Running: {}\n", display);
+
+    // Hand the terminal over to cmd_run/flutter so Ctrl-C reaches only the session
+    // (cmd_run turns it into a graceful quit) instead of killing this process first.
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let error = command.exec();
+        Err(error).with_context(|| format!("Failed to run: {}", display))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command
+            .status()
+            .with_context(|| format!("Failed to run: {}", display))?;
+        if !status.success() {
+            anyhow::bail!("flutter run failed ({})", status);
+        }
+        Ok(())
+    }
+}
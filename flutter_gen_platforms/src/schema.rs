@@ -0,0 +1,206 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::config::Config;
+
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+pub enum SchemaFormat {
+    /// JSON Schema, for editors validating app.toml/app.yaml/app.json
+    Json,
+    /// A pkl module to amend from app.pkl
+    Pkl,
+}
+
+/// Pkl keywords, which need backticks as property names.
+const PKL_KEYWORDS: [&str; 33] = [
+    "abstract", "amends", "as", "case", "class", "const", "delete", "else", "extends", "external", "false",
+    "fixed", "for", "function", "hidden", "if", "import", "in", "is", "let", "local", "module", "new",
+    "nothing", "null", "open", "out", "outer", "read", "super", "this", "throw", "true",
+];
+
+/// JSON Schema of the config, derived from the serde types (doc comments become descriptions).
+pub fn config_schema() -> Value {
+    schemars::schema_for!(Config).to_value()
+}
+
+fn pkl_name(name: &str) -> String {
+    if PKL_KEYWORDS.contains(&name) || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        format!("`{}`", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// A union of string literals, e.g. `"apk"|"appbundle"`, when every variant is a string constant.
+fn literal_union(variants: &[Value]) -> Option<String> {
+    let mut literals = Vec::new();
+    for variant in variants {
+        match (variant.get("const"), variant.get("enum")) {
+            (Some(Value::String(value)), _) => literals.push(serde_json::to_string(value).ok()?),
+            (_, Some(Value::Array(values))) => {
+                for value in values {
+                    literals.push(serde_json::to_string(value.as_str()?).ok()?);
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(literals.join("|"))
+}
+
+/// Pkl type of a schema and whether null is allowed.
+fn pkl_type(schema: &Value) -> (String, bool) {
+    let Some(object) = schema.as_object().filter(|object| !object.is_empty()) else {
+        return ("Any".to_string(), false);
+    };
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        return (reference.rsplit('/').next().unwrap_or(reference).to_string(), false);
+    }
+    if let Some(Value::Array(variants)) = object.get("anyOf").or_else(|| object.get("oneOf")) {
+        let is_null = |variant: &Value| variant.get("type").and_then(Value::as_str) == Some("null");
+        let nullable = variants.iter().any(is_null);
+        let rest: Vec<Value> = variants.iter().filter(|variant| !is_null(variant)).cloned().collect();
+        if let Some(union) = literal_union(&rest) {
+            return (union, nullable);
+        }
+        let types: Vec<String> = rest.iter().map(|variant| pkl_type(variant).0).collect();
+        return (types.join("|"), nullable);
+    }
+    if object.contains_key("enum")
+        && let Some(union) = literal_union(std::slice::from_ref(schema))
+    {
+        return (union, false);
+    }
+    let (kind, nullable) = match object.get("type") {
+        Some(Value::String(kind)) => (kind.as_str(), false),
+        Some(Value::Array(kinds)) => {
+            let nullable = kinds.iter().any(|kind| kind == "null");
+            let kind = kinds.iter().filter_map(Value::as_str).find(|kind| *kind != "null").unwrap_or("null");
+            (kind, nullable)
+        }
+        _ => return ("Any".to_string(), false),
+    };
+    let ty = match kind {
+        "string" => "String".to_string(),
+        "integer" if object.get("minimum").and_then(Value::as_f64) == Some(0.0) => "UInt".to_string(),
+        "integer" => "Int".to_string(),
+        "number" => "Float".to_string(),
+        "boolean" => "Boolean".to_string(),
+        "array" => format!("Listing<{}>", nullable_type(object.get("items").unwrap_or(&Value::Bool(true)))),
+        "object" => match object.get("additionalProperties") {
+            Some(values @ Value::Object(_)) => format!("Mapping<String, {}>", nullable_type(values)),
+            _ => "Mapping<String, Any>".to_string(),
+        },
+        _ => "Any".to_string(),
+    };
+    (ty, nullable)
+}
+
+/// The type with `?` appended when null is allowed.
+fn nullable_type(schema: &Value) -> String {
+    match pkl_type(schema) {
+        (ty, true) if ty.contains('|') => format!("({})?", ty),
+        (ty, true) => format!("{}?", ty),
+        (ty, false) => ty,
+    }
+}
+
+/// Pkl literal of a schema default; None for nulls, empty lists and objects (the type's
+/// own default already covers those).
+fn pkl_literal(value: &Value) -> Option<String> {
+    match value {
+        Value::Null | Value::Object(_) => None,
+        Value::Array(items) if items.is_empty() => None,
+        Value::Array(items) => {
+            let items: Option<Vec<String>> = items.iter().map(pkl_literal).collect();
+            Some(format!("new Listing {{ {} }}", items?.join(" ")))
+        }
+        Value::String(text) => serde_json::to_string(text).ok(),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+    }
+}
+
+fn push_doc(lines: &mut Vec<String>, indent: &str, schema: &Value) {
+    if let Some(description) = schema.get("description").and_then(Value::as_str) {
+        for line in description.lines() {
+            lines.push(format!("{}/// {}", indent, line).trim_end().to_string());
+        }
+    }
+}
+
+/// Properties of an object schema. Optional ones are nullable; the loader drops nulls
+/// from pkl's output, so unset properties fall back to the tool's defaults.
+fn push_properties(lines: &mut Vec<String>, indent: &str, schema: &Value) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return;
+    };
+    for (name, property) in properties {
+        push_doc(lines, indent, property);
+        let (ty, nullable) = pkl_type(property);
+        let default = property.get("default").and_then(pkl_literal);
+        // Classes, listings and mappings default to an empty instance.
+        let implicit_default = property.get("$ref").is_some() || ty.starts_with("Listing<") || ty.starts_with("Mapping<");
+        let line = match default {
+            Some(default) => format!("{}{}: {} = {}", indent, pkl_name(name), nullable_type(property), default),
+            None if nullable || implicit_default || required.contains(&name.as_str()) => {
+                format!("{}{}: {}", indent, pkl_name(name), nullable_type(property))
+            }
+            // Optional scalars without a known default, e.g. literal unions.
+            None if ty.contains('|') => format!("{}{}: ({})?", indent, pkl_name(name), ty),
+            None => format!("{}{}: {}?", indent, pkl_name(name), ty),
+        };
+        lines.push(line);
+    }
+}
+
+/// A pkl module mirroring the schema, for `amends "flutter_gen_platforms.pkl"` in app.pkl.
+pub fn pkl_module(schema: &Value) -> String {
+    let mut lines = vec![
+        "/// Config schema of flutter_gen_platforms, written by `flutter_gen_platforms schema --format pkl`.".to_string(),
+        "/// Start app.pkl with `amends \"flutter_gen_platforms.pkl\"` for type checking and completion;".to_string(),
+        "/// sections for custom `flutter-gen-platform-<name>` processors are not declared here.".to_string(),
+        "module flutter_gen_platforms".to_string(),
+        String::new(),
+    ];
+    push_properties(&mut lines, "", schema);
+    if let Some(definitions) = schema.get("$defs").and_then(Value::as_object) {
+        for (name, definition) in definitions {
+            lines.push(String::new());
+            push_doc(&mut lines, "", definition);
+            if definition.get("properties").is_some() {
+                lines.push(format!("class {} {{", name));
+                push_properties(&mut lines, "  ", definition);
+                lines.push("}".to_string());
+            } else {
+                lines.push(format!("typealias {} = {}", name, pkl_type(definition).0));
+            }
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Prints the JSON Schema or pkl module, or writes it to `output`.
+pub fn write_schema(format: SchemaFormat, output: Option<&Path>) -> Result<()> {
+    let schema = config_schema();
+    let content = match format {
+        SchemaFormat::Json => serde_json::to_string_pretty(&schema)? + "\n",
+        SchemaFormat::Pkl => pkl_module(&schema),
+    };
+    match output {
+        Some(path) => {
+            fs::write(path, content).with_context(|| format!("Failed to write file: {}", path.display()))?;
+            println!("✓ Config schema written: {}", path.display());
+        }
+        None => io::stdout().write_all(content.as_bytes())?,
+    }
+    Ok(())
+}
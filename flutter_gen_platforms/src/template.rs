@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::utils::{expand_variables, template_variables};
+
+/// Overlay files with this suffix are rendered with Tera and written without it.
+pub const TEMPLATE_SUFFIX: &str = ".tera";
+
+/// Renders platform overlay files on their way into the generated directories.
+///
+/// `*.tera` files get the full Tera language (`{% if %}`, `{% for %}`, filters), with the
+/// `template_variables` at the top level and the whole resolved config as `config`, e.g.
+/// `{% for host in config.android.deep_links.hosts %}`. Other text files only get the
+/// `{{name}}` variables, so Flutter's own placeholders like `{{flutter_js}}` survive.
+pub struct OverlayRenderer {
+    variables: Vec<(&'static str, String)>,
+    context: tera::Context,
+}
+
+impl OverlayRenderer {
+    pub fn new(cfg: &Config) -> Result<Self> {
+        let variables = template_variables(cfg);
+        let mut context = tera::Context::new();
+        for (name, value) in &variables {
+            context.insert(*name, value);
+        }
+        context.insert("config", &serde_json::to_value(cfg).context("Failed to serialize config")?);
+        Ok(Self { variables, context })
+    }
+
+    /// Where an overlay file lands, relative to the platform directory: `.tera` stripped.
+    pub fn output_path(relative: &Path) -> PathBuf {
+        match relative.file_name().and_then(|name| name.to_str()) {
+            Some(name) if name.len() > TEMPLATE_SUFFIX.len() && name.ends_with(TEMPLATE_SUFFIX) => {
+                relative.with_file_name(&name[..name.len() - TEMPLATE_SUFFIX.len()])
+            }
+            _ => relative.to_path_buf(),
+        }
+    }
+
+    /// Writes `src` rendered to `dst`, creating parent directories. Binary files are
+    /// copied unchanged.
+    pub fn copy_file(&self, src: &Path, dst: &Path) -> Result<()> {
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+        }
+        let bytes = fs::read(src).with_context(|| format!("Failed to read file: {}", src.display()))?;
+        let is_template = src.to_string_lossy().ends_with(TEMPLATE_SUFFIX);
+        let bytes = match String::from_utf8(bytes) {
+            Ok(text) if is_template => tera::Tera::one_off(&text, &self.context, false)
+                .with_context(|| format!("Failed to render template: {}", src.display()))?
+                .into_bytes(),
+            Ok(text) => expand_variables(text, &self.variables).into_bytes(),
+            Err(err) => err.into_bytes(),
+        };
+        fs::write(dst, bytes).with_context(|| format!("Failed to write file: {}", dst.display()))?;
+        Ok(())
+    }
+
+    /// `copy_file` for every file under `src`, mirrored under `dst`.
+    pub fn copy_dir(&self, src: &Path, dst: &Path) -> Result<Vec<PathBuf>> {
+        self.copy_dir_except(src, dst, |_| false)
+    }
+
+    /// `copy_dir` leaving out the files `skip` picks by their path relative to `src`, and
+    /// Finder's .DS_Store. Returns the written paths relative to `dst`.
+    pub fn copy_dir_except(&self, src: &Path, dst: &Path, skip: impl Fn(&Path) -> bool) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for entry in walkdir::WalkDir::new(src).sort_by_file_name() {
+            let entry = entry.with_context(|| format!("Failed to walk directory: {}", src.display()))?;
+            let relative = entry.path().strip_prefix(src).unwrap_or(entry.path());
+            if !entry.file_type().is_file() || entry.file_name() == ".DS_Store" || skip(relative) {
+                continue;
+            }
+            let output = Self::output_path(relative);
+            self.copy_file(entry.path(), &dst.join(&output))?;
+            written.push(output);
+        }
+        Ok(written)
+    }
+}
@@ -1,10 +1,14 @@
 use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::Path;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use sha2::{Digest, Sha256};
 use std::process::Command;
 use which::which;
 
-use crate::config::FlutterCreateConfig;
+use crate::backup::CACHE_DIRS;
+use crate::config::{Config, FlutterCreateConfig, GitignoreConfig, LineEnding, LineEndingsConfig};
 
 pub fn resolve_cmd(command: &str) -> Result<std::path::PathBuf> {
     if command.contains(['/', '\\']) {
@@ -17,23 +21,147 @@ pub fn resolve_cmd(command: &str) -> Result<std::path::PathBuf> {
     which(command).with_context(|| format!("command not found in PATH: {command}"))
 }
 
-pub fn run_flutter_create(
-    path: &Path,
+/// Fills the generation-time `{token}`s of a file name pattern from `values`, keeps the
+/// `late` ones (resolved by Gradle or the build command) and rejects anything else, so
+/// a typo never ends up literally in a file name. `${...}` Kotlin templates are left alone.
+/// Streams a file through SHA-256 and returns the lowercase hex digest.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file =
+        fs::File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+pub fn fill_name_pattern(
+    field: &str,
+    pattern: &str,
+    values: &BTreeMap<&str, Option<String>>,
+    late: &[&str],
+) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let token = &rest[start + 1..start + len];
+        out.push_str(&rest[..start]);
+        let literal = &rest[start..=start + len];
+        if rest[..start].ends_with('$') || late.contains(&token) {
+            out.push_str(literal);
+        } else {
+            match values.get(token) {
+                Some(Some(value)) => out.push_str(value),
+                Some(None) => bail!(
+                    "{} uses {} but it is not available (no version, build number or git checkout?): {}",
+                    field,
+                    literal,
+                    pattern
+                ),
+                None => {
+                    let known: Vec<String> = values
+                        .keys()
+                        .copied()
+                        .chain(late.iter().copied())
+                        .map(|token| format!("{{{}}}", token))
+                        .collect();
+                    bail!("Unknown token {} in {} (known: {})", literal, field, known.join(", "));
+                }
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Platform directories `flutter create` can generate.
+const PLATFORM_DIRS: [&str; 6] = ["android", "ios", "linux", "macos", "web", "windows"];
+
+/// App sources that regenerating platforms must never touch.
+const PROTECTED_PATHS: [&str; 4] = ["lib", "test", "pubspec.yaml", "pubspec.lock"];
+
+pub fn copy_dir_recursive(src: &Path, dst: &Path) -> Result<()> {
+    fs::create_dir_all(dst)
+        .with_context(|| format!("Failed to create dir: {}", dst.display()))?;
+    for entry in fs::read_dir(src)
+        .with_context(|| format!("Failed to read dir: {}", src.display()))?
+    {
+        let entry = entry?;
+        let src_path = entry.path();
+        let dst_path = dst.join(entry.file_name());
+        if src_path.is_dir() {
+            copy_dir_recursive(&src_path, &dst_path)?;
+        } else {
+            fs::copy(&src_path, &dst_path)
+                .with_context(|| format!("Failed to copy {} -> {}", src_path.display(), dst_path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copies the files under `src` that do not exist yet under `dst`, like `flutter create`
+/// does when run in an existing project.
+fn copy_missing_files(src: &Path, dst: &Path) -> Result<()> {
+    for entry in walkdir::WalkDir::new(src) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(src).unwrap_or(entry.path());
+        let target = dst.join(relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)
+                .with_context(|| format!("Failed to create dir: {}", target.display()))?;
+        } else if !target.exists() {
+            fs::copy(entry.path(), &target).with_context(|| {
+                format!("Failed to copy {} -> {}", entry.path().display(), target.display())
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// Content hash of every file under the protected paths, keyed by relative path.
+fn fingerprint_sources(project_dir: &Path) -> Result<BTreeMap<PathBuf, u64>> {
+    let mut files = BTreeMap::new();
+    for protected in PROTECTED_PATHS {
+        let root = project_dir.join(protected);
+        if !root.exists() {
+            continue;
+        }
+        for entry in walkdir::WalkDir::new(&root) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let content = fs::read(entry.path())
+                .with_context(|| format!("Failed to read file: {}", entry.path().display()))?;
+            let mut hasher = DefaultHasher::new();
+            content.hash(&mut hasher);
+            let relative = entry.path().strip_prefix(project_dir).unwrap_or(entry.path());
+            files.insert(relative.to_path_buf(), hasher.finish());
+        }
+    }
+    Ok(files)
+}
+
+fn flutter_create_into(
+    target: &Path,
     flutter_cmd: &Path,
     project_name: &str,
     org: Option<&str>,
     description: Option<&str>,
     create: &FlutterCreateConfig,
+    no_pub: bool,
 ) -> Result<()> {
     let mut command = Command::new(flutter_cmd);
     command
         .arg("create")
         .arg("--project-name")
         .arg(project_name);
-    if let Some(platforms) = create.platforms.as_ref() {
-        if !platforms.is_empty() {
-            command.arg("--platforms").arg(platforms.join(","));
-        }
+    if let Some(platforms) = create.platforms.as_ref()
+        && !platforms.is_empty()
+    {
+        command.arg("--platforms").arg(platforms.join(","));
     }
     if let Some(value) = create.android_language.as_deref() {
         command.arg("--android-language").arg(value);
@@ -44,8 +172,11 @@ pub fn run_flutter_create(
     if let Some(value) = description {
         command.arg("--description").arg(value);
     }
+    if no_pub {
+        command.arg("--no-pub");
+    }
     let status = command
-        .arg(path)
+        .arg(target)
         .status()
         .context("Failed to run flutter create")?;
     if !status.success() {
@@ -54,6 +185,57 @@ pub fn run_flutter_create(
     Ok(())
 }
 
+/// Runs `flutter create`. In an existing app it runs in a temp dir and only missing
+/// files of the platform directories are copied back, so lib/, test/ and pubspec are
+/// never touched; their contents are compared before and after to prove it.
+pub fn run_flutter_create(
+    path: &Path,
+    flutter_cmd: &Path,
+    project_name: &str,
+    org: Option<&str>,
+    description: Option<&str>,
+    create: &FlutterCreateConfig,
+) -> Result<()> {
+    if !path.join("pubspec.yaml").exists() {
+        return flutter_create_into(path, flutter_cmd, project_name, org, description, create, false);
+    }
+
+    let before = fingerprint_sources(path)?;
+    let work_dir = std::env::temp_dir().join(format!("flutter_gen_create_{}", std::process::id()));
+    if work_dir.exists() {
+        fs::remove_dir_all(&work_dir)
+            .with_context(|| format!("Failed to remove dir: {}", work_dir.display()))?;
+    }
+    let result = flutter_create_into(&work_dir, flutter_cmd, project_name, org, description, create, true)
+        .and_then(|()| {
+            for name in PLATFORM_DIRS {
+                let src = work_dir.join(name);
+                if src.is_dir() {
+                    copy_missing_files(&src, &path.join(name))?;
+                }
+            }
+            Ok(())
+        });
+    let _ = fs::remove_dir_all(&work_dir);
+    result?;
+
+    let after = fingerprint_sources(path)?;
+    if before != after {
+        let changed: Vec<String> = before
+            .keys()
+            .chain(after.keys())
+            .filter(|file| before.get(*file) != after.get(*file))
+            .map(|file| file.display().to_string())
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        bail!("flutter create modified app sources: {}", changed.join(", "));
+    }
+    println!("✓ flutter create left lib/, test/ and pubspec untouched");
+    Ok(())
+}
+
+#[allow(dead_code)]
 pub fn run_flutter_pub_get(path: &Path, flutter_cmd: &Path) -> Result<()> {
     let status = Command::new(flutter_cmd)
         .arg("pub")
@@ -67,6 +249,7 @@ pub fn run_flutter_pub_get(path: &Path, flutter_cmd: &Path) -> Result<()> {
     Ok(())
 }
 
+#[allow(dead_code)]
 pub fn run_flutter_pub_run(path: &Path, flutter_cmd: &Path, args: &[&str]) -> Result<()> {
     let mut command = Command::new(flutter_cmd);
     command.arg("pub").arg("run").current_dir(path);
@@ -82,6 +265,7 @@ pub fn run_flutter_pub_run(path: &Path, flutter_cmd: &Path, args: &[&str]) -> Re
     Ok(())
 }
 
+#[allow(dead_code)]
 pub fn run_gen_logo(path: &Path, script_path: &str) -> Result<()> {
     let python_cmd = which("python")
         .or_else(|_| which("python3"))
@@ -102,6 +286,7 @@ pub fn run_gen_logo(path: &Path, script_path: &str) -> Result<()> {
     Ok(())
 }
 
+#[allow(dead_code)]
 pub fn run_flutter_clean(path: &Path, flutter_cmd: &Path) -> Result<()> {
     let status = Command::new(flutter_cmd)
         .arg("clean")
@@ -114,6 +299,124 @@ pub fn run_flutter_clean(path: &Path, flutter_cmd: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Runs the `wrapper` task in `work_dir` with `gradle_cmd` (a `gradle` or `gradlew`).
+pub fn run_gradle_wrapper(
+    gradle_cmd: &Path,
+    work_dir: &Path,
+    version: &str,
+    distribution_type: &str,
+    sha256_sum: Option<&str>,
+) -> Result<()> {
+    let mut command = Command::new(gradle_cmd);
+    command
+        .arg("--project-dir")
+        .arg(work_dir)
+        .arg("wrapper")
+        .arg("--gradle-version")
+        .arg(version)
+        .arg("--distribution-type")
+        .arg(distribution_type);
+    if let Some(sum) = sha256_sum {
+        command.arg("--gradle-distribution-sha256-sum").arg(sum);
+    }
+    let status = command
+        .current_dir(work_dir)
+        .status()
+        .context("Failed to run gradle wrapper")?;
+    if !status.success() {
+        bail!("gradle wrapper failed with status: {status}");
+    }
+    Ok(())
+}
+
+/// Matches a `/`-separated relative path against a glob: `*` and `?` stay within one
+/// path segment, `**` matches any number of segments.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    fn matches(pattern: &[u8], path: &[u8]) -> bool {
+        match pattern.first() {
+            None => path.is_empty(),
+            Some(b'*') if pattern.get(1) == Some(&b'*') => {
+                let rest = pattern[2..].strip_prefix(b"/").unwrap_or(&pattern[2..]);
+                if rest.is_empty() {
+                    return true;
+                }
+                (0..=path.len()).any(|i| (i == 0 || path[i - 1] == b'/') && matches(rest, &path[i..]))
+                    || matches(rest, path)
+            }
+            Some(b'*') => (0..=path.len())
+                .take_while(|&i| i == 0 || path[i - 1] != b'/')
+                .any(|i| matches(&pattern[1..], &path[i..])),
+            Some(b'?') => path.first().is_some_and(|&c| c != b'/') && matches(&pattern[1..], &path[1..]),
+            Some(&c) => path.first() == Some(&c) && matches(&pattern[1..], &path[1..]),
+        }
+    }
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+/// Removes a generated platform directory (e.g. "android") under the project dir.
+///
+/// Refuses symlinked directories and anything that resolves outside the project, and keeps
+/// files matching `preserve` (globs relative to the project dir) in place.
+pub fn remove_platform_dir(project_dir: &Path, name: &str, preserve: &[String]) -> Result<()> {
+    let dir = project_dir.join(name);
+    let Ok(meta) = fs::symlink_metadata(&dir) else {
+        return Ok(());
+    };
+    if meta.file_type().is_symlink() {
+        bail!("Refusing to remove symlinked platform directory: {}", dir.display());
+    }
+    let project = fs::canonicalize(project_dir)
+        .with_context(|| format!("Failed to resolve project dir: {}", project_dir.display()))?;
+    let target = fs::canonicalize(&dir)
+        .with_context(|| format!("Failed to resolve directory: {}", dir.display()))?;
+    if target == project || !target.starts_with(&project) {
+        bail!(
+            "Refusing to remove {}: resolves outside the project dir {}",
+            dir.display(),
+            project.display()
+        );
+    }
+
+    let relative = |path: &Path| {
+        path.strip_prefix(&target)
+            .map(|rel| format!("{}/{}", name, rel.to_string_lossy().replace('\\', "/")))
+            .unwrap_or_default()
+    };
+    let is_preserved = |path: &Path| {
+        let rel = relative(path);
+        preserve.iter().any(|pattern| glob_match(pattern, &rel))
+    };
+    let preserved: Vec<_> = walkdir::WalkDir::new(&target)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| !entry.file_type().is_dir() && is_preserved(entry.path()))
+        .map(|entry| relative(entry.path()))
+        .collect();
+
+    if preserved.is_empty() {
+        return remove_dir_all_with_retry(&target);
+    }
+
+    // Delete everything else bottom-up; directories still holding preserved files stay.
+    for entry in walkdir::WalkDir::new(&target).contents_first(true).min_depth(1) {
+        let entry = entry.with_context(|| format!("Failed to walk directory: {}", dir.display()))?;
+        let path = entry.path();
+        if entry.file_type().is_dir() {
+            if fs::read_dir(path).map(|mut it| it.next().is_none()).unwrap_or(false) {
+                fs::remove_dir(path)
+                    .with_context(|| format!("Failed to remove directory: {}", path.display()))?;
+            }
+        } else if !is_preserved(path) {
+            fs::remove_file(path)
+                .with_context(|| format!("Failed to remove file: {}", path.display()))?;
+        }
+    }
+    for path in &preserved {
+        println!("✓ Preserved {}", path);
+    }
+    Ok(())
+}
+
 pub fn remove_dir_all_with_retry(path: &Path) -> Result<()> {
     fs::remove_dir_all(path).with_context(|| {
         format!(
@@ -122,3 +425,339 @@ pub fn remove_dir_all_with_retry(path: &Path) -> Result<()> {
         )
     })
 }
+
+/// Removes every previously inserted block between the marker comments, returning where
+/// each one was in the edited lines (ascending), for callers that also drop a separator.
+#[cfg_attr(not(any(feature = "ios", feature = "linux", feature = "web", feature = "windows")), allow(dead_code))]
+pub fn remove_marked_block(lines: &mut Vec<String>, begin: &str, end: &str) -> Vec<usize> {
+    let mut removed = Vec::new();
+    while let Some(start) = lines.iter().position(|line| line.trim() == begin) {
+        let stop = lines[start..]
+            .iter()
+            .position(|line| line.trim() == end)
+            .map(|offset| start + offset)
+            .unwrap_or(start);
+        lines.drain(start..=stop);
+        removed.push(start);
+    }
+    removed
+}
+
+const BLOCK_BEGIN: &str = "# BEGIN flutter_gen_platforms";
+const BLOCK_END: &str = "# END flutter_gen_platforms";
+
+/// Rewrites the marked block of a line-based file (.gitignore, .gitattributes) with the
+/// entries `entries` derives from the lines outside the block; an empty block is removed.
+fn update_marked_block(
+    path: &Path,
+    entries: impl FnOnce(&[String]) -> Vec<String>,
+) -> Result<()> {
+    let content = if path.exists() {
+        fs::read_to_string(path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?
+    } else {
+        String::new()
+    };
+
+    let mut lines = Vec::new();
+    let mut in_block = false;
+    let mut block_at = None;
+    for line in content.lines() {
+        match line.trim() {
+            BLOCK_BEGIN => {
+                in_block = true;
+                block_at.get_or_insert(lines.len());
+            }
+            BLOCK_END if in_block => in_block = false,
+            _ if in_block => {}
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let entries = entries(&lines);
+    if !entries.is_empty() {
+        let mut block = vec![BLOCK_BEGIN.to_string()];
+        block.extend(entries);
+        block.push(BLOCK_END.to_string());
+        let at = match block_at {
+            Some(at) => at,
+            None => {
+                if lines.last().is_some_and(|line| !line.trim().is_empty()) {
+                    lines.push(String::new());
+                }
+                lines.len()
+            }
+        };
+        lines.splice(at..at, block);
+    }
+
+    let updated = if lines.is_empty() { String::new() } else { lines.join("\n") + "\n" };
+    if updated != content {
+        fs::write(path, updated)
+            .with_context(|| format!("Failed to write file: {}", path.display()))?;
+        println!("✓ {} updated", path.display());
+    }
+    Ok(())
+}
+
+/// Maintains the marked .gitignore block; entries the user already lists outside the
+/// block are not repeated.
+pub fn update_gitignore(project_dir: &Path, config: &GitignoreConfig, platforms: &[&str]) -> Result<()> {
+    update_marked_block(&project_dir.join(".gitignore"), |lines| {
+        let existing: Vec<String> = lines
+            .iter()
+            .map(|line| line.trim().trim_start_matches('/').trim_end_matches('/').to_string())
+            .collect();
+        let mut entries: Vec<String> = Vec::new();
+        if config.platforms {
+            entries.extend(platforms.iter().map(|name| format!("/{}/", name)));
+            if platforms.contains(&"android") {
+                entries.push(format!("/{}/", crate::android::PRISTINE_DIR));
+            }
+        }
+        entries.push(format!("/{}/", crate::backup::BACKUP_DIR));
+        entries.extend(config.files.iter().cloned());
+        entries.retain(|entry| {
+            let bare = entry.trim_start_matches('/').trim_end_matches('/');
+            !existing.iter().any(|line| line == bare)
+        });
+        entries.dedup();
+        entries
+    })
+}
+
+/// Files whose line ending is dictated by the tool that runs them, whatever the platform
+/// policy: shells reject CRLF scripts and cmd.exe mis-parses LF batch files.
+const FIXED_LINE_ENDINGS: [(&str, LineEnding); 3] =
+    [("gradlew", LineEnding::Lf), ("*.sh", LineEnding::Lf), ("*.bat", LineEnding::Crlf)];
+
+fn fixed_line_ending(path: &Path) -> Option<LineEnding> {
+    let name = path.file_name()?.to_str()?;
+    FIXED_LINE_ENDINGS
+        .iter()
+        .find(|(pattern, _)| match pattern.strip_prefix('*') {
+            Some(suffix) => name.ends_with(suffix),
+            None => name == *pattern,
+        })
+        .map(|(_, ending)| *ending)
+}
+
+/// Converts every text file under `dir` to the given line ending, or the one
+/// `FIXED_LINE_ENDINGS` dictates; files with NUL bytes or invalid UTF-8 are treated as
+/// binary and left alone. Build caches are skipped.
+fn normalize_line_endings(dir: &Path, ending: LineEnding) -> Result<usize> {
+    let mut changed = 0;
+    let walker = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|entry| entry.depth() == 0 || !CACHE_DIRS.iter().any(|cache| entry.file_name() == *cache));
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let bytes = fs::read(entry.path())
+            .with_context(|| format!("Failed to read file: {}", entry.path().display()))?;
+        if bytes.contains(&0) {
+            continue;
+        }
+        let Ok(text) = String::from_utf8(bytes) else {
+            continue;
+        };
+        let lf = text.replace("\r\n", "\n");
+        let converted = match fixed_line_ending(entry.path()).unwrap_or(ending) {
+            LineEnding::Lf => lf,
+            LineEnding::Crlf => lf.replace('\n', "\r\n"),
+        };
+        if converted != text {
+            fs::write(entry.path(), converted)
+                .with_context(|| format!("Failed to write file: {}", entry.path().display()))?;
+            changed += 1;
+        }
+    }
+    Ok(changed)
+}
+
+/// Normalizes the generated platform directories to the configured line endings and
+/// records the same policy in a marked .gitattributes block, so checkouts on Windows
+/// and Linux produce identical files.
+pub fn apply_line_endings(project_dir: &Path, config: &LineEndingsConfig, platforms: &[&str]) -> Result<()> {
+    let policy: Vec<(&str, LineEnding)> = platforms
+        .iter()
+        .filter_map(|name| {
+            config
+                .platforms
+                .get(*name)
+                .copied()
+                .or(config.default)
+                .map(|ending| (*name, ending))
+        })
+        .collect();
+    for (name, ending) in &policy {
+        if !project_dir.join(name).is_dir() {
+            continue;
+        }
+        let changed = normalize_line_endings(&project_dir.join(name), *ending)?;
+        if changed > 0 {
+            println!("✓ Converted {} file(s) in {}/ to {}", changed, name, ending.as_str());
+        }
+    }
+    if config.gitattributes {
+        update_marked_block(&project_dir.join(".gitattributes"), |_| {
+            let mut lines: Vec<String> = policy
+                .iter()
+                .map(|(name, ending)| format!("/{}/** text=auto eol={}", name, ending.as_str()))
+                .collect();
+            // Later lines win, so the fixed endings override the directory policy.
+            for (name, _) in &policy {
+                lines.extend(
+                    FIXED_LINE_ENDINGS
+                        .iter()
+                        .map(|(pattern, ending)| format!("/{}/**/{} text eol={}", name, pattern, ending.as_str())),
+                );
+            }
+            lines
+        })?;
+    }
+    Ok(())
+}
+
+/// `{{name}}` variables expanded in platform overlay files; other `{{...}}` (like
+/// Flutter's `{{flutter_js}}`) are left alone.
+pub fn template_variables(cfg: &Config) -> Vec<(&'static str, String)> {
+    let mut variables = vec![
+        ("project_name", cfg.project_name.clone()),
+        ("application_id", cfg.android.app.build.application_id.clone()),
+    ];
+    if let Some(version) = &cfg.version {
+        variables.push(("version", version.clone()));
+    }
+    if let Some(org) = &cfg.org {
+        variables.push(("org", org.clone()));
+    }
+    if let Some(description) = &cfg.description {
+        variables.push(("description", description.clone()));
+    }
+    variables
+}
+
+pub fn expand_variables(text: String, variables: &[(&'static str, String)]) -> String {
+    variables
+        .iter()
+        .fold(text, |acc, (name, value)| acc.replace(&format!("{{{{{}}}}}", name), value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("flutter_gen_test_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn snapshot(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+        walkdir::WalkDir::new(dir)
+            .into_iter()
+            .map(Result::unwrap)
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| (entry.path().to_path_buf(), fs::read(entry.path()).unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn fingerprint_sources_tracks_protected_paths_only() {
+        let project = scratch_dir("fingerprint");
+        write(&project.join("lib/main.dart"), "void main() {}\n");
+        write(&project.join("pubspec.yaml"), "name: app\n");
+        write(&project.join("android/build.gradle"), "// gradle\n");
+        let before = fingerprint_sources(&project).unwrap();
+        assert_eq!(
+            before.keys().cloned().collect::<Vec<_>>(),
+            [PathBuf::from("lib/main.dart"), PathBuf::from("pubspec.yaml")]
+        );
+
+        write(&project.join("android/build.gradle"), "// changed\n");
+        assert_eq!(fingerprint_sources(&project).unwrap(), before);
+
+        write(&project.join("lib/main.dart"), "void main() { run(); }\n");
+        let after = fingerprint_sources(&project).unwrap();
+        assert_ne!(after.get(Path::new("lib/main.dart")), before.get(Path::new("lib/main.dart")));
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    #[test]
+    fn line_endings_keep_scripts_runnable() {
+        let project = scratch_dir("line_endings");
+        write(&project.join("android/gradlew"), "#!/bin/sh\r\nexec java\r\n");
+        write(&project.join("android/gradlew.bat"), "@echo off\nexit\n");
+        write(&project.join("android/app/build.gradle"), "android {\n}\n");
+        write(&project.join("android/.gradle/cache.txt"), "cached\n");
+        let config = LineEndingsConfig {
+            default: Some(LineEnding::Crlf),
+            platforms: BTreeMap::new(),
+            gitattributes: true,
+        };
+        apply_line_endings(&project, &config, &["android"]).unwrap();
+
+        let read = |path: &str| fs::read_to_string(project.join(path)).unwrap();
+        assert_eq!(read("android/gradlew"), "#!/bin/sh\nexec java\n");
+        assert_eq!(read("android/gradlew.bat"), "@echo off\r\nexit\r\n");
+        assert_eq!(read("android/app/build.gradle"), "android {\r\n}\r\n");
+        assert_eq!(read("android/.gradle/cache.txt"), "cached\n");
+        let attributes = read(".gitattributes");
+        let policy = attributes.find("/android/** text=auto eol=crlf").unwrap();
+        let gradlew = attributes.find("/android/**/gradlew text eol=lf").unwrap();
+        assert!(policy < gradlew);
+        assert!(attributes.contains("/android/**/*.bat text eol=crlf"));
+        fs::remove_dir_all(&project).unwrap();
+    }
+
+    /// A `flutter create` that overwrites everything in its target, as the real one may
+    /// do to lib/main.dart and pubspec.yaml.
+    #[cfg(unix)]
+    #[test]
+    fn flutter_create_leaves_app_sources_byte_identical() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = scratch_dir("create");
+        let flutter = root.join("flutter");
+        write(
+            &flutter,
+            "#!/bin/sh\n\
+             for target; do :; done\n\
+             mkdir -p \"$target/lib\" \"$target/test\" \"$target/android/app\"\n\
+             echo generated > \"$target/lib/main.dart\"\n\
+             echo generated > \"$target/test/widget_test.dart\"\n\
+             echo 'name: generated' > \"$target/pubspec.yaml\"\n\
+             echo generated > \"$target/android/app/build.gradle\"\n\
+             echo generated > \"$target/android/settings.gradle\"\n",
+        );
+        fs::set_permissions(&flutter, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let project = root.join("app");
+        write(&project.join("lib/main.dart"), "void main() {}\r\n");
+        write(&project.join("lib/src/app.dart"), "class App {}\n");
+        write(&project.join("test/app_test.dart"), "void main() {}\n");
+        write(&project.join("pubspec.yaml"), "name: app\n");
+        write(&project.join("android/app/build.gradle"), "// customized\n");
+        let sources: Vec<PathBuf> = ["lib", "test"].iter().map(|dir| project.join(dir)).collect();
+        let before: Vec<_> = sources.iter().map(|dir| snapshot(dir)).collect();
+        let pubspec = fs::read(project.join("pubspec.yaml")).unwrap();
+
+        run_flutter_create(&project, &flutter, "app", None, None, &FlutterCreateConfig::default()).unwrap();
+
+        let after: Vec<_> = sources.iter().map(|dir| snapshot(dir)).collect();
+        assert_eq!(before, after);
+        assert_eq!(fs::read(project.join("pubspec.yaml")).unwrap(), pubspec);
+        assert_eq!(fs::read_to_string(project.join("android/app/build.gradle")).unwrap(), "// customized\n");
+        assert_eq!(fs::read_to_string(project.join("android/settings.gradle")).unwrap(), "generated\n");
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,398 @@
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::builder::{target_platform, DESKTOP_PLATFORMS};
+use crate::config::{pkl_json_cache, Config, FieldErrors};
+use crate::xcode::check_version;
+
+/// Platforms `flutter create --platforms` accepts.
+pub const PLATFORMS: [&str; 6] = ["android", "ios", "linux", "macos", "web", "windows"];
+
+/// ABIs Gradle's `abiFilters` accepts for Flutter apps.
+const ANDROID_ABIS: [&str; 4] = ["arm64-v8a", "armeabi-v7a", "x86", "x86_64"];
+
+/// Problems found so far, as (config path, message).
+pub type Problems = Vec<(String, String)>;
+
+/// Dotted path (`android.app.build.abi_filters[1]`) of the value at `line`:`column` of a
+/// JSON document, so a syntax error "at line 40 column 12" names the field. serde_json
+/// counts columns in bytes.
+fn json_path_at(text: &str, line: usize, column: usize) -> String {
+    enum Frame {
+        Object { key: Option<String>, expect_key: bool },
+        Array(usize),
+    }
+    let offset: usize = text.split_inclusive('\n').take(line.saturating_sub(1)).map(str::len).sum::<usize>() + column;
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut string: Option<String> = None;
+    let mut escaped = false;
+    for (_, c) in text.char_indices().take_while(|(i, _)| *i < offset) {
+        if let Some(current) = string.as_mut() {
+            if escaped {
+                escaped = false;
+                current.push(c);
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                let done = string.take().unwrap_or_default();
+                if let Some(Frame::Object { key, expect_key: true }) = stack.last_mut() {
+                    *key = Some(done);
+                }
+            } else {
+                current.push(c);
+            }
+            continue;
+        }
+        match (c, stack.last_mut()) {
+            ('"', _) => string = Some(String::new()),
+            ('{', _) => stack.push(Frame::Object { key: None, expect_key: true }),
+            ('[', _) => stack.push(Frame::Array(0)),
+            ('}' | ']', _) => {
+                stack.pop();
+            }
+            (':', Some(Frame::Object { expect_key, .. })) => *expect_key = false,
+            (',', Some(Frame::Object { expect_key, .. })) => *expect_key = true,
+            (',', Some(Frame::Array(index))) => *index += 1,
+            _ => {}
+        }
+    }
+    let mut path = String::new();
+    for frame in &stack {
+        match frame {
+            Frame::Object { key: Some(key), .. } => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+            }
+            Frame::Object { key: None, .. } => {}
+            Frame::Array(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+/// Prints why the config could not be loaded: every field that failed to deserialize, in
+/// any format, and for JSON syntax errors (from .json configs and pkl's output) the path of
+/// the offending value.
+pub fn report_load_error(config_path: &Path, err: anyhow::Error) -> Result<()> {
+    println!("Config could not be loaded: {}", config_path.display());
+    for cause in err.chain() {
+        match cause.downcast_ref::<FieldErrors>() {
+            Some(FieldErrors(errors)) => {
+                for error in errors {
+                    println!("  {}", error);
+                }
+            }
+            None => println!("  {}", cause),
+        }
+    }
+    let json_source = match config_path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => Some(config_path.to_path_buf()),
+        Some("pkl") => Some(pkl_json_cache(config_path)),
+        _ => None,
+    };
+    if let Some(json_error) = err.chain().find_map(|cause| cause.downcast_ref::<serde_json::Error>())
+        && let Some(text) = json_source.and_then(|path| fs::read_to_string(path).ok())
+    {
+        let path = json_path_at(&text, json_error.line(), json_error.column());
+        if !path.is_empty() {
+            println!("  at: {}", path);
+        }
+    }
+    bail!("Config validation failed: {}", config_path.display())
+}
+
+pub fn check_project_name(problems: &mut Problems, path: &str, name: &str) {
+    let valid = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        problems.push((
+            path.to_string(),
+            format!("'{}' is not a valid Dart package name (lowercase letters, digits and _)", name),
+        ));
+    }
+}
+
+/// Reverse-DNS id with at least two segments. Android packages are Java identifiers;
+/// Apple bundle ids and GTK ids also allow hyphens.
+pub fn check_id(problems: &mut Problems, path: &str, id: &str, android: bool) {
+    let valid = id.split('.').count() >= 2
+        && id.split('.').all(|part| {
+            !part.is_empty()
+                && (!android || part.starts_with(|c: char| c.is_ascii_alphabetic()))
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || (!android && c == '-'))
+        });
+    if !valid {
+        let expected = if android {
+            "segments must start with a letter and hold only letters, digits and _"
+        } else {
+            "expected a reverse-DNS id like com.example.app"
+        };
+        problems.push((path.to_string(), format!("invalid id '{}': {}", id, expected)));
+    }
+}
+
+fn check_file(problems: &mut Problems, project_dir: &Path, path: &str, file: &str) {
+    if !project_dir.join(file).exists() {
+        problems.push((path.to_string(), format!("file not found: {}", project_dir.join(file).display())));
+    }
+}
+
+fn check_version_field(problems: &mut Problems, path: &str, version: &str) {
+    if let Err(err) = check_version(path, version) {
+        problems.push((path.to_string(), err.to_string()));
+    }
+}
+
+/// Maven repository URL as emitted into `maven { url = uri("...") }`.
+fn check_repository(problems: &mut Problems, path: &str, url: &str) {
+    let message = if url.trim().is_empty() {
+        Some("empty repository URL".to_string())
+    } else if url.contains(|c: char| c.is_whitespace() || c == '"') {
+        Some(format!("'{}' must be a plain URL without spaces or quotes", url))
+    } else {
+        match url.split_once("://") {
+            Some(("http", _)) => Some(format!("'{}': Gradle rejects insecure http:// repositories, use https://", url)),
+            Some(("https" | "file" | "s3" | "gcs" | "sftp", rest)) if !rest.is_empty() => None,
+            Some(_) => Some(format!("'{}': expected an https://, file://, s3://, gcs:// or sftp:// URL", url)),
+            None => None,
+        }
+    };
+    if let Some(message) = message {
+        problems.push((path.to_string(), message));
+    }
+}
+
+fn check_android(problems: &mut Problems, project_dir: &Path, cfg: &Config) {
+    let build = &cfg.android.app.build;
+    if build.application_id.is_empty() {
+        problems.push(("android.app.build.application_id".to_string(), "required for the android platform".to_string()));
+    } else {
+        check_id(problems, "android.app.build.application_id", &build.application_id, true);
+    }
+    if !build.namespace.is_empty() {
+        check_id(problems, "android.app.build.namespace", &build.namespace, true);
+    }
+    for (i, abi) in build.abi_filters.iter().flatten().enumerate() {
+        if !ANDROID_ABIS.contains(&abi.as_str()) {
+            problems.push((
+                format!("android.app.build.abi_filters[{}]", i),
+                format!("unknown ABI '{}' (expected one of {})", abi, ANDROID_ABIS.join(", ")),
+            ));
+        }
+    }
+    let resolution = cfg.android.settings.dependency_resolution.as_ref();
+    let repository_lists = [
+        (
+            "android.build.allprojects",
+            &cfg.android.build.allprojects.repositories,
+            cfg.android.build.allprojects.replace,
+        ),
+        (
+            "android.settings.plugin_management",
+            &cfg.android.settings.plugin_management.repositories,
+            cfg.android.settings.plugin_management.replace,
+        ),
+    ]
+    .into_iter()
+    .chain(resolution.map(|resolution| {
+        ("android.settings.dependency_resolution", &resolution.repositories, resolution.replace)
+    }));
+    for (path, urls, replace) in repository_lists {
+        // Gradle would be left with no repository to resolve anything from.
+        if replace && urls.is_empty() {
+            problems.push((format!("{}.replace", path), "replace = true needs at least one repository".to_string()));
+        }
+        for (i, url) in urls.iter().enumerate() {
+            check_repository(problems, &format!("{}.repositories[{}]", path, i), url);
+        }
+    }
+    if let Some(banner) = cfg.android.tv.as_ref().and_then(|tv| tv.banner.as_deref()) {
+        check_file(problems, project_dir, "android.tv.banner", banner);
+    }
+}
+
+fn check_ios(problems: &mut Problems, project_dir: &Path, cfg: &Config) {
+    let Some(ios) = &cfg.ios else {
+        return;
+    };
+    if let Some(version) = &ios.deployment_target {
+        check_version_field(problems, "ios.deployment_target", version);
+    }
+    if let Some(version) = &ios.swift_version {
+        check_version_field(problems, "ios.swift_version", version);
+    }
+    if let Some(signing) = &ios.signing {
+        let team = &signing.development_team;
+        if team.len() != 10 || !team.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+            problems.push((
+                "ios.signing.development_team".to_string(),
+                format!("'{}' is not a 10-character Apple team id", team),
+            ));
+        }
+    }
+    if let Some(icons) = &ios.icons {
+        check_file(problems, project_dir, "ios.icons.source", &icons.source);
+    }
+    if let Some(image) = ios.launch_screen.as_ref().and_then(|launch| launch.image.as_deref()) {
+        check_file(problems, project_dir, "ios.launch_screen.image", image);
+    }
+}
+
+fn check_desktop(problems: &mut Problems, project_dir: &Path, cfg: &Config) {
+    if let Some(macos) = &cfg.macos {
+        if let Some(bundle_id) = &macos.bundle_id {
+            check_id(problems, "macos.bundle_id", bundle_id, false);
+        }
+        if let Some(category) = &macos.category
+            && !category.starts_with("public.app-category.")
+        {
+            problems.push((
+                "macos.category".to_string(),
+                format!("'{}': expected e.g. \"public.app-category.productivity\"", category),
+            ));
+        }
+        if let Some(version) = &macos.deployment_target {
+            check_version_field(problems, "macos.deployment_target", version);
+        }
+        if let Some(icon) = &macos.icon {
+            check_file(problems, project_dir, "macos.icon.source", &icon.source);
+        }
+    }
+    if let Some(linux) = &cfg.linux {
+        if let Some(application_id) = &linux.application_id {
+            check_id(problems, "linux.application_id", application_id, false);
+        }
+        if linux.window_width.is_some() != linux.window_height.is_some() {
+            problems.push(("linux.window_width".to_string(), "must be set together with window_height".to_string()));
+        }
+        if let Some(icon) = &linux.icon {
+            check_file(problems, project_dir, "linux.icon.source", &icon.source);
+        }
+    }
+    if let Some(windows) = &cfg.windows {
+        if windows.window_width.is_some() != windows.window_height.is_some() {
+            problems.push(("windows.window_width".to_string(), "must be set together with window_height".to_string()));
+        }
+        if let Some(icon) = &windows.icon {
+            check_file(problems, project_dir, "windows.icon.source", &icon.source);
+        }
+    }
+}
+
+/// Cross-checks a loaded config and prints every problem with its config path, instead
+/// of stopping at the first one like generation does.
+pub fn validate_config(project_dir: &Path, config_path: &Path, cfg: &Config, platforms: &[&str]) -> Result<()> {
+    let mut problems = Problems::new();
+
+    check_project_name(&mut problems, "project_name", &cfg.project_name);
+    if let Some(org) = &cfg.org {
+        check_id(&mut problems, "org", org, false);
+    }
+    let lists = [
+        ("create.platforms", cfg.create.platforms.as_deref().unwrap_or_default()),
+        ("create.default_platforms", cfg.create.default_platforms.as_slice()),
+    ];
+    for (key, list) in lists {
+        for (i, platform) in list.iter().enumerate() {
+            if !PLATFORMS.contains(&platform.as_str()) {
+                problems.push((
+                    format!("{}[{}]", key, i),
+                    format!("unknown platform '{}' (expected one of {})", platform, PLATFORMS.join(", ")),
+                ));
+            }
+        }
+    }
+    for platform in cfg.hooks.platforms.keys() {
+        if !PLATFORMS.contains(&platform.as_str()) {
+            problems.push((
+                format!("hooks.platforms.{}", platform),
+                format!("unknown platform '{}' (expected one of {})", platform, PLATFORMS.join(", ")),
+            ));
+        }
+    }
+    if platforms.is_empty() {
+        problems.push((
+            "create.default_platforms".to_string(),
+            "no platforms selected (create.platforms is unset or empty and so is this)".to_string(),
+        ));
+    }
+    for (i, platform) in cfg.build.platforms.iter().enumerate() {
+        if platform != "android" && !DESKTOP_PLATFORMS.contains(&platform.as_str()) {
+            problems.push((
+                format!("build.platforms[{}]", i),
+                format!("unknown build platform '{}' (expected android, windows, macos or linux)", platform),
+            ));
+        }
+    }
+    for (i, abi) in cfg.build.matrix.abis.iter().enumerate() {
+        if let Err(err) = target_platform(abi) {
+            problems.push((format!("build.matrix.abis[{}]", i), err.to_string()));
+        }
+    }
+    let sections = [
+        ("ios", cfg.ios.is_some()),
+        ("linux", cfg.linux.is_some()),
+        ("macos", cfg.macos.is_some()),
+        ("web", cfg.web.is_some()),
+    ];
+    for (name, present) in sections {
+        if present && !platforms.contains(&name) {
+            problems.push((name.to_string(), format!("ignored: {} is not in create.platforms", name)));
+        }
+    }
+    for (name, url) in [
+        ("pubspec.homepage", cfg.pubspec.as_ref().and_then(|p| p.homepage.as_deref())),
+        ("pubspec.repository", cfg.pubspec.as_ref().and_then(|p| p.repository.as_deref())),
+    ] {
+        if let Some(url) = url
+            && !url.starts_with("https://")
+            && !url.starts_with("http://")
+        {
+            problems.push((name.to_string(), format!("'{}' must be an http(s):// URL", url)));
+        }
+    }
+
+    if platforms.contains(&"android") {
+        check_android(&mut problems, project_dir, cfg);
+    }
+    check_ios(&mut problems, project_dir, cfg);
+    check_desktop(&mut problems, project_dir, cfg);
+    if let Some(icons) = cfg.web.as_ref().and_then(|web| web.icons.as_ref()) {
+        check_file(&mut problems, project_dir, "web.icons.source", &icons.source);
+    }
+    for (i, image) in cfg.assets.iter().flat_map(|assets| &assets.images).enumerate() {
+        check_file(&mut problems, project_dir, &format!("assets.images[{}].source", i), &image.source);
+    }
+    for (i, family) in cfg.fonts.iter().flat_map(|fonts| &fonts.families).enumerate() {
+        for (j, file) in family.files.iter().enumerate() {
+            check_file(&mut problems, project_dir, &format!("fonts.families[{}].files[{}].source", i, j), &file.source);
+        }
+    }
+
+    if problems.is_empty() {
+        println!("✓ Config is valid: {}", config_path.display());
+        return Ok(());
+    }
+    println!("Config problems in {}:", config_path.display());
+    for (path, message) in &problems {
+        println!("  {}: {}", path, message);
+    }
+    bail!("Config validation failed: {} problem(s)", problems.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_path_at_counts_columns_in_bytes() {
+        let text = "{\"name\": \"café ☕\", \"android\": {\"abi_filters\": [\"x86\", 1]}}";
+        let column = text.find('1').unwrap() + 1;
+        let err = serde_json::from_str::<serde_json::Value>(&text.replace('1', "}")).unwrap_err();
+        assert_eq!(err.column(), column);
+        assert_eq!(json_path_at(text, 1, column), "android.abi_filters[1]");
+    }
+}
@@ -0,0 +1,216 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::android::{
+    android_block_lines, app_plugins, dependency_lines, dependency_resolution_lines,
+    gradle_property_entries, output_file_name_lines, read_properties, release_build_type_lines,
+    settings_plugins,
+};
+use crate::config::{AndroidConfig, RepositoriesMode};
+
+/// A line the gradle edits should have produced, inside the nested blocks `path`
+/// (e.g. `["android", "defaultConfig"]`); an empty path means anywhere in the file.
+struct Expected {
+    path: Vec<&'static str>,
+    line: String,
+}
+
+impl Expected {
+    fn new(path: &[&'static str], line: impl Into<String>) -> Self {
+        Self {
+            path: path.to_vec(),
+            line: line.into(),
+        }
+    }
+}
+
+/// Returns the lines between the braces of the first `<header> {` block.
+fn block_body<'a>(lines: &'a [&'a str], header: &str) -> Option<&'a [&'a str]> {
+    let opener = format!("{} {{", header);
+    let start = lines.iter().position(|line| line.trim().starts_with(&opener))?;
+    let mut depth = 0i32;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        depth += line.matches('{').count() as i32;
+        depth -= line.matches('}').count() as i32;
+        if depth <= 0 {
+            return Some(&lines[start + 1..i]);
+        }
+    }
+    None
+}
+
+fn check_file(android_dir: &Path, relative: &str, expected: &[Expected], report: &mut Vec<String>) -> Result<()> {
+    if expected.is_empty() {
+        return Ok(());
+    }
+    let path = android_dir.join(relative);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+
+    let mut missing = Vec::new();
+    for item in expected {
+        let mut scope: &[&str] = &lines;
+        let mut found_block = true;
+        for header in &item.path {
+            match block_body(scope, header) {
+                Some(body) => scope = body,
+                None => {
+                    found_block = false;
+                    break;
+                }
+            }
+        }
+        let location = if item.path.is_empty() {
+            String::new()
+        } else {
+            format!("  ({})", item.path.join(" > "))
+        };
+        if !found_block {
+            missing.push(format!("+ {}{}  [block not found]", item.line, location));
+        } else if !scope.iter().any(|line| line.trim() == item.line) {
+            missing.push(format!("+ {}{}", item.line, location));
+        }
+    }
+    if !missing.is_empty() {
+        report.push(format!("--- android/{}", relative));
+        report.extend(missing);
+    }
+    Ok(())
+}
+
+/// Re-reads the generated gradle files and fails if a configured edit is missing,
+/// which happens when the Flutter template changes and a line-based insertion no
+/// longer finds its anchor.
+pub fn verify_android_platform(android_dir: &Path, config: &AndroidConfig) -> Result<()> {
+    let build = &config.app.build;
+    let mut report = Vec::new();
+
+    let fail_on_project_repos = config
+        .settings
+        .dependency_resolution
+        .as_ref()
+        .is_some_and(|resolution| resolution.mode == RepositoriesMode::FailOnProjectRepos);
+    let root_repositories: &[String] = if fail_on_project_repos {
+        &[]
+    } else {
+        &config.build.allprojects.repositories
+    };
+    let root: Vec<Expected> = root_repositories
+        .iter()
+        .map(|repo| {
+            Expected::new(
+                &["allprojects", "repositories"],
+                format!("maven {{ url = uri(\"{}\") }}", repo),
+            )
+        })
+        .collect();
+    check_file(android_dir, "build.gradle.kts", &root, &mut report)?;
+
+    let mut settings: Vec<Expected> = config
+        .settings
+        .plugin_management
+        .repositories
+        .iter()
+        .map(|repo| {
+            Expected::new(
+                &["pluginManagement", "repositories"],
+                format!("maven {{ url = uri(\"{}\") }}", repo),
+            )
+        })
+        .collect();
+    for line in dependency_resolution_lines(config) {
+        let path: &[&str] = if line.starts_with("        ") {
+            &["dependencyResolutionManagement", "repositories"]
+        } else {
+            &["dependencyResolutionManagement"]
+        };
+        if !line.trim().ends_with('{') && line.trim() != "}" {
+            settings.push(Expected::new(path, line.trim()));
+        }
+    }
+    settings.extend(
+        settings_plugins(config)
+            .into_iter()
+            .map(|plugin| Expected::new(&["plugins"], plugin)),
+    );
+    check_file(android_dir, "settings.gradle.kts", &settings, &mut report)?;
+
+    let mut app = Vec::new();
+    if !build.namespace.is_empty() {
+        app.push(Expected::new(&["android"], format!("namespace = \"{}\"", build.namespace)));
+    }
+    if !build.application_id.is_empty() {
+        app.push(Expected::new(
+            &["android", "defaultConfig"],
+            format!("applicationId = \"{}\"", build.application_id),
+        ));
+    }
+    app.extend(app_plugins(config).into_iter().map(|plugin| Expected::new(&["plugins"], plugin)));
+    for abi in build.abi_filters.iter().flatten() {
+        app.push(Expected::new(
+            &["android", "defaultConfig", "ndk"],
+            format!("abiFilters.add(\"{}\")", abi),
+        ));
+    }
+    if let Some(false) = config.build_performance.kotlin_incremental {
+        app.push(Expected::new(&[], "incremental = false"));
+    }
+    app.extend(
+        release_build_type_lines(config)
+            .into_iter()
+            .filter(|line| line.trim() != "}")
+            .map(|line| Expected::new(&["android", "buildTypes", "release"], line.trim())),
+    );
+    app.extend(
+        output_file_name_lines(config)
+            .into_iter()
+            .filter(|line| line.trim() != "}")
+            .map(|line| Expected::new(&["android", "applicationVariants.all", "outputs.all"], line.trim())),
+    );
+    app.extend(
+        android_block_lines(config)
+            .into_iter()
+            .filter(|line| line.trim() != "}")
+            .map(|line| Expected::new(&["android"], line.trim())),
+    );
+    app.extend(
+        dependency_lines(config)
+            .into_iter()
+            .map(|line| Expected::new(&["dependencies"], line.trim())),
+    );
+    check_file(android_dir, "app/build.gradle.kts", &app, &mut report)?;
+
+    if let Some(url) = &config.gradle_wrapper.distribution_url {
+        let path = android_dir.join("gradle/wrapper/gradle-wrapper.properties");
+        let props = read_properties(&path)?;
+        if props.get("distributionUrl") != Some(url) {
+            report.push("--- android/gradle/wrapper/gradle-wrapper.properties".to_string());
+            report.push(format!("+ distributionUrl={}", url));
+        }
+    }
+
+    let entries = gradle_property_entries(config);
+    if !entries.is_empty() {
+        let props = read_properties(&android_dir.join("gradle.properties"))?;
+        let missing: Vec<String> = entries
+            .into_iter()
+            .filter(|(key, value)| props.get(*key) != Some(value))
+            .map(|(key, value)| format!("+ {}={}", key, value))
+            .collect();
+        if !missing.is_empty() {
+            report.push("--- android/gradle.properties".to_string());
+            report.extend(missing);
+        }
+    }
+
+    if !report.is_empty() {
+        bail!(
+            "Gradle edits were not applied (the Flutter template format may have changed); expected but missing:\n{}",
+            report.join("\n")
+        );
+    }
+    println!("✓ Gradle edits verified");
+    Ok(())
+}
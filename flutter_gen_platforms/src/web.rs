@@ -1,8 +1,535 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
 use std::path::Path;
 
-pub fn process_web_platform(project_dir: &Path) -> Result<()> {
+use crate::config::{
+    Config, WebConfig, WebLinkConfig, WebLoaderConfig, WebLoadingConfig, WebScriptConfig,
+    WebSocialConfig,
+};
+use crate::hosting::write_hosting_configs;
+use crate::images::run_image_job;
+use crate::template::OverlayRenderer;
+use crate::utils::{expand_variables, remove_marked_block, template_variables};
+
+/// Icon sizes referenced by the Flutter web template's manifest.json.
+const ICON_SIZES: [u32; 2] = [192, 512];
+
+/// Flutter's template ships a 16x16 favicon.png.
+const FAVICON_SIZE: u32 = 16;
+
+/// Maskable icons keep their content inside the central safe zone (80% circle).
+const MASKABLE_PADDING: f64 = 0.1;
+
+fn default_manifest_icons() -> Value {
+    let mut icons = Vec::new();
+    for purpose in ["", "maskable-"] {
+        for size in ICON_SIZES {
+            let mut icon = json!({
+                "src": format!("icons/Icon-{}{}.png", purpose, size),
+                "sizes": format!("{0}x{0}", size),
+                "type": "image/png",
+            });
+            if !purpose.is_empty() {
+                icon["purpose"] = json!("maskable");
+            }
+            icons.push(icon);
+        }
+    }
+    Value::Array(icons)
+}
+
+fn generate_web_icons(project_dir: &Path, source: &str, background: Option<&str>) -> Result<()> {
+    if !project_dir.join(source).exists() {
+        bail!("Web icon source image not found: {}", project_dir.join(source).display());
+    }
+    let mut outputs = vec![json!({
+        "path": "web/favicon.png",
+        "width": FAVICON_SIZE,
+        "height": FAVICON_SIZE,
+    })];
+    for size in ICON_SIZES {
+        outputs.push(json!({
+            "path": format!("web/icons/Icon-{}.png", size),
+            "width": size,
+            "height": size,
+        }));
+        outputs.push(json!({
+            "path": format!("web/icons/Icon-maskable-{}.png", size),
+            "width": size,
+            "height": size,
+            "padding": MASKABLE_PADDING,
+            // Maskable icons must be opaque; the launcher crops them to its own shape.
+            "background": background.unwrap_or("#ffffff"),
+            "opaque": true,
+        }));
+    }
+    run_image_job(project_dir, &json!({ "images": [{ "source": source, "outputs": outputs }] }))?;
+    println!("✓ Generated web favicon and icons from {}", source);
+    Ok(())
+}
+
+fn apply_web_manifest(web_dir: &Path, config: &WebConfig) -> Result<()> {
+    let path = web_dir.join("manifest.json");
+    let mut manifest: Value = if path.exists() {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse JSON: {}", path.display()))?
+    } else {
+        json!({})
+    };
+    let Some(fields) = manifest.as_object_mut() else {
+        bail!("Expected a JSON object in: {}", path.display());
+    };
+
+    let strings = [
+        ("name", &config.name),
+        ("short_name", &config.short_name),
+        ("description", &config.description),
+        ("start_url", &config.start_url),
+        ("theme_color", &config.theme_color),
+        ("background_color", &config.background_color),
+        ("orientation", &config.orientation),
+    ];
+    for (key, value) in strings {
+        if let Some(value) = value {
+            fields.insert(key.to_string(), json!(value));
+        }
+    }
+    if let Some(display) = config.display {
+        fields.insert("display".to_string(), json!(display.as_str()));
+    }
+    if config.icons.is_some() && !fields.contains_key("icons") {
+        fields.insert("icons".to_string(), default_manifest_icons());
+    }
+
+    fs::write(&path, serde_json::to_string_pretty(&manifest)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Web manifest updated: {}", path.display());
+    Ok(())
+}
+
+fn html_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Sets `<meta name="{name}">` in place, or adds it before `</head>`.
+fn set_meta(lines: &mut Vec<String>, name: &str, content: &str) -> Result<()> {
+    let tag = format!(
+        "  <meta name=\"{}\" content=\"{}\">",
+        html_escape(name),
+        html_escape(content)
+    );
+    let needle = format!("<meta name=\"{}\"", html_escape(name));
+    if let Some(line) = lines.iter_mut().find(|line| line.contains(&needle)) {
+        *line = tag;
+        return Ok(());
+    }
+    let Some(head_end) = lines.iter().position(|line| line.trim() == "</head>") else {
+        bail!("No </head> found in web/index.html");
+    };
+    lines.insert(head_end, tag);
+    Ok(())
+}
+
+const LOADING_BEGIN: &str = "<!-- flutter_gen_platforms:loading:begin -->";
+const LOADING_END: &str = "<!-- flutter_gen_platforms:loading:end -->";
+
+const SOCIAL_BEGIN: &str = "<!-- flutter_gen_platforms:social:begin -->";
+const SOCIAL_END: &str = "<!-- flutter_gen_platforms:social:end -->";
+
+const HEAD_TAGS_BEGIN: &str = "<!-- flutter_gen_platforms:head:begin -->";
+const HEAD_TAGS_END: &str = "<!-- flutter_gen_platforms:head:end -->";
+
+fn attributes_html(attributes: &BTreeMap<String, String>) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| {
+            if value.is_empty() {
+                format!(" {}", html_escape(name))
+            } else {
+                format!(" {}=\"{}\"", html_escape(name), html_escape(value))
+            }
+        })
+        .collect()
+}
+
+fn script_lines(script: &WebScriptConfig) -> Result<Vec<String>> {
+    let mut attributes = String::new();
+    if let Some(src) = &script.src {
+        attributes.push_str(&format!(" src=\"{}\"", html_escape(src)));
+    }
+    if script.is_async {
+        attributes.push_str(" async");
+    }
+    if script.defer {
+        attributes.push_str(" defer");
+    }
+    attributes.push_str(&attributes_html(&script.attributes));
+    match (&script.src, &script.content) {
+        (Some(_), _) => Ok(vec![format!("  <script{}></script>", attributes)]),
+        (None, Some(content)) => {
+            let mut lines = vec![format!("  <script{}>", attributes)];
+            lines.extend(content.trim().lines().map(|line| format!("    {}", line)));
+            lines.push("  </script>".to_string());
+            Ok(lines)
+        }
+        (None, None) => bail!("web.scripts entries need a src or content"),
+    }
+}
+
+fn link_line(link: &WebLinkConfig) -> String {
+    format!(
+        "  <link rel=\"{}\" href=\"{}\"{}>",
+        html_escape(&link.rel),
+        html_escape(&link.href),
+        attributes_html(&link.attributes)
+    )
+}
+
+/// Puts the CSP meta tag first in `<head>` (it only covers what follows it) and the
+/// configured `<link>`/`<script>` tags before `</head>`, each in a marked block so removing
+/// them from the config removes them from the page.
+fn apply_head_tags(lines: &mut Vec<String>, config: &WebConfig) -> Result<()> {
+    remove_marked_block(lines, HEAD_TAGS_BEGIN, HEAD_TAGS_END);
+    if let Some(csp) = &config.csp {
+        // Earlier versions wrote the tag unmarked; the configured policy replaces it.
+        lines.retain(|line| !line.contains("http-equiv=\"Content-Security-Policy\""));
+        let anchor = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with("<meta charset"))
+            .or_else(|| lines.iter().position(|line| line.trim() == "<head>"));
+        let Some(anchor) = anchor else {
+            bail!("No <head> found in web/index.html");
+        };
+        let block = [
+            format!("  {}", HEAD_TAGS_BEGIN),
+            format!("  <meta http-equiv=\"Content-Security-Policy\" content=\"{}\">", html_escape(csp)),
+            format!("  {}", HEAD_TAGS_END),
+        ];
+        lines.splice(anchor + 1..anchor + 1, block);
+    }
+
+    if config.scripts.is_empty() && config.links.is_empty() {
+        return Ok(());
+    }
+    let mut block = vec![format!("  {}", HEAD_TAGS_BEGIN)];
+    block.extend(config.links.iter().map(link_line));
+    for script in &config.scripts {
+        block.extend(script_lines(script)?);
+    }
+    block.push(format!("  {}", HEAD_TAGS_END));
+    let Some(head_end) = lines.iter().position(|line| line.trim() == "</head>") else {
+        bail!("No </head> found in web/index.html");
+    };
+    lines.splice(head_end..head_end, block);
+    Ok(())
+}
+
+/// Open Graph (`property=`) and Twitter (`name=`) tags, in a marked block before `</head>`.
+fn apply_social_tags(
+    lines: &mut Vec<String>,
+    cfg: &Config,
+    web: &WebConfig,
+    social: &WebSocialConfig,
+) -> Result<()> {
+    let variables = template_variables(cfg);
+    let expand = |value: &str| expand_variables(value.to_string(), &variables);
+    let title = social
+        .title
+        .as_ref()
+        .or(web.title.as_ref())
+        .or(web.name.as_ref())
+        .unwrap_or(&cfg.project_name);
+    let description = social
+        .description
+        .as_ref()
+        .or(web.description.as_ref())
+        .or(cfg.description.as_ref());
+    let image = match &social.image {
+        Some(image) if image.starts_with("http://") || image.starts_with("https://") => {
+            Some(expand(image))
+        }
+        Some(image) => {
+            let Some(url) = &social.url else {
+                bail!("web.social.image must be an absolute URL unless web.social.url is set: {}", image);
+            };
+            Some(format!(
+                "{}/{}",
+                expand(url).trim_end_matches('/'),
+                expand(image).trim_start_matches('/')
+            ))
+        }
+        None => None,
+    };
+    let card = social.twitter_card.clone().unwrap_or_else(|| {
+        if image.is_some() { "summary_large_image" } else { "summary" }.to_string()
+    });
+
+    let mut tags: Vec<(&str, &str, String)> = vec![
+        ("property", "og:type", "website".to_string()),
+        ("property", "og:title", expand(title)),
+    ];
+    if let Some(description) = description {
+        tags.push(("property", "og:description", expand(description)));
+    }
+    if let Some(url) = &social.url {
+        tags.push(("property", "og:url", expand(url)));
+    }
+    if let Some(site_name) = &social.site_name {
+        tags.push(("property", "og:site_name", expand(site_name)));
+    }
+    if let Some(image) = &image {
+        tags.push(("property", "og:image", image.clone()));
+        if let Some(alt) = &social.image_alt {
+            tags.push(("property", "og:image:alt", expand(alt)));
+        }
+    }
+    tags.push(("name", "twitter:card", card));
+    tags.push(("name", "twitter:title", expand(title)));
+    if let Some(description) = description {
+        tags.push(("name", "twitter:description", expand(description)));
+    }
+    if let Some(image) = &image {
+        tags.push(("name", "twitter:image", image.clone()));
+    }
+    if let Some(site) = &social.twitter_site {
+        tags.push(("name", "twitter:site", site.clone()));
+    }
+
+    let mut block = vec![format!("  {}", SOCIAL_BEGIN)];
+    block.extend(tags.iter().map(|(attribute, key, value)| {
+        format!("  <meta {}=\"{}\" content=\"{}\">", attribute, key, html_escape(value))
+    }));
+    block.push(format!("  {}", SOCIAL_END));
+    let Some(head_end) = lines.iter().position(|line| line.trim() == "</head>") else {
+        bail!("No </head> found in web/index.html");
+    };
+    lines.splice(head_end..head_end, block);
+    Ok(())
+}
+
+/// Inserts the loading indicator right after `<body>` (and its CSS before `</head>`);
+/// a `flutter-first-frame` listener removes it once the app has painted.
+fn apply_loading_splash(
+    lines: &mut Vec<String>,
+    templates_dir: &Path,
+    config: &WebLoadingConfig,
+) -> Result<()> {
+    let markup = match (&config.template, &config.html) {
+        (Some(template), _) => {
+            let path = templates_dir.join(template);
+            fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read file: {}", path.display()))?
+        }
+        (None, Some(html)) => html.clone(),
+        (None, None) => String::new(),
+    };
+
+    remove_marked_block(lines, LOADING_BEGIN, LOADING_END);
+    if let Some(css) = &config.css {
+        let Some(head_end) = lines.iter().position(|line| line.trim() == "</head>") else {
+            bail!("No </head> found in web/index.html");
+        };
+        let mut block = vec![format!("  {}", LOADING_BEGIN), "  <style>".to_string()];
+        block.extend(css.lines().map(|line| format!("    {}", line)));
+        block.push("  </style>".to_string());
+        block.push(format!("  {}", LOADING_END));
+        lines.splice(head_end..head_end, block);
+    }
+
+    let Some(body_start) = lines.iter().position(|line| line.trim_start().starts_with("<body")) else {
+        bail!("No <body> found in web/index.html");
+    };
+    let mut block = vec![
+        format!("  {}", LOADING_BEGIN),
+        "  <div id=\"flutter-loading\">".to_string(),
+    ];
+    block.extend(markup.lines().map(|line| format!("    {}", line)));
+    block.push("  </div>".to_string());
+    block.push("  <script>".to_string());
+    block.push("    window.addEventListener('flutter-first-frame', function () {".to_string());
+    block.push("      var loading = document.getElementById('flutter-loading');".to_string());
+    block.push("      if (loading) loading.remove();".to_string());
+    block.push("    });".to_string());
+    block.push("  </script>".to_string());
+    block.push(format!("  {}", LOADING_END));
+    lines.splice(body_start + 1..body_start + 1, block);
+    Ok(())
+}
+
+/// Applies title, description, base href, meta/CSP/social/script/link tags and the
+/// loading splash from `[web]` to index.html (after the overlay may have replaced it).
+fn apply_index_html(web_dir: &Path, templates_dir: &Path, cfg: &Config, config: &WebConfig) -> Result<()> {
+    let path = web_dir.join("index.html");
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    if let Some(base_href) = &config.base_href {
+        if !base_href.starts_with('/') || !base_href.ends_with('/') {
+            bail!("web.base_href must start and end with '/': {}", base_href);
+        }
+        let Some(line) = lines.iter_mut().find(|line| line.trim_start().starts_with("<base href=")) else {
+            bail!("No <base href> found in: {}", path.display());
+        };
+        *line = format!("  <base href=\"{}\">", html_escape(base_href));
+    }
+    if let Some(title) = &config.title {
+        let Some(line) = lines.iter_mut().find(|line| line.trim_start().starts_with("<title>")) else {
+            bail!("No <title> found in: {}", path.display());
+        };
+        *line = format!("  <title>{}</title>", html_escape(title));
+        set_meta(&mut lines, "apple-mobile-web-app-title", title)?;
+    }
+    if let Some(description) = &config.description {
+        set_meta(&mut lines, "description", description)?;
+    }
+    for (name, content) in &config.meta {
+        set_meta(&mut lines, name, content)?;
+    }
+    remove_marked_block(&mut lines, SOCIAL_BEGIN, SOCIAL_END);
+    if let Some(social) = &config.social {
+        apply_social_tags(&mut lines, cfg, config, social)?;
+    }
+    apply_head_tags(&mut lines, config)?;
+    if let Some(loading) = &config.loading {
+        apply_loading_splash(&mut lines, templates_dir, loading)?;
+    }
+
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ index.html updated: {}", path.display());
+    Ok(())
+}
+
+/// Writes web/flutter_bootstrap.js; `flutter build web` fills in the `{{...}}` tokens.
+fn write_flutter_bootstrap(web_dir: &Path, config: &WebLoaderConfig) -> Result<()> {
+    let index_path = web_dir.join("index.html");
+    let index = fs::read_to_string(&index_path)
+        .with_context(|| format!("Failed to read file: {}", index_path.display()))?;
+    if !index.contains("flutter_bootstrap.js") {
+        bail!(
+            "{} does not load flutter_bootstrap.js (pre-3.22 template?); web.loader needs the current template",
+            index_path.display()
+        );
+    }
+
+    let mut options = Vec::new();
+    if let Some(renderer) = config.renderer {
+        options.push(format!("renderer: \"{}\"", renderer.as_str()));
+    }
+    if let Some(url) = &config.canvas_kit_base_url {
+        options.push(format!("canvasKitBaseUrl: {}", json!(url)));
+    }
+    if let Some(variant) = &config.canvas_kit_variant {
+        if !["auto", "full", "chromium"].contains(&variant.as_str()) {
+            bail!("web.loader.canvas_kit_variant must be auto, full or chromium: {}", variant);
+        }
+        options.push(format!("canvasKitVariant: \"{}\"", variant));
+    }
+    if let Some(single_threaded) = config.force_single_threaded_skwasm {
+        options.push(format!("forceSingleThreadedSkwasm: {}", single_threaded));
+    }
+    if let Some(url) = &config.entrypoint_base_url {
+        options.push(format!("entrypointBaseUrl: {}", json!(url)));
+    }
+
+    let mut lines = vec![
+        "{{flutter_js}}".to_string(),
+        "{{flutter_build_config}}".to_string(),
+        String::new(),
+        "_flutter.loader.load({".to_string(),
+    ];
+    if !options.is_empty() {
+        lines.push("  config: {".to_string());
+        lines.extend(options.into_iter().map(|option| format!("    {},", option)));
+        lines.push("  },".to_string());
+    }
+    if config.service_worker {
+        lines.push("  serviceWorkerSettings: {".to_string());
+        lines.push("    serviceWorkerVersion: {{flutter_service_worker_version}},".to_string());
+        lines.push("  },".to_string());
+    }
+    lines.push("});".to_string());
+
+    let path = web_dir.join("flutter_bootstrap.js");
+    fs::write(&path, lines.join("\n") + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Flutter web loader written to {}", path.display());
+    Ok(())
+}
+
+fn write_assetlinks(web_dir: &Path, cfg: &Config) -> Result<()> {
+    let Some(deep_links) = &cfg.android.deep_links else {
+        return Ok(());
+    };
+    if deep_links.sha256_cert_fingerprints.is_empty() {
+        return Ok(());
+    }
+    let assetlinks = json!([{
+        "relation": ["delegate_permission/common.handle_all_urls"],
+        "target": {
+            "namespace": "android_app",
+            "package_name": cfg.android.app.build.application_id,
+            "sha256_cert_fingerprints": deep_links.sha256_cert_fingerprints,
+        }
+    }]);
+    let path = web_dir.join(".well-known/assetlinks.json");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(&assetlinks)? + "\n")
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+    println!("✓ Digital Asset Links written to {}", path.display());
+    Ok(())
+}
+
+/// Copies everything under `platforms/web/` over the generated web/ directory, like the
+/// Android res/ templates, expanding `{{variables}}` in text files. Runs before the
+/// config-driven edits, so `[web]` settings still apply on top. The loading splash
+/// template is an input, not a deployable file, and is skipped.
+fn apply_web_overlay(templates_dir: &Path, web_dir: &Path, cfg: &Config) -> Result<()> {
+    if !templates_dir.is_dir() {
+        return Ok(());
+    }
+    let skip = cfg
+        .web
+        .as_ref()
+        .and_then(|web| web.loading.as_ref())
+        .and_then(|loading| loading.template.as_deref())
+        .map(Path::new);
+    let copied = OverlayRenderer::new(cfg)?
+        .copy_dir_except(templates_dir, web_dir, |relative| Some(relative) == skip)?;
+    println!("✓ Web overlay applied: {} file(s) from {}", copied.len(), templates_dir.display());
+    Ok(())
+}
+
+pub fn process_web_platform(project_dir: &Path, cfg: &Config) -> Result<()> {
     let web_dir = project_dir.join("web");
+    let templates_dir = cfg.platforms_root(project_dir).join("web");
+    apply_web_overlay(&templates_dir, &web_dir, cfg)?;
+    write_assetlinks(&web_dir, cfg)?;
+    if let Some(web) = &cfg.web {
+        if let Some(icons) = &web.icons {
+            generate_web_icons(project_dir, &icons.source, web.background_color.as_deref())?;
+        }
+        apply_web_manifest(&web_dir, web)?;
+        apply_index_html(&web_dir, &templates_dir, cfg, web)?;
+        if let Some(loader) = &web.loader {
+            write_flutter_bootstrap(&web_dir, loader)?;
+        }
+        if let Some(hosting) = &web.hosting {
+            write_hosting_configs(project_dir, web, hosting)?;
+        }
+    }
     println!("Web directory generated at: {}", web_dir.display());
     Ok(())
 }
@@ -1,11 +1,683 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
+use serde_json::json;
 use std::fs;
 use std::path::Path;
 
-use crate::config::WindowsConfig;
+use crate::config::{
+    Config, WindowsCmakeConfig, WindowsConfig, WindowsFileAssociation, WindowsManifestConfig,
+};
+use crate::images::run_image_job;
+use crate::pubspec::read_pubspec_version;
+use crate::template::OverlayRenderer;
+use crate::utils::remove_marked_block;
+
+/// Sizes Explorer, the taskbar and Alt+Tab pick from at 100-200% scaling.
+const ICON_SIZES: [u32; 9] = [16, 20, 24, 32, 40, 48, 64, 128, 256];
+
+const MIN_SIZE_BEGIN: &str = "// flutter_gen_platforms:min_size:begin";
+const MIN_SIZE_END: &str = "// flutter_gen_platforms:min_size:end";
+const SINGLE_INSTANCE_BEGIN: &str = "// flutter_gen_platforms:single_instance:begin";
+const SINGLE_INSTANCE_END: &str = "// flutter_gen_platforms:single_instance:end";
+/// WM_COPYDATA tag ("FGPS") marking a command line forwarded by a second instance.
+const SINGLE_INSTANCE_COPYDATA: &str = "0x46475053";
+/// Method channel the running instance receives forwarded arguments on.
+const SINGLE_INSTANCE_CHANNEL: &str = "flutter_gen_platforms/single_instance";
+const REGISTRATION_BEGIN: &str = "// flutter_gen_platforms:registration:begin";
+const REGISTRATION_END: &str = "// flutter_gen_platforms:registration:end";
+const CMAKE_BEGIN: &str = "# flutter_gen_platforms:cmake:begin";
+const CMAKE_END: &str = "# flutter_gen_platforms:cmake:end";
+
+/// Snippet under `platforms/windows/` spliced into runner/CMakeLists.txt rather than copied.
+const CMAKE_SNIPPET: &str = "runner.cmake";
+
+/// Rewrites a runner source file line by line, keeping its line endings; writes only
+/// when something changed.
+fn edit_runner_file(path: &Path, edit: impl FnOnce(&mut Vec<String>) -> Result<()>) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    edit(&mut lines)?;
+    let updated = lines.join(newline) + newline;
+    if updated == content {
+        return Ok(false);
+    }
+    fs::write(path, updated).with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(true)
+}
+
+/// C++ wide string literal contents; non-ASCII characters become universal
+/// character names so the source stays valid without `/utf-8`.
+fn cpp_wide_string(value: &str) -> String {
+    let mut out = String::new();
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            c if c.is_ascii_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c if c.is_ascii() => out.push(c),
+            c if (c as u32) <= 0xFFFF => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push_str(&format!("\\U{:08x}", c as u32)),
+        }
+    }
+    out
+}
+
+/// Window title passed to `window.Create` in main.cpp.
+fn apply_window_title(runner_dir: &Path, title: &str) -> Result<()> {
+    let path = runner_dir.join("main.cpp");
+    let changed = edit_runner_file(&path, |lines| {
+        let Some(line) = lines.iter_mut().find(|line| line.contains("window.Create(L\"")) else {
+            bail!("No window.Create call found in: {}", path.display());
+        };
+        let start = line.find("L\"").unwrap_or(0);
+        let Some(end) = line[start + 2..].find("\", origin").map(|offset| start + 2 + offset) else {
+            bail!("Unexpected window.Create call in: {}", path.display());
+        };
+        line.replace_range(start..=end, &format!("L\"{}\"", cpp_wide_string(title)));
+        Ok(())
+    })?;
+    if changed {
+        println!("✓ Windows main.cpp updated with window title {:?}", title);
+    }
+    Ok(())
+}
+
+/// Single-instance check at the top of wWinMain: a named mutex (derived from the
+/// application id, so retitling the window or a localized title keeps one instance) detects
+/// a running instance, whose window is restored and brought to the front before this
+/// process exits.
+fn apply_single_instance(runner_dir: &Path, enabled: bool, application_id: &str) -> Result<()> {
+    let path = runner_dir.join("main.cpp");
+    let changed = edit_runner_file(&path, |lines| {
+        remove_marked_block(lines, SINGLE_INSTANCE_BEGIN, SINGLE_INSTANCE_END);
+        if !enabled {
+            return Ok(());
+        }
+        let Some(title) = lines.iter().find_map(|line| {
+            let start = line.find("window.Create(L\"")? + "window.Create(".len();
+            let end = line[start..].find(", origin")? + start;
+            Some(line[start..end].to_string())
+        }) else {
+            bail!("No window.Create call found in: {}", path.display());
+        };
+        let Some(main) = lines.iter().position(|line| line.contains("wWinMain(")) else {
+            bail!("No wWinMain found in: {}", path.display());
+        };
+        let Some(body) = lines[main..].iter().position(|line| line.trim_end().ends_with('{')) else {
+            bail!("Unexpected wWinMain in: {}", path.display());
+        };
+        let block = vec![
+            format!("  {}", SINGLE_INSTANCE_BEGIN),
+            format!(
+                "  ::CreateMutexW(nullptr, TRUE, L\"Local\\\\{}.SingleInstance\");",
+                cpp_wide_string(application_id)
+            ),
+            "  if (::GetLastError() == ERROR_ALREADY_EXISTS) {".to_string(),
+            format!("    HWND existing = ::FindWindowW(L\"FLUTTER_RUNNER_WIN32_WINDOW\", {});", title),
+            "    if (existing != nullptr) {".to_string(),
+            "      if (::IsIconic(existing)) {".to_string(),
+            "        ::ShowWindow(existing, SW_RESTORE);".to_string(),
+            "      }".to_string(),
+            "      ::SetForegroundWindow(existing);".to_string(),
+            "      const wchar_t* forwarded = ::GetCommandLineW();".to_string(),
+            format!("      COPYDATASTRUCT arguments{{{},", SINGLE_INSTANCE_COPYDATA),
+            "          static_cast<DWORD>((wcslen(forwarded) + 1) * sizeof(wchar_t)),".to_string(),
+            "          const_cast<wchar_t*>(forwarded)};".to_string(),
+            "      ::SendMessageW(existing, WM_COPYDATA, 0, reinterpret_cast<LPARAM>(&arguments));".to_string(),
+            "    }".to_string(),
+            "    return EXIT_SUCCESS;".to_string(),
+            "  }".to_string(),
+            format!("  {}", SINGLE_INSTANCE_END),
+        ];
+        let insert_at = main + body + 1;
+        lines.splice(insert_at..insert_at, block);
+        Ok(())
+    })?;
+    if changed {
+        println!("✓ Windows main.cpp updated with single-instance check");
+    }
+    apply_single_instance_receiver(runner_dir, enabled)
+}
+
+/// WM_COPYDATA handler in flutter_window.cpp for the command line a second instance
+/// forwards: the arguments after the executable reach Dart as a list of strings through
+/// `arguments` on the SINGLE_INSTANCE_CHANNEL method channel.
+fn apply_single_instance_receiver(runner_dir: &Path, enabled: bool) -> Result<()> {
+    let path = runner_dir.join("flutter_window.cpp");
+    let changed = edit_runner_file(&path, |lines| {
+        remove_marked_block(lines, SINGLE_INSTANCE_BEGIN, SINGLE_INSTANCE_END);
+        if !enabled {
+            return Ok(());
+        }
+        let includes_end = lines.iter().rposition(|line| line.starts_with("#include")).map_or(0, |index| index + 1);
+        lines.splice(
+            includes_end..includes_end,
+            [
+                SINGLE_INSTANCE_BEGIN.to_string(),
+                "#include <flutter/method_channel.h>".to_string(),
+                "#include <flutter/standard_method_codec.h>".to_string(),
+                "#include <shellapi.h>".to_string(),
+                String::new(),
+                "#include \"utils.h\"".to_string(),
+                SINGLE_INSTANCE_END.to_string(),
+            ],
+        );
+        let Some(switch) = lines.iter().position(|line| line.trim() == "switch (message) {") else {
+            bail!("No message switch found in: {}", path.display());
+        };
+        let block = vec![
+            format!("    {}", SINGLE_INSTANCE_BEGIN),
+            "    case WM_COPYDATA: {".to_string(),
+            "      auto data = reinterpret_cast<const COPYDATASTRUCT*>(lparam);".to_string(),
+            format!("      if (data->dwData != {} || !flutter_controller_) {{", SINGLE_INSTANCE_COPYDATA),
+            "        break;".to_string(),
+            "      }".to_string(),
+            "      int argc = 0;".to_string(),
+            "      wchar_t** argv = ::CommandLineToArgvW(static_cast<const wchar_t*>(data->lpData), &argc);".to_string(),
+            "      flutter::EncodableList arguments;".to_string(),
+            "      for (int i = 1; argv != nullptr && i < argc; i++) {".to_string(),
+            "        arguments.push_back(flutter::EncodableValue(Utf8FromUtf16(argv[i])));".to_string(),
+            "      }".to_string(),
+            "      ::LocalFree(argv);".to_string(),
+            "      flutter::MethodChannel<flutter::EncodableValue> channel(".to_string(),
+            format!("          flutter_controller_->engine()->messenger(), \"{}\",", SINGLE_INSTANCE_CHANNEL),
+            "          &flutter::StandardMethodCodec::GetInstance());".to_string(),
+            "      channel.InvokeMethod(\"arguments\", std::make_unique<flutter::EncodableValue>(arguments));".to_string(),
+            "      return TRUE;".to_string(),
+            "    }".to_string(),
+            format!("    {}", SINGLE_INSTANCE_END),
+        ];
+        lines.splice(switch + 1..switch + 1, block);
+        Ok(())
+    })?;
+    if changed {
+        println!("✓ Windows flutter_window.cpp forwards a second instance's arguments to Dart");
+    }
+    Ok(())
+}
+
+/// Helpers inserted before wWinMain that register the URL protocols and file types
+/// under HKEY_CURRENT_USER\Software\Classes (no elevation needed). They run on every
+/// start so the registration follows the exe when the app is moved or updated.
+const REGISTRATION_HELPERS: &str = r#"#include <shlobj.h>
+
+#include <filesystem>
+#include <string>
+
+namespace {
+
+{{tables}}
+
+void SetRegistryString(const std::wstring& key, const wchar_t* name,
+                       const std::wstring& value) {
+  ::RegSetKeyValueW(HKEY_CURRENT_USER, key.c_str(), name, REG_SZ, value.c_str(),
+                    static_cast<DWORD>((value.size() + 1) * sizeof(wchar_t)));
+}
+
+bool HasRegistryDefault(const std::wstring& key) {
+  return ::RegGetValueW(HKEY_CURRENT_USER, key.c_str(), nullptr, RRF_RT_REG_SZ,
+                        nullptr, nullptr, nullptr) == ERROR_SUCCESS;
+}
+
+void RegisterShellHandlers() {
+  wchar_t exe_path[MAX_PATH];
+  DWORD length = ::GetModuleFileNameW(nullptr, exe_path, MAX_PATH);
+  if (length == 0 || length == MAX_PATH) {
+    return;
+  }
+  const std::wstring exe(exe_path);
+  const std::wstring exe_name = std::filesystem::path(exe).stem().wstring();
+  const std::wstring open_command = L"\"" + exe + L"\" \"%1\"";
+  const std::wstring classes = L"Software\\Classes\\";
+  for (const auto& protocol : kUrlProtocols) {
+    if (protocol[0][0] == L'\0') {
+      continue;
+    }
+    const std::wstring key = classes + protocol[0];
+    SetRegistryString(key, nullptr, protocol[1]);
+    SetRegistryString(key, L"URL Protocol", L"");
+    SetRegistryString(key + L"\\DefaultIcon", nullptr, exe + L",0");
+    SetRegistryString(key + L"\\shell\\open\\command", nullptr, open_command);
+  }
+  for (const auto& file_type : kFileTypes) {
+    if (file_type[0][0] == L'\0') {
+      continue;
+    }
+    const std::wstring prog_id =
+        file_type[2][0] != L'\0' ? std::wstring(file_type[2])
+                                 : exe_name + file_type[0];
+    SetRegistryString(classes + prog_id, nullptr, file_type[1]);
+    SetRegistryString(classes + prog_id + L"\\DefaultIcon", nullptr, exe + L",0");
+    SetRegistryString(classes + prog_id + L"\\shell\\open\\command", nullptr,
+                      open_command);
+    const std::wstring extension_key = classes + file_type[0];
+    SetRegistryString(extension_key + L"\\OpenWithProgids", prog_id.c_str(), L"");
+    if (!HasRegistryDefault(extension_key)) {
+      SetRegistryString(extension_key, nullptr, prog_id);
+    }
+  }
+  ::SHChangeNotify(SHCNE_ASSOCCHANGED, SHCNF_IDLIST, nullptr, nullptr);
+}
+
+}  // namespace"#;
+
+/// URL protocol and file association registration in main.cpp.
+fn apply_shell_registration(
+    runner_dir: &Path,
+    protocols: &[String],
+    file_associations: &[WindowsFileAssociation],
+) -> Result<()> {
+    let mut protocol_rows = Vec::new();
+    for scheme in protocols {
+        let valid = scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        if !valid {
+            bail!("Invalid windows.protocols scheme '{}': expected letters, digits, '+', '-' or '.'", scheme);
+        }
+        protocol_rows.push(format!(
+            "    {{L\"{}\", L\"URL:{} Protocol\"}},",
+            scheme.to_ascii_lowercase(),
+            cpp_wide_string(scheme)
+        ));
+    }
+    let mut file_rows = Vec::new();
+    for association in file_associations {
+        let extension = format!(".{}", association.extension.trim_start_matches('.'));
+        if extension.len() < 2 || extension[1..].contains(['.', '\\', '/', ' ']) {
+            bail!("Invalid windows.file_associations extension: {}", association.extension);
+        }
+        file_rows.push(format!(
+            "    {{L\"{}\", L\"{}\", L\"{}\"}},",
+            cpp_wide_string(&extension.to_ascii_lowercase()),
+            cpp_wide_string(association.description.as_deref().unwrap_or("")),
+            cpp_wide_string(association.prog_id.as_deref().unwrap_or(""))
+        ));
+    }
+
+    let path = runner_dir.join("main.cpp");
+    let changed = edit_runner_file(&path, |lines| {
+        // Both blocks are inserted with a separator line after them.
+        for start in remove_marked_block(lines, REGISTRATION_BEGIN, REGISTRATION_END).into_iter().rev() {
+            if lines.get(start).is_some_and(|line| line.is_empty()) {
+                lines.remove(start);
+            }
+        }
+        if protocol_rows.is_empty() && file_rows.is_empty() {
+            return Ok(());
+        }
+        let Some(main) = lines.iter().position(|line| line.contains("wWinMain(")) else {
+            bail!("No wWinMain found in: {}", path.display());
+        };
+        // Arrays can't be empty, so a missing kind gets a single empty row that is skipped.
+        let mut tables = vec!["const wchar_t* kUrlProtocols[][2] = {".to_string()];
+        if protocol_rows.is_empty() {
+            tables.push("    {L\"\", L\"\"},".to_string());
+        }
+        tables.extend(protocol_rows.iter().cloned());
+        tables.push("};".to_string());
+        tables.push("const wchar_t* kFileTypes[][3] = {".to_string());
+        if file_rows.is_empty() {
+            tables.push("    {L\"\", L\"\", L\"\"},".to_string());
+        }
+        tables.extend(file_rows.iter().cloned());
+        tables.push("};".to_string());
+
+        let mut helpers = vec![REGISTRATION_BEGIN.to_string()];
+        for line in REGISTRATION_HELPERS.lines() {
+            if line == "{{tables}}" {
+                helpers.extend(tables.iter().cloned());
+            } else {
+                helpers.push(line.to_string());
+            }
+        }
+        helpers.push(REGISTRATION_END.to_string());
+        helpers.push(String::new());
+        lines.splice(main..main, helpers);
+
+        let Some(project) = lines.iter().position(|line| line.contains("flutter::DartProject project(")) else {
+            bail!("No DartProject found in: {}", path.display());
+        };
+        lines.splice(
+            project..project,
+            [
+                format!("  {}", REGISTRATION_BEGIN),
+                "  RegisterShellHandlers();".to_string(),
+                format!("  {}", REGISTRATION_END),
+                String::new(),
+            ],
+        );
+        Ok(())
+    })?;
+    if changed {
+        println!(
+            "✓ Windows main.cpp registers {} URL protocol(s) and {} file type(s)",
+            protocols.len(),
+            file_associations.len()
+        );
+    }
+    Ok(())
+}
+
+/// `1.2.3-beta+4` -> (`1.2.3-beta+4`, `1,2,3,4`), like FLUTTER_VERSION and its
+/// numeric parts; a missing build number is 0.
+fn version_parts(version: &str) -> Result<(String, String)> {
+    let (name, build) = version.split_once('+').unwrap_or((version, "0"));
+    let core = name.split('-').next().unwrap_or(name);
+    let mut numbers = Vec::new();
+    for part in core.split('.').chain(std::iter::once(build)) {
+        let number: u16 = part
+            .parse()
+            .with_context(|| format!("Invalid version for Runner.rc (parts must be 0-65535): {}", version))?;
+        numbers.push(number.to_string());
+    }
+    if numbers.len() != 4 {
+        bail!("Invalid version for Runner.rc (expected major.minor.patch[+build]): {}", version);
+    }
+    Ok((version.to_string(), numbers.join(",")))
+}
+
+/// VERSIONINFO strings in Runner.rc, plus the fallback version used when the build
+/// does not pass FLUTTER_VERSION (the Flutter CMake build takes it from pubspec.yaml).
+fn apply_version_info(runner_dir: &Path, config: &WindowsConfig, version: Option<&str>) -> Result<()> {
+    let path = runner_dir.join("Runner.rc");
+    let product_name = config.product_name.as_ref().or(config.title.as_ref());
+    let values: Vec<(&str, &String)> = [
+        ("CompanyName", config.company.as_ref()),
+        ("LegalCopyright", config.copyright.as_ref()),
+        ("ProductName", product_name),
+        ("FileDescription", product_name),
+    ]
+    .into_iter()
+    .filter_map(|(key, value)| value.map(|value| (key, value)))
+    .collect();
+    let version = version.map(version_parts).transpose()?;
+    let changed = edit_runner_file(&path, |lines| {
+        for (key, value) in &values {
+            let needle = format!("VALUE \"{}\",", key);
+            let Some(line) = lines.iter_mut().find(|line| line.trim_start().starts_with(&needle)) else {
+                bail!("No {} entry found in: {}", key, path.display());
+            };
+            let indent = &line[..line.len() - line.trim_start().len()];
+            *line = format!("{}{} \"{}\" \"\\0\"", indent, needle, value.replace('"', "\"\""));
+        }
+        if let Some((string, numbers)) = &version {
+            for line in lines.iter_mut() {
+                let trimmed = line.trim_start();
+                if trimmed.contains("FLUTTER_VERSION") {
+                    continue;
+                }
+                if trimmed.starts_with("#define VERSION_AS_NUMBER ") {
+                    *line = format!("#define VERSION_AS_NUMBER {}", numbers);
+                } else if trimmed.starts_with("#define VERSION_AS_STRING ") {
+                    *line = format!("#define VERSION_AS_STRING \"{}\"", string);
+                }
+            }
+        }
+        Ok(())
+    })?;
+    if changed {
+        println!("✓ Windows Runner.rc version info updated");
+    }
+    Ok(())
+}
+
+/// Replaces runner/resources/app_icon.ico, which Runner.rc embeds as IDI_APP_ICON.
+fn generate_windows_icon(project_dir: &Path, source: &str) -> Result<()> {
+    if !project_dir.join(source).exists() {
+        bail!("Windows icon source image not found: {}", project_dir.join(source).display());
+    }
+    let outputs = [json!({
+        "path": "windows/runner/resources/app_icon.ico",
+        "format": "ico",
+        "sizes": ICON_SIZES,
+    })];
+    run_image_job(project_dir, &json!({ "images": [{ "source": source, "outputs": outputs }] }))?;
+    println!("✓ Generated Windows app icon from {}", source);
+    Ok(())
+}
+
+/// Initial size in main.cpp.
+fn apply_window_size(runner_dir: &Path, width: u32, height: u32) -> Result<()> {
+    let path = runner_dir.join("main.cpp");
+    let changed = edit_runner_file(&path, |lines| {
+        let Some(line) = lines.iter_mut().find(|line| line.contains("Win32Window::Size size(")) else {
+            bail!("No Win32Window::Size found in: {}", path.display());
+        };
+        *line = format!("  Win32Window::Size size({}, {});", width, height);
+        Ok(())
+    })?;
+    if changed {
+        println!("✓ Windows main.cpp updated with window size {}x{}", width, height);
+    }
+    Ok(())
+}
+
+/// Window style and show command in win32_window.cpp: a fixed-size window drops the
+/// sizing border and maximize box, and `maximized` shows the window maximized when the
+/// first frame is ready (FlutterWindow calls Show() then).
+fn apply_window_style(runner_dir: &Path, resizable: bool, maximized: bool) -> Result<()> {
+    let path = runner_dir.join("win32_window.cpp");
+    let style = if resizable {
+        "WS_OVERLAPPEDWINDOW"
+    } else {
+        "WS_OVERLAPPEDWINDOW & ~(WS_THICKFRAME | WS_MAXIMIZEBOX)"
+    };
+    let show = if maximized { "SW_SHOWMAXIMIZED" } else { "SW_SHOWNORMAL" };
+    let changed = edit_runner_file(&path, |lines| {
+        let Some(line) = lines.iter_mut().find(|line| line.contains("title.c_str(), WS_OVERLAPPEDWINDOW")) else {
+            bail!("No CreateWindow call found in: {}", path.display());
+        };
+        let indent = &line[..line.len() - line.trim_start().len()];
+        *line = format!("{}window_class, title.c_str(), {},", indent, style);
+        let Some(line) = lines.iter_mut().find(|line| line.contains("ShowWindow(window_handle_, SW_SHOW")) else {
+            bail!("No ShowWindow call found in: {}", path.display());
+        };
+        *line = format!("  return ShowWindow(window_handle_, {});", show);
+        Ok(())
+    })?;
+    if changed {
+        println!(
+            "✓ Windows win32_window.cpp updated (resizable: {}, maximized: {})",
+            resizable, maximized
+        );
+    }
+    Ok(())
+}
+
+/// Minimum size via WM_GETMINMAXINFO in flutter_window.cpp, in logical pixels scaled
+/// by the window's DPI like the initial size.
+fn apply_min_size(runner_dir: &Path, min_width: Option<u32>, min_height: Option<u32>) -> Result<()> {
+    let path = runner_dir.join("flutter_window.cpp");
+    let changed = edit_runner_file(&path, |lines| {
+        remove_marked_block(lines, MIN_SIZE_BEGIN, MIN_SIZE_END);
+        if min_width.is_none() && min_height.is_none() {
+            return Ok(());
+        }
+        let Some(switch) = lines.iter().position(|line| line.trim() == "switch (message) {") else {
+            bail!("No message switch found in: {}", path.display());
+        };
+        let mut block = vec![
+            format!("    {}", MIN_SIZE_BEGIN),
+            "    case WM_GETMINMAXINFO: {".to_string(),
+            "      double scale = FlutterDesktopGetDpiForHWND(hwnd) / 96.0;".to_string(),
+            "      auto info = reinterpret_cast<MINMAXINFO*>(lparam);".to_string(),
+        ];
+        if let Some(width) = min_width {
+            block.push(format!("      info->ptMinTrackSize.x = static_cast<LONG>({} * scale);", width));
+        }
+        if let Some(height) = min_height {
+            block.push(format!("      info->ptMinTrackSize.y = static_cast<LONG>({} * scale);", height));
+        }
+        block.push("      return 0;".to_string());
+        block.push("    }".to_string());
+        block.push(format!("    {}", MIN_SIZE_END));
+        lines.splice(switch + 1..switch + 1, block);
+        Ok(())
+    })?;
+    if changed {
+        println!("✓ Windows flutter_window.cpp updated with minimum size");
+    }
+    Ok(())
+}
+
+/// Writes runner/runner.exe.manifest. Flutter's runner CMakeLists lists the manifest as
+/// an executable source, which MSVC embeds as the RT_MANIFEST resource, so it must not
+/// also be referenced from Runner.rc (the linker rejects a second manifest).
+fn write_app_manifest(runner_dir: &Path, manifest: &WindowsManifestConfig) -> Result<()> {
+    let mut lines = vec![
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#.to_string(),
+        r#"<assembly xmlns="urn:schemas-microsoft-com:asm.v1" manifestVersion="1.0">"#.to_string(),
+        r#"  <application xmlns="urn:schemas-microsoft-com:asm.v3">"#.to_string(),
+        "    <windowsSettings>".to_string(),
+        format!(
+            r#"      <dpiAware xmlns="http://schemas.microsoft.com/SMI/2005/WindowsSettings">{}</dpiAware>"#,
+            manifest.dpi_awareness.legacy_value()
+        ),
+        format!(
+            r#"      <dpiAwareness xmlns="http://schemas.microsoft.com/SMI/2016/WindowsSettings">{}</dpiAwareness>"#,
+            manifest.dpi_awareness.as_str()
+        ),
+    ];
+    if manifest.long_path_aware {
+        lines.push(
+            r#"      <longPathAware xmlns="http://schemas.microsoft.com/SMI/2016/WindowsSettings">true</longPathAware>"#
+                .to_string(),
+        );
+    }
+    lines.extend([
+        "    </windowsSettings>".to_string(),
+        "  </application>".to_string(),
+        r#"  <compatibility xmlns="urn:schemas-microsoft-com:compatibility.v1">"#.to_string(),
+        "    <application>".to_string(),
+        "      <!-- Windows 10 and Windows 11 -->".to_string(),
+        r#"      <supportedOS Id="{8e0f7a12-bfb3-4fe8-b9a5-48fd50a15a9a}"/>"#.to_string(),
+        "    </application>".to_string(),
+        "  </compatibility>".to_string(),
+    ]);
+    if let Some(level) = manifest.execution_level {
+        lines.extend([
+            r#"  <trustInfo xmlns="urn:schemas-microsoft-com:asm.v3">"#.to_string(),
+            "    <security>".to_string(),
+            "      <requestedPrivileges>".to_string(),
+            format!(r#"        <requestedExecutionLevel level="{}" uiAccess="false"/>"#, level.as_str()),
+            "      </requestedPrivileges>".to_string(),
+            "    </security>".to_string(),
+            "  </trustInfo>".to_string(),
+        ]);
+    }
+    lines.push("</assembly>".to_string());
+
+    let path = runner_dir.join("runner.exe.manifest");
+    let newline = match fs::read_to_string(&path) {
+        Ok(existing) if existing.contains("\r\n") => "\r\n",
+        _ => "\n",
+    };
+    fs::write(&path, lines.join(newline) + newline)
+        .with_context(|| format!("Failed to write file: {}", path.display()))?;
+
+    let cmake = runner_dir.join("CMakeLists.txt");
+    edit_runner_file(&cmake, |lines| {
+        if lines.iter().any(|line| line.contains("runner.exe.manifest")) {
+            return Ok(());
+        }
+        let Some(rc) = lines.iter().position(|line| line.trim() == "\"Runner.rc\"") else {
+            bail!("No Runner.rc source found in: {}", cmake.display());
+        };
+        lines.insert(rc + 1, "  \"runner.exe.manifest\"".to_string());
+        Ok(())
+    })?;
+    println!(
+        "✓ Windows runner.exe.manifest written (dpi: {}, long paths: {})",
+        manifest.dpi_awareness.as_str(),
+        manifest.long_path_aware
+    );
+    Ok(())
+}
+
+/// CMake quoted argument.
+fn cmake_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Project-relative path as seen from windows/CMakeLists.txt.
+fn cmake_project_path(path: &str) -> String {
+    cmake_quote(&format!("${{CMAKE_SOURCE_DIR}}/../{}", path.trim_start_matches("./").replace('\\', "/")))
+}
+
+/// Appends the configured sources, include dirs, defines, libraries and C++ standard,
+/// plus the runner.cmake snippet, to runner/CMakeLists.txt as one marked block.
+fn apply_cmake(project_dir: &Path, runner_dir: &Path, cmake: &WindowsCmakeConfig, snippet: &Path) -> Result<()> {
+    for path in cmake.sources.iter().chain(&cmake.include_dirs) {
+        if !project_dir.join(path).exists() {
+            bail!("windows.cmake path not found: {}", project_dir.join(path).display());
+        }
+    }
+    let mut block = Vec::new();
+    if !cmake.sources.is_empty() {
+        block.push("target_sources(${BINARY_NAME} PRIVATE".to_string());
+        block.extend(cmake.sources.iter().map(|source| format!("  {}", cmake_project_path(source))));
+        block.push(")".to_string());
+    }
+    for dir in &cmake.include_dirs {
+        block.push(format!("target_include_directories(${{BINARY_NAME}} PRIVATE {})", cmake_project_path(dir)));
+    }
+    for define in &cmake.defines {
+        block.push(format!("target_compile_definitions(${{BINARY_NAME}} PRIVATE {})", cmake_quote(define)));
+    }
+    for library in &cmake.libraries {
+        block.push(format!("target_link_libraries(${{BINARY_NAME}} PRIVATE {})", cmake_quote(library)));
+    }
+    if let Some(standard) = cmake.cpp_standard {
+        if ![17, 20, 23].contains(&standard) {
+            bail!("windows.cmake.cpp_standard must be 17, 20 or 23, got {}", standard);
+        }
+        block.push(format!("target_compile_features(${{BINARY_NAME}} PRIVATE cxx_std_{})", standard));
+    }
+    if snippet.exists() {
+        let content = fs::read_to_string(snippet)
+            .with_context(|| format!("Failed to read file: {}", snippet.display()))?;
+        block.extend(content.lines().map(|line| line.to_string()));
+    }
+
+    let path = runner_dir.join("CMakeLists.txt");
+    let changed = edit_runner_file(&path, |lines| {
+        remove_marked_block(lines, CMAKE_BEGIN, CMAKE_END);
+        if block.is_empty() {
+            return Ok(());
+        }
+        lines.push(CMAKE_BEGIN.to_string());
+        lines.extend(block);
+        lines.push(CMAKE_END.to_string());
+        Ok(())
+    })?;
+    if changed {
+        println!("✓ Windows runner CMakeLists.txt updated");
+    }
+    Ok(())
+}
+
+/// Copies everything under `platforms/windows/` over the generated windows/ directory,
+/// expanding `{{variables}}` in text files, so custom runner code (tray icons, COM
+/// setup, extra sources) survives regeneration. Runs before the config-driven edits,
+/// which then apply to the copied files.
+fn apply_windows_overlay(templates_dir: &Path, windows_dir: &Path, cfg: &Config) -> Result<()> {
+    if !templates_dir.is_dir() {
+        return Ok(());
+    }
+    let copied = OverlayRenderer::new(cfg)?
+        .copy_dir_except(templates_dir, windows_dir, |relative| relative == Path::new(CMAKE_SNIPPET))?;
+    if !copied.is_empty() {
+        println!("✓ Windows overlay applied: {} file(s) from {}", copied.len(), templates_dir.display());
+    }
+    Ok(())
+}
 
 /// Process Windows platform directory
-pub fn process_windows_platform(project_dir: &Path, config: &WindowsConfig) -> Result<()> {
+pub fn process_windows_platform(project_dir: &Path, cfg: &Config) -> Result<()> {
     let windows_dir = project_dir.join("windows");
 
     if !windows_dir.exists() {
@@ -13,33 +685,42 @@ pub fn process_windows_platform(project_dir: &Path, config: &WindowsConfig) -> R
             "Windows directory not found. Run 'flutter create --platforms=windows .' first."
         );
     }
+    let runner_dir = windows_dir.join("runner");
+    let default_config = WindowsConfig::default();
+    let config = cfg.windows.as_ref().unwrap_or(&default_config);
 
-    // Update main.cpp with window size if configured
-    if let (Some(width), Some(height)) = (config.window_width, config.window_height) {
-        let main_cpp_path = windows_dir.join("runner").join("main.cpp");
-        if main_cpp_path.exists() {
-            let content = fs::read_to_string(&main_cpp_path)
-                .context("Failed to read main.cpp")?;
-            
-            // Replace the window size line
-            let updated_content = content
-                .lines()
-                .map(|line| {
-                    if line.contains("Win32Window::Size size(") {
-                        format!("  Win32Window::Size size({}, {});  // Configured window size", width, height)
-                    } else {
-                        line.to_string()
-                    }
-                })
-                .collect::<Vec<_>>()
-                .join("\n");
-            
-            fs::write(&main_cpp_path, updated_content)
-                .context("Failed to write main.cpp")?;
-            
-            println!("✓ Windows main.cpp updated with window size {}x{}", width, height);
-        }
+    let templates_dir = cfg.platforms_root(project_dir).join("windows");
+    apply_windows_overlay(&templates_dir, &windows_dir, cfg)?;
+
+    match (config.window_width, config.window_height) {
+        (Some(width), Some(height)) => apply_window_size(&runner_dir, width, height)?,
+        (None, None) => {}
+        _ => bail!("windows.window_width and windows.window_height must be set together"),
+    }
+    if let (Some(width), Some(height)) = (config.window_width, config.window_height)
+        && (config.min_width.is_some_and(|min| min > width)
+            || config.min_height.is_some_and(|min| min > height))
+    {
+        bail!("windows.min_width/min_height must not exceed the window size {}x{}", width, height);
+    }
+    if let Some(title) = &config.title {
+        apply_window_title(&runner_dir, title)?;
+    }
+    apply_single_instance(&runner_dir, config.single_instance, &cfg.android.app.build.application_id)?;
+    apply_shell_registration(&runner_dir, &config.protocols, &config.file_associations)?;
+    if let Some(icon) = &config.icon {
+        generate_windows_icon(project_dir, &icon.source)?;
+    }
+    // pubspec.yaml is what `flutter build windows` reads, so it wins over the config.
+    let pubspec = project_dir.join("pubspec.yaml");
+    let pubspec_version = if pubspec.exists() { read_pubspec_version(&pubspec)? } else { None };
+    apply_version_info(&runner_dir, config, pubspec_version.as_deref().or(cfg.version.as_deref()))?;
+    apply_window_style(&runner_dir, config.resizable, config.maximized)?;
+    apply_min_size(&runner_dir, config.min_width, config.min_height)?;
+    if let Some(manifest) = &config.manifest {
+        write_app_manifest(&runner_dir, manifest)?;
     }
+    apply_cmake(project_dir, &runner_dir, &config.cmake, &templates_dir.join(CMAKE_SNIPPET))?;
 
     println!("✓ Windows platform directory configured");
 
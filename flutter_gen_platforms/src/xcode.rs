@@ -0,0 +1,291 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::Path;
+
+/// Project file of the Runner project in a generated ios/ or macos/ directory.
+pub const PBXPROJ: &str = "Runner.xcodeproj/project.pbxproj";
+
+/// Overlay filter for Apple platforms: the Xcode project is generated, never copied.
+pub fn skip_xcode_project(relative: &Path) -> bool {
+    let skip = relative.starts_with("Runner.xcodeproj");
+    if skip {
+        println!("Warning: Skipping overlay file {}: the Xcode project is generated", relative.display());
+    }
+    skip
+}
+
+/// The `buildSettings` of one XCBuildConfiguration: its configuration name (Debug,
+/// Release, Profile) and the line range between the braces.
+pub struct BuildSettings {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn build_settings_blocks(lines: &[String]) -> Vec<BuildSettings> {
+    let mut blocks = Vec::new();
+    let mut name: Option<String> = None;
+    let mut i = 0;
+    while i < lines.len() {
+        let trimmed = lines[i].trim();
+        if trimmed.ends_with("= {")
+            && let Some((_, rest)) = trimmed.split_once("/* ")
+            && let Some((comment, _)) = rest.split_once(" */")
+        {
+            name = Some(comment.to_string());
+        } else if trimmed == "buildSettings = {"
+            && let Some(name) = &name
+        {
+            let Some(end) = lines[i + 1..].iter().position(|line| line.trim() == "};") else {
+                break;
+            };
+            blocks.push(BuildSettings {
+                name: name.clone(),
+                start: i + 1,
+                end: i + 1 + end,
+            });
+            i += end + 1;
+        }
+        i += 1;
+    }
+    blocks
+}
+
+/// Configurations of the Runner app target (not the project or RunnerTests).
+pub fn is_runner_target(lines: &[String], block: &BuildSettings) -> bool {
+    lines[block.start..block.end]
+        .iter()
+        .any(|line| line.trim() == "INFOPLIST_FILE = Runner/Info.plist;")
+}
+
+/// A pbxproj key or value, quoted unless it is a plain word, number or path.
+pub fn pbx_quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "_./".contains(c)) {
+        value.to_string()
+    } else {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Unquoted key of a `KEY = value;` settings line.
+pub fn setting_key(line: &str) -> Option<&str> {
+    let (key, _) = line.trim().split_once(" = ")?;
+    Some(key.trim_matches('"'))
+}
+
+/// Sets, or with `None` removes, build settings in the selected configurations, keeping
+/// Xcode's alphabetical order. `settings` gets the configuration name.
+pub fn set_build_settings(
+    lines: &mut Vec<String>,
+    select: impl Fn(&[String], &BuildSettings) -> bool,
+    settings: impl Fn(&str) -> Vec<(String, Option<String>)>,
+) {
+    // Back to front, so edits don't move the blocks still to be visited.
+    for block in build_settings_blocks(lines).into_iter().rev() {
+        if !select(lines, &block) {
+            continue;
+        }
+        let indent: String = lines[block.start]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let mut end = block.end;
+        for (key, value) in settings(&block.name) {
+            if let Some(pos) = (block.start..end).find(|&i| setting_key(&lines[i]) == Some(key.as_str())) {
+                // A list value runs until its closing `);`
+                let stop = if lines[pos].trim_end().ends_with('(') {
+                    (pos..end).find(|&i| lines[i].trim() == ");").unwrap_or(pos)
+                } else {
+                    pos
+                };
+                lines.drain(pos..=stop);
+                end -= stop - pos + 1;
+            }
+            let Some(value) = value else {
+                continue;
+            };
+            let insert_at = (block.start..end)
+                .find(|&i| {
+                    lines[i].starts_with(&indent)
+                        && !lines[i][indent.len()..].starts_with(char::is_whitespace)
+                        && setting_key(&lines[i]).is_some_and(|existing| existing > key.as_str())
+                })
+                .unwrap_or(end);
+            lines.insert(insert_at, format!("{}{} = {};", indent, pbx_quote(&key), pbx_quote(&value)));
+            end += 1;
+        }
+    }
+}
+
+/// Stable 24-digit object id derived from `seed`, so regenerating yields the same pbxproj.
+/// SHA-256 rather than `DefaultHasher`, whose output may change between Rust releases.
+fn object_id(seed: &str) -> String {
+    let digest = format!("{:X}", Sha256::digest(format!("flutter_gen_platforms:{}", seed)));
+    digest[..24].to_string()
+}
+
+/// Line range of `/* Begin <name> section */` .. `/* End <name> section */`.
+fn section(lines: &[String], name: &str) -> Result<(usize, usize)> {
+    let begin = format!("/* Begin {} section */", name);
+    let end = format!("/* End {} section */", name);
+    let Some(start) = lines.iter().position(|line| line.trim() == begin) else {
+        bail!("{} section not found in {}", name, PBXPROJ);
+    };
+    let Some(stop) = lines[start..].iter().position(|line| line.trim() == end) else {
+        bail!("Unterminated {} section in {}", name, PBXPROJ);
+    };
+    Ok((start, start + stop))
+}
+
+/// Line range (header through `};`) of the object in `section` whose header comment is
+/// `comment` and whose body has the line `marker`.
+fn find_object(lines: &[String], section_name: &str, comment: &str, marker: &str) -> Result<(usize, usize)> {
+    let (start, stop) = section(lines, section_name)?;
+    let header = format!("/* {} */ = {{", comment);
+    let mut i = start;
+    while i < stop {
+        if lines[i].trim_end().ends_with(&header) {
+            let indent = &lines[i][..lines[i].len() - lines[i].trim_start().len()];
+            let close = format!("{}}};", indent);
+            let end = (i + 1..stop).find(|&j| lines[j].trim_end() == close).unwrap_or(stop);
+            if lines[i..end].iter().any(|line| line.trim() == marker) {
+                return Ok((i, end));
+            }
+            i = end;
+        }
+        i += 1;
+    }
+    bail!("{} {} not found in {}", section_name, comment, PBXPROJ)
+}
+
+/// Appends `entry` to the `<list> = ( ... );` of the object spanning `start..end`.
+fn add_to_list(lines: &mut Vec<String>, (start, end): (usize, usize), list: &str, entry: &str) -> Result<()> {
+    let open = format!("{} = (", list);
+    let Some(list_start) = (start..end).find(|&i| lines[i].trim() == open) else {
+        bail!("{} list not found in {}", list, lines[start].trim());
+    };
+    let Some(close) = (list_start..end).find(|&i| lines[i].trim() == ");") else {
+        bail!("Unterminated {} list in {}", list, lines[start].trim());
+    };
+    let indent = &lines[list_start][..lines[list_start].len() - lines[list_start].trim_start().len()];
+    let line = format!("{}\t{},", indent, entry);
+    lines.insert(close, line);
+    Ok(())
+}
+
+/// Line range of the object `id` in `section`.
+fn object_by_id(lines: &[String], section_name: &str, id: &str) -> Result<(usize, usize)> {
+    let (start, stop) = section(lines, section_name)?;
+    let header = format!("{} /* ", id);
+    let Some(begin) = (start..stop).find(|&i| lines[i].trim_start().starts_with(&header)) else {
+        bail!("{} {} not found in {}", section_name, id, PBXPROJ);
+    };
+    let indent = &lines[begin][..lines[begin].len() - lines[begin].trim_start().len()];
+    let close = format!("{}}};", indent);
+    let end = (begin + 1..stop).find(|&j| lines[j].trim_end() == close).unwrap_or(stop);
+    Ok((begin, end))
+}
+
+/// Adds `Runner/<path>` to the Runner group and, with `phase` ("Sources" or "Resources"),
+/// to that build phase of the Runner target. Returns false when the file is already
+/// referenced.
+pub fn add_runner_file(lines: &mut Vec<String>, path: &str, file_type: &str, phase: Option<&str>) -> Result<bool> {
+    let (refs_start, refs_end) = section(lines, "PBXFileReference")?;
+    let path_attr = format!("path = {};", pbx_quote(path));
+    if lines[refs_start..refs_end].iter().any(|line| line.contains(&path_attr)) {
+        return Ok(false);
+    }
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    let file_ref = object_id(&format!("{}:fileRef", path));
+    let build_file = object_id(&format!("{}:buildFile", path));
+
+    if let Some(phase) = phase {
+        // The target's phase, looked up through its buildPhases list.
+        let target = find_object(lines, "PBXNativeTarget", "Runner", "isa = PBXNativeTarget;")?;
+        let suffix = format!("/* {} */,", phase);
+        let Some(phase_id) = lines[target.0..target.1]
+            .iter()
+            .find(|line| line.trim().ends_with(&suffix))
+            .and_then(|line| line.split_whitespace().next())
+            .map(|id| id.to_string())
+        else {
+            bail!("Runner target has no {} build phase in {}", phase, PBXPROJ);
+        };
+        let phase_object = object_by_id(lines, &format!("PBX{}BuildPhase", phase), &phase_id)?;
+        add_to_list(lines, phase_object, "files", &format!("{} /* {} in {} */", build_file, file_name, phase))?;
+    }
+
+    let group = find_object(lines, "PBXGroup", "Runner", "path = Runner;")?;
+    add_to_list(lines, group, "children", &format!("{} /* {} */", file_ref, file_name))?;
+
+    // Files in subdirectories keep the group flat: `name` is what Xcode shows.
+    let name_attr = if file_name == path {
+        String::new()
+    } else {
+        format!("name = {}; ", pbx_quote(file_name))
+    };
+    let (_, refs_end) = section(lines, "PBXFileReference")?;
+    lines.insert(
+        refs_end,
+        format!(
+            "\t\t{} /* {} */ = {{isa = PBXFileReference; lastKnownFileType = {}; {}{} sourceTree = \"<group>\"; }};",
+            file_ref, file_name, file_type, name_attr, path_attr
+        ),
+    );
+    if let Some(phase) = phase {
+        let (_, builds_end) = section(lines, "PBXBuildFile")?;
+        lines.insert(
+            builds_end,
+            format!(
+                "\t\t{} /* {} in {} */ = {{isa = PBXBuildFile; fileRef = {} /* {} */; }};",
+                build_file, file_name, phase, file_ref, file_name
+            ),
+        );
+    }
+    Ok(true)
+}
+
+/// Rewrites a generated Xcode project file line by line; writes only when changed.
+pub fn edit_file(path: &Path, edit: impl FnOnce(&mut Vec<String>) -> Result<()>) -> Result<bool> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+    edit(&mut lines)?;
+    let updated = lines.join("\n") + "\n";
+    if updated == content {
+        return Ok(false);
+    }
+    fs::write(path, updated).with_context(|| format!("Failed to write file: {}", path.display()))?;
+    Ok(true)
+}
+
+/// Checks a dotted version like "13.0" before it goes into build settings.
+pub fn check_version(key: &str, version: &str) -> Result<()> {
+    let valid = !version.is_empty()
+        && version.split('.').count() <= 3
+        && version.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+    if !valid {
+        bail!("Invalid {} '{}': expected a version like \"13.0\"", key, version);
+    }
+    Ok(())
+}
+
+/// Ruby single-quoted string literal.
+pub fn ruby_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+/// Sets the `platform :<os>, '<version>'` line of a Podfile, uncommenting the template's.
+pub fn set_podfile_platform(lines: &mut Vec<String>, os: &str, version: &str) {
+    let prefix = format!("platform :{}", os);
+    let platform = format!("{}, {}", prefix, ruby_quote(version));
+    match lines
+        .iter()
+        .position(|line| line.trim_start_matches(['#', ' ']).starts_with(&prefix))
+    {
+        Some(pos) => lines[pos] = platform,
+        None => lines.insert(0, platform),
+    }
+    lines.retain(|line| line != "# Uncomment this line to define a global platform for your project");
+}
@@ -7,24 +7,44 @@
 //! - Reads the current `version:` from `pubspec.yaml` (YAML parser, with a regex fallback).
 //! - Checks if a tag already exists for that version (`vX.Y.Z` or `X.Y.Z`).
 //! - If not, creates a **lightweight** tag pointing at `HEAD` with the expected name.
+//! - With `--github-release`, creates a GitHub release for the tag: the CHANGELOG section of
+//!   the version becomes the body and every file in `dist/` is uploaded as an asset.
+//!   The token is read from `GITHUB_TOKEN` (or `GH_TOKEN`); the repository defaults to the
+//!   `origin` remote. The tag must already be pushed for GitHub to attach the release to it.
+//! - With `--notify-webhook URL` (or `RELEASE_WEBHOOK_URL`), posts the version, a changelog
+//!   excerpt and artifact links to a Slack or Teams incoming webhook once a tag/release is created.
+//!
+//! ## Version control
+//! `--vcs auto` (default) uses git when a repository is found, otherwise a Jujutsu (`jj`)
+//! repository where tags map to bookmarks on `@-`; with neither (or `--vcs none`) tagging is
+//! skipped instead of failing.
+//!
+//! CI checkouts are often shallow and carry no tags, which would make an existing tag look
+//! missing. Shallow clones are refused unless `--fetch-tags` fetches the remote tags first
+//! or `--check-remote` asks `origin` (`git ls-remote`) before creating the tag, so a tag that
+//! exists only remotely fails the run here instead of as a rejected push at the end of the
+//! release. A detached HEAD is fine (the checked-out commit is tagged).
 //!
 //! Usage:
 //!   rust-script git_tag_version.rs [--pubspec PATH] [--tag-prefix v|none]
+//!   rust-script git_tag_version.rs --github-release [--github-repo OWNER/NAME] [--dist-dir dist]
 //!
 //! ```cargo
 //! [dependencies]
-//! clap = { version = "4.4", features = ["derive"] }
+//! clap = { version = "4.4", features = ["derive", "env"] }
 //! regex = "1.10"
 //! anyhow = "1.0"
 //! semver = "1.0"
 //! gix = "0.78"
 //! serde = { version = "1.0", features = ["derive"] }
 //! serde_yaml = "0.9"
+//! serde_json = "1.0"
+//! ureq = { version = "2.10", features = ["json"] }
 //! ```
 
 use clap::{Parser, ValueEnum};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use regex::Regex;
 use semver::{Version, BuildMetadata};
@@ -44,24 +64,303 @@ struct Args {
     #[arg(long, value_enum, default_value = "v")]
     tag_prefix: TagPrefix,
 
+    /// Version control backend used for tags
+    #[arg(long, value_enum, default_value = "auto")]
+    vcs: VcsKind,
+
+    /// Fetch tags from the remote before checking whether the tag exists
+    #[arg(long)]
+    fetch_tags: bool,
+
+    /// Fail if the tag already exists on `origin` but not locally; also allows tagging a
+    /// shallow clone without --fetch-tags
+    #[arg(long)]
+    check_remote: bool,
+
     /// Force recreate tag even if it already exists
     #[arg(short = 'f', long)]
     force: bool,
+
+    /// Create a GitHub release for the tag (token from GITHUB_TOKEN / GH_TOKEN)
+    #[arg(long)]
+    github_release: bool,
+
+    /// GitHub repository as OWNER/NAME (default: parsed from the `origin` remote)
+    #[arg(long)]
+    github_repo: Option<String>,
+
+    /// Changelog used for the release body
+    #[arg(long, default_value = "CHANGELOG.md")]
+    changelog: String,
+
+    /// Directory whose files are uploaded as release assets
+    #[arg(long, default_value = "dist")]
+    dist_dir: String,
+
+    /// Incoming webhook to announce the release on (default: RELEASE_WEBHOOK_URL)
+    #[arg(long, env = "RELEASE_WEBHOOK_URL")]
+    notify_webhook: Option<String>,
+
+    /// Message format of the webhook
+    #[arg(long, value_enum, default_value = "slack")]
+    notify_format: NotifyFormat,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum NotifyFormat {
+    Slack,
+    Teams,
+}
+
+/// Max changelog lines included in webhook announcements.
+const NOTIFY_EXCERPT_LINES: usize = 12;
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
 enum TagPrefix {
     V,
     None,
 }
 
-fn tag_exists(repo: &gix::Repository, tag: &str) -> Result<bool> {
-    let full = format!("refs/tags/{tag}");
-    Ok(repo.try_find_reference(full.as_str())?.is_some())
+#[derive(Copy, Clone, PartialEq, Eq, ValueEnum, Debug)]
+enum VcsKind {
+    /// git if a repository is found, else jj, else tags are skipped
+    Auto,
+    Git,
+    /// Jujutsu: tags are mapped to bookmarks
+    Jj,
+    /// Disable tagging
+    None,
+}
+
+/// Tag operations used by this tool, so jj checkouts (or plain source archives)
+/// don't depend on a git repository being discoverable.
+trait Vcs {
+    fn name(&self) -> &'static str;
+    /// True when local history (and therefore the tag list) may be incomplete.
+    fn is_shallow(&self) -> bool;
+    /// True when the revision being tagged is not on a branch (typical CI checkout).
+    fn is_detached(&self) -> Result<bool>;
+    /// Fetches tags from the default remote so existence checks see them.
+    fn fetch_tags(&self) -> Result<()>;
+    /// Asks the default remote whether `tag` exists there, without fetching it.
+    fn remote_tag_exists(&self, tag: &str) -> Result<bool>;
+    fn tag_exists(&self, tag: &str) -> Result<bool>;
+    /// Points `tag` at the current revision. Returns `false` when there is no
+    /// revision to tag yet (e.g. an unborn HEAD).
+    fn create_tag(&self, tag: &str, force: bool) -> Result<bool>;
+    fn delete_tag(&self, tag: &str) -> Result<()>;
+}
+
+struct GitVcs {
+    repo: gix::Repository,
+    dir: PathBuf,
+}
+
+/// Local reads and tag writes go through gix; talking to the remote (`fetch_tags`,
+/// `remote_tag_exists`) runs the git CLI. gix only connects with its
+/// `blocking-network-client` feature plus an HTTP backend, a much heavier build for this
+/// script, and the CLI already honors the user's credential helpers, SSH config and proxies.
+impl Vcs for GitVcs {
+    fn name(&self) -> &'static str {
+        "git"
+    }
+
+    fn is_shallow(&self) -> bool {
+        self.repo.is_shallow()
+    }
+
+    fn is_detached(&self) -> Result<bool> {
+        Ok(self.repo.head_name()?.is_none())
+    }
+
+    fn fetch_tags(&self) -> Result<()> {
+        let status = std::process::Command::new("git")
+            .args(["fetch", "--tags", "--force", "--quiet", "origin"])
+            .current_dir(&self.dir)
+            .status()
+            .context("Failed to run 'git fetch'")?;
+        if !status.success() {
+            anyhow::bail!("git fetch --tags origin failed");
+        }
+        Ok(())
+    }
+
+    fn remote_tag_exists(&self, tag: &str) -> Result<bool> {
+        let output = std::process::Command::new("git")
+            .args(["ls-remote", "--tags", "origin", &format!("refs/tags/{tag}")])
+            .current_dir(&self.dir)
+            .output()
+            .context("Failed to run 'git ls-remote'")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "git ls-remote origin failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(!String::from_utf8_lossy(&output.stdout).trim().is_empty())
+    }
+
+    fn tag_exists(&self, tag: &str) -> Result<bool> {
+        let full = format!("refs/tags/{tag}");
+        Ok(self.repo.try_find_reference(full.as_str())?.is_some())
+    }
+
+    fn create_tag(&self, tag: &str, force: bool) -> Result<bool> {
+        let head_id = match self.repo.head_id() {
+            Ok(id) => id.detach(),
+            Err(_) => return Ok(false),
+        };
+        let previous = if force { PreviousValue::Any } else { PreviousValue::MustNotExist };
+        self.repo
+            .tag_reference(tag, head_id, previous)
+            .with_context(|| format!("Failed to create lightweight tag '{tag}'"))?;
+        Ok(true)
+    }
+
+    fn delete_tag(&self, tag: &str) -> Result<()> {
+        let full = format!("refs/tags/{tag}");
+        if let Some(reference) = self.repo.try_find_reference(full.as_str())? {
+            reference.delete()?;
+        }
+        Ok(())
+    }
+}
+
+/// Jujutsu repository; tags are bookmarks on the parent of the working-copy commit (`@-`).
+struct JjVcs {
+    root: PathBuf,
+}
+
+impl JjVcs {
+    fn run(&self, args: &[&str]) -> Result<String> {
+        let output = std::process::Command::new("jj")
+            .args(args)
+            .current_dir(&self.root)
+            .output()
+            .context("Failed to run 'jj'")?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "jj {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+    }
+}
+
+impl Vcs for JjVcs {
+    fn name(&self) -> &'static str {
+        "jj"
+    }
+
+    fn is_shallow(&self) -> bool {
+        false
+    }
+
+    fn is_detached(&self) -> Result<bool> {
+        // jj has no current branch; bookmarks are always placed explicitly.
+        Ok(false)
+    }
+
+    fn fetch_tags(&self) -> Result<()> {
+        self.run(&["git", "fetch"])?;
+        Ok(())
+    }
+
+    fn remote_tag_exists(&self, _tag: &str) -> Result<bool> {
+        anyhow::bail!("Remote tag checks are not supported for jj repositories; use --fetch-tags instead")
+    }
+
+    fn tag_exists(&self, tag: &str) -> Result<bool> {
+        let pattern = format!("exact:{tag}");
+        let template = r#"name ++ "\n""#;
+        let bookmarks = self.run(&["bookmark", "list", "-T", template, &pattern])?;
+        let tags = self.run(&["tag", "list", "-T", template, &pattern])?;
+        Ok(bookmarks.lines().chain(tags.lines()).any(|name| name.trim() == tag))
+    }
+
+    fn create_tag(&self, tag: &str, force: bool) -> Result<bool> {
+        if force {
+            self.run(&["bookmark", "set", tag, "-r", "@-", "--allow-backwards"])?;
+        } else {
+            self.run(&["bookmark", "create", tag, "-r", "@-"])?;
+        }
+        Ok(true)
+    }
+
+    fn delete_tag(&self, tag: &str) -> Result<()> {
+        self.run(&["bookmark", "delete", &format!("exact:{tag}")])?;
+        Ok(())
+    }
+}
+
+fn find_jj_root(start_dir: &Path) -> Option<PathBuf> {
+    let start = start_dir.canonicalize().ok()?;
+    start.ancestors().find(|dir| dir.join(".jj").is_dir()).map(Path::to_path_buf)
+}
+
+/// Returns `None` when tagging is disabled or no supported repository is found.
+fn open_vcs(kind: VcsKind, start_dir: &Path) -> Result<Option<Box<dyn Vcs>>> {
+    match kind {
+        VcsKind::None => Ok(None),
+        VcsKind::Git => {
+            let repo = gix::discover(start_dir)
+                .with_context(|| format!("No git repository found at {}", start_dir.display()))?;
+            Ok(Some(Box::new(GitVcs { repo, dir: start_dir.to_path_buf() })))
+        }
+        VcsKind::Jj => {
+            let root = find_jj_root(start_dir)
+                .with_context(|| format!("No jj repository found at {}", start_dir.display()))?;
+            Ok(Some(Box::new(JjVcs { root })))
+        }
+        VcsKind::Auto => {
+            // Colocated jj repos are handled as git; jj imports the refs on its next command.
+            if let Ok(repo) = gix::discover(start_dir) {
+                return Ok(Some(Box::new(GitVcs { repo, dir: start_dir.to_path_buf() })));
+            }
+            if which_jj() {
+                if let Some(root) = find_jj_root(start_dir) {
+                    return Ok(Some(Box::new(JjVcs { root })));
+                }
+            }
+            Ok(None)
+        }
+    }
+}
+
+/// Makes sure the tag list is trustworthy before checking for existing tags.
+/// Returns `false` when tags can't be trusted (shallow clone without `--fetch-tags` or
+/// `--check-remote`, which asks origin instead of the local tag list).
+fn prepare_tags(vcs: &dyn Vcs, fetch_tags: bool, check_remote: bool) -> Result<bool> {
+    if fetch_tags {
+        vcs.fetch_tags()?;
+        println!("[tag-version] Fetched tags from the remote");
+    } else if vcs.is_shallow() {
+        if check_remote {
+            println!("[tag-version] Shallow clone detected: checking origin for existing tags (--check-remote)");
+        } else {
+            println!("[tag-version] Shallow clone detected: existing tags may be missing locally (use --fetch-tags or --check-remote)");
+            return Ok(false);
+        }
+    }
+    if vcs.is_detached()? {
+        println!("[tag-version] Detached HEAD: tagging the checked-out commit");
+    }
+    Ok(true)
+}
+
+fn which_jj() -> bool {
+    std::process::Command::new("jj")
+        .arg("--version")
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
 }
 
 #[derive(Debug, Deserialize)]
 struct PubspecYaml {
+    name: Option<String>,
     version: Option<String>,
 }
 
@@ -84,6 +383,201 @@ fn read_pubspec_version(content: &str) -> Option<String> {
         .filter(|s| !s.is_empty())
 }
 
+/// Returns the body of the `## <version>` CHANGELOG section (also matches `## [1.2.3]`).
+fn extract_changelog_section(changelog: &str, version: &Version) -> Option<String> {
+    let mut core = Version::new(version.major, version.minor, version.patch);
+    core.pre = version.pre.clone();
+    let wanted = [version.to_string(), core.to_string()];
+
+    let mut section = Vec::new();
+    let mut found = false;
+    for line in changelog.lines() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            if found {
+                break;
+            }
+            let name = heading
+                .split_whitespace()
+                .next()
+                .unwrap_or("")
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim_start_matches('v');
+            found = wanted.iter().any(|w| w == name);
+            continue;
+        }
+        if found {
+            section.push(line);
+        }
+    }
+    found.then(|| section.join("\n").trim().to_string())
+}
+
+/// Parses `owner/name` from a GitHub remote URL (https or ssh form).
+fn github_repo_from_remote(start_dir: &Path) -> Result<String> {
+    let output = std::process::Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .current_dir(start_dir)
+        .output()
+        .context("Failed to run 'git remote get-url origin'")?;
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let path = url
+        .strip_prefix("git@github.com:")
+        .or_else(|| url.strip_prefix("https://github.com/"))
+        .or_else(|| url.strip_prefix("ssh://git@github.com/"))
+        .with_context(|| format!("origin remote is not a GitHub URL: '{}' (use --github-repo)", url))?;
+    Ok(path.trim_end_matches('/').trim_end_matches(".git").to_string())
+}
+
+/// Creates the release and returns `(label, url)` links for the release page and its assets,
+/// or `None` when a release for the tag already exists.
+fn create_github_release(
+    args: &Args,
+    start_dir: &Path,
+    tag: &str,
+    version: &Version,
+) -> Result<Option<Vec<(String, String)>>> {
+    let token = std::env::var("GITHUB_TOKEN")
+        .or_else(|_| std::env::var("GH_TOKEN"))
+        .context("GITHUB_TOKEN (or GH_TOKEN) is required for --github-release")?;
+    let repo = match &args.github_repo {
+        Some(repo) => repo.clone(),
+        None => github_repo_from_remote(start_dir)?,
+    };
+    let api = format!("https://api.github.com/repos/{}/releases", repo);
+    let auth = format!("Bearer {}", token);
+
+    match ureq::get(&format!("{}/tags/{}", api, tag))
+        .set("Authorization", &auth)
+        .set("Accept", "application/vnd.github+json")
+        .call()
+    {
+        Ok(_) => {
+            println!("[tag-version] GitHub release for '{}' already exists. Skipping.", tag);
+            return Ok(None);
+        }
+        Err(ureq::Error::Status(404, _)) => {}
+        Err(e) => return Err(e).context("Failed to query GitHub releases"),
+    }
+
+    let changelog_path = start_dir.join(&args.changelog);
+    let body = fs::read_to_string(&changelog_path)
+        .ok()
+        .and_then(|content| extract_changelog_section(&content, version))
+        .unwrap_or_default();
+    if body.is_empty() {
+        println!("[tag-version] Note: no CHANGELOG section found for {}", version);
+    }
+
+    let release: serde_json::Value = ureq::post(&api)
+        .set("Authorization", &auth)
+        .set("Accept", "application/vnd.github+json")
+        .send_json(serde_json::json!({
+            "tag_name": tag,
+            "name": tag,
+            "body": body,
+            "prerelease": !version.pre.is_empty(),
+        }))
+        .context("Failed to create GitHub release")?
+        .into_json()
+        .context("Failed to parse GitHub release response")?;
+    println!(
+        "[tag-version] Created GitHub release '{}': {}",
+        tag,
+        release["html_url"].as_str().unwrap_or("")
+    );
+
+    let mut links = vec![(
+        "Release notes".to_string(),
+        release["html_url"].as_str().unwrap_or("").to_string(),
+    )];
+
+    let upload_url = release["upload_url"]
+        .as_str()
+        .context("GitHub release response has no upload_url")?;
+    let upload_url = upload_url.split('{').next().unwrap_or(upload_url);
+
+    let dist_dir = start_dir.join(&args.dist_dir);
+    if !dist_dir.is_dir() {
+        println!("[tag-version] No {} directory; release has no assets", dist_dir.display());
+        return Ok(Some(links));
+    }
+    let mut assets: Vec<_> = fs::read_dir(&dist_dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    assets.sort();
+    for asset in assets {
+        let name = asset.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let bytes = fs::read(&asset).with_context(|| format!("Failed to read {}", asset.display()))?;
+        let uploaded: serde_json::Value = ureq::post(upload_url)
+            .query("name", &name)
+            .set("Authorization", &auth)
+            .set("Content-Type", "application/octet-stream")
+            .send_bytes(&bytes)
+            .with_context(|| format!("Failed to upload release asset '{}'", name))?
+            .into_json()
+            .context("Failed to parse GitHub asset response")?;
+        println!("[tag-version] Uploaded {}", name);
+        if let Some(url) = uploaded["browser_download_url"].as_str() {
+            links.push((name, url.to_string()));
+        }
+    }
+    Ok(Some(links))
+}
+
+fn notify_release(
+    args: &Args,
+    start_dir: &Path,
+    project: &str,
+    version: &Version,
+    tag: &str,
+    links: &[(String, String)],
+) -> Result<()> {
+    let Some(webhook) = &args.notify_webhook else {
+        return Ok(());
+    };
+
+    let excerpt = fs::read_to_string(start_dir.join(&args.changelog))
+        .ok()
+        .and_then(|content| extract_changelog_section(&content, version))
+        .map(|section| {
+            let lines: Vec<&str> = section.lines().collect();
+            let mut excerpt = lines[..lines.len().min(NOTIFY_EXCERPT_LINES)].join("\n");
+            if lines.len() > NOTIFY_EXCERPT_LINES {
+                excerpt.push_str("\n…");
+            }
+            excerpt
+        })
+        .unwrap_or_default();
+
+    let mut text = match args.notify_format {
+        NotifyFormat::Slack => format!("*{} {} released* (`{}`)", project, version, tag),
+        NotifyFormat::Teams => format!("**{} {} released** (`{}`)", project, version, tag),
+    };
+    if !excerpt.is_empty() {
+        text.push_str("\n\n");
+        text.push_str(&excerpt);
+    }
+    if !links.is_empty() {
+        text.push_str("\n\n");
+        let rendered: Vec<String> = links
+            .iter()
+            .map(|(label, url)| match args.notify_format {
+                NotifyFormat::Slack => format!("• <{}|{}>", url, label),
+                NotifyFormat::Teams => format!("- [{}]({})", label, url),
+            })
+            .collect();
+        text.push_str(&rendered.join("\n"));
+    }
+
+    // Both Slack and Teams incoming webhooks accept a plain `text` payload.
+    ureq::post(webhook)
+        .send_json(serde_json::json!({ "text": text }))
+        .context("Failed to post release notification")?;
+    println!("[tag-version] Posted release notification ({:?})", args.notify_format);
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
     let pubspec_path = Path::new(&args.pubspec);
@@ -93,13 +587,18 @@ fn main() -> Result<()> {
         .filter(|p| !p.as_os_str().is_empty())
         .unwrap_or_else(|| Path::new("."));
     
-    let repo = match gix::discover(start_dir) {
-        Ok(r) => r,
-        Err(_) => {
-            println!("[tag-version] Skipping: not in a git repository");
+    let vcs = match open_vcs(args.vcs, start_dir)? {
+        Some(vcs) => vcs,
+        None => {
+            println!("[tag-version] Skipping: no git/jj repository (or --vcs none)");
             return Ok(());
         }
     };
+    if !prepare_tags(vcs.as_ref(), args.fetch_tags, args.check_remote)? {
+        anyhow::bail!(
+            "Refusing to tag a shallow clone without --fetch-tags or --check-remote (the tag may already exist on the remote)"
+        );
+    }
 
     let content = fs::read_to_string(pubspec_path)
         .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
@@ -111,6 +610,10 @@ fn main() -> Result<()> {
             return Ok(());
         }
     };
+    let project_name = serde_yaml::from_str::<PubspecYaml>(&content)
+        .ok()
+        .and_then(|doc| doc.name)
+        .unwrap_or_else(|| "app".to_string());
     
     let v = match Version::parse(&version_str) {
         Ok(v) => v,
@@ -148,59 +651,82 @@ fn main() -> Result<()> {
     };
 
     // If either convention exists, do nothing or maybe just report it.
-    if tag_exists(&repo, &tag_plain)? {
+    if vcs.tag_exists(&tag_plain)? {
         if args.force {
             println!("[tag-version] Tag '{}' already exists. Force deleting...", tag_plain);
-            let tag_ref = format!("refs/tags/{}", tag_plain);
-            if let Some(reference) = repo.try_find_reference(&tag_ref)? {
-                reference.delete()?;
-            }
+            vcs.delete_tag(&tag_plain)?;
         } else {
             println!("[tag-version] Tag '{}' already exists.", tag_plain);
-            if preferred_tag == tag_v && !tag_exists(&repo, &tag_v)? {
+            if preferred_tag == tag_v && !vcs.tag_exists(&tag_v)? {
                  println!("[tag-version] Note: '{}' exists but you asked for prefix 'v'. Skipping to avoid duplicates.", tag_plain);
             }
+            if args.github_release {
+                if let Some(links) = create_github_release(&args, start_dir, &tag_plain, &v)? {
+                    notify_release(&args, start_dir, &project_name, &v, &tag_plain, &links)?;
+                }
+            }
             return Ok(());
         }
     }
     
-    if tag_exists(&repo, &tag_v)? {
+    if vcs.tag_exists(&tag_v)? {
         if args.force {
             println!("[tag-version] Tag '{}' already exists. Force deleting...", tag_v);
-            let tag_ref = format!("refs/tags/{}", tag_v);
-            if let Some(reference) = repo.try_find_reference(&tag_ref)? {
-                reference.delete()?;
-            }
+            vcs.delete_tag(&tag_v)?;
         } else {
             println!("[tag-version] Tag '{}' already exists.", tag_v);
              if preferred_tag == tag_plain {
                  println!("[tag-version] Note: '{}' exists but you asked for 'none'. Skipping.", tag_v);
             }
+            if args.github_release {
+                if let Some(links) = create_github_release(&args, start_dir, &tag_v, &v)? {
+                    notify_release(&args, start_dir, &project_name, &v, &tag_v, &links)?;
+                }
+            }
             return Ok(());
         }
     }
 
-    let head_id = match repo.head_id() {
-        Ok(id) => id.detach(),
-        Err(_) => {
-            println!("[tag-version] Skipping: repository has no commits yet");
-            return Ok(());
-        }
-    };
-
-    let tag_creation_result = if args.force {
-        repo.tag_reference(&preferred_tag, head_id, PreviousValue::Any)
+    // Either spelling on origin means this version was already released, like locally.
+    let remote_tag = if !args.check_remote {
+        None
+    } else if vcs.remote_tag_exists(&tag_plain)? {
+        Some(&tag_plain)
+    } else if vcs.remote_tag_exists(&tag_v)? {
+        Some(&tag_v)
     } else {
-        repo.tag_reference(&preferred_tag, head_id, PreviousValue::MustNotExist)
+        None
     };
+    if let Some(remote_tag) = remote_tag {
+        if args.force {
+            println!(
+                "[tag-version] Warning: '{}' exists on origin; pushing the recreated tag needs --force",
+                remote_tag
+            );
+        } else {
+            anyhow::bail!(
+                "Tag '{}' already exists on origin but not locally (run with --fetch-tags to fetch it)",
+                remote_tag
+            );
+        }
+    }
+
+    if !vcs.create_tag(&preferred_tag, args.force)? {
+        println!("[tag-version] Skipping: repository has no commits yet");
+        return Ok(());
+    }
 
-    tag_creation_result
-        .with_context(|| format!("Failed to create lightweight tag '{preferred_tag}'"))?;
-    
     println!(
-        "[tag-version] Created lightweight tag '{}' for version {}",
-        preferred_tag, version_str
+        "[tag-version] Created {} tag '{}' for version {}",
+        vcs.name(), preferred_tag, version_str
     );
+
+    let links = if args.github_release {
+        create_github_release(&args, start_dir, &preferred_tag, &v)?.unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+    notify_release(&args, start_dir, &project_name, &v, &preferred_tag, &links)?;
     
     Ok(())
 }